@@ -0,0 +1,207 @@
+//! CPU overhead of rapax's state management compared to the same calls on raw glow.
+//!
+//! Needs a GL 3.3 capable display to create a headless context: `cargo bench --bench overhead`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use glow::HasContext;
+use rapax::{BindableBuffer, ProgramSource};
+use std::hint::black_box;
+use std::sync::Arc;
+
+const VS: &str = r#"#version 330 core
+layout (location = 0) in vec2 position;
+layout (location = 1) in vec2 texcoord;
+uniform mat4 u_transform;
+out vec2 v_texcoord;
+void main() {
+    gl_Position = u_transform * vec4(position, 0.0, 1.0);
+    v_texcoord = texcoord;
+}
+"#;
+
+const FS: &str = r#"#version 330 core
+in vec2 v_texcoord;
+uniform vec4 u_color;
+out vec4 o_color;
+void main() {
+    o_color = u_color * vec4(v_texcoord, 0.0, 1.0);
+}
+"#;
+
+const VERTEX_COUNT: usize = 1024;
+
+struct Fixture {
+    ctx: rapax::ManagedContext,
+    gl: Arc<glow::Context>,
+    pipeline: rapax::RenderPipeline,
+    vertices: rapax::BufferHandle,
+    vertex_data: Vec<u8>,
+    raw_vao: glow::NativeVertexArray,
+    _context: glutin::Context<glutin::PossiblyCurrent>,
+}
+
+#[allow(clippy::arc_with_non_send_sync)]
+fn fixture() -> Fixture {
+    let event_loop = glutin::event_loop::EventLoop::new();
+    let context = glutin::ContextBuilder::new()
+        .with_gl(glutin::GlRequest::Specific(glutin::Api::OpenGl, (3, 3)))
+        .with_gl_profile(glutin::GlProfile::Core)
+        .build_headless(&event_loop, glutin::dpi::PhysicalSize::new(64, 64))
+        .expect("failed to create headless context");
+    let context = unsafe { context.make_current() }.expect("failed to make context current");
+    let gl = Arc::new(unsafe {
+        glow::Context::from_loader_function(|s| context.get_proc_address(s) as *const _)
+    });
+
+    let ctx = rapax::ManagedContext::new(gl.clone());
+    let program = rapax::ShaderProgram::new(&ctx, VS, FS).expect("failed to compile shader");
+    let layout = rapax::VertexLayout::new(0)
+        .with_attribute(2, rapax::DataType::Float, false)
+        .with_attribute(2, rapax::DataType::Float, false);
+    let pipeline = rapax::RenderPipeline::new(program).with_vertex_layout(&layout);
+
+    let vertex_data = vec![0u8; VERTEX_COUNT * layout.stride()];
+    let vertices =
+        rapax::BufferHandle::array_buffer(&ctx, rapax::BufferUsage::Dynamic, &vertex_data)
+            .expect("failed to create vertex buffer");
+    let raw_vao = unsafe { gl.create_vertex_array() }.expect("failed to create vertex array");
+
+    Fixture {
+        ctx,
+        gl,
+        pipeline,
+        vertices,
+        vertex_data,
+        raw_vao,
+        _context: context,
+    }
+}
+
+/// Point the raw vertex array at `buffer` the way `apply_bindings` does without caching.
+unsafe fn raw_bindings(gl: &glow::Context, buffer: glow::NativeBuffer) {
+    let stride = 4 * std::mem::size_of::<f32>() as i32;
+    gl.bind_buffer(glow::ARRAY_BUFFER, Some(buffer));
+    gl.vertex_attrib_pointer_f32(0, 2, glow::FLOAT, false, stride, 0);
+    gl.enable_vertex_attrib_array(0);
+    gl.vertex_attrib_pointer_f32(1, 2, glow::FLOAT, false, stride, 8);
+    gl.enable_vertex_attrib_array(1);
+}
+
+fn with_pipeline(c: &mut Criterion, f: &mut Fixture) {
+    let mut group = c.benchmark_group("with_pipeline");
+    group.bench_function("rapax", |b| {
+        b.iter(|| {
+            f.ctx.with_pipeline(&f.pipeline, |d| {
+                black_box(d);
+            })
+        })
+    });
+    let program = f.pipeline.program().native_program();
+    group.bench_function("glow", |b| {
+        b.iter(|| unsafe {
+            f.gl.disable(glow::BLEND);
+            f.gl.disable(glow::DEPTH_TEST);
+            f.gl.color_mask(true, true, true, true);
+            f.gl.depth_mask(true);
+            f.gl.use_program(Some(program));
+            f.gl.bind_vertex_array(Some(f.raw_vao));
+            f.gl.disable(glow::SCISSOR_TEST);
+            f.gl.disable(glow::STENCIL_TEST);
+        })
+    });
+    group.finish();
+}
+
+fn apply_bindings(c: &mut Criterion, f: &mut Fixture) {
+    let mut group = c.benchmark_group("apply_bindings");
+    let vertices = &f.vertices;
+    group.bench_function("rapax", |b| {
+        f.ctx.with_pipeline(&f.pipeline, |d| {
+            b.iter(|| d.apply_bindings(&[vertices], None::<&rapax::BufferHandle>))
+        })
+    });
+    let buffer = (&f.vertices)
+        .native_buffer(glow::ARRAY_BUFFER)
+        .expect("buffer handles are native buffers");
+    group.bench_function("glow", |b| unsafe {
+        f.gl.bind_vertex_array(Some(f.raw_vao));
+        b.iter(|| raw_bindings(&f.gl, buffer));
+    });
+    group.finish();
+}
+
+fn uniforms(c: &mut Criterion, f: &mut Fixture) {
+    let mut group = c.benchmark_group("uniforms");
+    let transform = [1.0; 16];
+    let mut frame = 0.0f32;
+    group.bench_function("rapax", |b| {
+        f.ctx.with_pipeline(&f.pipeline, |d| {
+            b.iter(|| {
+                frame += 1.0;
+                d.set_uniform_mat4("u_transform", &transform, false);
+                d.set_uniform_float4("u_color", &[frame, 1.0, 1.0, 1.0]);
+            })
+        })
+    });
+    let program = f.pipeline.program().native_program();
+    group.bench_function("glow", |b| unsafe {
+        f.gl.use_program(Some(program));
+        b.iter(|| {
+            frame += 1.0;
+            let transform_loc = f.gl.get_uniform_location(program, "u_transform");
+            f.gl.uniform_matrix_4_f32_slice(transform_loc.as_ref(), false, &transform);
+            let color_loc = f.gl.get_uniform_location(program, "u_color");
+            f.gl.uniform_4_f32(color_loc.as_ref(), frame, 1.0, 1.0, 1.0);
+        })
+    });
+    group.finish();
+}
+
+fn buffer_updates(c: &mut Criterion, f: &mut Fixture) {
+    let mut group = c.benchmark_group("buffer_update");
+    let len = f.vertex_data.len();
+    group.bench_function(BenchmarkId::new("rapax", "update"), |b| {
+        b.iter(|| f.vertices.update(&f.ctx, 0, &f.vertex_data))
+    });
+    group.bench_function(BenchmarkId::new("rapax", "invalidate+update"), |b| {
+        b.iter(|| {
+            f.vertices.invalidate(&f.ctx);
+            f.vertices.update(&f.ctx, 0, &f.vertex_data);
+        })
+    });
+    group.bench_function(BenchmarkId::new("rapax", "realloc"), |b| {
+        b.iter(|| {
+            f.vertices
+                .realloc(&f.ctx, rapax::BufferUsage::Dynamic, &f.vertex_data)
+        })
+    });
+    let buffer = (&f.vertices)
+        .native_buffer(glow::ARRAY_BUFFER)
+        .expect("buffer handles are native buffers");
+    group.bench_function(BenchmarkId::new("glow", "buffer_sub_data"), |b| {
+        b.iter(|| unsafe {
+            f.gl.bind_buffer(glow::ARRAY_BUFFER, Some(buffer));
+            f.gl.buffer_sub_data_u8_slice(glow::ARRAY_BUFFER, 0, &f.vertex_data);
+        })
+    });
+    group.bench_function(BenchmarkId::new("glow", "orphan"), |b| {
+        b.iter(|| unsafe {
+            f.gl.bind_buffer(glow::ARRAY_BUFFER, Some(buffer));
+            f.gl.buffer_data_size(glow::ARRAY_BUFFER, len as i32, glow::DYNAMIC_DRAW);
+            f.gl.buffer_sub_data_u8_slice(glow::ARRAY_BUFFER, 0, &f.vertex_data);
+        })
+    });
+    group.finish();
+}
+
+fn overhead(c: &mut Criterion) {
+    let mut f = fixture();
+    with_pipeline(c, &mut f);
+    apply_bindings(c, &mut f);
+    uniforms(c, &mut f);
+    buffer_updates(c, &mut f);
+    unsafe { f.gl.delete_vertex_array(f.raw_vao) };
+}
+
+criterion_group!(benches, overhead);
+criterion_main!(benches);
@@ -0,0 +1,296 @@
+//! rapax as the GL backend of an ECS-style renderer.
+//!
+//! The game world only stores plain data, the `Renderer` resource owns the `ManagedContext`. Each frame runs the
+//! usual phases:
+//!
+//! - extract: copy what the renderer needs out of the world, so the world can keep changing
+//! - prepare: create & destroy GPU resources, components only ever hold `TextureId`s
+//! - queue: record draw commands, sorted to minimize texture switches
+//! - render: replay the recorded commands
+//!
+//! Despawned entities hand their textures back with `destroy_texture`, which defers the deletion until
+//! `end_frame` knows the GPU is done with them.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use cgmath::*;
+
+use glutin::event::{Event, WindowEvent};
+use glutin::event_loop::ControlFlow;
+
+type Entity = u32;
+
+/// A component: where to draw a sprite & how to tint its checkerboard.
+#[derive(Debug, Clone, Copy)]
+struct Sprite {
+    position: Vector2<f32>,
+    size: f32,
+    color: [u8; 3],
+}
+
+/// A minimal stand-in for an ECS world.
+#[derive(Debug, Default)]
+struct World {
+    next_entity: Entity,
+    sprites: Vec<(Entity, Sprite)>,
+    time: f32,
+}
+
+impl World {
+    fn spawn(&mut self, sprite: Sprite) -> Entity {
+        let entity = self.next_entity;
+        self.next_entity += 1;
+        self.sprites.push((entity, sprite));
+        entity
+    }
+
+    fn update(&mut self) {
+        self.time += 1.0 / 60.0;
+
+        // keep entities coming & going, so the renderer has to free their textures
+        if self.sprites.len() > 12 {
+            self.sprites.remove(0);
+        }
+        if ((self.time * 60.0) as u32) % 20 == 0 {
+            let n = self.next_entity as f32;
+            self.spawn(Sprite {
+                position: vec2(80.0 + (n * 90.0) % 800.0, 120.0 + (n * 37.0) % 500.0),
+                size: 48.0 + (n * 13.0) % 48.0,
+                color: [(n * 67.0) as u8, (n * 151.0) as u8, 255 - (n * 29.0) as u8],
+            });
+        }
+        for (entity, sprite) in &mut self.sprites {
+            sprite.position.y += (self.time * 2.0 + *entity as f32).sin();
+        }
+    }
+}
+
+/// The render world's copy of a sprite, see [`Renderer::extract`].
+#[derive(Debug, Clone, Copy)]
+struct ExtractedSprite {
+    entity: Entity,
+    sprite: Sprite,
+}
+
+/// A recorded draw, see [`Renderer::queue`].
+#[derive(Debug, Clone, Copy)]
+struct DrawCommand {
+    texture: rapax::TextureId,
+    transform: Matrix4<f32>,
+}
+
+/// The renderer resource: owns the context and everything the GPU needs.
+struct Renderer {
+    ctx: rapax::ManagedContext,
+    pipeline: rapax::RenderPipeline,
+    quad: rapax::BufferId,
+    textures: HashMap<Entity, rapax::TextureId>,
+    extracted: Vec<ExtractedSprite>,
+    commands: Vec<DrawCommand>,
+    size: (u32, u32),
+}
+
+impl Renderer {
+    fn new(mut ctx: rapax::ManagedContext) -> Self {
+        let program = rapax::ShaderProgram::new(
+            &ctx,
+            r#"#version 330 core
+            layout(location = 0) in vec2 aPos;
+            out vec2 uv;
+            uniform mat4 uMVP;
+            void main() {
+                uv = aPos;
+                gl_Position = uMVP * vec4(aPos - 0.5, 0.0, 1.0);
+            }"#,
+            r#"#version 330 core
+            in vec2 uv;
+            out vec4 color;
+            uniform sampler2D uTexture;
+            void main() {
+                color = texture(uTexture, uv);
+            }"#,
+        )
+        .unwrap();
+        let pipeline = rapax::RenderPipeline::new(program).with_vertex_attribute(
+            rapax::VertexAttributeDescriptor {
+                buffer_index: 0,
+                size: 2,
+                ty: rapax::DataType::Float,
+                normalized: false,
+                stride: 0,
+                offset: 0,
+                divisor: 0,
+                location: None,
+            },
+        );
+
+        let corners: [f32; 12] = [0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0];
+        let quad = ctx
+            .create_buffer(&rapax::PendingBuffer {
+                data: corners.iter().flat_map(|c| c.to_ne_bytes()).collect(),
+                ty: rapax::BufferType::ArrayBuffer,
+                usage: rapax::BufferUsage::Immutable,
+            })
+            .unwrap();
+
+        Self {
+            ctx,
+            pipeline,
+            quad,
+            textures: HashMap::new(),
+            extracted: vec![],
+            commands: vec![],
+            size: (1024, 768),
+        }
+    }
+
+    /// Copy the render-relevant parts of the world.
+    fn extract(&mut self, world: &World) {
+        self.extracted.clear();
+        self.extracted.extend(
+            world
+                .sprites
+                .iter()
+                .map(|(entity, sprite)| ExtractedSprite {
+                    entity: *entity,
+                    sprite: *sprite,
+                }),
+        );
+    }
+
+    /// Create textures for new entities and destroy the textures of despawned ones.
+    fn prepare(&mut self) {
+        let alive: Vec<Entity> = self.extracted.iter().map(|e| e.entity).collect();
+        let ctx = &mut self.ctx;
+        self.textures.retain(|entity, id| {
+            let keep = alive.contains(entity);
+            if !keep {
+                // the texture may still be used by in-flight frames, `end_frame` drops it once they are done
+                ctx.destroy_texture(*id);
+            }
+            keep
+        });
+
+        for extracted in &self.extracted {
+            if self.textures.contains_key(&extracted.entity) {
+                continue;
+            }
+            let [r, g, b] = extracted.sprite.color;
+            let pixels = [[r, g, b, 255], [255; 4], [255; 4], [r, g, b, 255]].concat();
+            let id = self
+                .ctx
+                .create_texture(&rapax::PendingTexture {
+                    pixels,
+                    internal_format: rapax::InternalTextureFormat::Rgba8,
+                    format: rapax::TextureFormat::Rgba,
+                    ty: rapax::DataType::UnsignedByte,
+                    width: 2,
+                    height: 2,
+                    wrapping_mode_s: rapax::TextureWrap::ClampToEdge,
+                    wrapping_mode_t: rapax::TextureWrap::ClampToEdge,
+                    min_filter: rapax::TextureFilteringMode::Nearest,
+                    mag_filter: rapax::TextureFilteringMode::Nearest,
+                    mipmap_policy: rapax::MipmapPolicy::None,
+                })
+                .unwrap();
+            self.textures.insert(extracted.entity, id);
+        }
+    }
+
+    /// Record the draws of this frame, sorted by texture, skipping sprites outside the view.
+    fn queue(&mut self) {
+        let view = ortho(0.0, self.size.0 as f32, self.size.1 as f32, 0.0, -1.0, 1.0);
+        let frustum = rapax::Frustum::from_matrix(view.as_ref());
+        self.commands.clear();
+        self.commands.extend(
+            self.extracted
+                .iter()
+                .filter(|extracted| {
+                    let Sprite { position, size, .. } = extracted.sprite;
+                    let min = position - Vector2::new(size, size) * 0.5;
+                    let max = position + Vector2::new(size, size) * 0.5;
+                    frustum.intersects_aabb([min.x, min.y, 0.0], [max.x, max.y, 0.0])
+                })
+                .map(|extracted| DrawCommand {
+                    texture: self.textures[&extracted.entity],
+                    transform: view
+                        * Matrix4::from_translation(extracted.sprite.position.extend(0.0))
+                        * Matrix4::from_scale(extracted.sprite.size),
+                }),
+        );
+        self.commands.sort_by_key(|command| command.texture.index());
+    }
+
+    /// Replay the recorded draws.
+    fn render(&mut self) {
+        self.ctx.clear_with([0.1, 0.1, 0.12, 1.0]);
+
+        let commands = std::mem::take(&mut self.commands);
+        let quad = self.quad;
+        self.ctx.with_pipeline(&self.pipeline, |dctx| {
+            dctx.apply_buffer_ids(&[quad], None);
+            for command in &commands {
+                dctx.apply_texture_ids(&[(command.texture, "uTexture")]);
+                let transform: &[f32; 16] = command.transform.as_ref();
+                dctx.set_uniform_mat4("uMVP", transform, false);
+                dctx.draw_arrays(rapax::DrawMode::Triangles, 0, 6);
+            }
+        });
+        self.commands = commands;
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.size = (width, height);
+        self.ctx.set_viewport(0, 0, width as i32, height as i32);
+    }
+}
+
+#[allow(clippy::arc_with_non_send_sync)]
+fn main() {
+    let (gl, window, event_loop) = unsafe {
+        let event_loop = glutin::event_loop::EventLoop::new();
+        let window_builder = glutin::window::WindowBuilder::new()
+            .with_title("ECS Renderer Demo")
+            .with_inner_size(glutin::dpi::LogicalSize::new(1024.0, 768.0));
+        let window = glutin::ContextBuilder::new()
+            .with_vsync(true)
+            .build_windowed(window_builder, &event_loop)
+            .unwrap()
+            .make_current()
+            .unwrap();
+        let gl = glow::Context::from_loader_function(|s| window.get_proc_address(s) as *const _);
+        (Arc::new(gl), window, event_loop)
+    };
+
+    let mut world = World::default();
+    let mut renderer = Renderer::new(rapax::ManagedContext::new(gl));
+
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = ControlFlow::Poll;
+        match event {
+            Event::LoopDestroyed => {}
+            Event::MainEventsCleared => {
+                world.update();
+                window.window().request_redraw();
+            }
+            Event::RedrawRequested(_) => {
+                renderer.extract(&world);
+                renderer.prepare();
+                renderer.queue();
+                renderer.render();
+                window.swap_buffers().unwrap();
+                renderer.ctx.end_frame();
+            }
+            Event::WindowEvent { ref event, .. } => match event {
+                WindowEvent::Resized(physical_size) => {
+                    window.resize(*physical_size);
+                    renderer.resize(physical_size.width, physical_size.height);
+                }
+                WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+                _ => (),
+            },
+            _ => (),
+        }
+    });
+}
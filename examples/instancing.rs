@@ -7,6 +7,7 @@ use cgmath::*;
 use glutin::event::{Event, WindowEvent};
 use glutin::event_loop::ControlFlow;
 
+#[allow(clippy::arc_with_non_send_sync)]
 fn main() {
     let (gl, window, event_loop) = unsafe {
         let event_loop = glutin::event_loop::EventLoop::new();
@@ -28,7 +29,7 @@ fn main() {
     println!("Max samples detected: {}", max_samples);
     let mut ctx = rapax::ManagedContext::new(gl);
     let program = rapax::ShaderProgram::new(
-        &mut ctx,
+        &ctx,
         r#"#version 410
         const vec2 verts[3] = vec2[3](
             vec2(100.0f, 200.0f),
@@ -54,7 +55,8 @@ fn main() {
 			vec4 instanceColor = vec4(uColor.r / (instanceID + 1.0f), uColor.gba);
             color = instanceColor;
         }"#,
-    );
+    )
+    .unwrap();
 
     let pipeline = rapax::RenderPipeline::new(program);
 
@@ -63,9 +65,7 @@ fn main() {
     event_loop.run(move |event, _, control_flow| {
         *control_flow = ControlFlow::Poll;
         match event {
-            Event::LoopDestroyed => {
-                return;
-            }
+            Event::LoopDestroyed => {}
             Event::MainEventsCleared => {
                 window.window().request_redraw();
             }
@@ -86,7 +86,7 @@ fn main() {
                         0.0,
                     ));
                     let mvp = view * model;
-                    dctx.set_uniform_mat4("uMVP", &mvp.as_ref(), false);
+                    dctx.set_uniform_mat4("uMVP", mvp.as_ref(), false);
 
                     dctx.draw_arrays_instanced(rapax::DrawMode::Triangles, 0, 3, 10);
                 });
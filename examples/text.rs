@@ -4,12 +4,13 @@ use cosmic_text::{
     Action, Attrs, Buffer, Color, Edit, Editor, FontSystem, Metrics, Shaping, SwashCache,
 };
 use glutin::event::{
-    ElementState, Event, ModifiersState, MouseScrollDelta, VirtualKeyCode, WindowEvent,
+    ElementState, Event, Ime, ModifiersState, MouseScrollDelta, VirtualKeyCode, WindowEvent,
 };
 use glutin::event_loop::ControlFlow;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Instant;
 
 /// From https://github.com/pop-os/cosmic-text/blob/main/examples/editor-libcosmic/src/text_box.rs#L59C1-L107C1
 fn draw_pixel(
@@ -40,7 +41,7 @@ fn draw_pixel(
 
     let mut current = buffer[offset + 2] as u32
         | (buffer[offset + 1] as u32) << 8
-        | (buffer[offset + 0] as u32) << 16
+        | (buffer[offset] as u32) << 16
         | (buffer[offset + 3] as u32) << 24;
 
     if alpha >= 255 || current == 0 {
@@ -57,32 +58,50 @@ fn draw_pixel(
 
     buffer[offset + 2] = current as u8;
     buffer[offset + 1] = (current >> 8) as u8;
-    buffer[offset + 0] = (current >> 16) as u8;
+    buffer[offset] = (current >> 16) as u8;
     buffer[offset + 3] = (current >> 24) as u8;
     //buffer[offset + 3] = (((color.0 >> 24) as f32 / 255.0).powf(0.8) * 255.0) as u8;
 }
 
-fn main() {
-    let (gl, window, event_loop) = unsafe {
-        let event_loop = glutin::event_loop::EventLoop::new();
-        let window_builder = glutin::window::WindowBuilder::new()
-            .with_title("Text Demo")
-            .with_inner_size(glutin::dpi::LogicalSize::new(1024.0, 768.0));
-        let window = glutin::ContextBuilder::new()
-            .with_vsync(true)
-            //.with_multisampling(16)
-            .with_pixel_format(24, 0)
-            .build_windowed(window_builder, &event_loop)
-            .unwrap()
-            .make_current()
-            .unwrap();
-        let gl = glow::Context::from_loader_function(|s| window.get_proc_address(s) as *const _);
-        (Arc::new(gl), window, event_loop)
-    };
-    let mut ctx = rapax::ManagedContext::new(gl);
-    let program = rapax::ShaderProgram::new(
-        &mut ctx,
-        r#"#version 330 core
+/// Renders the editor's text, either the library-supported way or the old way for comparison.
+#[allow(clippy::large_enum_variant)]
+enum TextRenderer {
+    /// Glyphs are rasterized once into a `GlyphAtlas` and drawn as one instanced quad each, so an edit only
+    /// re-lays out the text and uploads a few bytes per glyph.
+    Glyphs {
+        pipeline: rapax::RenderPipeline,
+        atlas: rapax::text::GlyphAtlas,
+        instances: rapax::BufferHandle,
+        count: u32,
+        highlight_pipeline: rapax::RenderPipeline,
+        highlights: rapax::text::HighlightBuffer,
+    },
+    /// Every edit rasterizes the whole window on the CPU and uploads it as one texture.
+    FullTexture {
+        pipeline: rapax::RenderPipeline,
+        vertex_buffer: rapax::BufferHandle,
+        index_buffer: rapax::BufferHandle,
+        texture: rapax::Texture2D,
+    },
+}
+
+impl TextRenderer {
+    fn glyphs(ctx: &mut rapax::ManagedContext) -> Self {
+        Self::Glyphs {
+            pipeline: rapax::RenderPipeline::glyphs(ctx).unwrap(),
+            atlas: rapax::text::GlyphAtlas::new(ctx, 1024).unwrap(),
+            instances: rapax::BufferHandle::array_buffer(ctx, rapax::BufferUsage::Dynamic, &[])
+                .unwrap(),
+            count: 0,
+            highlight_pipeline: rapax::RenderPipeline::text_highlights(ctx).unwrap(),
+            highlights: rapax::text::HighlightBuffer::new(ctx).unwrap(),
+        }
+    }
+
+    fn full_texture(ctx: &mut rapax::ManagedContext, size: glutin::dpi::PhysicalSize<u32>) -> Self {
+        let program = rapax::ShaderProgram::new(
+            ctx,
+            r#"#version 330 core
 
 		layout (location = 0) in vec2 position;
 		layout (location = 1) in vec2 texcoord;
@@ -97,7 +116,7 @@ fn main() {
 			texcoord_out = texcoord;
 		}
 		"#,
-        r#"#version 330 core
+            r#"#version 330 core
 
 		in vec2 texcoord_out;
 		out vec4 FragColor;
@@ -108,71 +127,261 @@ fn main() {
 			FragColor = texture(uTexture, texcoord_out);
 		}
 "#,
-    );
+        )
+        .unwrap();
+
+        let pipeline = rapax::RenderPipeline::new(program)
+            .with_blend(true)
+            .with_blend_func(
+                rapax::BlendFactor::SourceAlpha,
+                rapax::BlendFactor::OneMinusSourceAlpha,
+            )
+            .with_vertex_attribute(rapax::VertexAttributeDescriptor {
+                buffer_index: 0,
+                size: 2,
+                ty: rapax::DataType::Float,
+                normalized: false,
+                stride: 4 * rapax::DataType::Float.sizeof() as i32,
+                offset: 0,
+                divisor: 0,
+                location: None,
+            })
+            .with_vertex_attribute(rapax::VertexAttributeDescriptor {
+                buffer_index: 0,
+                size: 2,
+                ty: rapax::DataType::Float,
+                normalized: false,
+                stride: 4 * rapax::DataType::Float.sizeof() as i32,
+                offset: 2 * rapax::DataType::Float.sizeof() as i32,
+                divisor: 0,
+                location: None,
+            });
+
+        #[rustfmt::skip]
+        let vertex_data: [f32; 16] = [
+            0.0,  0.0,  0.0, 0.0,   // top left
+            1.0, 0.0, 1.0, 0.0,   // top right
+            0.0, 1.0, 0.0, 1.0,   // bottom left
+            1.0,  1.0, 1.0, 1.0    // bottom right
+        ];
+
+        #[rustfmt::skip]
+        let index_data: [u16; 6] = [
+            0, 1, 2,
+            2, 3, 1
+        ];
+
+        let vertex_buffer = rapax::BufferHandle::array_buffer(
+            ctx,
+            rapax::BufferUsage::Immutable,
+            bytemuck::cast_slice(&vertex_data),
+        )
+        .unwrap();
 
-    let pipeline = rapax::RenderPipeline::new(program)
-        .with_blend(true)
-        .with_blend_func(
-            rapax::BlendFactor::SourceAlpha,
-            rapax::BlendFactor::OneMinusSourceAlpha,
+        let index_buffer = rapax::BufferHandle::index_buffer(
+            ctx,
+            rapax::BufferUsage::Immutable,
+            bytemuck::cast_slice(&index_data),
         )
-        .with_vertex_attribute(rapax::VertexAttributeDescriptor {
-            buffer_index: 0,
-            size: 2,
-            ty: rapax::DataType::Float,
-            normalized: false,
-            stride: 4 * rapax::DataType::Float.sizeof() as i32,
-            offset: 0,
-            divisor: 0,
-        })
-        .with_vertex_attribute(rapax::VertexAttributeDescriptor {
-            buffer_index: 0,
-            size: 2,
-            ty: rapax::DataType::Float,
-            normalized: false,
-            stride: 4 * rapax::DataType::Float.sizeof() as i32,
-            offset: 2 * rapax::DataType::Float.sizeof() as i32,
-            divisor: 0,
-        });
-
-    #[rustfmt::skip]
-    let vertex_data: [f32; 16] = [
-        0.0,  0.0,  0.0, 0.0,   // top left
-        1.0, 0.0, 1.0, 0.0,   // top right
-        0.0, 1.0, 0.0, 1.0,   // bottom left
-        1.0,  1.0, 1.0, 1.0    // bottom right
-    ];
-
-    #[rustfmt::skip]
-    let index_data: [u16; 6] = [
-        0, 1, 2,
-        2, 3, 1
-    ];
-
-    let vertex_buffer = rapax::BufferHandle::array_buffer(
-        &mut ctx,
-        rapax::BufferUsage::Immutable,
-        bytemuck::cast_slice(&vertex_data),
-    )
-    .unwrap();
+        .unwrap()
+        .with_index_type(rapax::DataType::UnsignedShort);
 
-    let index_buffer = rapax::BufferHandle::index_buffer(
-        &mut ctx,
-        rapax::BufferUsage::Immutable,
-        bytemuck::cast_slice(&index_data),
-    )
-    .unwrap();
+        let texture = rapax::texture::TextureHandle::new_with(
+            ctx,
+            &rapax::SamplerDescriptor {
+                wrapping_mode_s: rapax::texture::TextureWrap::ClampToBorder,
+                wrapping_mode_t: rapax::texture::TextureWrap::ClampToBorder,
+                ..rapax::SamplerDescriptor::pixel_art()
+            },
+        )
+        .expect("failed to create texture")
+        .allocate_2d_data(
+            ctx,
+            None,
+            rapax::texture::InternalTextureFormat::Rgba,
+            rapax::texture::TextureFormat::Rgba,
+            size.width as _,
+            size.height as _,
+            rapax::DataType::UnsignedByte,
+        );
 
-    let texture = rapax::texture::TextureHandle::new(
-        &mut ctx,
-        rapax::texture::TextureWrap::ClampToBorder,
-        rapax::texture::TextureWrap::ClampToBorder,
-        rapax::texture::TextureFilteringMode::Nearest,
-        rapax::texture::TextureFilteringMode::Nearest,
-    )
-    .expect("failed to create texture");
+        Self::FullTexture {
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+            texture,
+        }
+    }
+
+    /// Bring the rendered text up to date with the editor, printing how long it took.
+    ///
+    /// Returns the caret position, to place the IME candidate window next to it.
+    #[allow(clippy::too_many_arguments)]
+    fn rerender(
+        &mut self,
+        ctx: &mut rapax::ManagedContext,
+        editor: &mut Editor,
+        composition: &rapax::text::Composition,
+        font_system: &mut FontSystem,
+        swash_cache: &mut SwashCache,
+        size: glutin::dpi::PhysicalSize<u32>,
+    ) -> Option<rapax::text::CaretPosition> {
+        let start = Instant::now();
+        let mut caret = None;
+        editor.shape_as_needed(font_system);
+        let text_color = Color::rgb(0, 0, 0);
+        match self {
+            Self::Glyphs {
+                atlas,
+                instances,
+                count,
+                highlights,
+                ..
+            } => {
+                let cursor = editor.cursor();
+                let selection = editor.select_opt().map(|select| (select, cursor));
+                let layout = atlas
+                    .layout(
+                        ctx,
+                        font_system,
+                        swash_cache,
+                        editor.buffer(),
+                        selection,
+                        text_color,
+                    )
+                    .unwrap();
+                instances.realloc(ctx, rapax::BufferUsage::Dynamic, &layout.instance_data());
+                *count = layout.glyphs.len() as u32;
+                let mut quads = layout.highlights(
+                    Some(cursor).filter(|_| composition.shows_caret()),
+                    2.0,
+                    text_color,
+                    Color::rgba(0x33, 0x99, 0xff, 0x66),
+                );
+                if let Some(range) = composition.range() {
+                    quads.extend(layout.underline(range, 1.0, Color::rgb(0x33, 0x66, 0xcc)));
+                }
+                highlights.update(ctx, &quads);
+                caret = layout.caret(cursor);
+            }
+            Self::FullTexture { texture, .. } => {
+                let mut pixels = vec![0u8; size.width as usize * size.height as usize * 4];
+                editor.draw(font_system, swash_cache, text_color, |x, y, w, h, color| {
+                    for row in 0..h as i32 {
+                        for col in 0..w as i32 {
+                            draw_pixel(
+                                &mut pixels,
+                                size.width as _,
+                                size.height as _,
+                                x + col,
+                                y + row,
+                                color,
+                            );
+                        }
+                    }
+                });
+
+                if texture.width() != size.width as i32 || texture.height() != size.height as i32 {
+                    texture.reallocate(ctx, Some(&pixels), size.width as _, size.height as _);
+                } else {
+                    texture
+                        .write_subimage(
+                            ctx,
+                            0,
+                            0,
+                            size.width as _,
+                            size.height as _,
+                            rapax::texture::TextureFormat::Rgba,
+                            rapax::DataType::UnsignedByte,
+                            &pixels,
+                        )
+                        .expect("failed to write subimage");
+                }
+            }
+        }
+        eprintln!("text update took {:?}", start.elapsed());
+        caret
+    }
+
+    fn draw(&self, ctx: &mut rapax::ManagedContext, size: glutin::dpi::PhysicalSize<u32>) {
+        let ortho = cgmath::ortho(0.0, size.width as f32, size.height as f32, 0.0, 0.0, 1.0);
+        match self {
+            Self::Glyphs {
+                pipeline,
+                atlas,
+                instances,
+                count,
+                highlight_pipeline,
+                highlights,
+            } => {
+                ctx.with_pipeline(highlight_pipeline, |dctx| {
+                    dctx.set_uniform_mat4("u_transform", ortho.as_ref(), false);
+                    highlights.draw(dctx, rapax::FramebufferEncoding::Auto);
+                });
+                if *count == 0 {
+                    return;
+                }
+                ctx.with_pipeline(pipeline, |dctx| {
+                    dctx.apply_bindings(&[instances], None::<&rapax::BufferHandle>);
+                    atlas.apply(dctx, rapax::FramebufferEncoding::Auto);
+                    dctx.set_uniform_mat4("u_transform", ortho.as_ref(), false);
+                    dctx.draw_arrays_instanced(rapax::DrawMode::TriangleStrip, 0, 4, *count);
+                });
+            }
+            Self::FullTexture {
+                pipeline,
+                vertex_buffer,
+                index_buffer,
+                texture,
+            } => {
+                ctx.with_pipeline(pipeline, |dctx| {
+                    dctx.apply_bindings(&[vertex_buffer], Some(index_buffer));
+                    dctx.apply_textures(&[(texture, "uTexture")]);
+                    dctx.set_uniform_mat4(
+                        "view",
+                        (ortho
+                            * cgmath::Matrix4::from_nonuniform_scale(
+                                size.width as f32,
+                                size.height as f32,
+                                1.0,
+                            ))
+                        .as_ref(),
+                        false,
+                    );
+                    dctx.draw_indexed(rapax::DrawMode::Triangles, 0..6, 0, 1);
+                });
+            }
+        }
+    }
+}
+
+/// Pass `--full-texture` to render the old way, re-uploading the whole window on every edit.
+#[allow(clippy::arc_with_non_send_sync)]
+fn main() {
+    let (gl, window, event_loop) = unsafe {
+        let event_loop = glutin::event_loop::EventLoop::new();
+        let window_builder = glutin::window::WindowBuilder::new()
+            .with_title("Text Demo")
+            .with_inner_size(glutin::dpi::LogicalSize::new(1024.0, 768.0));
+        let window = glutin::ContextBuilder::new()
+            .with_vsync(true)
+            //.with_multisampling(16)
+            .with_pixel_format(24, 0)
+            .build_windowed(window_builder, &event_loop)
+            .unwrap()
+            .make_current()
+            .unwrap();
+        let gl = glow::Context::from_loader_function(|s| window.get_proc_address(s) as *const _);
+        (Arc::new(gl), window, event_loop)
+    };
+    let mut ctx = rapax::ManagedContext::new(gl);
 
     let mut size = window.window().inner_size();
+    let mut renderer = if std::env::args().any(|arg| arg == "--full-texture") {
+        TextRenderer::full_texture(&mut ctx, size)
+    } else {
+        TextRenderer::glyphs(&mut ctx)
+    };
 
     let mut font_system = FontSystem::new_with_fonts([Source::File(
         PathBuf::from_str("../../Downloads/Times New Roman.ttf").unwrap(),
@@ -190,76 +399,18 @@ fn main() {
         Shaping::Advanced,
     );
     buffer.shape_until_scroll(&mut font_system);
-    let text_color = Color::rgb(0, 0, 0);
-    let mut pixels = vec![0u8; size.width as usize * size.height as usize * 4];
-    buffer.draw(
-        &mut font_system,
-        &mut swash_cache,
-        text_color,
-        |x, y, w, h, color| {
-            for row in 0..h as i32 {
-                for col in 0..w as i32 {
-                    draw_pixel(
-                        &mut pixels,
-                        size.width as _,
-                        size.height as _,
-                        x + col,
-                        y + row,
-                        color,
-                    );
-                }
-            }
-        },
-    );
 
-    let mut texture = texture.allocate_2d_data(
+    let mut editor = Editor::new(buffer);
+    let mut composition = rapax::text::Composition::new().with_color(Color::rgb(0x33, 0x66, 0xcc));
+    renderer.rerender(
         &mut ctx,
-        Some(&pixels),
-        rapax::texture::InternalTextureFormat::Rgba,
-        rapax::texture::TextureFormat::Rgba,
-        size.width as _,
-        size.height as _,
-        rapax::DataType::UnsignedByte,
+        &mut editor,
+        &composition,
+        &mut font_system,
+        &mut swash_cache,
+        size,
     );
 
-    let mut editor = Editor::new(buffer);
-
-    let rerender = |editor: &mut Editor,
-                    font_system: &mut FontSystem,
-                    texture: &mut rapax::Texture2D,
-                    size: glutin::dpi::PhysicalSize<u32>,
-                    swash_cache: &mut SwashCache,
-                    ctx: &mut rapax::ManagedContext| {
-        editor.shape_as_needed(font_system);
-        let text_color = Color::rgb(0, 0, 0);
-        let mut pixels = vec![0u8; size.width as usize * size.height as usize * 4];
-        editor.draw(font_system, swash_cache, text_color, |x, y, w, h, color| {
-            for row in 0..h as i32 {
-                for col in 0..w as i32 {
-                    draw_pixel(
-                        &mut pixels,
-                        size.width as _,
-                        size.height as _,
-                        x + col,
-                        y + row,
-                        color,
-                    );
-                }
-            }
-        });
-
-        texture.write_subimage(
-            ctx,
-            0,
-            0,
-            size.width as _,
-            size.height as _,
-            rapax::texture::TextureFormat::Rgba,
-            rapax::DataType::UnsignedByte,
-            &pixels,
-        );
-    };
-
     let mut clicking = false;
 
     let mut mouse_x = 0;
@@ -268,6 +419,8 @@ fn main() {
     window
         .window()
         .set_cursor_icon(glutin::window::CursorIcon::Text);
+    // composed input, e.g. CJK, arrives as `Ime` events instead of `ReceivedCharacter`
+    window.window().set_ime_allowed(true);
 
     let mut modifiers = ModifiersState::default();
 
@@ -276,37 +429,15 @@ fn main() {
     event_loop.run(move |event, _, control_flow| {
         *control_flow = ControlFlow::Wait;
         match event {
-            Event::LoopDestroyed => {
-                return;
-            }
+            Event::LoopDestroyed => {}
             Event::MainEventsCleared => {
                 window.window().request_redraw();
             }
             Event::RedrawRequested(_) => {
                 ctx.set_clear_color([1., 1., 1., 1.0]);
                 ctx.clear(rapax::ClearFlags::COLOR);
-                ctx.with_pipeline(&pipeline, |dctx| {
-                    size = window.window().inner_size();
-                    dctx.apply_bindings(&[&vertex_buffer], Some(&index_buffer));
-                    dctx.apply_textures(&[(&texture, "uTexture")]);
-                    dctx.set_uniform_mat4(
-                        "view",
-                        (cgmath::ortho(0.0, size.width as f32, size.height as f32, 0.0, 0.0, 1.0)
-                            * cgmath::Matrix4::from_nonuniform_scale(
-                                size.width as f32,
-                                size.height as f32,
-                                1.0,
-                            ))
-                        .as_ref(),
-                        false,
-                    );
-                    dctx.draw_elements(
-                        rapax::DrawMode::Triangles,
-                        index_data.len() as _,
-                        rapax::DataType::UnsignedShort,
-                        0,
-                    );
-                });
+                size = window.window().inner_size();
+                renderer.draw(&mut ctx, size);
                 window.swap_buffers().unwrap();
             }
             Event::WindowEvent { ref event, .. } => match event {
@@ -324,37 +455,13 @@ fn main() {
                         physical_size.width as f32,
                         physical_size.height as f32,
                     );
-                    editor.shape_as_needed(&mut font_system);
-                    let text_color = Color::rgb(0, 0, 0);
-                    let mut pixels = vec![0u8; size.width as usize * size.height as usize * 4];
-                    editor.draw(
+                    renderer.rerender(
+                        &mut ctx,
+                        &mut editor,
+                        &composition,
                         &mut font_system,
                         &mut swash_cache,
-                        text_color,
-                        |x, y, w, h, color| {
-                            for row in 0..h as i32 {
-                                for col in 0..w as i32 {
-                                    draw_pixel(
-                                        &mut pixels,
-                                        size.width as _,
-                                        size.height as _,
-                                        x + col,
-                                        y + row,
-                                        color,
-                                    );
-                                }
-                            }
-                        },
-                    );
-
-                    texture.reallocate_2d_data(
-                        &mut ctx,
-                        Some(&pixels),
-                        rapax::texture::InternalTextureFormat::Rgba,
-                        rapax::texture::TextureFormat::Rgba,
-                        physical_size.width as _,
-                        physical_size.height as _,
-                        rapax::DataType::UnsignedByte,
+                        size,
                     );
                 }
                 WindowEvent::KeyboardInput {
@@ -386,47 +493,33 @@ fn main() {
                                 VirtualKeyCode::Delete => {
                                     editor.action(&mut font_system, Action::Delete)
                                 }
-                                VirtualKeyCode::C => {
-                                    if modifiers.ctrl() {
-                                        if let Some(content) = editor.copy_selection() {
-                                            clipboard.set_contents(content).unwrap();
-                                        }
+                                VirtualKeyCode::C if modifiers.ctrl() => {
+                                    if let Some(content) = editor.copy_selection() {
+                                        clipboard.set_contents(content).unwrap();
                                     }
                                 }
-                                VirtualKeyCode::X => {
-                                    if modifiers.ctrl() {
-                                        if let Some(content) = editor.copy_selection() {
-                                            clipboard.set_contents(content).unwrap();
-                                            editor.delete_selection();
-                                        }
+                                VirtualKeyCode::X if modifiers.ctrl() => {
+                                    if let Some(content) = editor.copy_selection() {
+                                        clipboard.set_contents(content).unwrap();
+                                        editor.delete_selection();
                                     }
                                 }
-                                VirtualKeyCode::V => {
-                                    if modifiers.ctrl() {
-                                        editor.insert_string(
-                                            &clipboard.get_contents().unwrap(),
-                                            None,
-                                        );
-                                    }
+                                VirtualKeyCode::V if modifiers.ctrl() => {
+                                    editor.insert_string(&clipboard.get_contents().unwrap(), None);
                                 }
-                                VirtualKeyCode::A => {
-                                    if modifiers.ctrl() {
-                                        editor.insert_string(
-                                            &clipboard.get_contents().unwrap(),
-                                            None,
-                                        );
-                                    }
+                                VirtualKeyCode::A if modifiers.ctrl() => {
+                                    editor.insert_string(&clipboard.get_contents().unwrap(), None);
                                 }
                                 _ => {}
                             }
 
-                            rerender(
+                            renderer.rerender(
+                                &mut ctx,
                                 &mut editor,
+                                &composition,
                                 &mut font_system,
-                                &mut texture,
-                                size,
                                 &mut swash_cache,
-                                &mut ctx,
+                                size,
                             );
                         }
                     }
@@ -446,27 +539,56 @@ fn main() {
                             },
                         },
                     );
-                    rerender(
+                    renderer.rerender(
+                        &mut ctx,
                         &mut editor,
+                        &composition,
                         &mut font_system,
-                        &mut texture,
-                        size,
                         &mut swash_cache,
-                        &mut ctx,
+                        size,
                     );
                 }
                 WindowEvent::ModifiersChanged(new_modifiers) => {
                     modifiers = *new_modifiers;
                 }
+                WindowEvent::Ime(ime) => {
+                    match ime {
+                        Ime::Preedit(text, cursor) => {
+                            composition.preedit(&mut editor, text, cursor.map(|(start, _)| start))
+                        }
+                        Ime::Commit(text) => composition.commit(&mut editor, text),
+                        Ime::Disabled => composition.clear(&mut editor),
+                        Ime::Enabled => {}
+                    }
+                    let caret = renderer.rerender(
+                        &mut ctx,
+                        &mut editor,
+                        &composition,
+                        &mut font_system,
+                        &mut swash_cache,
+                        size,
+                    );
+                    if let Some(caret) = caret {
+                        window
+                            .window()
+                            .set_ime_position(glutin::dpi::PhysicalPosition::new(
+                                caret.x,
+                                caret.y + caret.height,
+                            ));
+                    }
+                }
                 WindowEvent::ReceivedCharacter(c) => {
+                    if composition.is_composing() {
+                        return;
+                    }
                     editor.action(&mut font_system, Action::Insert(*c));
-                    rerender(
+                    renderer.rerender(
+                        &mut ctx,
                         &mut editor,
+                        &composition,
                         &mut font_system,
-                        &mut texture,
-                        size,
                         &mut swash_cache,
-                        &mut ctx,
+                        size,
                     );
                 }
                 WindowEvent::CursorMoved {
@@ -483,13 +605,13 @@ fn main() {
                                 y: position.y as i32,
                             },
                         );
-                        rerender(
+                        renderer.rerender(
+                            &mut ctx,
                             &mut editor,
+                            &composition,
                             &mut font_system,
-                            &mut texture,
-                            size,
                             &mut swash_cache,
-                            &mut ctx,
+                            size,
                         );
                     }
                 }
@@ -505,13 +627,13 @@ fn main() {
                                 y: mouse_y,
                             },
                         );
-                        rerender(
+                        renderer.rerender(
+                            &mut ctx,
                             &mut editor,
+                            &composition,
                             &mut font_system,
-                            &mut texture,
-                            size,
                             &mut swash_cache,
-                            &mut ctx,
+                            size,
                         );
                         clicking = true;
                     } else if *state == ElementState::Released {
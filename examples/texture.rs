@@ -3,6 +3,7 @@ use glutin::event_loop::ControlFlow;
 use image::io::Reader as ImageReader;
 use std::sync::Arc;
 
+#[allow(clippy::arc_with_non_send_sync)]
 fn main() {
     let (gl, window, event_loop) = unsafe {
         let event_loop = glutin::event_loop::EventLoop::new();
@@ -20,7 +21,7 @@ fn main() {
     };
     let mut ctx = rapax::ManagedContext::new(gl);
     let program = rapax::ShaderProgram::new(
-        &mut ctx,
+        &ctx,
         r#"#version 330 core
 
 		layout (location = 0) in vec2 position;
@@ -48,7 +49,8 @@ fn main() {
 			FragColor = texture(uTexture, texcoord_out);
 		}
 "#,
-    );
+    )
+    .unwrap();
 
     let pipeline = rapax::RenderPipeline::new(program)
         .with_vertex_attribute(rapax::VertexAttributeDescriptor {
@@ -59,6 +61,7 @@ fn main() {
             stride: 4 * rapax::DataType::Float.sizeof() as i32,
             offset: 0,
             divisor: 0,
+            location: None,
         })
         .with_vertex_attribute(rapax::VertexAttributeDescriptor {
             buffer_index: 0,
@@ -68,6 +71,7 @@ fn main() {
             stride: 4 * rapax::DataType::Float.sizeof() as i32,
             offset: 2 * rapax::DataType::Float.sizeof() as i32,
             divisor: 0,
+            location: None,
         });
 
     #[rustfmt::skip]
@@ -78,7 +82,7 @@ fn main() {
     	-0.5,  0.5, 0.0, 1.0    // top let 
 	];
     let vertex_buffer = rapax::BufferHandle::array_buffer(
-        &mut ctx,
+        &ctx,
         rapax::BufferUsage::Immutable,
         bytemuck::cast_slice(&vertex_data),
     )
@@ -88,47 +92,38 @@ fn main() {
         .unwrap()
         .decode()
         .unwrap();
-    let converted = img.into_rgb8();
-    let texture = rapax::texture::TextureHandle::new(
+    let texture = rapax::texture::Texture2D::from_image(
         &mut ctx,
-        rapax::texture::TextureWrap::ClampToBorder,
-        rapax::texture::TextureWrap::ClampToBorder,
-        rapax::texture::TextureFilteringMode::Linear,
-        rapax::texture::TextureFilteringMode::Linear,
+        &img,
+        rapax::texture::TextureOptions {
+            wrapping_mode_s: rapax::texture::TextureWrap::ClampToBorder,
+            wrapping_mode_t: rapax::texture::TextureWrap::ClampToBorder,
+            ..Default::default()
+        },
     )
     .expect("failed to create texture");
 
-    let texture = texture.allocate_2d_data(
-        &mut ctx,
-        Some(converted.as_raw()),
-        rapax::texture::InternalTextureFormat::Rgb,
-        rapax::texture::TextureFormat::Rgb,
-        converted.width() as _,
-        converted.height() as _,
-        rapax::DataType::UnsignedByte,
-    );
-
     // test subimage
     let data = [0u8; 100 * 100 * 3];
-    texture.write_subimage(
-        &mut ctx,
-        500,
-        100,
-        100,
-        100,
-        rapax::texture::TextureFormat::Rgb,
-        rapax::DataType::UnsignedByte,
-        &data,
-    );
+    texture
+        .write_subimage(
+            &mut ctx,
+            500,
+            100,
+            100,
+            100,
+            rapax::texture::TextureFormat::Rgb,
+            rapax::DataType::UnsignedByte,
+            &data,
+        )
+        .expect("failed to write subimage");
 
     texture.generate_mipmaps(&mut ctx);
 
     event_loop.run(move |event, _, control_flow| {
         *control_flow = ControlFlow::Poll;
         match event {
-            Event::LoopDestroyed => {
-                return;
-            }
+            Event::LoopDestroyed => {}
             Event::MainEventsCleared => {
                 window.window().request_redraw();
             }
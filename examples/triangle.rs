@@ -2,6 +2,7 @@ use glutin::event::{Event, WindowEvent};
 use glutin::event_loop::ControlFlow;
 use std::sync::Arc;
 
+#[allow(clippy::arc_with_non_send_sync)]
 fn main() {
     let (gl, window, event_loop) = unsafe {
         let event_loop = glutin::event_loop::EventLoop::new();
@@ -20,7 +21,7 @@ fn main() {
     };
     let mut ctx = rapax::ManagedContext::new(gl);
     let program = rapax::ShaderProgram::new(
-        &mut ctx,
+        &ctx,
         r#"#version 330 core
 
 		layout (location = 0) in vec2 position;
@@ -46,7 +47,8 @@ fn main() {
             FragColor = vec4(1.0, 1.0, 1.0, 1.0);
 		}
 "#,
-    );
+    )
+    .unwrap();
 
     let pipeline = rapax::RenderPipeline::new(program)
         .with_vertex_attribute(rapax::VertexAttributeDescriptor {
@@ -57,6 +59,7 @@ fn main() {
             stride: 4 * rapax::DataType::Float.sizeof() as i32,
             offset: 0,
             divisor: 0,
+            location: None,
         })
         .with_vertex_attribute(rapax::VertexAttributeDescriptor {
             buffer_index: 0,
@@ -66,6 +69,7 @@ fn main() {
             stride: 4 * rapax::DataType::Float.sizeof() as i32,
             offset: 2 * rapax::DataType::Float.sizeof() as i32,
             divisor: 0,
+            location: None,
         });
 
     #[rustfmt::skip]
@@ -83,7 +87,7 @@ fn main() {
     ];
 
     let vertex_buffer = rapax::BufferHandle::array_buffer(
-        &mut ctx,
+        &ctx,
         rapax::BufferUsage::Immutable,
         bytemuck::cast_slice(&vertex_data),
     )
@@ -94,14 +98,13 @@ fn main() {
         rapax::BufferUsage::Immutable,
         bytemuck::cast_slice(&index_data),
     )
-    .unwrap();
+    .unwrap()
+    .with_index_type(rapax::DataType::UnsignedShort);
 
     event_loop.run(move |event, _, control_flow| {
         *control_flow = ControlFlow::Poll;
         match event {
-            Event::LoopDestroyed => {
-                return;
-            }
+            Event::LoopDestroyed => {}
             Event::MainEventsCleared => {
                 window.window().request_redraw();
             }
@@ -111,12 +114,7 @@ fn main() {
                 ctx.with_pipeline(&pipeline, |dctx| {
                     dctx.apply_bindings(&[&vertex_buffer], Some(&index_buffer));
                     //dctx.apply_textures(&[(&texture, "uTexture")]);
-                    dctx.draw_elements(
-                        rapax::DrawMode::Triangles,
-                        index_data.len() as _,
-                        rapax::DataType::UnsignedShort,
-                        0,
-                    );
+                    dctx.draw_indexed(rapax::DrawMode::Triangles, 0..index_data.len() as u32, 0, 1);
                 });
                 window.swap_buffers().unwrap();
             }
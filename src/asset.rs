@@ -0,0 +1,922 @@
+//! Loading meshes and materials from OBJ and glTF 2.0 files.
+//!
+//! Loaders produce CPU-side [`Model`]s, which are uploaded into interleaved vertex buffers with [`MeshData::upload`].
+//! Each mesh comes with a [`VertexLayout`] to build its pipeline from: the position is at location 0, the normal at
+//! location 1 and the texture coordinates at location 2, if the mesh has them. Static meshes sharing a pipeline can be
+//! merged with [`MeshBundle::bake`] to draw them with fewer calls.
+//!
+//! Texture coordinates follow glTF's convention, with the origin at the top left of the image, so material textures
+//! are uploaded without flipping them.
+
+use super::*;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+/// Where the pixels of a material texture come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageSource {
+    /// An image file, relative paths are resolved against the model's directory.
+    Path(PathBuf),
+    /// An encoded image embedded in the model, e.g. a PNG in a `.glb` buffer.
+    Embedded(Vec<u8>),
+}
+
+/// A material of a [`Model`], reduced to what a simple forward renderer needs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    pub name: String,
+    /// The linear base color, multiplied with the base color texture.
+    pub base_color: [f32; 4],
+    pub base_color_texture: Option<ImageSource>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            base_color: [1.0; 4],
+            base_color_texture: None,
+        }
+    }
+}
+
+/// Meshes & materials loaded from a file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Model {
+    pub meshes: Vec<MeshData>,
+    pub materials: Vec<Material>,
+}
+
+impl Model {
+    /// Load an OBJ file, or a glTF 2.0 file in the `.gltf` or `.glb` format, picking the loader from the extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("obj") => Self::load_obj(path),
+            Some("gltf" | "glb") => Self::load_gltf(path),
+            _ => Err(asset_error(format!(
+                "unknown model format: {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Load an OBJ file and the materials of the MTL files it references.
+    ///
+    /// Polygons are triangulated as fans, and every `o`, `g` or `usemtl` statement starts a new mesh.
+    pub fn load_obj(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        span!("load_obj");
+        let source = read_to_string(path)?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        Self::parse_obj(&source, |name| read_to_string(&dir.join(name)))
+    }
+
+    /// Parse the contents of an OBJ file, reading MTL files referenced by `mtllib` with `read_mtl`.
+    pub fn parse_obj(
+        source: &str,
+        mut read_mtl: impl FnMut(&str) -> Result<String, Error>,
+    ) -> Result<Self, Error> {
+        let mut positions: Vec<[f32; 3]> = vec![];
+        let mut normals: Vec<[f32; 3]> = vec![];
+        let mut tex_coords: Vec<[f32; 2]> = vec![];
+        let mut materials: Vec<Material> = vec![];
+        let mut builder = ObjMeshBuilder::default();
+        let mut meshes = vec![];
+
+        for (line_number, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let mut words = line.split_whitespace();
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+            let error = |what: &str| asset_error(format!("OBJ line {}: {}", line_number + 1, what));
+
+            match keyword {
+                "v" => positions.push(parse_floats(words).ok_or_else(|| error("bad vertex"))?),
+                "vn" => normals.push(parse_floats(words).ok_or_else(|| error("bad normal"))?),
+                "vt" => {
+                    let [u, v] =
+                        parse_floats(words).ok_or_else(|| error("bad texture coordinate"))?;
+                    tex_coords.push([u, 1.0 - v]);
+                }
+                "f" => {
+                    let corners = words
+                        .map(|corner| {
+                            parse_obj_corner(
+                                corner,
+                                [positions.len(), tex_coords.len(), normals.len()],
+                            )
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| error("bad face"))?;
+                    if corners.len() < 3 {
+                        return Err(error("face with less than 3 vertices"));
+                    }
+                    let corners: Vec<u32> = corners
+                        .into_iter()
+                        .map(|corner| builder.vertex(corner, &positions, &tex_coords, &normals))
+                        .collect();
+                    for i in 1..corners.len() - 1 {
+                        builder
+                            .mesh
+                            .indices
+                            .extend([corners[0], corners[i], corners[i + 1]]);
+                    }
+                }
+                "o" | "g" => {
+                    builder.finish(&mut meshes);
+                    builder.mesh.name = words.collect::<Vec<_>>().join(" ");
+                }
+                "usemtl" => {
+                    let name = words.collect::<Vec<_>>().join(" ");
+                    builder.finish(&mut meshes);
+                    builder.mesh.material = materials.iter().position(|m| m.name == name);
+                }
+                "mtllib" => {
+                    for name in words {
+                        materials.extend(parse_mtl(&read_mtl(name)?));
+                    }
+                }
+                _ => {}
+            }
+        }
+        builder.finish(&mut meshes);
+
+        Ok(Self { meshes, materials })
+    }
+
+    /// Load a glTF 2.0 file, either a `.gltf` JSON file with its external or embedded buffers, or a binary `.glb`.
+    ///
+    /// Every primitive of every mesh becomes a [`MeshData`]. Node transforms, skins & animations are ignored.
+    pub fn load_gltf(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        span!("load_gltf");
+        let bytes = std::fs::read(path)
+            .map_err(|err| asset_error(format!("{}: {}", path.display(), err)))?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        Self::parse_gltf(&bytes, |uri| {
+            std::fs::read(dir.join(uri)).map_err(|err| asset_error(format!("{}: {}", uri, err)))
+        })
+    }
+
+    /// Parse a glTF 2.0 JSON document or GLB container, reading external buffers with `read_uri`.
+    ///
+    /// Image URIs are resolved by the caller, see [`ImageSource::Path`].
+    pub fn parse_gltf(
+        bytes: &[u8],
+        mut read_uri: impl FnMut(&str) -> Result<Vec<u8>, Error>,
+    ) -> Result<Self, Error> {
+        let (json, glb_buffer) = split_glb(bytes)?;
+        let document: Value = serde_json::from_slice(json)
+            .map_err(|err| asset_error(format!("invalid glTF JSON: {}", err)))?;
+        let array = |name: &str| document[name].as_array().cloned().unwrap_or_default();
+
+        let buffers = array("buffers")
+            .iter()
+            .enumerate()
+            .map(|(index, buffer)| match buffer["uri"].as_str() {
+                Some(uri) if uri.starts_with("data:") => decode_data_uri(uri),
+                Some(uri) => read_uri(&decode_uri(uri)),
+                None if index == 0 => glb_buffer
+                    .map(<[u8]>::to_vec)
+                    .ok_or_else(|| asset_error("glTF buffer without a URI")),
+                None => Err(asset_error("glTF buffer without a URI")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let gltf = Gltf {
+            buffers,
+            buffer_views: array("bufferViews"),
+            accessors: array("accessors"),
+        };
+
+        let images = array("images");
+        let textures = array("textures");
+        let materials = array("materials")
+            .iter()
+            .map(|material| {
+                let pbr = &material["pbrMetallicRoughness"];
+                let base_color_texture = match pbr["baseColorTexture"]["index"].as_u64() {
+                    Some(texture) => {
+                        let image = textures
+                            .get(texture as usize)
+                            .and_then(|texture| texture["source"].as_u64())
+                            .and_then(|image| images.get(image as usize))
+                            .ok_or_else(|| asset_error("glTF texture without an image"))?;
+                        Some(gltf.image_source(image)?)
+                    }
+                    None => None,
+                };
+                let mut base_color = [1.0; 4];
+                if let Some(factor) = pbr["baseColorFactor"].as_array() {
+                    for (channel, value) in base_color.iter_mut().zip(factor) {
+                        *channel = value.as_f64().unwrap_or(1.0) as f32;
+                    }
+                }
+
+                Ok(Material {
+                    name: material["name"].as_str().unwrap_or_default().to_owned(),
+                    base_color,
+                    base_color_texture,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut meshes = vec![];
+        for mesh in array("meshes") {
+            let name = mesh["name"].as_str().unwrap_or_default();
+            for primitive in mesh["primitives"].as_array().into_iter().flatten() {
+                if primitive["mode"].as_u64().unwrap_or(4) != 4 {
+                    return Err(asset_error(
+                        "only triangle list glTF primitives are supported",
+                    ));
+                }
+                let attributes = &primitive["attributes"];
+                let attribute = |name: &str, components: usize| match attributes[name].as_u64() {
+                    Some(accessor) => gltf.read_accessor(accessor as usize, components),
+                    None => Ok(vec![]),
+                };
+
+                let positions = attribute("POSITION", 3)?;
+                let vertex_count = positions.len() / 3;
+                let normals = attribute("NORMAL", 3)?;
+                let tex_coords = attribute("TEXCOORD_0", 2)?;
+                if !normals.is_empty() && normals.len() / 3 != vertex_count {
+                    return Err(asset_error(
+                        "glTF NORMAL count differs from the POSITION count",
+                    ));
+                }
+                if !tex_coords.is_empty() && tex_coords.len() / 2 != vertex_count {
+                    return Err(asset_error(
+                        "glTF TEXCOORD_0 count differs from the POSITION count",
+                    ));
+                }
+                let indices = match primitive["indices"].as_u64() {
+                    Some(accessor) => gltf.read_indices(accessor as usize)?,
+                    None => (0..vertex_count as u32).collect(),
+                };
+                if indices.len() % 3 != 0 {
+                    return Err(asset_error(
+                        "glTF triangle list index count is not a multiple of 3",
+                    ));
+                }
+                if indices.iter().any(|&index| index as usize >= vertex_count) {
+                    return Err(asset_error("glTF index out of range"));
+                }
+                let material = primitive["material"].as_u64().map(|m| m as usize);
+                if material.is_some_and(|material| material >= materials.len()) {
+                    return Err(asset_error("glTF material out of range"));
+                }
+                meshes.push(MeshData {
+                    name: name.to_owned(),
+                    normals,
+                    tex_coords,
+                    positions,
+                    indices,
+                    material,
+                });
+            }
+        }
+
+        Ok(Self { meshes, materials })
+    }
+
+    /// Upload every mesh of the model.
+    pub fn upload(&self, ctx: &mut ManagedContext) -> Result<Vec<Mesh>, Error> {
+        self.meshes.iter().map(|mesh| mesh.upload(ctx)).collect()
+    }
+}
+
+#[cfg(feature = "image")]
+impl Material {
+    /// Decode & upload the base color texture, resolving relative paths against `dir`, usually the model's directory.
+    ///
+    /// Supported image formats depend on the features enabled on the `image` crate.
+    pub fn load_base_color_texture(
+        &self,
+        ctx: &mut ManagedContext,
+        dir: impl AsRef<Path>,
+        options: TextureOptions,
+    ) -> Result<Option<Texture2D>, Error> {
+        let image = match &self.base_color_texture {
+            Some(ImageSource::Path(path)) => image::open(dir.as_ref().join(path)),
+            Some(ImageSource::Embedded(data)) => image::load_from_memory(data),
+            None => return Ok(None),
+        }
+        .map_err(|err| asset_error(format!("failed to decode texture: {}", err)))?;
+        Texture2D::from_image(ctx, &image, options).map(Some)
+    }
+}
+
+fn asset_error(message: impl Into<String>) -> Error {
+    Error::Asset(message.into())
+}
+
+fn read_to_string(path: &Path) -> Result<String, Error> {
+    std::fs::read_to_string(path).map_err(|err| asset_error(format!("{}: {}", path.display(), err)))
+}
+
+fn parse_floats<'a, const N: usize>(words: impl Iterator<Item = &'a str>) -> Option<[f32; N]> {
+    let mut values = [0.0; N];
+    let mut words = words;
+    for value in values.iter_mut() {
+        *value = words.next()?.parse().ok()?;
+    }
+    Some(values)
+}
+
+/// The 0-based position, texture coordinate & normal indices of a face corner such as `1/2/3`, `1//3` or `-1`.
+fn parse_obj_corner(corner: &str, lens: [usize; 3]) -> Option<[Option<usize>; 3]> {
+    let mut indices = [None; 3];
+    for ((index, part), len) in indices.iter_mut().zip(corner.split('/')).zip(lens) {
+        if part.is_empty() {
+            continue;
+        }
+        let value: i64 = part.parse().ok()?;
+        let resolved = if value < 0 {
+            len as i64 + value
+        } else {
+            value - 1
+        };
+        if resolved < 0 || resolved >= len as i64 {
+            return None;
+        }
+        *index = Some(resolved as usize);
+    }
+    indices[0].map(|_| indices)
+}
+
+/// Collects OBJ faces into a mesh, de-duplicating vertices that share all indices.
+#[derive(Default)]
+struct ObjMeshBuilder {
+    mesh: MeshData,
+    vertices: HashMap<[Option<usize>; 3], u32>,
+    has_tex_coords: bool,
+    has_normals: bool,
+}
+
+impl ObjMeshBuilder {
+    fn vertex(
+        &mut self,
+        corner: [Option<usize>; 3],
+        positions: &[[f32; 3]],
+        tex_coords: &[[f32; 2]],
+        normals: &[[f32; 3]],
+    ) -> u32 {
+        if let Some(index) = self.vertices.get(&corner) {
+            return *index;
+        }
+
+        let index = self.mesh.vertex_count() as u32;
+        let [position, tex_coord, normal] = corner;
+        self.mesh
+            .positions
+            .extend(positions[position.expect("corner without position")]);
+        self.mesh
+            .tex_coords
+            .extend(tex_coord.map_or([0.0; 2], |i| tex_coords[i]));
+        self.mesh
+            .normals
+            .extend(normal.map_or([0.0; 3], |i| normals[i]));
+        self.has_tex_coords |= tex_coord.is_some();
+        self.has_normals |= normal.is_some();
+        self.vertices.insert(corner, index);
+        index
+    }
+
+    /// Push the mesh built so far, if it has any faces, and start a new one with the same name & material.
+    fn finish(&mut self, meshes: &mut Vec<MeshData>) {
+        let mut mesh = std::mem::take(&mut self.mesh);
+        self.mesh.name = mesh.name.clone();
+        self.mesh.material = mesh.material;
+        if !mesh.indices.is_empty() {
+            if !self.has_tex_coords {
+                mesh.tex_coords.clear();
+            }
+            if !self.has_normals {
+                mesh.normals.clear();
+            }
+            meshes.push(mesh);
+        }
+        self.vertices.clear();
+        self.has_tex_coords = false;
+        self.has_normals = false;
+    }
+}
+
+fn parse_mtl(source: &str) -> Vec<Material> {
+    let mut materials: Vec<Material> = vec![];
+    for line in source.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let Some((keyword, rest)) = line.split_once(char::is_whitespace) else {
+            continue;
+        };
+        let rest = rest.trim();
+        if keyword == "newmtl" {
+            materials.push(Material {
+                name: rest.to_owned(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(material) = materials.last_mut() else {
+            continue;
+        };
+        match keyword {
+            "Kd" => {
+                if let Some([r, g, b]) = parse_floats(rest.split_whitespace()) {
+                    material.base_color[..3].copy_from_slice(&[r, g, b]);
+                }
+            }
+            "d" => {
+                if let Some([alpha]) = parse_floats(rest.split_whitespace()) {
+                    material.base_color[3] = alpha;
+                }
+            }
+            "Tr" => {
+                if let Some([transparency]) = parse_floats::<1>(rest.split_whitespace()) {
+                    material.base_color[3] = 1.0 - transparency;
+                }
+            }
+            // options such as `-bm 1` precede the file name, which is the last word
+            "map_Kd" => {
+                if let Some(file) = rest.split_whitespace().last() {
+                    material.base_color_texture = Some(ImageSource::Path(file.into()));
+                }
+            }
+            _ => {}
+        }
+    }
+    materials
+}
+
+/// The most elements a glTF accessor without a buffer view may have. Its zeros aren't backed by any data, so a
+/// malformed count would otherwise allocate without bound.
+const MAX_ZERO_ACCESSOR_COUNT: usize = 1 << 24;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_CHUNK_JSON: u32 = 0x4E4F_534A;
+const GLB_CHUNK_BIN: u32 = 0x004E_4942;
+
+/// Split a GLB container into its JSON & binary chunks. Plain JSON documents are returned as they are.
+fn split_glb(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>), Error> {
+    let word = |offset: usize| {
+        bytes
+            .get(offset..offset.checked_add(4)?)
+            .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+    };
+    if word(0) != Some(GLB_MAGIC) {
+        return Ok((bytes, None));
+    }
+
+    let mut json = None;
+    let mut bin = None;
+    let mut offset = 12;
+    while let (Some(length), Some(ty)) = (word(offset), word(offset + 4)) {
+        let start = offset + 8;
+        let end = start
+            .checked_add(length as usize)
+            .ok_or_else(|| asset_error("truncated GLB chunk"))?;
+        let chunk = bytes
+            .get(start..end)
+            .ok_or_else(|| asset_error("truncated GLB chunk"))?;
+        match ty {
+            GLB_CHUNK_JSON => json = Some(chunk),
+            GLB_CHUNK_BIN => bin = Some(chunk),
+            _ => {}
+        }
+        offset = end;
+    }
+    Ok((
+        json.ok_or_else(|| asset_error("GLB without a JSON chunk"))?,
+        bin,
+    ))
+}
+
+/// Decode percent-escapes in a relative URI, e.g. `%20` for spaces.
+fn decode_uri(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| uri.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Decode a base64 `data:` URI.
+fn decode_data_uri(uri: &str) -> Result<Vec<u8>, Error> {
+    let error = || asset_error("unsupported data URI");
+    let (header, data) = uri.split_once(',').ok_or_else(error)?;
+    if !header.ends_with(";base64") {
+        return Err(error());
+    }
+
+    let mut decoded = Vec::with_capacity(data.len() / 4 * 3);
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+    for byte in data.bytes().filter(|byte| *byte != b'=') {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return Err(error()),
+        };
+        bits = (bits << 6) | value as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            decoded.push((bits >> bit_count) as u8);
+        }
+    }
+    Ok(decoded)
+}
+
+/// The parts of a glTF document needed to read accessors.
+struct Gltf {
+    buffers: Vec<Vec<u8>>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl Gltf {
+    fn buffer_view(&self, index: usize) -> Result<(&[u8], usize), Error> {
+        let view = self
+            .buffer_views
+            .get(index)
+            .ok_or_else(|| asset_error("glTF buffer view out of range"))?;
+        let buffer = self
+            .buffers
+            .get(view["buffer"].as_u64().unwrap_or(0) as usize)
+            .ok_or_else(|| asset_error("glTF buffer out of range"))?;
+        let offset = view["byteOffset"].as_u64().unwrap_or(0) as usize;
+        let length = view["byteLength"].as_u64().unwrap_or(0) as usize;
+        let data = offset
+            .checked_add(length)
+            .and_then(|end| buffer.get(offset..end))
+            .ok_or_else(|| asset_error("glTF buffer view exceeds its buffer"))?;
+        Ok((data, view["byteStride"].as_u64().unwrap_or(0) as usize))
+    }
+
+    fn image_source(&self, image: &Value) -> Result<ImageSource, Error> {
+        match (image["uri"].as_str(), image["bufferView"].as_u64()) {
+            (Some(uri), _) if uri.starts_with("data:") => {
+                decode_data_uri(uri).map(ImageSource::Embedded)
+            }
+            (Some(uri), _) => Ok(ImageSource::Path(decode_uri(uri).into())),
+            (None, Some(view)) => Ok(ImageSource::Embedded(
+                self.buffer_view(view as usize)?.0.to_vec(),
+            )),
+            (None, None) => Err(asset_error("glTF image without data")),
+        }
+    }
+
+    /// Read an accessor with `components` components per element, converting them to floats.
+    fn read_accessor(&self, index: usize, components: usize) -> Result<Vec<f32>, Error> {
+        let mut values = vec![];
+        self.read_components(index, components, |bytes, ty, normalized| {
+            values.push(read_component(bytes, ty, normalized));
+            Ok(())
+        })?;
+        Ok(values)
+    }
+
+    /// Read an index accessor without going through floats, which can't represent every 32-bit index.
+    fn read_indices(&self, index: usize) -> Result<Vec<u32>, Error> {
+        let mut indices = vec![];
+        self.read_components(index, 1, |bytes, ty, _| {
+            indices.push(match ty {
+                DataType::UnsignedByte => bytes[0] as u32,
+                DataType::UnsignedShort => u16::from_le_bytes([bytes[0], bytes[1]]) as u32,
+                DataType::UnsignedInt => u32::from_le_bytes(bytes.try_into().unwrap()),
+                _ => return Err(asset_error("glTF indices must be unsigned integers")),
+            });
+            Ok(())
+        })?;
+        Ok(indices)
+    }
+
+    /// Call `f` with the bytes, type & normalization of each component of an accessor, in order.
+    fn read_components(
+        &self,
+        index: usize,
+        components: usize,
+        mut f: impl FnMut(&[u8], DataType, bool) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let accessor = self
+            .accessors
+            .get(index)
+            .ok_or_else(|| asset_error("glTF accessor out of range"))?;
+        if !accessor["sparse"].is_null() {
+            return Err(asset_error("sparse glTF accessors are not supported"));
+        }
+        let expected = match components {
+            1 => "SCALAR",
+            2 => "VEC2",
+            3 => "VEC3",
+            _ => "VEC4",
+        };
+        if accessor["type"].as_str() != Some(expected) {
+            return Err(asset_error(format!("glTF accessor is not a {}", expected)));
+        }
+
+        let ty = match accessor["componentType"].as_u64() {
+            Some(5120) => DataType::SignedByte,
+            Some(5121) => DataType::UnsignedByte,
+            Some(5122) => DataType::SignedShort,
+            Some(5123) => DataType::UnsignedShort,
+            Some(5125) => DataType::UnsignedInt,
+            Some(5126) => DataType::Float,
+            _ => return Err(asset_error("unknown glTF component type")),
+        };
+        let normalized = accessor["normalized"].as_bool().unwrap_or(false);
+        let count = accessor["count"].as_u64().unwrap_or(0) as usize;
+        let out_of_range = || asset_error("glTF accessor exceeds its buffer view");
+        let Some(view) = accessor["bufferView"].as_u64() else {
+            // accessors without a buffer view are all zeros
+            if count > MAX_ZERO_ACCESSOR_COUNT {
+                return Err(asset_error(
+                    "glTF accessor without a buffer view is too large",
+                ));
+            }
+            let zero = vec![0; ty.sizeof()];
+            return (0..count * components).try_for_each(|_| f(&zero, ty, normalized));
+        };
+
+        let (data, stride) = self.buffer_view(view as usize)?;
+        let offset = accessor["byteOffset"].as_u64().unwrap_or(0) as usize;
+        let element_size = ty.sizeof() * components;
+        let stride = if stride == 0 { element_size } else { stride };
+        for element in 0..count {
+            let bytes = element
+                .checked_mul(stride)
+                .and_then(|start| start.checked_add(offset))
+                .and_then(|start| data.get(start..start.checked_add(element_size)?))
+                .ok_or_else(out_of_range)?;
+            for component in bytes.chunks_exact(ty.sizeof()) {
+                f(component, ty, normalized)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decode a little-endian glTF component, mapping normalized integers to `[0, 1]` or `[-1, 1]`.
+fn read_component(bytes: &[u8], ty: DataType, normalized: bool) -> f32 {
+    let (value, max) = match ty {
+        DataType::SignedByte => (bytes[0] as i8 as f32, i8::MAX as f32),
+        DataType::UnsignedByte => (bytes[0] as f32, u8::MAX as f32),
+        DataType::SignedShort => (
+            i16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+            i16::MAX as f32,
+        ),
+        DataType::UnsignedShort => (
+            u16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+            u16::MAX as f32,
+        ),
+        DataType::UnsignedInt => (
+            u32::from_le_bytes(bytes.try_into().unwrap()) as f32,
+            u32::MAX as f32,
+        ),
+        _ => return f32::from_le_bytes(bytes.try_into().unwrap()),
+    };
+    if normalized {
+        (value / max).max(-1.0)
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    /// Three positions followed by three `u16` indices, padded to 4 bytes.
+    fn triangle_buffer() -> Vec<u8> {
+        let positions: [f32; 9] = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        let mut buffer: Vec<u8> = positions.iter().flat_map(|v| v.to_le_bytes()).collect();
+        buffer.extend([0u16, 1, 2].iter().flat_map(|i| i.to_le_bytes()));
+        buffer.extend([0; 2]);
+        buffer
+    }
+
+    fn base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut encoded = String::new();
+        for chunk in bytes.chunks(3) {
+            let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &byte)| {
+                bits | (byte as u32) << (16 - 8 * i)
+            });
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char);
+                } else {
+                    encoded.push('=');
+                }
+            }
+        }
+        encoded
+    }
+
+    /// A glTF document with one red triangle, its buffer embedded as a data URI.
+    fn triangle_gltf() -> Value {
+        json!({
+            "asset": { "version": "2.0" },
+            "buffers": [{
+                "byteLength": 44,
+                "uri": format!("data:application/octet-stream;base64,{}", base64(&triangle_buffer())),
+            }],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+                { "buffer": 0, "byteOffset": 36, "byteLength": 6 },
+            ],
+            "accessors": [
+                { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" },
+                { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" },
+            ],
+            "materials": [{ "name": "red", "pbrMetallicRoughness": { "baseColorFactor": [1, 0, 0, 1] } }],
+            "meshes": [{
+                "name": "triangle",
+                "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1, "material": 0 }],
+            }],
+        })
+    }
+
+    fn parse(document: &Value) -> Result<Model, Error> {
+        Model::parse_gltf(&serde_json::to_vec(document).unwrap(), |uri| {
+            Err(asset_error(format!("unexpected read of {}", uri)))
+        })
+    }
+
+    fn parse_error(document: &Value) -> String {
+        match parse(document) {
+            Err(Error::Asset(message)) => message,
+            other => panic!("expected an asset error, got {:?}", other),
+        }
+    }
+
+    fn assert_triangle(model: &Model) {
+        assert_eq!(model.meshes.len(), 1);
+        let mesh = &model.meshes[0];
+        assert_eq!(mesh.name, "triangle");
+        assert_eq!(
+            mesh.positions,
+            [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+        );
+        assert_eq!(mesh.indices, [0, 1, 2]);
+        assert_eq!(mesh.material, Some(0));
+        assert_eq!(model.materials[0].name, "red");
+        assert_eq!(model.materials[0].base_color, [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn parses_an_obj_triangle() {
+        let source =
+            "mtllib a.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nusemtl red\nf 1/1 2/1 -1/1\n";
+        let model = Model::parse_obj(source, |name| {
+            assert_eq!(name, "a.mtl");
+            Ok("newmtl red\nKd 1 0 0\n".to_owned())
+        })
+        .unwrap();
+        assert_eq!(model.meshes.len(), 1);
+        assert_eq!(
+            model.meshes[0].positions,
+            [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+        );
+        assert_eq!(model.meshes[0].tex_coords, [0.0, 1.0, 0.0, 1.0, 0.0, 1.0]);
+        assert!(model.meshes[0].normals.is_empty());
+        assert_eq!(model.meshes[0].indices, [0, 1, 2]);
+        assert_eq!(model.meshes[0].material, Some(0));
+        assert_eq!(model.materials[0].base_color, [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn rejects_obj_indices_out_of_range() {
+        let result = Model::parse_obj("v 0 0 0\nv 1 0 0\nf 1 2 3\n", |_| unreachable!());
+        assert!(matches!(result, Err(Error::Asset(message)) if message.contains("bad face")));
+    }
+
+    #[test]
+    fn parses_a_gltf_triangle_from_a_data_uri() {
+        assert_triangle(&parse(&triangle_gltf()).unwrap());
+    }
+
+    #[test]
+    fn parses_a_glb_container() {
+        let mut document = triangle_gltf();
+        document["buffers"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("uri");
+        let mut json = serde_json::to_vec(&document).unwrap();
+        json.resize(json.len().next_multiple_of(4), b' ');
+        let bin = triangle_buffer();
+
+        let mut glb = vec![];
+        glb.extend(GLB_MAGIC.to_le_bytes());
+        glb.extend(2u32.to_le_bytes());
+        glb.extend((12 + 8 + json.len() as u32 + 8 + bin.len() as u32).to_le_bytes());
+        glb.extend((json.len() as u32).to_le_bytes());
+        glb.extend(GLB_CHUNK_JSON.to_le_bytes());
+        glb.extend(&json);
+        glb.extend((bin.len() as u32).to_le_bytes());
+        glb.extend(GLB_CHUNK_BIN.to_le_bytes());
+        glb.extend(&bin);
+
+        let model = Model::parse_gltf(&glb, |_| unreachable!()).unwrap();
+        assert_triangle(&model);
+
+        let truncated = Model::parse_gltf(&glb[..glb.len() - 4], |_| unreachable!());
+        assert!(
+            matches!(truncated, Err(Error::Asset(message)) if message == "truncated GLB chunk")
+        );
+    }
+
+    #[test]
+    fn rejects_indices_out_of_range() {
+        let mut document = triangle_gltf();
+        document["accessors"][0]["count"] = json!(2);
+        assert_eq!(parse_error(&document), "glTF index out of range");
+    }
+
+    #[test]
+    fn rejects_truncated_buffer_views() {
+        let mut document = triangle_gltf();
+        document["bufferViews"][1]["byteLength"] = json!(16);
+        assert_eq!(
+            parse_error(&document),
+            "glTF buffer view exceeds its buffer"
+        );
+
+        let mut document = triangle_gltf();
+        document["accessors"][0]["count"] = json!(4);
+        assert_eq!(
+            parse_error(&document),
+            "glTF accessor exceeds its buffer view"
+        );
+    }
+
+    #[test]
+    fn rejects_sparse_accessors() {
+        let mut document = triangle_gltf();
+        document["accessors"][0]["sparse"] = json!({ "count": 1 });
+        assert_eq!(
+            parse_error(&document),
+            "sparse glTF accessors are not supported"
+        );
+    }
+
+    #[test]
+    fn rejects_huge_accessors_without_a_buffer_view() {
+        let mut document = triangle_gltf();
+        let accessor = document["accessors"][0].as_object_mut().unwrap();
+        accessor.remove("bufferView");
+        accessor.insert("count".to_owned(), json!(1_000_000_000_000u64));
+        assert_eq!(
+            parse_error(&document),
+            "glTF accessor without a buffer view is too large"
+        );
+    }
+
+    #[test]
+    fn rejects_incomplete_triangles() {
+        let mut document = triangle_gltf();
+        document["accessors"][1]["count"] = json!(2);
+        assert_eq!(
+            parse_error(&document),
+            "glTF triangle list index count is not a multiple of 3"
+        );
+    }
+
+    #[test]
+    fn rejects_materials_out_of_range() {
+        let mut document = triangle_gltf();
+        document["meshes"][0]["primitives"][0]["material"] = json!(1);
+        assert_eq!(parse_error(&document), "glTF material out of range");
+    }
+}
@@ -0,0 +1,89 @@
+use super::*;
+
+/// Textures and uniform buffers applied together with [`Drawable::apply_binding_set`].
+///
+/// Textures go to the units assigned to their sampler uniforms (see [`ShaderProgram::samplers`]) and uniform buffers
+/// to binding points in the order they were added. Pipelines with the same samplers and blocks thus share bindings,
+/// and switching between them only re-binds what changed.
+#[derive(Default)]
+pub struct BindingSet<'a> {
+    textures: Vec<(&'a str, &'a dyn BindableTexture)>,
+    uniform_buffers: Vec<(&'a str, &'a BufferHandle)>,
+}
+
+impl std::fmt::Debug for BindingSet<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BindingSet")
+            .field(
+                "textures",
+                &self
+                    .textures
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .field("uniform_buffers", &self.uniform_buffers)
+            .finish()
+    }
+}
+
+impl<'a> BindingSet<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bind `texture` to the unit of the sampler uniform `name`.
+    pub fn with_texture(self, name: &'a str, texture: &'a dyn BindableTexture) -> Self {
+        let mut textures = self.textures;
+        textures.push((name, texture));
+
+        Self { textures, ..self }
+    }
+
+    /// Bind `buffer` to the uniform block `name`, at the next binding point.
+    pub fn with_uniform_buffer(self, name: &'a str, buffer: &'a BufferHandle) -> Self {
+        let mut uniform_buffers = self.uniform_buffers;
+        uniform_buffers.push((name, buffer));
+
+        Self {
+            uniform_buffers,
+            ..self
+        }
+    }
+}
+
+impl Drawable<'_> {
+    /// Bind the textures and uniform buffers of `set`, skipping those already bound from a previous draw.
+    ///
+    /// ## Panics
+    /// The program must have a sampler uniform for every texture and a uniform block for every buffer, and buffers
+    /// must be uniform buffers.
+    pub fn apply_binding_set(&self, set: &BindingSet) {
+        span!(
+            "apply_binding_set",
+            textures = set.textures.len(),
+            uniform_buffers = set.uniform_buffers.len()
+        );
+        for (name, texture) in &set.textures {
+            let unit = self
+                .current_program
+                .sampler_unit(name)
+                .expect("No such sampler uniform!");
+            self.bind_texture_unit(unit, *texture);
+        }
+
+        for (binding, (name, buffer)) in set.uniform_buffers.iter().enumerate() {
+            assert_eq!(
+                buffer.ty(),
+                BufferType::UniformBuffer,
+                "Attempted to bind buffer to invalid binding point"
+            );
+            self.current_program
+                .bind_uniform_block(name, binding as u32);
+            unsafe {
+                self.ctx
+                    .bind_uniform_buffer(binding as u32, buffer.buffer, 0, 0);
+            }
+        }
+    }
+}
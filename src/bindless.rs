@@ -0,0 +1,141 @@
+use super::*;
+
+use std::ffi::c_void;
+
+/// A 64-bit bindless texture handle, as returned by `glGetTextureHandleARB`.
+///
+/// Handles can be passed to shaders through uniforms or written into uniform/storage buffers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct TextureHandleU64(pub u64);
+
+unsafe impl bytemuck::Zeroable for TextureHandleU64 {}
+unsafe impl bytemuck::Pod for TextureHandleU64 {}
+
+type GetTextureHandle = unsafe extern "system" fn(texture: u32) -> u64;
+type MakeTextureHandleResident = unsafe extern "system" fn(handle: u64);
+type UniformHandle = unsafe extern "system" fn(location: i32, value: u64);
+
+/// Entry points of the `ARB_bindless_texture` extension, which glow does not load.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BindlessTextures {
+    get_texture_handle: GetTextureHandle,
+    make_texture_handle_resident: MakeTextureHandleResident,
+    make_texture_handle_non_resident: MakeTextureHandleResident,
+    uniform_handle: UniformHandle,
+}
+
+impl BindlessTextures {
+    /// Resolve the extension entry points using the platform's `GetProcAddress` function.
+    pub(crate) unsafe fn load(
+        mut loader: impl FnMut(&str) -> *const c_void,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            get_texture_handle: load_fn(&mut loader, "glGetTextureHandleARB")?,
+            make_texture_handle_resident: load_fn(&mut loader, "glMakeTextureHandleResidentARB")?,
+            make_texture_handle_non_resident: load_fn(
+                &mut loader,
+                "glMakeTextureHandleNonResidentARB",
+            )?,
+            uniform_handle: load_fn(&mut loader, "glUniformHandleui64ARB")?,
+        })
+    }
+
+    pub(crate) unsafe fn make_resident(&self, texture: NativeTexture) -> TextureHandleU64 {
+        let handle = (self.get_texture_handle)(texture.0.get());
+        (self.make_texture_handle_resident)(handle);
+        TextureHandleU64(handle)
+    }
+
+    pub(crate) unsafe fn make_non_resident(&self, handle: TextureHandleU64) {
+        (self.make_texture_handle_non_resident)(handle.0);
+    }
+
+    pub(crate) unsafe fn uniform_handle(&self, location: &NativeUniformLocation, value: u64) {
+        (self.uniform_handle)(location.0 as i32, value);
+    }
+}
+
+/// Load a single function pointer of type `F`.
+pub(crate) unsafe fn load_fn<F: Copy>(
+    loader: &mut impl FnMut(&str) -> *const c_void,
+    name: &str,
+) -> Result<F, Error> {
+    let ptr = loader(name);
+    if ptr.is_null() {
+        return Err(Error::Unsupported(format!("{} is not available", name)));
+    }
+
+    Ok(std::mem::transmute_copy::<*const c_void, F>(&ptr))
+}
+
+impl ManagedContext {
+    /// Enable bindless texture support, if the driver supports `GL_ARB_bindless_texture`.
+    ///
+    /// `loader` is the same function used to create the glow context, e.g. `window.get_proc_address`.
+    ///
+    /// # Safety
+    /// `loader` must return valid function pointers for the current context.
+    pub unsafe fn load_bindless_textures(
+        &mut self,
+        loader: impl FnMut(&str) -> *const c_void,
+    ) -> Result<(), Error> {
+        if !self.has_extension("GL_ARB_bindless_texture") {
+            return Err(Error::Unsupported(
+                "GL_ARB_bindless_texture is not supported".into(),
+            ));
+        }
+
+        self.bindless = Some(BindlessTextures::load(loader)?);
+        Ok(())
+    }
+
+    /// Returns true if bindless textures were successfully loaded.
+    pub fn supports_bindless_textures(&self) -> bool {
+        self.bindless.is_some()
+    }
+
+    pub(crate) fn bindless(&self) -> Result<&BindlessTextures, Error> {
+        self.bindless
+            .as_ref()
+            .ok_or_else(|| Error::Unsupported("bindless textures are not loaded".into()))
+    }
+}
+
+impl Texture2D {
+    /// Make the texture resident and receive its bindless handle.
+    ///
+    /// Texture parameters can no longer be changed once a handle has been created.
+    pub fn make_resident(&self, ctx: &mut ManagedContext) -> Result<TextureHandleU64, Error> {
+        let bindless = ctx.bindless()?;
+        Ok(unsafe { bindless.make_resident(self.handle.texture) })
+    }
+
+    /// Make a previously returned bindless handle non-resident.
+    pub fn make_non_resident(
+        &self,
+        ctx: &mut ManagedContext,
+        handle: TextureHandleU64,
+    ) -> Result<(), Error> {
+        let bindless = ctx.bindless()?;
+        unsafe { bindless.make_non_resident(handle) };
+        Ok(())
+    }
+}
+
+impl<'a> Drawable<'a> {
+    /// Set a bindless sampler uniform on the currently applied pipeline.
+    ///
+    /// ## Panics
+    /// Bindless textures must have been loaded using [`ManagedContext::load_bindless_textures`].
+    pub fn set_uniform_texture_handle(&self, name: &str, handle: TextureHandleU64) {
+        let bindless = self
+            .ctx
+            .bindless()
+            .expect("bindless textures are not loaded");
+        unsafe {
+            let loc = self.uniform_location(name);
+            bindless.uniform_handle(&loc, handle.0);
+        }
+    }
+}
@@ -1,10 +1,12 @@
 use glow::{
-    CONSTANT_ALPHA, CONSTANT_COLOR, DST_ALPHA, DST_COLOR, ONE_MINUS_CONSTANT_ALPHA,
-    ONE_MINUS_CONSTANT_COLOR, ONE_MINUS_DST_ALPHA, ONE_MINUS_DST_COLOR, ONE_MINUS_SRC_ALPHA,
-    ONE_MINUS_SRC_COLOR, SRC_ALPHA, SRC_COLOR,
+    CONSTANT_ALPHA, CONSTANT_COLOR, DST_ALPHA, DST_COLOR, ONE, ONE_MINUS_CONSTANT_ALPHA,
+    ONE_MINUS_CONSTANT_COLOR, ONE_MINUS_DST_ALPHA, ONE_MINUS_DST_COLOR, ONE_MINUS_SRC1_ALPHA,
+    ONE_MINUS_SRC1_COLOR, ONE_MINUS_SRC_ALPHA, ONE_MINUS_SRC_COLOR, SRC1_ALPHA, SRC1_COLOR,
+    SRC_ALPHA, SRC_COLOR, ZERO,
 };
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum BlendFactor {
     ConstantAlpha = CONSTANT_ALPHA,
@@ -19,4 +21,38 @@ pub enum BlendFactor {
     OneMinusSourceColor = ONE_MINUS_SRC_COLOR,
     SourceAlpha = SRC_ALPHA,
     SourceColor = SRC_COLOR,
+    /// The second color output of the fragment shader, for dual-source blending. Requires
+    /// [`Features::DUAL_SOURCE_BLEND`](crate::Features::DUAL_SOURCE_BLEND).
+    Source1Color = SRC1_COLOR,
+    /// The alpha of the second color output of the fragment shader, for dual-source blending.
+    Source1Alpha = SRC1_ALPHA,
+    OneMinusSource1Color = ONE_MINUS_SRC1_COLOR,
+    OneMinusSource1Alpha = ONE_MINUS_SRC1_ALPHA,
+    One = ONE,
+    Zero = ZERO,
+}
+
+impl BlendFactor {
+    /// Returns true if the factor reads the second color output of the fragment shader.
+    pub fn is_dual_source(&self) -> bool {
+        is_dual_source(*self as u32)
+    }
+}
+
+pub(crate) fn is_dual_source(factor: u32) -> bool {
+    matches!(
+        factor,
+        SRC1_COLOR | SRC1_ALPHA | ONE_MINUS_SRC1_COLOR | ONE_MINUS_SRC1_ALPHA
+    )
+}
+
+/// The single-source factor closest to `factor`, used as a best-effort downgrade.
+pub(crate) fn single_source(factor: u32) -> u32 {
+    match factor {
+        SRC1_COLOR => SRC_COLOR,
+        SRC1_ALPHA => SRC_ALPHA,
+        ONE_MINUS_SRC1_COLOR => ONE_MINUS_SRC_COLOR,
+        ONE_MINUS_SRC1_ALPHA => ONE_MINUS_SRC_ALPHA,
+        factor => factor,
+    }
 }
@@ -0,0 +1,220 @@
+use super::*;
+
+/// Downsample `sampler2D u_texture` by half and keep what is brighter than `float u_threshold`, for use with
+/// [`shaders::FULLSCREEN_VS`].
+pub const BLOOM_THRESHOLD_FS: &str = r#"
+uniform sampler2D u_texture;
+uniform float u_threshold;
+
+in vec2 v_uv;
+
+out vec4 o_color;
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(u_texture, 0));
+    vec3 color = 0.25 * (
+        texture(u_texture, v_uv + vec2(-0.5, -0.5) * texel).rgb +
+        texture(u_texture, v_uv + vec2(0.5, -0.5) * texel).rgb +
+        texture(u_texture, v_uv + vec2(-0.5, 0.5) * texel).rgb +
+        texture(u_texture, v_uv + vec2(0.5, 0.5) * texel).rgb);
+    float brightness = max(color.r, max(color.g, color.b));
+    // scale rather than subtract, so the hue is kept
+    float contribution = max(brightness - u_threshold, 0.0) / max(brightness, 1e-4);
+    o_color = vec4(color * contribution, 1.0);
+}
+"#;
+
+/// Downsample `sampler2D u_texture` by half with a 4-tap box filter, for use with [`shaders::FULLSCREEN_VS`].
+pub const BLOOM_DOWNSAMPLE_FS: &str = r#"
+uniform sampler2D u_texture;
+
+in vec2 v_uv;
+
+out vec4 o_color;
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(u_texture, 0));
+    o_color = 0.25 * (
+        texture(u_texture, v_uv + vec2(-1.0, -1.0) * texel) +
+        texture(u_texture, v_uv + vec2(1.0, -1.0) * texel) +
+        texture(u_texture, v_uv + vec2(-1.0, 1.0) * texel) +
+        texture(u_texture, v_uv + vec2(1.0, 1.0) * texel));
+}
+"#;
+
+/// Upsample `sampler2D u_texture` with a 9-tap tent filter spanning `float u_radius` source texels, for use with
+/// [`shaders::FULLSCREEN_VS`] and additive blending.
+pub const BLOOM_UPSAMPLE_FS: &str = r#"
+uniform sampler2D u_texture;
+uniform float u_radius;
+
+in vec2 v_uv;
+
+out vec4 o_color;
+
+void main() {
+    vec2 d = u_radius / vec2(textureSize(u_texture, 0));
+    vec4 sum = texture(u_texture, v_uv) * 4.0;
+    sum += (texture(u_texture, v_uv + vec2(-d.x, 0.0)) + texture(u_texture, v_uv + vec2(d.x, 0.0)) +
+        texture(u_texture, v_uv + vec2(0.0, -d.y)) + texture(u_texture, v_uv + vec2(0.0, d.y))) * 2.0;
+    sum += texture(u_texture, v_uv + vec2(-d.x, -d.y)) + texture(u_texture, v_uv + vec2(d.x, -d.y)) +
+        texture(u_texture, v_uv + vec2(-d.x, d.y)) + texture(u_texture, v_uv + vec2(d.x, d.y));
+    o_color = sum / 16.0;
+}
+"#;
+
+/// Add `sampler2D u_bloom` scaled by `float u_intensity` to `sampler2D u_texture`, for use with
+/// [`shaders::FULLSCREEN_VS`].
+pub const BLOOM_COMPOSITE_FS: &str = r#"
+uniform sampler2D u_texture;
+uniform sampler2D u_bloom;
+uniform float u_intensity;
+
+in vec2 v_uv;
+
+out vec4 o_color;
+
+void main() {
+    vec4 color = texture(u_texture, v_uv);
+    o_color = vec4(color.rgb + texture(u_bloom, v_uv).rgb * u_intensity, color.a);
+}
+"#;
+
+/// A bloom pass for a [`PostChain`], see [`PostChain::bloom`].
+///
+/// Bright parts of the image are extracted at half resolution, downsampled through a chain of ever smaller targets
+/// and upsampled back with additive blending, so the glow spreads wide at little cost. Apply it to the HDR image,
+/// before tone mapping.
+#[derive(Debug)]
+pub struct Bloom {
+    levels: Vec<PostTarget>,
+    threshold: f32,
+    intensity: f32,
+    radius: f32,
+    prefilter: RenderPipeline,
+    downsample: RenderPipeline,
+    upsample: RenderPipeline,
+    composite: RenderPipeline,
+}
+
+impl Bloom {
+    /// Create a chain of `levels` targets, starting at half of `width` x `height`.
+    ///
+    /// ## Panics
+    /// There must be at least one level.
+    pub fn new(
+        ctx: &mut ManagedContext,
+        width: i32,
+        height: i32,
+        levels: usize,
+    ) -> Result<Self, Error> {
+        assert!(levels > 0, "at least one bloom level is required!");
+        let levels = (0..levels)
+            .map(|level| {
+                let (width, height) = level_size(width, height, level);
+                PostTarget::new(ctx, width, height)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let pass = |ctx: &ManagedContext, fs: &str| {
+            RenderPipeline::built_in(
+                ctx,
+                shaders::FULLSCREEN_VS,
+                fs,
+                VertexLayout::new(0).with_attribute(2, DataType::Float, false),
+            )
+        };
+
+        Ok(Self {
+            levels,
+            threshold: 1.0,
+            intensity: 0.05,
+            radius: 1.0,
+            prefilter: pass(ctx, BLOOM_THRESHOLD_FS)?,
+            downsample: pass(ctx, BLOOM_DOWNSAMPLE_FS)?,
+            upsample: pass(ctx, BLOOM_UPSAMPLE_FS)?
+                .with_blend(true)
+                .with_blend_func(BlendFactor::One, BlendFactor::One),
+            composite: pass(ctx, BLOOM_COMPOSITE_FS)?,
+        })
+    }
+
+    /// Only colors brighter than `threshold` bloom. Defaults to 1, i.e. values outside the displayable range.
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// The strength the bloom is added to the image with. Defaults to 0.05.
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// The spread of the upsampling filter, in texels of each level. Defaults to 1.
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// Resize the chain to match a resized [`PostChain`].
+    pub fn resize(&mut self, ctx: &mut ManagedContext, width: i32, height: i32) {
+        for (level, target) in self.levels.iter_mut().enumerate() {
+            let (width, height) = level_size(width, height, level);
+            target.color.resize(ctx, width, height);
+        }
+    }
+}
+
+fn level_size(width: i32, height: i32, level: usize) -> (i32, i32) {
+    (
+        (width >> (level + 1)).max(1),
+        (height >> (level + 1)).max(1),
+    )
+}
+
+impl PostChain {
+    /// Add a glow around bright parts of the image. See [`Bloom`].
+    pub fn bloom(&mut self, ctx: &mut ManagedContext, bloom: &Bloom) {
+        let source = &self.targets[self.current];
+        let triangle = &self.triangle;
+
+        bloom.levels[0].draw(
+            ctx,
+            &bloom.prefilter,
+            triangle,
+            &[(&source.color, "u_texture")],
+            |dctx| dctx.set_uniform_float1("u_threshold", bloom.threshold),
+        );
+        for pair in bloom.levels.windows(2) {
+            pair[1].draw(
+                ctx,
+                &bloom.downsample,
+                triangle,
+                &[(&pair[0].color, "u_texture")],
+                |_| {},
+            );
+        }
+        for pair in bloom.levels.windows(2).rev() {
+            pair[0].draw(
+                ctx,
+                &bloom.upsample,
+                triangle,
+                &[(&pair[1].color, "u_texture")],
+                |dctx| dctx.set_uniform_float1("u_radius", bloom.radius),
+            );
+        }
+
+        self.targets[1 - self.current].draw(
+            ctx,
+            &bloom.composite,
+            triangle,
+            &[
+                (&source.color, "u_texture"),
+                (&bloom.levels[0].color, "u_bloom"),
+            ],
+            |dctx| dctx.set_uniform_float1("u_intensity", bloom.intensity),
+        );
+        self.current = 1 - self.current;
+    }
+}
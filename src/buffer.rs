@@ -2,13 +2,15 @@ use super::*;
 
 use std::sync::Arc;
 
-/// The two buffer types supported by rapax.
-/// `ArrayBuffer` corresponds to `GL_ARRAY_BUFFER` and `ElementArrayBuffer` corresponds to `GL_ELEMENT_ARRAY_BUFFER`.
-#[derive(Debug, Clone, Copy)]
+/// The buffer types supported by rapax.
+/// `ArrayBuffer` corresponds to `GL_ARRAY_BUFFER`, `ElementArrayBuffer` corresponds to `GL_ELEMENT_ARRAY_BUFFER`,
+/// and `UniformBuffer` corresponds to `GL_UNIFORM_BUFFER`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u32)]
 pub enum BufferType {
     ArrayBuffer = ARRAY_BUFFER,
     ElementArrayBuffer = ELEMENT_ARRAY_BUFFER,
+    UniformBuffer = UNIFORM_BUFFER,
 }
 
 /// The buffer usage flag passed when allocating buffer data using `glBufferData`.
@@ -28,7 +30,8 @@ impl BufferUsage {
 }
 
 /// The size of an index buffer's indices.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum DataType {
     SignedByte = BYTE,
@@ -41,6 +44,12 @@ pub enum DataType {
     Float = FLOAT,
     Double = DOUBLE,
     Fixed = FIXED,
+    /// Four signed components packed into 32 bits, 10 bits each for xyz and 2 bits for w.
+    #[allow(non_camel_case_types)]
+    Int2_10_10_10_Rev = INT_2_10_10_10_REV,
+    /// Four unsigned components packed into 32 bits, 10 bits each for xyz and 2 bits for w.
+    #[allow(non_camel_case_types)]
+    UnsignedInt2_10_10_10_Rev = UNSIGNED_INT_2_10_10_10_REV,
 }
 
 impl DataType {
@@ -48,6 +57,7 @@ impl DataType {
         *self as u32
     }
 
+    /// The size of a single component, in bytes. Packed types return the size of all four components.
     pub fn sizeof(&self) -> usize {
         match *self {
             Self::SignedByte => 1,
@@ -60,8 +70,36 @@ impl DataType {
             Self::Float => 4,
             Self::Double => 8,
             Self::Fixed => 4,
+            Self::Int2_10_10_10_Rev => 4,
+            Self::UnsignedInt2_10_10_10_Rev => 4,
         }
     }
+
+    /// Returns true if all components of a vertex attribute are packed into a single value.
+    pub fn is_packed(&self) -> bool {
+        matches!(
+            self,
+            Self::Int2_10_10_10_Rev | Self::UnsignedInt2_10_10_10_Rev
+        )
+    }
+}
+
+/// An index element type, see [`BufferHandle::index_buffer_from`].
+pub trait IndexElement: bytemuck::Pod {
+    /// The matching [`DataType`].
+    const DATA_TYPE: DataType;
+}
+
+impl IndexElement for u8 {
+    const DATA_TYPE: DataType = DataType::UnsignedByte;
+}
+
+impl IndexElement for u16 {
+    const DATA_TYPE: DataType = DataType::UnsignedShort;
+}
+
+impl IndexElement for u32 {
+    const DATA_TYPE: DataType = DataType::UnsignedInt;
 }
 
 /// A handle to an OpenGL buffer. The internal OpenGL buffer object will be automatically freed on drop.
@@ -69,8 +107,12 @@ impl DataType {
 pub struct BufferHandle {
     gl: Arc<Context>,
     capacity: usize,
-    buffer: NativeBuffer,
+    pub(crate) buffer: NativeBuffer,
     ty: BufferType,
+    owner_thread: std::thread::ThreadId,
+    share_group: ShareGroup,
+    index_type: Option<DataType>,
+    memory: MemoryAllocation,
 }
 
 impl BufferHandle {
@@ -79,9 +121,10 @@ impl BufferHandle {
         ctx: &ManagedContext,
         usage: BufferUsage,
         data: &[u8],
-    ) -> Result<Self, String> {
+    ) -> Result<Self, Error> {
+        span!("array_buffer", bytes = data.len());
         let buffer = unsafe {
-            let buffer = ctx.gl.create_buffer()?;
+            let buffer = ctx.gl.create_buffer().map_err(Error::BufferAlloc)?;
             ctx.gl.bind_buffer(ARRAY_BUFFER, Some(buffer));
             ctx.gl
                 .buffer_data_u8_slice(ARRAY_BUFFER, data, usage.to_gl());
@@ -94,6 +137,10 @@ impl BufferHandle {
             gl: ctx.gl.clone(),
             ty: BufferType::ArrayBuffer,
             capacity: data.len(),
+            owner_thread: ctx.owner_thread,
+            share_group: ctx.share_group.clone(),
+            index_type: None,
+            memory: MemoryAllocation::new(ctx, ResourceKind::Buffer, data.len()),
         })
     }
 
@@ -102,9 +149,10 @@ impl BufferHandle {
         ctx: &mut ManagedContext,
         usage: BufferUsage,
         data: &[u8],
-    ) -> Result<Self, String> {
+    ) -> Result<Self, Error> {
+        span!("index_buffer", bytes = data.len());
         let buffer = unsafe {
-            let buffer = ctx.gl.create_buffer()?;
+            let buffer = ctx.gl.create_buffer().map_err(Error::BufferAlloc)?;
             ctx.gl.bind_buffer(ELEMENT_ARRAY_BUFFER, Some(buffer));
             ctx.gl
                 .buffer_data_u8_slice(ELEMENT_ARRAY_BUFFER, data, usage.to_gl());
@@ -117,60 +165,190 @@ impl BufferHandle {
             gl: ctx.gl.clone(),
             ty: BufferType::ElementArrayBuffer,
             capacity: data.len(),
+            owner_thread: ctx.owner_thread,
+            share_group: ctx.share_group.clone(),
+            index_type: None,
+            memory: MemoryAllocation::new(ctx, ResourceKind::Buffer, data.len()),
         })
     }
 
+    /// Create an index buffer from typed indices, recording their element type, see
+    /// [`BufferHandle::with_index_type`], e.g. `BufferHandle::index_buffer_from::<u16>(ctx, usage, &indices)`.
+    pub fn index_buffer_from<T: IndexElement>(
+        ctx: &mut ManagedContext,
+        usage: BufferUsage,
+        data: &[T],
+    ) -> Result<Self, Error> {
+        Ok(Self::index_buffer(ctx, usage, bytemuck::cast_slice(data))?
+            .with_index_type(T::DATA_TYPE))
+    }
+
+    /// Create a uniform buffer, filling it with the given data slice.
+    ///
+    /// See [`Std140Writer`] for laying out the contents of a uniform block.
+    pub fn uniform_buffer(
+        ctx: &ManagedContext,
+        usage: BufferUsage,
+        data: &[u8],
+    ) -> Result<Self, Error> {
+        span!("uniform_buffer", bytes = data.len());
+        let buffer = unsafe {
+            let buffer = ctx.gl.create_buffer().map_err(Error::BufferAlloc)?;
+            ctx.gl.bind_buffer(UNIFORM_BUFFER, Some(buffer));
+            ctx.gl
+                .buffer_data_u8_slice(UNIFORM_BUFFER, data, usage.to_gl());
+
+            buffer
+        };
+
+        Ok(Self {
+            buffer,
+            gl: ctx.gl.clone(),
+            ty: BufferType::UniformBuffer,
+            capacity: data.len(),
+            owner_thread: ctx.owner_thread,
+            share_group: ctx.share_group.clone(),
+            index_type: None,
+            memory: MemoryAllocation::new(ctx, ResourceKind::Buffer, data.len()),
+        })
+    }
+
+    /// Set the element type of an index buffer, used by [`Drawable::draw_indexed`] to compute byte offsets.
+    ///
+    /// ## Panics
+    /// The buffer must be an element array buffer, and `ty` must be one of the unsigned integer types.
+    pub fn with_index_type(mut self, ty: DataType) -> Self {
+        assert_eq!(
+            self.ty,
+            BufferType::ElementArrayBuffer,
+            "only index buffers have an index type"
+        );
+        assert!(
+            matches!(
+                ty,
+                DataType::UnsignedByte | DataType::UnsignedShort | DataType::UnsignedInt
+            ),
+            "invalid index type!"
+        );
+        self.index_type = Some(ty);
+        self
+    }
+
+    /// Account the buffer's memory under `label` in [`ManagedContext::memory_report`], e.g. "meshes".
+    pub fn with_memory_label(mut self, label: &str) -> Self {
+        self.memory.set_label(label);
+        self
+    }
+
+    /// The element type of an index buffer, see [`BufferHandle::with_index_type`].
+    pub fn index_type(&self) -> Option<DataType> {
+        self.index_type
+    }
+
     /// The capacity of the buffer, in bytes.
     pub fn capacity(&self) -> usize {
         self.capacity
     }
 
     /// Reallocate the buffer's underlying storage.
-    pub fn realloc(&mut self, usage: BufferUsage, data: &[u8]) {
-        match self.ty() {
-            BufferType::ArrayBuffer => unsafe {
-                self.gl.bind_buffer(ARRAY_BUFFER, Some(self.buffer));
-
-                self.gl
-                    .buffer_data_u8_slice(ARRAY_BUFFER, data, usage.to_gl());
-            },
-            BufferType::ElementArrayBuffer => unsafe {
-                self.gl.bind_buffer(ELEMENT_ARRAY_BUFFER, Some(self.buffer));
-
+    pub fn realloc(&mut self, ctx: &ManagedContext, usage: BufferUsage, data: &[u8]) {
+        span!(
+            "buffer_realloc",
+            buffer = self.buffer.0.get(),
+            bytes = data.len()
+        );
+        debug_assert_thread(self.owner_thread);
+        let target = self.ty() as u32;
+        unsafe {
+            if ctx.supports_dsa() {
                 self.gl
-                    .buffer_data_u8_slice(ELEMENT_ARRAY_BUFFER, data, usage.to_gl());
-            },
+                    .named_buffer_data_u8_slice(self.buffer, data, usage.to_gl());
+            } else {
+                self.gl.bind_buffer(target, Some(self.buffer));
+                self.gl.buffer_data_u8_slice(target, data, usage.to_gl());
+            }
         }
 
         self.capacity = data.len();
+        self.memory.resize(data.len());
+    }
+
+    /// Tell the driver the buffer's contents are no longer needed, before overwriting them.
+    ///
+    /// This avoids waiting for draws that still read the old contents. Uses `glInvalidateBufferSubData` if
+    /// available, otherwise maps the buffer with `MAP_INVALIDATE_BUFFER_BIT`.
+    pub fn invalidate(&self, ctx: &ManagedContext) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let target = self.ty() as u32;
+        unsafe {
+            self.gl.bind_buffer(target, Some(self.buffer));
+            if ctx.supports_invalidate_subdata() {
+                self.gl
+                    .invalidate_buffer_sub_data(target, 0, self.capacity as i32);
+            } else {
+                self.gl.map_buffer_range(
+                    target,
+                    0,
+                    self.capacity as i32,
+                    MAP_WRITE_BIT | MAP_INVALIDATE_BUFFER_BIT,
+                );
+                self.gl.unmap_buffer(target);
+            }
+        }
     }
 
     /// Update data in the buffer's data storage.
     /// When updating the entire buffer, consider this function over `realloc`.
     /// This avoids the cost of reallocating the buffer object's data store.
     ///
+    /// The buffer isn't bound if [`ManagedContext::load_direct_state_access`] was called.
+    ///
     /// ## Panics
     /// The offset and the data being updated must lie inside the buffer.
-    pub fn update(&self, offset: i32, data: &[u8]) {
+    pub fn update(&self, ctx: &ManagedContext, offset: i32, data: &[u8]) {
+        span!(
+            "buffer_update",
+            buffer = self.buffer.0.get(),
+            offset,
+            bytes = data.len()
+        );
         assert!(
             offset as usize + data.len() <= self.capacity,
             "out of bounds write!"
         );
+        debug_assert_thread(self.owner_thread);
 
-        match self.ty() {
-            BufferType::ArrayBuffer => unsafe {
-                self.gl.bind_buffer(ARRAY_BUFFER, Some(self.buffer));
-                self.gl.buffer_sub_data_u8_slice(ARRAY_BUFFER, offset, data);
-            },
-            BufferType::ElementArrayBuffer => unsafe {
-                self.gl.bind_buffer(ELEMENT_ARRAY_BUFFER, Some(self.buffer));
-                self.gl
-                    .buffer_sub_data_u8_slice(ELEMENT_ARRAY_BUFFER, offset, data);
-            },
+        let target = self.ty() as u32;
+        unsafe {
+            if let Some(dsa) = ctx.dsa() {
+                dsa.named_buffer_sub_data(self.buffer, offset, data);
+            } else {
+                self.gl.bind_buffer(target, Some(self.buffer));
+                self.gl.buffer_sub_data_u8_slice(target, offset, data);
+            }
         }
     }
 
-    /// The buffer type, either `ArrayBuffer` or `ElementArrayBuffer`.
+    /// Fill the entire buffer by repeating `value`, e.g. to reset GPU counters each frame.
+    ///
+    /// `glClearBufferData` is not exposed by glow, so the fill pattern is uploaded from the CPU.
+    ///
+    /// ## Panics
+    /// The buffer capacity must be a multiple of the length of `value`.
+    pub fn clear(&self, ctx: &ManagedContext, value: &[u8]) {
+        assert!(
+            !value.is_empty() && self.capacity % value.len() == 0,
+            "buffer capacity is not a multiple of the clear value size!"
+        );
+
+        let data = value.repeat(self.capacity / value.len());
+        self.update(ctx, 0, &data);
+    }
+
+    /// The buffer type.
     pub fn ty(&self) -> BufferType {
         self.ty
     }
@@ -178,6 +356,7 @@ impl BufferHandle {
 
 impl Drop for BufferHandle {
     fn drop(&mut self) {
+        self.share_group.forget_buffer(self.buffer);
         unsafe { self.gl.delete_buffer(self.buffer) }
     }
 }
@@ -186,19 +365,54 @@ impl BindableBuffer for &BufferHandle {
     unsafe fn bind(&self, target: u32, gl: &Context) {
         assert_eq!(
             target,
-            self.ty() as _,
+            self.ty() as u32,
             "Attempted to bind buffer to invalid binding point"
         );
         gl.bind_buffer(target, Some(self.buffer));
     }
+
+    fn index_type(&self) -> Option<DataType> {
+        self.index_type
+    }
+
+    fn native_buffer(&self, target: u32) -> Option<NativeBuffer> {
+        assert_eq!(
+            target,
+            self.ty() as u32,
+            "Attempted to bind buffer to invalid binding point"
+        );
+        Some(self.buffer)
+    }
 }
 
 impl BindableBuffer for NativeBuffer {
     unsafe fn bind(&self, target: u32, gl: &Context) {
         gl.bind_buffer(target, Some(*self));
     }
+
+    fn native_buffer(&self, _target: u32) -> Option<NativeBuffer> {
+        Some(*self)
+    }
 }
 
 pub trait BindableBuffer {
+    /// Bind the buffer to the given target.
+    ///
+    /// # Safety
+    /// The caller must ensure `target` is a valid binding point for the underlying buffer object.
     unsafe fn bind(&self, target: u32, gl: &Context);
+
+    /// The element type when used as an index buffer, if known.
+    fn index_type(&self) -> Option<DataType> {
+        None
+    }
+
+    /// The buffer object to attach without binding it, e.g. with `glBindVertexBuffer`. `None` if the buffer can
+    /// only be bound with [`BindableBuffer::bind`].
+    ///
+    /// ## Panics
+    /// Implementations may panic if `target` is not a valid binding point for the buffer.
+    fn native_buffer(&self, _target: u32) -> Option<NativeBuffer> {
+        None
+    }
 }
@@ -0,0 +1,198 @@
+use super::*;
+
+use std::ops::Range;
+
+/// A uniform value recorded into a [`DrawBundle`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BundleUniform {
+    Float1(f32),
+    Float2([f32; 2]),
+    Float3([f32; 3]),
+    Float4([f32; 4]),
+    Int1(i32),
+    Mat4([f32; 16]),
+}
+
+/// A command recorded into a [`DrawBundle`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum BundleCommand {
+    /// See [`Drawable::apply_buffer_ids`].
+    Buffers {
+        vertex_buffers: Vec<BufferId>,
+        index_buffer: Option<BufferId>,
+    },
+    /// See [`Drawable::apply_texture_ids`].
+    Textures(Vec<(TextureId, String)>),
+    Uniform(String, BundleUniform),
+    /// See [`Drawable::draw_arrays_instanced`].
+    DrawArrays {
+        mode: DrawMode,
+        first: i32,
+        count: i32,
+        instances: u32,
+    },
+    /// See [`Drawable::draw_indexed`].
+    DrawIndexed {
+        mode: DrawMode,
+        range: Range<u32>,
+        base_vertex: i32,
+        instances: u32,
+    },
+}
+
+/// A retained list of draws, recorded once and replayed every frame with [`Drawable::draw_bundle`], e.g. for static
+/// UI panels or HUDs that rarely change.
+///
+/// Buffers & textures are referenced by their ids in the context's registries, see [`ManagedContext::create_buffer`]
+/// and [`ManagedContext::create_texture`], so a bundle stays valid while its resources are updated in place. Replaying
+/// skips the work of building the draws, but issues the same GL calls as recording them directly would.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DrawBundle {
+    commands: Vec<BundleCommand>,
+}
+
+impl DrawBundle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record binding vertex & index buffers.
+    pub fn apply_buffer_ids(
+        &mut self,
+        vertex_buffers: &[BufferId],
+        index_buffer: Option<BufferId>,
+    ) {
+        self.commands.push(BundleCommand::Buffers {
+            vertex_buffers: vertex_buffers.to_vec(),
+            index_buffer,
+        });
+    }
+
+    /// Record binding textures to the given sampler uniforms.
+    pub fn apply_texture_ids(&mut self, textures: &[(TextureId, &str)]) {
+        self.commands.push(BundleCommand::Textures(
+            textures
+                .iter()
+                .map(|(id, name)| (*id, name.to_string()))
+                .collect(),
+        ));
+    }
+
+    /// Record setting a uniform of the pipeline the bundle is replayed with.
+    pub fn set_uniform(&mut self, name: &str, value: BundleUniform) {
+        self.commands
+            .push(BundleCommand::Uniform(name.to_owned(), value));
+    }
+
+    /// Record a non-indexed draw. `instances` is 1 for a regular draw.
+    pub fn draw_arrays(&mut self, mode: DrawMode, first: i32, count: i32, instances: u32) {
+        self.commands.push(BundleCommand::DrawArrays {
+            mode,
+            first,
+            count,
+            instances,
+        });
+    }
+
+    /// Record an indexed draw, see [`Drawable::draw_indexed`].
+    pub fn draw_indexed(
+        &mut self,
+        mode: DrawMode,
+        range: Range<u32>,
+        base_vertex: i32,
+        instances: u32,
+    ) {
+        self.commands.push(BundleCommand::DrawIndexed {
+            mode,
+            range,
+            base_vertex,
+            instances,
+        });
+    }
+
+    pub fn commands(&self) -> &[BundleCommand] {
+        &self.commands
+    }
+
+    /// Remove every command, to record the bundle again after the content changed.
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Returns true if every buffer & texture the bundle references still exists in `ctx`.
+    pub fn is_valid(&self, ctx: &ManagedContext) -> bool {
+        self.commands.iter().all(|command| match command {
+            BundleCommand::Buffers {
+                vertex_buffers,
+                index_buffer,
+            } => vertex_buffers
+                .iter()
+                .chain(index_buffer)
+                .all(|id| ctx.buffers.contains(*id)),
+            BundleCommand::Textures(textures) => {
+                textures.iter().all(|(id, _)| ctx.textures.contains(*id))
+            }
+            _ => true,
+        })
+    }
+}
+
+impl Drawable<'_> {
+    /// Replay the commands of `bundle` with the current pipeline.
+    ///
+    /// ## Panics
+    /// Every buffer & texture the bundle references must still exist, see [`DrawBundle::is_valid`], and the
+    /// pipeline must have the bundle's uniforms.
+    pub fn draw_bundle(&mut self, bundle: &DrawBundle) {
+        span!("draw_bundle", commands = bundle.len());
+        for command in &bundle.commands {
+            match command {
+                BundleCommand::Buffers {
+                    vertex_buffers,
+                    index_buffer,
+                } => self.apply_buffer_ids(vertex_buffers, *index_buffer),
+                BundleCommand::Textures(textures) => {
+                    let textures: Vec<(TextureId, &str)> = textures
+                        .iter()
+                        .map(|(id, name)| (*id, name.as_str()))
+                        .collect();
+                    self.apply_texture_ids(&textures);
+                }
+                BundleCommand::Uniform(name, value) => match value {
+                    BundleUniform::Float1(value) => self.set_uniform_float1(name, *value),
+                    BundleUniform::Float2(value) => self.set_uniform_float2(name, value),
+                    BundleUniform::Float3(value) => self.set_uniform_float3(name, value),
+                    BundleUniform::Float4(value) => self.set_uniform_float4(name, value),
+                    BundleUniform::Int1(value) => self.set_uniform_int1(name, *value),
+                    BundleUniform::Mat4(value) => self.set_uniform_mat4(name, value, false),
+                },
+                BundleCommand::DrawArrays {
+                    mode,
+                    first,
+                    count,
+                    instances: 1,
+                } => self.draw_arrays(*mode, *first, *count),
+                BundleCommand::DrawArrays {
+                    mode,
+                    first,
+                    count,
+                    instances,
+                } => self.draw_arrays_instanced(*mode, *first, *count, *instances),
+                BundleCommand::DrawIndexed {
+                    mode,
+                    range,
+                    base_vertex,
+                    instances,
+                } => self.draw_indexed(*mode, range.clone(), *base_vertex, *instances),
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! A ring log of rapax-level calls and their arguments, enabled by the `call-log` feature.
+//!
+//! Unlike a GL trace, the log shows what the application asked rapax to do, e.g. which pipeline it bound and how many
+//! vertices it drew, so the calls of a retained-mode layer can be reconstructed from a crash report. Entries are
+//! recorded at the same entry points as the `tracing` spans.
+//!
+//! GL contexts are bound to a thread, so every thread keeps its own log.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt::{Debug, Write as _};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// The number of entries kept by default.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+#[derive(Debug)]
+struct CallLog {
+    capacity: usize,
+    next: u64,
+    entries: VecDeque<String>,
+}
+
+thread_local! {
+    static LOG: RefCell<CallLog> = const {
+        RefCell::new(CallLog {
+            capacity: DEFAULT_CAPACITY,
+            next: 0,
+            entries: VecDeque::new(),
+        })
+    };
+}
+
+/// Record a call. `fields` writes its arguments, see [`write_field`].
+pub(crate) fn record(name: &str, fields: impl FnOnce(&mut String)) {
+    LOG.with(|log| {
+        let mut log = log.borrow_mut();
+        if log.capacity == 0 {
+            return;
+        }
+        let mut entry = if log.entries.len() == log.capacity {
+            log.entries.pop_front().unwrap_or_default()
+        } else {
+            String::new()
+        };
+        entry.clear();
+        let _ = write!(entry, "#{} {}", log.next, name);
+        fields(&mut entry);
+        log.next += 1;
+        log.entries.push_back(entry);
+    });
+}
+
+/// Append a ` key=value` argument to a call log entry.
+pub(crate) fn write_field(out: &mut String, key: &str, value: &dyn Debug) {
+    let _ = write!(out, " {}={:?}", key, value);
+}
+
+/// Keep the last `capacity` calls of this thread, 0 to stop recording.
+pub fn set_capacity(capacity: usize) {
+    LOG.with(|log| {
+        let mut log = log.borrow_mut();
+        log.capacity = capacity;
+        while log.entries.len() > capacity {
+            log.entries.pop_front();
+        }
+    });
+}
+
+/// The recorded calls of this thread, oldest first.
+pub fn entries() -> Vec<String> {
+    LOG.with(|log| log.borrow().entries.iter().cloned().collect())
+}
+
+/// Forget the recorded calls of this thread.
+pub fn clear() {
+    LOG.with(|log| log.borrow_mut().entries.clear());
+}
+
+/// Write the recorded calls of this thread, one per line, oldest first.
+pub fn dump(mut writer: impl Write) -> io::Result<()> {
+    LOG.with(|log| {
+        for entry in &log.borrow().entries {
+            writeln!(writer, "{}", entry)?;
+        }
+        Ok(())
+    })
+}
+
+/// Write the recorded calls of this thread to the file at `path`, replacing it.
+pub fn dump_to_file(path: impl AsRef<Path>) -> io::Result<()> {
+    dump(io::BufWriter::new(std::fs::File::create(path)?))
+}
+
+/// Dump the log of the panicking thread to the file at `path` when a panic occurs, then run the previous panic hook.
+pub fn dump_on_panic(path: impl Into<PathBuf>) {
+    let path = path.into();
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // the log may be mid-update if the panic came from inside it
+        if LOG.with(|log| log.try_borrow_mut().is_ok()) {
+            let _ = dump_to_file(&path);
+        }
+        previous(info);
+    }));
+}
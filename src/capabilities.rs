@@ -0,0 +1,77 @@
+use super::*;
+
+/// Compressed texture format families the context can sample, see [`Capabilities::texture_compression`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextureCompression {
+    /// BC1-BC3 (`EXT_texture_compression_s3tc`), common on desktop GPUs.
+    pub s3tc: bool,
+    /// BC4-BC5 single and dual channel formats, core since GL 3.0.
+    pub rgtc: bool,
+    /// BC6H-BC7, core since GL 4.2.
+    pub bptc: bool,
+    /// ETC2 and EAC, core in OpenGL ES 3.0 and GL 4.3.
+    pub etc2: bool,
+    /// ASTC LDR blocks, common on mobile GPUs and core in OpenGL ES 3.2.
+    pub astc: bool,
+}
+
+/// Optional capabilities and limits of a context, grouped for asset pipelines choosing formats at load time. See
+/// [`ManagedContext::capabilities`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capabilities {
+    pub texture_compression: TextureCompression,
+    /// The maximum anisotropy, 1.0 without [`Features::ANISOTROPIC_FILTERING`].
+    pub max_anisotropy: f32,
+    pub max_texture_size: i32,
+    pub max_samples: i32,
+    pub max_color_attachments: i32,
+    pub max_vertex_attributes: i32,
+}
+
+impl Capabilities {
+    pub(crate) fn query(info: &DriverInfo, gl: &Context, features: Features) -> Self {
+        let extensions = gl.supported_extensions();
+        let has = |name: &str| extensions.contains(name);
+        let (desktop, es) = if info.is_embedded {
+            (false, true)
+        } else {
+            (true, false)
+        };
+
+        let texture_compression = TextureCompression {
+            s3tc: has("GL_EXT_texture_compression_s3tc") || has("WEBGL_compressed_texture_s3tc"),
+            rgtc: (desktop && info.is_at_least(3, 0))
+                || has("GL_ARB_texture_compression_rgtc")
+                || has("GL_EXT_texture_compression_rgtc"),
+            bptc: (desktop && info.is_at_least(4, 2))
+                || has("GL_ARB_texture_compression_bptc")
+                || has("GL_EXT_texture_compression_bptc"),
+            etc2: (es && info.is_at_least(3, 0))
+                || (desktop && info.is_at_least(4, 3))
+                || has("GL_ARB_ES3_compatibility"),
+            astc: (es && info.is_at_least(3, 2)) || has("GL_KHR_texture_compression_astc_ldr"),
+        };
+
+        unsafe {
+            Self {
+                texture_compression,
+                max_anisotropy: if features.contains(Features::ANISOTROPIC_FILTERING) {
+                    gl.get_parameter_f32(MAX_TEXTURE_MAX_ANISOTROPY)
+                } else {
+                    1.0
+                },
+                max_texture_size: gl.get_parameter_i32(MAX_TEXTURE_SIZE),
+                max_samples: gl.get_parameter_i32(MAX_SAMPLES),
+                max_color_attachments: gl.get_parameter_i32(MAX_COLOR_ATTACHMENTS),
+                max_vertex_attributes: gl.get_parameter_i32(MAX_VERTEX_ATTRIBS),
+            }
+        }
+    }
+}
+
+impl ManagedContext {
+    /// The compressed texture formats, anisotropy and limits of the context, queried once at creation.
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+}
@@ -0,0 +1,78 @@
+use super::*;
+
+/// A `glEnable`/`glDisable` capability.
+///
+/// Associated constants cover common capabilities, any other `GLenum` can be wrapped directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlCapability(pub u32);
+
+impl GlCapability {
+    pub const CULL_FACE: Self = Self(glow::CULL_FACE);
+    pub const DITHER: Self = Self(glow::DITHER);
+    pub const POLYGON_OFFSET_FILL: Self = Self(glow::POLYGON_OFFSET_FILL);
+    pub const SAMPLE_ALPHA_TO_COVERAGE: Self = Self(glow::SAMPLE_ALPHA_TO_COVERAGE);
+    pub const SAMPLE_COVERAGE: Self = Self(glow::SAMPLE_COVERAGE);
+    pub const MULTISAMPLE: Self = Self(glow::MULTISAMPLE);
+    pub const FRAMEBUFFER_SRGB: Self = Self(glow::FRAMEBUFFER_SRGB);
+    pub const RASTERIZER_DISCARD: Self = Self(glow::RASTERIZER_DISCARD);
+    pub const PRIMITIVE_RESTART_FIXED_INDEX: Self = Self(glow::PRIMITIVE_RESTART_FIXED_INDEX);
+    pub const PROGRAM_POINT_SIZE: Self = Self(glow::PROGRAM_POINT_SIZE);
+    pub const DEPTH_CLAMP: Self = Self(glow::DEPTH_CLAMP);
+    pub const TEXTURE_CUBE_MAP_SEAMLESS: Self = Self(glow::TEXTURE_CUBE_MAP_SEAMLESS);
+
+    /// Capabilities that are part of [`RenderPipeline`] state and applied on every bind.
+    const PIPELINE_MANAGED: [u32; 9] = [
+        BLEND,
+        DEPTH_TEST,
+        DEPTH_CLAMP,
+        RASTERIZER_DISCARD,
+        SAMPLE_SHADING,
+        SCISSOR_TEST,
+        STENCIL_TEST,
+        LINE_SMOOTH,
+        POINT_SMOOTH,
+    ];
+
+    /// Returns true if the capability is set by [`ManagedContext::bind_pipeline`] and must be configured on the pipeline instead.
+    pub fn is_pipeline_managed(&self) -> bool {
+        Self::PIPELINE_MANAGED.contains(&self.0)
+    }
+}
+
+impl ManagedContext {
+    /// Enable or disable a capability rapax doesn't otherwise wrap.
+    ///
+    /// The state is tracked, so redundant calls are skipped and pipelines leave it alone.
+    ///
+    /// ## Panics
+    /// The capability must not be pipeline state, see [`GlCapability::is_pipeline_managed`].
+    pub fn set_raw_capability(&mut self, cap: GlCapability, enabled: bool) {
+        assert!(
+            !cap.is_pipeline_managed(),
+            "capability is managed by RenderPipeline!"
+        );
+        if self.raw_capabilities.insert(cap, enabled) == Some(enabled) {
+            return;
+        }
+
+        unsafe {
+            if enabled {
+                self.gl.enable(cap.0);
+            } else {
+                self.gl.disable(cap.0);
+            }
+        }
+    }
+
+    /// The state last set using [`ManagedContext::set_raw_capability`], if any.
+    pub fn raw_capability(&self, cap: GlCapability) -> Option<bool> {
+        self.raw_capabilities.get(&cap).copied()
+    }
+
+    /// Forget the tracked capability state, e.g. after external code changed GL state behind rapax's back.
+    ///
+    /// The next [`ManagedContext::set_raw_capability`] call for each capability always reaches the driver.
+    pub fn invalidate_raw_capabilities(&mut self) {
+        self.raw_capabilities.clear();
+    }
+}
@@ -0,0 +1,198 @@
+use super::*;
+
+use std::sync::Arc;
+
+/// Tightly packed RGBA8 pixels, with the first row at the top of the image unless read back with
+/// [`ManagedContext::capture_rect_with`] without [`ReadbackOptions::flip_y`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageData {
+    pub width: i32,
+    pub height: i32,
+    pub rgba: Vec<u8>,
+}
+
+/// Options for reading back pixels with [`ManagedContext::capture_rect_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReadbackOptions {
+    /// Flip the data so its first row ends up at the top, the row order [`UploadOptions::flip_y`] expects. Use this
+    /// for images stored top-down, e.g. screenshots saved to a file.
+    pub flip_y: bool,
+}
+
+impl ImageData {
+    /// Convert from OpenGL's bottom-up row order to top-down row order.
+    fn from_gl_rows(width: i32, height: i32, rgba: Vec<u8>) -> Self {
+        let mut image = Self {
+            width,
+            height,
+            rgba,
+        };
+        image.flip_vertically();
+        image
+    }
+
+    /// Reverse the row order in place, e.g. to get OpenGL's bottom-up order back.
+    pub fn flip_vertically(&mut self) {
+        let row_len = self.width as usize * 4;
+        let rows = self.height as usize;
+        for row in 0..rows / 2 {
+            let (top, bottom) = self.rgba.split_at_mut((rows - row - 1) * row_len);
+            top[row * row_len..(row + 1) * row_len].swap_with_slice(&mut bottom[..row_len]);
+        }
+    }
+}
+
+impl ManagedContext {
+    pub(crate) fn viewport_rect(&self) -> Rect {
+        let mut viewport = [0; 4];
+        unsafe { self.gl.get_parameter_i32_slice(VIEWPORT, &mut viewport) };
+        Rect::new(viewport[0], viewport[1], viewport[2], viewport[3])
+    }
+
+    /// Read back the current viewport of the bound read framebuffer, e.g. for screenshots.
+    ///
+    /// This stalls until rendering has finished; see [`ManagedContext::capture_frame_async`] to avoid stalls.
+    pub fn capture_frame(&self) -> ImageData {
+        self.capture_rect(self.viewport_rect())
+    }
+
+    /// Read back a region of the bound read framebuffer, flipped so the first row is the top of the image. `rect` uses
+    /// OpenGL's bottom-left origin.
+    pub fn capture_rect(&self, rect: Rect) -> ImageData {
+        self.capture_rect_with(rect, ReadbackOptions { flip_y: true })
+    }
+
+    /// Read back a region of the bound read framebuffer like [`ManagedContext::capture_rect`].
+    ///
+    /// By default the rows are left in OpenGL's bottom-up order, e.g. for re-uploading them to a texture.
+    pub fn capture_rect_with(&self, rect: Rect, options: ReadbackOptions) -> ImageData {
+        span!("capture_rect", width = rect.width, height = rect.height);
+        let mut rgba = vec![0; rect.width as usize * rect.height as usize * 4];
+        unsafe {
+            self.gl.pixel_store_i32(PACK_ALIGNMENT, 1);
+            self.gl.read_pixels(
+                rect.x,
+                rect.y,
+                rect.width,
+                rect.height,
+                RGBA,
+                UNSIGNED_BYTE,
+                PixelPackData::Slice(&mut rgba),
+            );
+            self.gl.pixel_store_i32(PACK_ALIGNMENT, 4);
+        }
+
+        if options.flip_y {
+            ImageData::from_gl_rows(rect.width, rect.height, rgba)
+        } else {
+            ImageData {
+                width: rect.width,
+                height: rect.height,
+                rgba,
+            }
+        }
+    }
+
+    /// Start reading back the current viewport into a pixel buffer object without stalling the pipeline.
+    ///
+    /// Poll the returned [`PendingFrame`] on later frames, e.g. when piping frames to a video encoder.
+    pub fn capture_frame_async(&self) -> Result<PendingFrame, Error> {
+        let rect = self.viewport_rect();
+        let size = rect.width * rect.height * 4;
+        unsafe {
+            let buffer = self.gl.create_buffer().map_err(Error::BufferAlloc)?;
+            self.gl.bind_buffer(PIXEL_PACK_BUFFER, Some(buffer));
+            self.gl
+                .buffer_data_size(PIXEL_PACK_BUFFER, size, STREAM_READ);
+            self.gl.pixel_store_i32(PACK_ALIGNMENT, 1);
+            self.gl.read_pixels(
+                rect.x,
+                rect.y,
+                rect.width,
+                rect.height,
+                RGBA,
+                UNSIGNED_BYTE,
+                PixelPackData::BufferOffset(0),
+            );
+            self.gl.pixel_store_i32(PACK_ALIGNMENT, 4);
+            self.gl.bind_buffer(PIXEL_PACK_BUFFER, None);
+
+            let fence = match self.gl.fence_sync(SYNC_GPU_COMMANDS_COMPLETE, 0) {
+                Ok(fence) => fence,
+                Err(err) => {
+                    self.gl.delete_buffer(buffer);
+                    return Err(Error::Unsupported(err));
+                }
+            };
+
+            Ok(PendingFrame {
+                gl: self.gl.clone(),
+                buffer,
+                fence,
+                width: rect.width,
+                height: rect.height,
+            })
+        }
+    }
+}
+
+/// A frame being read back asynchronously. The pixel buffer is freed on drop.
+#[derive(Debug)]
+pub struct PendingFrame {
+    gl: Arc<Context>,
+    buffer: NativeBuffer,
+    fence: NativeFence,
+    width: i32,
+    height: i32,
+}
+
+impl PendingFrame {
+    /// Returns true if the GPU has finished writing the frame.
+    pub fn is_ready(&self) -> bool {
+        unsafe { self.gl.get_sync_status(self.fence) == SIGNALED }
+    }
+
+    /// Receive the frame if it is ready, otherwise get the pending frame back.
+    ///
+    /// Fails like [`PendingFrame::resolve`] once the frame is ready.
+    pub fn try_resolve(self) -> Result<Result<ImageData, Error>, Self> {
+        if self.is_ready() {
+            Ok(self.resolve())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Receive the frame, blocking until the GPU has finished writing it.
+    ///
+    /// Returns [`Error::Gl`] if the pixel buffer can't be mapped, e.g. after the context was lost.
+    pub fn resolve(self) -> Result<ImageData, Error> {
+        let size = self.width as usize * self.height as usize * 4;
+        let mut rgba = vec![0; size];
+        unsafe {
+            self.gl.bind_buffer(PIXEL_PACK_BUFFER, Some(self.buffer));
+            let ptr = self
+                .gl
+                .map_buffer_range(PIXEL_PACK_BUFFER, 0, size as i32, MAP_READ_BIT);
+            if ptr.is_null() {
+                let error = self.gl.get_error();
+                self.gl.bind_buffer(PIXEL_PACK_BUFFER, None);
+                return Err(Error::Gl(error));
+            }
+            rgba.copy_from_slice(std::slice::from_raw_parts(ptr, size));
+            self.gl.unmap_buffer(PIXEL_PACK_BUFFER);
+            self.gl.bind_buffer(PIXEL_PACK_BUFFER, None);
+        }
+
+        Ok(ImageData::from_gl_rows(self.width, self.height, rgba))
+    }
+}
+
+impl Drop for PendingFrame {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_sync(self.fence);
+            self.gl.delete_buffer(self.buffer);
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use super::*;
+
+/// The perspective camera a [`CascadedShadowMaps`] is fitted to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CascadeCamera {
+    /// Column-major view matrix. It must be a rigid transform, i.e. contain no scaling.
+    pub view: [f32; 16],
+    /// Vertical field of view, in radians.
+    pub fov_y: f32,
+    /// Width divided by height.
+    pub aspect: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+/// One cascade of a [`CascadedShadowMaps`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cascade {
+    /// View-space distance where the cascade starts.
+    pub split_near: f32,
+    /// View-space distance where the cascade ends; compare it against the fragment depth to pick the cascade.
+    pub split_far: f32,
+    /// Column-major light view-projection, snapped to whole shadow map texels.
+    pub view_projection: [f32; 16],
+}
+
+/// Splits a camera frustum into cascades and fits a light-space crop matrix to each, for directional light shadows.
+///
+/// Each cascade is fitted to the bounding sphere of its frustum slice and snapped to shadow map texels, so shadows
+/// don't shimmer as the camera moves or rotates. Render with [`CascadedShadowMaps::render`] into the layers of a
+/// [`Texture2DArray`].
+#[derive(Debug, Clone)]
+pub struct CascadedShadowMaps {
+    cascades: Vec<Cascade>,
+    count: usize,
+    resolution: i32,
+    split_lambda: f32,
+    caster_distance: f32,
+}
+
+impl CascadedShadowMaps {
+    /// Create `count` cascades for shadow maps of `resolution` x `resolution` texels.
+    ///
+    /// ## Panics
+    /// There must be at least one cascade.
+    pub fn new(count: usize, resolution: i32) -> Self {
+        assert!(count > 0, "at least one cascade is required!");
+        Self {
+            cascades: Vec::with_capacity(count),
+            count,
+            resolution,
+            split_lambda: 0.5,
+            caster_distance: 0.0,
+        }
+    }
+
+    /// Blend between uniform (0) and logarithmic (1) split distances, see [`cascade_splits`].
+    pub fn with_split_lambda(mut self, lambda: f32) -> Self {
+        self.split_lambda = lambda;
+        self
+    }
+
+    /// Extend each cascade towards the light, so casters outside the camera frustum still cast shadows into it.
+    pub fn with_caster_distance(mut self, distance: f32) -> Self {
+        self.caster_distance = distance;
+        self
+    }
+
+    /// Recompute the cascades for a camera and a light shining along `light_dir`.
+    pub fn update(&mut self, camera: &CascadeCamera, light_dir: [f32; 3]) {
+        let splits = cascade_splits(camera.near, camera.far, self.count, self.split_lambda);
+        let camera_to_world = rigid_inverse(&camera.view);
+        let dir = normalize(light_dir);
+        let tan_y = (camera.fov_y * 0.5).tan();
+        let tan_x = tan_y * camera.aspect;
+
+        self.cascades.clear();
+        for pair in splits.windows(2) {
+            let (near, far) = (pair[0], pair[1]);
+            let mut corners = Vec::with_capacity(8);
+            for z in [near, far] {
+                for (sx, sy) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+                    let view_corner = [sx * tan_x * z, sy * tan_y * z, -z, 1.0];
+                    let world = transform(&camera_to_world, view_corner);
+                    corners.push([world[0], world[1], world[2]]);
+                }
+            }
+
+            let mut center = [0.0; 3];
+            for corner in &corners {
+                for axis in 0..3 {
+                    center[axis] += corner[axis] / 8.0;
+                }
+            }
+            let radius = corners
+                .iter()
+                .map(|corner| length(sub(*corner, center)))
+                .fold(0.0f32, f32::max);
+            // quantize the radius, so the projection size doesn't jitter with floating point error
+            let radius = (radius * 16.0).ceil() / 16.0;
+
+            let back = radius + self.caster_distance;
+            let eye = [
+                center[0] - dir[0] * back,
+                center[1] - dir[1] * back,
+                center[2] - dir[2] * back,
+            ];
+            let up = if dir[1].abs() > 0.99 {
+                [0.0, 0.0, 1.0]
+            } else {
+                [0.0, 1.0, 0.0]
+            };
+            let view = look_at(eye, center, up);
+            let mut projection = ortho(-radius, radius, -radius, radius, 0.0, back + radius);
+
+            // snap the world origin to a texel, so the shadow map only moves in whole texels
+            let origin = transform(&multiply(&projection, &view), [0.0, 0.0, 0.0, 1.0]);
+            let texels = self.resolution as f32 * 0.5;
+            let (x, y) = (origin[0] * texels, origin[1] * texels);
+            projection[12] += (x.round() - x) / texels;
+            projection[13] += (y.round() - y) / texels;
+
+            self.cascades.push(Cascade {
+                split_near: near,
+                split_far: far,
+                view_projection: multiply(&projection, &view),
+            });
+        }
+    }
+
+    /// The cascades computed by the last [`CascadedShadowMaps::update`], nearest first.
+    pub fn cascades(&self) -> &[Cascade] {
+        &self.cascades
+    }
+
+    /// The shadow map resolution, in texels.
+    pub fn resolution(&self) -> i32 {
+        self.resolution
+    }
+
+    /// Render depth into one layer of `shadow_maps` per cascade, calling `draw` with the cascade index.
+    ///
+    /// The depth of every layer is cleared beforehand, and the previous framebuffer and viewport are restored
+    /// afterwards.
+    ///
+    /// ## Panics
+    /// `shadow_maps` must have a layer per cascade.
+    pub fn render(
+        &self,
+        ctx: &mut ManagedContext,
+        framebuffer: &Framebuffer,
+        shadow_maps: &Texture2DArray,
+        mut draw: impl FnMut(&mut ManagedContext, usize, &Cascade),
+    ) {
+        assert!(
+            self.cascades.len() <= shadow_maps.layers() as usize,
+            "not enough shadow map layers!"
+        );
+        let (previous, viewport) = (ctx.framebuffer_binding(), ctx.viewport_rect());
+        for (index, cascade) in self.cascades.iter().enumerate() {
+            framebuffer.attach_layer(ctx, Attachment::Depth, shadow_maps, index as i32);
+            ctx.begin_depth_pass(
+                framebuffer,
+                Rect::new(0, 0, self.resolution, self.resolution),
+            );
+            ctx.clear(ClearFlags::DEPTH);
+            draw(ctx, index, cascade);
+        }
+
+        ctx.restore_framebuffer(previous);
+        ctx.set_viewport(viewport.x, viewport.y, viewport.width, viewport.height);
+    }
+}
+
+/// Split distances between `near` and `far` for `count` cascades, including both ends.
+///
+/// `lambda` blends between uniform (0) and logarithmic (1) splits; logarithmic splits match perspective aliasing
+/// but leave the first cascade tiny.
+pub fn cascade_splits(near: f32, far: f32, count: usize, lambda: f32) -> Vec<f32> {
+    (0..=count)
+        .map(|i| {
+            let t = i as f32 / count as f32;
+            let log = near * (far / near).powf(t);
+            let uniform = near + (far - near) * t;
+            lambda * log + (1.0 - lambda) * uniform
+        })
+        .collect()
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn length(a: [f32; 3]) -> f32 {
+    dot(a, a).sqrt()
+}
+
+fn normalize(a: [f32; 3]) -> [f32; 3] {
+    let len = length(a);
+    [a[0] / len, a[1] / len, a[2] / len]
+}
+
+fn multiply(a: &[f32; 16], b: &[f32; 16]) -> [f32; 16] {
+    let mut out = [0.0; 16];
+    for col in 0..4 {
+        for row in 0..4 {
+            out[col * 4 + row] = (0..4).map(|k| a[k * 4 + row] * b[col * 4 + k]).sum();
+        }
+    }
+    out
+}
+
+fn transform(m: &[f32; 16], v: [f32; 4]) -> [f32; 4] {
+    let mut out = [0.0; 4];
+    for (row, value) in out.iter_mut().enumerate() {
+        *value = (0..4).map(|k| m[k * 4 + row] * v[k]).sum();
+    }
+    out
+}
+
+/// Inverse of a rotation + translation matrix.
+fn rigid_inverse(m: &[f32; 16]) -> [f32; 16] {
+    let t = [m[12], m[13], m[14]];
+    let columns = [[m[0], m[1], m[2]], [m[4], m[5], m[6]], [m[8], m[9], m[10]]];
+    #[rustfmt::skip]
+    let inverse = [
+        m[0], m[4], m[8], 0.0,
+        m[1], m[5], m[9], 0.0,
+        m[2], m[6], m[10], 0.0,
+        -dot(columns[0], t), -dot(columns[1], t), -dot(columns[2], t), 1.0,
+    ];
+    inverse
+}
+
+fn look_at(eye: [f32; 3], target: [f32; 3], up: [f32; 3]) -> [f32; 16] {
+    let f = normalize(sub(target, eye));
+    let s = normalize(cross(f, up));
+    let u = cross(s, f);
+    #[rustfmt::skip]
+    let view = [
+        s[0], u[0], -f[0], 0.0,
+        s[1], u[1], -f[1], 0.0,
+        s[2], u[2], -f[2], 0.0,
+        -dot(s, eye), -dot(u, eye), dot(f, eye), 1.0,
+    ];
+    view
+}
+
+fn ortho(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> [f32; 16] {
+    #[rustfmt::skip]
+    let projection = [
+        2.0 / (right - left), 0.0, 0.0, 0.0,
+        0.0, 2.0 / (top - bottom), 0.0, 0.0,
+        0.0, 0.0, -2.0 / (far - near), 0.0,
+        -(right + left) / (right - left), -(top + bottom) / (top - bottom), -(far + near) / (far - near), 1.0,
+    ];
+    projection
+}
@@ -10,3 +10,11 @@ bitflags! {
         const STENCIL = STENCIL_BUFFER_BIT;
     }
 }
+
+/// The clear values last set through [`ManagedContext`], `None` if unknown.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct ClearState {
+    pub(crate) color: Option<[f32; 4]>,
+    pub(crate) depth: Option<f32>,
+    pub(crate) stencil: Option<i32>,
+}
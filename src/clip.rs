@@ -0,0 +1,80 @@
+use super::*;
+
+/// The deepest supported nesting of [`Drawable::with_clip_shape`], limited by an 8-bit stencil buffer.
+pub const MAX_CLIP_DEPTH: u32 = 255;
+
+/// Stencil state that only passes inside the clip shapes at `depth`, applying `op` where it passes.
+fn clip_stencil(depth: u32, op: StencilOp) -> StencilState {
+    let func = StencilFuncState {
+        mask: 0xFF,
+        func: StencilFunc::Equal,
+        sref: depth as i32,
+    };
+    let ops = [StencilOp::Keep, StencilOp::Keep, op];
+    StencilState {
+        front: func.clone(),
+        back: func,
+        front_stencil_op: ops,
+        back_stencil_op: ops,
+        ..Default::default()
+    }
+}
+
+impl Drawable<'_> {
+    /// Draw `clipped` clipped to the shape drawn by `mask`, e.g. for scroll views and rounded UI panels.
+    ///
+    /// `mask` is drawn into the stencil buffer only, then `clipped` is drawn where the mask covered. Calls nest:
+    /// inside `clipped`, further clip shapes are intersected with this one. `mask` is drawn a second time afterwards
+    /// to remove the shape again, so it must draw the same geometry both times.
+    ///
+    /// The bound framebuffer needs a stencil buffer cleared to 0. The pipeline's own stencil state is restored
+    /// once the outermost clip ends.
+    ///
+    /// ## Panics
+    /// Clips must not be nested deeper than [`MAX_CLIP_DEPTH`].
+    pub fn with_clip_shape(
+        &mut self,
+        mut mask: impl FnMut(&mut Self),
+        clipped: impl FnOnce(&mut Self),
+    ) {
+        let depth = self.ctx.clip_depth;
+        assert!(depth < MAX_CLIP_DEPTH, "too many nested clip shapes!");
+
+        self.draw_clip_mask(depth, StencilOp::Increment, &mut mask);
+        self.ctx.clip_depth = depth + 1;
+        unsafe {
+            self.ctx
+                .apply_stencil(Some(&clip_stencil(depth + 1, StencilOp::Keep)));
+        }
+
+        clipped(self);
+
+        self.draw_clip_mask(depth + 1, StencilOp::Decrement, &mut mask);
+        self.ctx.clip_depth = depth;
+        unsafe {
+            if depth == 0 {
+                self.ctx.apply_stencil(self.pipeline.stencil_state.as_ref());
+            } else {
+                self.ctx
+                    .apply_stencil(Some(&clip_stencil(depth, StencilOp::Keep)));
+            }
+        }
+    }
+
+    /// Draw `mask` into the stencil buffer only, applying `op` inside the clip shapes at `depth`.
+    fn draw_clip_mask(&mut self, depth: u32, op: StencilOp, mask: &mut impl FnMut(&mut Self)) {
+        unsafe {
+            self.ctx.apply_stencil(Some(&clip_stencil(depth, op)));
+            self.ctx.gl.color_mask(false, false, false, false);
+            self.ctx.gl.depth_mask(false);
+        }
+
+        mask(self);
+
+        let [r, g, b, a] = self.pipeline.color_write;
+        unsafe {
+            self.ctx.gl.color_mask(r, g, b, a);
+            self.ctx.gl.depth_mask(self.pipeline.depth_write);
+        }
+    }
+}
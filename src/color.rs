@@ -0,0 +1,155 @@
+use super::*;
+
+/// An RGBA color with sRGB-encoded color components and linear alpha, as colors are usually authored, e.g. in CSS
+/// or color pickers.
+///
+/// Blending and lighting math happens on linear values, see [`Color::to_linear`]. Functions taking a `Color` pick
+/// the encoding the framebuffer expects, so callers don't convert by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const TRANSPARENT: Self = Self::new(0.0, 0.0, 0.0, 0.0);
+    pub const BLACK: Self = Self::new(0.0, 0.0, 0.0, 1.0);
+    pub const WHITE: Self = Self::new(1.0, 1.0, 1.0, 1.0);
+
+    /// Create a color from sRGB-encoded components.
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Create an opaque color from 8-bit sRGB components.
+    pub fn rgb8(r: u8, g: u8, b: u8) -> Self {
+        Self::rgba8(r, g, b, 255)
+    }
+
+    /// Create a color from 8-bit sRGB components.
+    pub fn rgba8(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self::new(
+            r as f32 / 255.0,
+            g as f32 / 255.0,
+            b as f32 / 255.0,
+            a as f32 / 255.0,
+        )
+    }
+
+    /// Parse a `#rgb`, `#rrggbb` or `#rrggbbaa` hex color. The `#` is optional.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        let digit = |i: usize| u8::from_str_radix(hex.get(i..i + 1)?, 16).ok();
+        let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        match hex.len() {
+            3 => Some(Self::rgb8(digit(0)? * 17, digit(1)? * 17, digit(2)? * 17)),
+            6 => Some(Self::rgb8(byte(0)?, byte(2)?, byte(4)?)),
+            8 => Some(Self::rgba8(byte(0)?, byte(2)?, byte(4)?, byte(6)?)),
+            _ => None,
+        }
+    }
+
+    /// Create a color from linear components.
+    pub fn from_linear(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self::new(linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b), a)
+    }
+
+    /// The same color with a different alpha.
+    pub fn with_alpha(self, a: f32) -> Self {
+        Self { a, ..self }
+    }
+
+    /// The linear components, e.g. for shader uniforms or vertex colors blended in linear space.
+    pub fn to_linear(&self) -> [f32; 4] {
+        [
+            srgb_to_linear(self.r),
+            srgb_to_linear(self.g),
+            srgb_to_linear(self.b),
+            self.a,
+        ]
+    }
+
+    /// The sRGB-encoded components.
+    pub fn to_srgb(&self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+}
+
+impl From<[f32; 4]> for Color {
+    /// Interpret the components as sRGB-encoded.
+    fn from(color: [f32; 4]) -> Self {
+        Self::new(color[0], color[1], color[2], color[3])
+    }
+}
+
+/// Decode a single sRGB-encoded component.
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encode a single linear component as sRGB.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// How the framebuffer stores written colors, for code that passes colors to shaders, e.g. the 2D renderers.
+///
+/// Passing sRGB-encoded colors to a framebuffer that encodes them again makes everything look washed out, so colors
+/// are linear for [`FramebufferEncoding::Srgb`] and sRGB-encoded for [`FramebufferEncoding::Linear`]. Blending
+/// happens on the values as stored, i.e. in linear space for sRGB framebuffers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FramebufferEncoding {
+    /// Follow the bound framebuffer, see [`ManagedContext::framebuffer_is_srgb`].
+    #[default]
+    Auto,
+    /// The framebuffer encodes written colors to sRGB.
+    Srgb,
+    /// The framebuffer stores written colors as-is.
+    Linear,
+}
+
+impl ManagedContext {
+    /// Returns true if colors must be passed linear for `encoding`, resolving [`FramebufferEncoding::Auto`] with
+    /// [`ManagedContext::framebuffer_is_srgb`].
+    pub fn encodes_srgb(&self, encoding: FramebufferEncoding) -> bool {
+        match encoding {
+            FramebufferEncoding::Auto => self.framebuffer_is_srgb(),
+            FramebufferEncoding::Srgb => true,
+            FramebufferEncoding::Linear => false,
+        }
+    }
+
+    /// The components of `color` as the bound framebuffer expects them: linear if the driver encodes them on write,
+    /// see [`ManagedContext::framebuffer_is_srgb`], sRGB-encoded otherwise.
+    pub fn framebuffer_color(&self, color: Color) -> [f32; 4] {
+        self.encode_color(color, FramebufferEncoding::Auto)
+    }
+
+    /// Like [`ManagedContext::framebuffer_color`], with an explicit encoding, e.g. for the `u_color` uniform of the
+    /// built-in 2D pipelines when rendering into a target whose encoding rapax doesn't track.
+    pub fn encode_color(&self, color: Color, encoding: FramebufferEncoding) -> [f32; 4] {
+        if self.encodes_srgb(encoding) {
+            color.to_linear()
+        } else {
+            color.to_srgb()
+        }
+    }
+
+    /// Set the constant color of [`BlendFactor::ConstantColor`] and [`BlendFactor::ConstantAlpha`].
+    pub fn set_blend_color(&self, color: impl Into<Color>) {
+        let [r, g, b, a] = self.framebuffer_color(color.into());
+        unsafe { self.gl.blend_color(r, g, b, a) };
+    }
+}
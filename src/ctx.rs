@@ -1,9 +1,12 @@
 use super::*;
 
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 use std::sync::Arc;
 
 /// The primitive mode used when calling draw\* functions.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u32)]
 pub enum DrawMode {
     /// Draws a triangle for a group of three vertices
@@ -28,27 +31,175 @@ impl DrawMode {
 pub struct ManagedContext {
     pub(crate) gl: Arc<glow::Context>,
     default_vao: NativeVertexArray,
+    vertex_arrays: HashMap<Vec<VertexAttributeDescriptor>, NativeVertexArray>,
+    driver_info: DriverInfo,
+    quirks: DriverQuirks,
+    pub(crate) bindless: Option<BindlessTextures>,
+    pub(crate) dsa: Option<DirectStateAccess>,
+    pub(crate) hint_fn: Option<HintFn>,
+    pub(crate) min_sample_shading_fn: Option<MinSampleShadingFn>,
+    pub(crate) get_active_uniforms_fn: Option<GetActiveUniformsFn>,
+    pub(crate) bind_frag_data_location_indexed_fn: Option<BindFragDataLocationIndexedFn>,
+    pub(crate) tex_buffer_fn: Option<TexBufferFn>,
+    #[cfg(feature = "culling")]
+    pub(crate) multi_draw_elements_indirect_fn: Option<MultiDrawElementsIndirectFn>,
+    pub(crate) raw_capabilities: HashMap<GlCapability, bool>,
+    pub(crate) uploads: UploadQueue,
+    pub(crate) features: Features,
+    pub(crate) capabilities: Capabilities,
+    pub(crate) capability_warning: Option<CapabilityWarningHandler>,
+    pub(crate) pipeline_warnings: HashSet<(NativeProgram, Features)>,
+    pub(crate) bindings: SharedBindingTables,
+    pub(crate) share_group: ShareGroup,
+    pub(crate) push_constants: Option<PushConstantRing>,
+    pub(crate) memory: SharedMemoryReport,
+    pub(crate) staging: StagingAlloc,
+    pub(crate) owner_thread: std::thread::ThreadId,
+    pub(crate) current_check: Option<CurrentContextCheck>,
+    pub(crate) state_verification: Option<u64>,
+    pub(crate) deletions: DeletionQueue,
+    pub(crate) clear_state: Cell<ClearState>,
+    pub(crate) framebuffer_srgb: Cell<bool>,
+    pub(crate) default_framebuffer_srgb: bool,
+    pub(crate) clip_depth: u32,
+    pub(crate) instancing_emulation: bool,
+    pub(crate) default_sampler: SamplerDescriptor,
+    pub(crate) textures: Registry<Texture2D>,
+    pub(crate) buffers: Registry<BufferHandle>,
+    #[cfg(all(target_os = "linux", feature = "dmabuf"))]
+    pub(crate) egl_image: Option<EglImageImport>,
 }
 
 impl ManagedContext {
     pub fn new(gl: Arc<glow::Context>) -> Self {
+        Self::new_shared(gl, &ShareGroup::new())
+    }
+
+    /// Create a context whose GL context shares objects with the other contexts of `share_group`.
+    ///
+    /// The GL contexts must have been created sharing objects, e.g. through glutin's `with_shared_lists`; rapax only
+    /// keeps its per-context state consistent. See [`ShareGroup`] for what can be shared.
+    pub fn new_shared(gl: Arc<glow::Context>, share_group: &ShareGroup) -> Self {
+        let driver_info = DriverInfo::query(&gl);
+        let quirks = driver_info.quirks();
+        let features = Features::query(&driver_info, &gl, quirks);
+        let capabilities = Capabilities::query(&driver_info, &gl, features);
+        let bindings = BindingTables::query(&gl);
+        share_group.join(&bindings);
         Self {
             gl: gl.clone(),
             default_vao: unsafe { gl.create_vertex_array().expect("vertex array is required") },
+            vertex_arrays: HashMap::new(),
+            driver_info,
+            quirks,
+            bindless: None,
+            dsa: None,
+            hint_fn: None,
+            min_sample_shading_fn: None,
+            get_active_uniforms_fn: None,
+            bind_frag_data_location_indexed_fn: None,
+            tex_buffer_fn: None,
+            #[cfg(feature = "culling")]
+            multi_draw_elements_indirect_fn: None,
+            raw_capabilities: HashMap::new(),
+            uploads: UploadQueue::new(),
+            features,
+            capabilities,
+            capability_warning: None,
+            pipeline_warnings: HashSet::new(),
+            bindings,
+            share_group: share_group.clone(),
+            push_constants: None,
+            memory: Default::default(),
+            staging: StagingAlloc::default(),
+            owner_thread: std::thread::current().id(),
+            current_check: None,
+            state_verification: None,
+            deletions: DeletionQueue::new(),
+            clear_state: Cell::new(ClearState::default()),
+            framebuffer_srgb: Cell::new(false),
+            default_framebuffer_srgb: false,
+            clip_depth: 0,
+            instancing_emulation: false,
+            default_sampler: SamplerDescriptor::default(),
+            textures: Registry::default(),
+            buffers: Registry::default(),
+            #[cfg(all(target_os = "linux", feature = "dmabuf"))]
+            egl_image: None,
         }
     }
 
+    /// The group of contexts sharing objects with this one.
+    pub fn share_group(&self) -> &ShareGroup {
+        &self.share_group
+    }
+
+    /// Information about the driver backing this context.
+    pub fn driver_info(&self) -> &DriverInfo {
+        &self.driver_info
+    }
+
+    /// Known driver quirks that apply to this context.
+    pub fn quirks(&self) -> DriverQuirks {
+        self.quirks
+    }
+
+    /// Returns true if the driver advertises the given extension, e.g. `GL_ARB_copy_image`.
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.gl.supported_extensions().contains(name)
+    }
+
     /// Create a scope in which the referenced pipeline is active.
+    ///
+    /// This is sugar for [`ManagedContext::bind_pipeline`].
     pub fn with_pipeline(
         &mut self,
         pipeline: &RenderPipeline,
         draw_cb: impl FnOnce(&mut Drawable),
     ) {
+        let mut drawable = self.bind_pipeline(pipeline);
+        draw_cb(&mut drawable);
+    }
+
+    /// Make the referenced pipeline active until the returned [`Drawable`] is dropped.
+    ///
+    /// Unlike [`ManagedContext::with_pipeline`], this allows early returns and `?` propagation while drawing.
+    pub fn bind_pipeline<'a>(&'a mut self, pipeline: &'a RenderPipeline) -> Drawable<'a> {
+        self.bind_pipeline_vertex_array(pipeline, None)
+    }
+
+    /// Like [`ManagedContext::with_pipeline`], but draws from a precomputed vertex array instead of calling
+    /// [`Drawable::apply_bindings`], if one is given.
+    pub fn with_pipeline_vertex_array(
+        &mut self,
+        pipeline: &RenderPipeline,
+        vertex_array: Option<&VertexArrayObject>,
+        draw_cb: impl FnOnce(&mut Drawable),
+    ) {
+        let mut drawable = self.bind_pipeline_vertex_array(pipeline, vertex_array);
+        draw_cb(&mut drawable);
+    }
+
+    /// Like [`ManagedContext::bind_pipeline`], but draws from a precomputed vertex array, if one is given.
+    ///
+    /// ## Panics
+    /// The vertex array must have been recorded for a pipeline with the same vertex layout.
+    pub fn bind_pipeline_vertex_array<'a>(
+        &'a mut self,
+        pipeline: &'a RenderPipeline,
+        vertex_array: Option<&VertexArrayObject>,
+    ) -> Drawable<'a> {
+        span!("bind_pipeline", program = pipeline.program.program.0.get());
+        self.debug_assert_current();
+        self.warn_missing_pipeline_features(pipeline);
         unsafe {
             if pipeline.blend_enabled {
+                let (mut src, mut dst) = pipeline.blend_func;
+                if !self.features.contains(Features::DUAL_SOURCE_BLEND) {
+                    (src, dst) = (single_source(src), single_source(dst));
+                }
                 self.gl.enable(BLEND);
-                self.gl
-                    .blend_func(pipeline.blend_func.0, pipeline.blend_func.1);
+                self.gl.blend_func(src, dst);
             } else {
                 self.gl.disable(BLEND);
             }
@@ -59,18 +210,51 @@ impl ManagedContext {
                 self.gl.disable(DEPTH_TEST);
             }
 
+            if self.features.contains(Features::DEPTH_CLAMP) {
+                if pipeline.depth_clamp {
+                    self.gl.enable(DEPTH_CLAMP);
+                } else {
+                    self.gl.disable(DEPTH_CLAMP);
+                }
+            }
+
+            if self.features.contains(Features::RASTERIZER_DISCARD) {
+                if pipeline.rasterizer_discard {
+                    self.gl.enable(RASTERIZER_DISCARD);
+                } else {
+                    self.gl.disable(RASTERIZER_DISCARD);
+                }
+            }
+
             self.gl.color_mask(
                 pipeline.color_write[0],
                 pipeline.color_write[1],
                 pipeline.color_write[2],
                 pipeline.color_write[3],
             );
+            // the mask above applies to every draw buffer, resetting the last pipeline's overrides
+            if self.features.contains(Features::DRAW_BUFFERS_INDEXED) {
+                for (attachment, [r, g, b, a]) in &pipeline.color_write_attachments {
+                    self.gl.color_mask_draw_buffer(*attachment, *r, *g, *b, *a);
+                }
+            }
 
             self.gl.depth_mask(pipeline.depth_write);
 
             self.gl.use_program(Some(pipeline.program.program));
 
-            self.gl.bind_vertex_array(Some(self.default_vao));
+            let vao = match vertex_array {
+                Some(vertex_array) => {
+                    assert_eq!(
+                        vertex_array.layout_id,
+                        pipeline.vertex_layout_id(),
+                        "vertex array layout does not match the pipeline"
+                    );
+                    vertex_array.vao
+                }
+                None => self.vertex_array_for(pipeline),
+            };
+            self.gl.bind_vertex_array(Some(vao));
 
             if pipeline.scissor_enabled {
                 self.gl.enable(SCISSOR_TEST);
@@ -78,92 +262,232 @@ impl ManagedContext {
                 self.gl.disable(SCISSOR_TEST);
             }
 
-            match &pipeline.stencil_state {
-                Some(stencil) => {
-                    self.gl.enable(STENCIL_TEST);
-                    self.gl.stencil_mask_separate(FRONT, stencil.front_mask);
-                    self.gl.stencil_mask_separate(BACK, stencil.back_mask);
-                    self.gl.stencil_func_separate(
-                        FRONT,
-                        stencil.front.func as _,
-                        stencil.front.sref,
-                        stencil.front.mask,
-                    );
-                    self.gl.stencil_func_separate(
-                        BACK,
-                        stencil.back.func as _,
-                        stencil.back.sref,
-                        stencil.back.mask,
-                    );
-                    self.gl.stencil_op_separate(
-                        FRONT,
-                        stencil.front_stencil_op[0] as _,
-                        stencil.front_stencil_op[1] as _,
-                        stencil.front_stencil_op[2] as _,
-                    );
-                    self.gl.stencil_op_separate(
-                        BACK,
-                        stencil.back_stencil_op[0] as _,
-                        stencil.back_stencil_op[1] as _,
-                        stencil.back_stencil_op[2] as _,
-                    )
-                }
-                None => self.gl.disable(STENCIL_TEST),
-            }
+            self.apply_stencil(pipeline.stencil_state.as_ref());
+
+            self.apply_smoothing(pipeline);
+
+            self.apply_sample_shading(pipeline);
         }
 
-        draw_cb(&mut Drawable {
+        Drawable {
             ctx: self,
             pipeline,
             current_program: pipeline.program.clone(),
-            vertices_applied: false,
-            has_index_buffer: false,
-        });
+            vertices_applied: vertex_array.is_some(),
+            has_index_buffer: vertex_array
+                .is_some_and(|vertex_array| vertex_array.has_index_buffer),
+            index_type: vertex_array.and_then(|vertex_array| vertex_array.index_type),
+            explicit_vertex_array: vertex_array.is_some(),
+        }
+    }
 
-        // disable vertex attribs
-        for i in 0..pipeline.vertex_attributes.len() {
-            unsafe { self.gl.disable_vertex_attrib_array(i as _) }
+    /// Apply a pipeline's stencil state, disabling the stencil test if `None`.
+    pub(crate) unsafe fn apply_stencil(&self, stencil: Option<&StencilState>) {
+        match stencil {
+            Some(stencil) => {
+                self.gl.enable(STENCIL_TEST);
+                self.gl.stencil_mask_separate(FRONT, stencil.front_mask);
+                self.gl.stencil_mask_separate(BACK, stencil.back_mask);
+                self.gl.stencil_func_separate(
+                    FRONT,
+                    stencil.front.func as _,
+                    stencil.front.sref,
+                    stencil.front.mask,
+                );
+                self.gl.stencil_func_separate(
+                    BACK,
+                    stencil.back.func as _,
+                    stencil.back.sref,
+                    stencil.back.mask,
+                );
+                self.gl.stencil_op_separate(
+                    FRONT,
+                    stencil.front_stencil_op[0] as _,
+                    stencil.front_stencil_op[1] as _,
+                    stencil.front_stencil_op[2] as _,
+                );
+                self.gl.stencil_op_separate(
+                    BACK,
+                    stencil.back_stencil_op[0] as _,
+                    stencil.back_stencil_op[1] as _,
+                    stencil.back_stencil_op[2] as _,
+                )
+            }
+            None => self.gl.disable(STENCIL_TEST),
         }
     }
 
+    /// The vertex array object shared by all pipelines with the same layout as `pipeline`.
+    fn vertex_array_for(&mut self, pipeline: &RenderPipeline) -> NativeVertexArray {
+        if pipeline.vertex_attributes.is_empty() {
+            return self.default_vao;
+        }
+
+        // keyed by the attributes rather than their hash, so colliding layouts never share formats
+        if let Some(vao) = self
+            .vertex_arrays
+            .get(pipeline.vertex_attributes.as_slice())
+        {
+            return *vao;
+        }
+        let vao = unsafe {
+            let vao = match self.dsa() {
+                Some(dsa) => dsa.create_vertex_array(),
+                None => self.gl.create_vertex_array().ok(),
+            }
+            .expect("vertex array is required");
+            if self.features.contains(Features::VERTEX_ATTRIB_BINDING) {
+                // pipelines sharing the layout share the formats, draws only swap buffers
+                if self.dsa().is_none() {
+                    self.gl.bind_vertex_array(Some(vao));
+                }
+                set_vertex_formats(self, vao, &pipeline.vertex_attributes);
+            }
+            vao
+        };
+        self.vertex_arrays
+            .insert(pipeline.vertex_attributes.clone(), vao);
+        vao
+    }
+
+    /// The number of pooled vertex array objects, one per distinct vertex layout.
+    pub fn vertex_array_count(&self) -> usize {
+        self.vertex_arrays.len()
+    }
+
     /// Clear specified buffers.
     pub fn clear(&self, mask: ClearFlags) {
+        self.debug_assert_current();
         unsafe {
             self.gl.clear(mask.bits());
         }
     }
 
-    /// Set the clear color.
-    pub fn set_clear_color(&self, color: [f32; 4]) {
-        unsafe { self.gl.clear_color(color[0], color[1], color[2], color[3]) };
+    /// Clear the color buffer to `color`, encoded as the bound framebuffer expects it.
+    pub fn clear_with(&self, color: impl Into<Color>) {
+        self.set_clear_color(color);
+        self.clear(ClearFlags::COLOR);
+    }
+
+    /// Set the clear color, encoded as the framebuffer expects it, see [`ManagedContext::framebuffer_color`].
+    ///
+    /// Setting the color it already has is skipped.
+    pub fn set_clear_color(&self, color: impl Into<Color>) {
+        let color = self.framebuffer_color(color.into());
+        let mut state = self.clear_state.get();
+        if state.color == Some(color) {
+            return;
+        }
+        let [r, g, b, a] = color;
+        unsafe { self.gl.clear_color(r, g, b, a) };
+        state.color = Some(color);
+        self.clear_state.set(state);
     }
 
     /// Set the depth clear value.
     pub fn set_depth_clear(&self, value: f32) {
+        let mut state = self.clear_state.get();
+        if state.depth == Some(value) {
+            return;
+        }
         unsafe { self.gl.clear_depth_f32(value) };
+        state.depth = Some(value);
+        self.clear_state.set(state);
     }
 
     /// Set the stencil clear value.
     pub fn set_stencil_clear(&self, value: i32) {
+        let mut state = self.clear_state.get();
+        if state.stencil == Some(value) {
+            return;
+        }
         unsafe { self.gl.clear_stencil(value) };
+        state.stencil = Some(value);
+        self.clear_state.set(state);
+    }
+
+    /// Forget the tracked clear values, e.g. after external code changed them behind rapax's back.
+    pub fn invalidate_clear_state(&self) {
+        self.clear_state.set(ClearState::default());
+    }
+
+    /// Forget the cached uniform values of every program, e.g. after external code set uniforms behind rapax's back.
+    /// See [`ShaderProgram::invalidate_uniform_cache`] to forget those of a single program.
+    pub fn invalidate_uniform_caches(&self) {
+        self.share_group.invalidate_uniform_caches();
     }
 
     /// Set the viewport position & dimensions.
     pub fn set_viewport(&self, x: i32, y: i32, w: i32, h: i32) {
+        self.debug_assert_current();
         unsafe { self.gl.viewport(x, y, w, h) };
     }
 }
 
-/// A pipeline draw context.
+/// A pipeline draw context. Vertex attribute state is reset when it is dropped.
 pub struct Drawable<'a> {
-    ctx: &'a mut ManagedContext,
-    pipeline: &'a RenderPipeline,
-    current_program: Arc<ShaderProgram>,
-    vertices_applied: bool,
-    has_index_buffer: bool,
+    pub(crate) ctx: &'a mut ManagedContext,
+    pub(crate) pipeline: &'a RenderPipeline,
+    pub(crate) current_program: Arc<ShaderProgram>,
+    pub(crate) vertices_applied: bool,
+    pub(crate) has_index_buffer: bool,
+    pub(crate) index_type: Option<DataType>,
+    explicit_vertex_array: bool,
 }
 
 impl<'a> Drawable<'a> {
+    /// The program of the currently applied pipeline.
+    pub fn program(&self) -> &ShaderProgram {
+        &self.current_program
+    }
+
+    /// Look up a uniform of the currently applied pipeline's program.
+    ///
+    /// ## Panics
+    /// The uniform must exist.
+    pub(crate) fn uniform_location(&self, name: &str) -> NativeUniformLocation {
+        let loc = unsafe {
+            self.ctx
+                .gl
+                .get_uniform_location(self.current_program.program, name)
+        };
+        loc.expect("No such uniform name!")
+    }
+
+    /// Whether `value` differs from the value last set to the uniform, recording it if so. Always true without the
+    /// `uniform-cache` feature.
+    #[cfg(feature = "uniform-cache")]
+    pub(crate) fn uniform_changed(&self, name: &str, value: &[u8], transpose: bool) -> bool {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        value.hash(&mut hasher);
+        transpose.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let mut cache = self.current_program.uniform_cache.lock().unwrap();
+        let generation = self.ctx.share_group.uniform_cache_generation.get();
+        if cache.generation != generation {
+            cache.values.clear();
+            cache.generation = generation;
+        }
+        match cache.values.get_mut(name) {
+            Some(cached) if *cached == hash => false,
+            Some(cached) => {
+                *cached = hash;
+                true
+            }
+            None => {
+                cache.values.insert(name.to_owned(), hash);
+                true
+            }
+        }
+    }
+
+    #[cfg(not(feature = "uniform-cache"))]
+    pub(crate) fn uniform_changed(&self, _name: &str, _value: &[u8], _transpose: bool) -> bool {
+        true
+    }
+
     /// Set scissor rect
     pub fn set_scissor(&self, x: i32, y: i32, w: i32, h: i32) {
         unsafe { self.ctx.gl.scissor(x, y, w, h) }
@@ -171,180 +495,295 @@ impl<'a> Drawable<'a> {
 
     /// Set a float4 uniform on the currently applied pipeline.
     pub fn set_uniform_float4(&self, name: &str, value: &[f32; 4]) {
+        if !self.uniform_changed(name, bytemuck::cast_slice(value), false) {
+            return;
+        }
         unsafe {
-            let program = self.current_program.program;
-            let loc = self.ctx.gl.get_uniform_location(program, name);
-            assert!(loc.is_some(), "No such uniform name!");
+            let loc = self.uniform_location(name);
             self.ctx
                 .gl
-                .uniform_4_f32(loc.as_ref(), value[0], value[1], value[2], value[3]);
+                .uniform_4_f32(Some(&loc), value[0], value[1], value[2], value[3]);
         }
     }
 
     /// Set a float3 uniform on the currently applied pipeline.
     pub fn set_uniform_float3(&self, name: &str, value: &[f32; 3]) {
+        if !self.uniform_changed(name, bytemuck::cast_slice(value), false) {
+            return;
+        }
         unsafe {
-            let program = self.current_program.program;
-            let loc = self.ctx.gl.get_uniform_location(program, name);
-            assert!(loc.is_some(), "No such uniform name!");
+            let loc = self.uniform_location(name);
             self.ctx
                 .gl
-                .uniform_3_f32(loc.as_ref(), value[0], value[1], value[2]);
+                .uniform_3_f32(Some(&loc), value[0], value[1], value[2]);
         }
     }
 
     /// Set a float3 uniform on the currently applied pipeline.
     pub fn set_uniform_float2(&self, name: &str, value: &[f32; 2]) {
+        if !self.uniform_changed(name, bytemuck::cast_slice(value), false) {
+            return;
+        }
         unsafe {
-            let program = self.current_program.program;
-            let loc = self.ctx.gl.get_uniform_location(program, name);
-            assert!(loc.is_some(), "No such uniform name!");
-            self.ctx.gl.uniform_2_f32(loc.as_ref(), value[0], value[1]);
+            let loc = self.uniform_location(name);
+            self.ctx.gl.uniform_2_f32(Some(&loc), value[0], value[1]);
         }
     }
 
     /// Set a float1 uniform on the currently applied pipeline.
     pub fn set_uniform_float1(&self, name: &str, value: f32) {
+        if !self.uniform_changed(name, bytemuck::bytes_of(&value), false) {
+            return;
+        }
         unsafe {
-            let program = self.current_program.program;
-            let loc = self.ctx.gl.get_uniform_location(program, name);
-            assert!(loc.is_some(), "No such uniform name!");
-            self.ctx.gl.uniform_1_f32(loc.as_ref(), value);
+            let loc = self.uniform_location(name);
+            self.ctx.gl.uniform_1_f32(Some(&loc), value);
         }
     }
 
     /// Set a int4 uniform on the currently applied pipeline.
     pub fn set_uniform_int4(&self, name: &str, value: &[i32; 4]) {
+        if !self.uniform_changed(name, bytemuck::cast_slice(value), false) {
+            return;
+        }
         unsafe {
-            let program = self.current_program.program;
-            let loc = self.ctx.gl.get_uniform_location(program, name);
-            assert!(loc.is_some(), "No such uniform name!");
+            let loc = self.uniform_location(name);
             self.ctx
                 .gl
-                .uniform_4_i32(loc.as_ref(), value[0], value[1], value[2], value[3]);
+                .uniform_4_i32(Some(&loc), value[0], value[1], value[2], value[3]);
         }
     }
 
     /// Set a int3 uniform on the currently applied pipeline.
     pub fn set_uniform_int3(&self, name: &str, value: &[i32; 3]) {
+        if !self.uniform_changed(name, bytemuck::cast_slice(value), false) {
+            return;
+        }
         unsafe {
-            let program = self.current_program.program;
-            let loc = self.ctx.gl.get_uniform_location(program, name);
-            assert!(loc.is_some(), "No such uniform name!");
+            let loc = self.uniform_location(name);
             self.ctx
                 .gl
-                .uniform_3_i32(loc.as_ref(), value[0], value[1], value[2]);
+                .uniform_3_i32(Some(&loc), value[0], value[1], value[2]);
         }
     }
 
     /// Set a int3 uniform on the currently applied pipeline.
     pub fn set_uniform_int2(&self, name: &str, value: &[i32; 2]) {
+        if !self.uniform_changed(name, bytemuck::cast_slice(value), false) {
+            return;
+        }
         unsafe {
-            let program = self.current_program.program;
-            let loc = self.ctx.gl.get_uniform_location(program, name);
-            assert!(loc.is_some(), "No such uniform name!");
-            self.ctx.gl.uniform_2_i32(loc.as_ref(), value[0], value[1]);
+            let loc = self.uniform_location(name);
+            self.ctx.gl.uniform_2_i32(Some(&loc), value[0], value[1]);
         }
     }
 
     /// Set a int1 uniform on the currently applied pipeline.
     pub fn set_uniform_int1(&self, name: &str, value: i32) {
+        if !self.uniform_changed(name, bytemuck::bytes_of(&value), false) {
+            return;
+        }
         unsafe {
-            let program = self.current_program.program;
-            let loc = self.ctx.gl.get_uniform_location(program, name);
-            assert!(loc.is_some(), "No such uniform name!");
-            self.ctx.gl.uniform_1_i32(loc.as_ref(), value);
+            let loc = self.uniform_location(name);
+            self.ctx.gl.uniform_1_i32(Some(&loc), value);
         }
     }
 
     /// Set a mat2 uniform on the currently applied pipeline.
     /// If you're not sure what `transpose` means, simply make it false.
     pub fn set_uniform_mat2(&self, name: &str, value: &[f32; 4], transpose: bool) {
+        if !self.uniform_changed(name, bytemuck::cast_slice(value), transpose) {
+            return;
+        }
         unsafe {
-            let program = self.current_program.program;
-            let loc = self.ctx.gl.get_uniform_location(program, name);
-            assert!(loc.is_some(), "No such uniform name!");
+            let loc = self.uniform_location(name);
             self.ctx
                 .gl
-                .uniform_matrix_2_f32_slice(loc.as_ref(), transpose, value);
+                .uniform_matrix_2_f32_slice(Some(&loc), transpose, value);
         }
     }
 
     /// Set a mat3 uniform on the currently applied pipeline.
     /// If you're not sure what `transpose` means, simply make it false.
     pub fn set_uniform_mat3(&self, name: &str, value: &[f32; 9], transpose: bool) {
+        if !self.uniform_changed(name, bytemuck::cast_slice(value), transpose) {
+            return;
+        }
         unsafe {
-            let program = self.current_program.program;
-            let loc = self.ctx.gl.get_uniform_location(program, name);
-            assert!(loc.is_some(), "No such uniform name!");
+            let loc = self.uniform_location(name);
             self.ctx
                 .gl
-                .uniform_matrix_3_f32_slice(loc.as_ref(), transpose, value);
+                .uniform_matrix_3_f32_slice(Some(&loc), transpose, value);
         }
     }
 
     /// Set a mat4 uniform on the currently applied pipeline.
     /// If you're not sure what `transpose` means, simply make it false.
     pub fn set_uniform_mat4(&self, name: &str, value: &[f32; 16], transpose: bool) {
+        if !self.uniform_changed(name, bytemuck::cast_slice(value), transpose) {
+            return;
+        }
         unsafe {
-            let program = self.current_program.program;
-            let loc = self.ctx.gl.get_uniform_location(program, name);
-            assert!(loc.is_some(), "No such uniform name!");
+            let loc = self.uniform_location(name);
             self.ctx
                 .gl
-                .uniform_matrix_4_f32_slice(loc.as_ref(), transpose, value);
+                .uniform_matrix_4_f32_slice(Some(&loc), transpose, value);
         }
     }
 
     /// Bind vertex buffer(s) and index buffer.
+    ///
+    /// With [`Features::VERTEX_ATTRIB_BINDING`], only the buffers are attached, the attribute formats are recorded
+    /// once per vertex layout.
     pub fn apply_bindings(
         &mut self,
         vertex_buffers: &[impl BindableBuffer],
         index_buffer: Option<impl BindableBuffer>,
     ) {
-        self.vertices_applied = true;
+        let index_type = self.bind_buffers(vertex_buffers, index_buffer);
+        self.record_bindings(index_type);
+    }
+
+    /// Bind the buffers of [`Drawable::apply_bindings`], returning the index type if an index buffer was bound.
+    pub(crate) fn bind_buffers(
+        &self,
+        vertex_buffers: &[impl BindableBuffer],
+        index_buffer: Option<impl BindableBuffer>,
+    ) -> Option<Option<DataType>> {
+        span!(
+            "apply_bindings",
+            attributes = self.pipeline.vertex_attributes.len()
+        );
+        assert!(
+            !self.explicit_vertex_array,
+            "bindings are part of the explicit vertex array"
+        );
         // setup vaos
-        for (idx, attr) in self.pipeline.vertex_attributes.iter().enumerate() {
-            let buffer = &vertex_buffers[attr.buffer_index];
-            unsafe {
-                buffer.bind(ARRAY_BUFFER, &self.ctx.gl);
-
-                self.ctx.gl.vertex_attrib_pointer_f32(
-                    idx as _,
-                    attr.size,
-                    attr.ty as _,
-                    attr.normalized,
-                    attr.stride,
-                    attr.offset,
-                );
-                //self.ctx.gl.vertex_attrib_divisor(idx as _, attr.divisor);
-                self.ctx.gl.enable_vertex_attrib_array(idx as _);
+        unsafe {
+            let attributes = &self.pipeline.vertex_attributes;
+            let attached = self.ctx.features.contains(Features::VERTEX_ATTRIB_BINDING)
+                && bind_vertex_buffers(&self.ctx.gl, attributes, vertex_buffers);
+            if !attached {
+                set_vertex_attributes(self.ctx, attributes, vertex_buffers);
             }
         }
 
-        unsafe {
-            if let Some(index_buffer) = index_buffer {
-                index_buffer.bind(ELEMENT_ARRAY_BUFFER, &self.ctx.gl);
-                self.has_index_buffer = true;
-            }
+        index_buffer.map(|index_buffer| {
+            unsafe { index_buffer.bind(ELEMENT_ARRAY_BUFFER, &self.ctx.gl) };
+            index_buffer.index_type()
+        })
+    }
+
+    /// Record the buffers bound by [`Drawable::bind_buffers`] for the next draw.
+    pub(crate) fn record_bindings(&mut self, index_type: Option<Option<DataType>>) {
+        self.vertices_applied = true;
+        if let Some(index_type) = index_type {
+            self.has_index_buffer = true;
+            self.index_type = index_type;
         }
     }
 
     /// Bind textures and sets the corresponding uniform.
+    ///
+    /// Units are assigned in slice order, replacing the automatic units used by [`Drawable::bind_texture_unit`].
+    /// Textures already bound to their unit are not re-bound.
+    ///
+    /// ## Panics
+    /// At most [`ManagedContext::user_texture_units`] textures can be applied, and each texture must match the type
+    /// of its sampler: a cubemap can't be bound to a `sampler2D`, a 2D texture can't be bound to a `samplerCube` or
+    /// `sampler2DArray`, and shadow samplers need a depth texture.
     pub fn apply_textures(&self, textures: &[(&dyn BindableTexture, &str)]) {
+        assert!(
+            textures.len() <= self.ctx.user_texture_units() as usize,
+            "too many textures!"
+        );
         for (unit, (texture, uniform_name)) in textures.iter().enumerate() {
+            self.check_sampler(uniform_name, *texture);
             unsafe {
-                self.ctx.gl.active_texture(TEXTURE0 + unit as u32);
-                texture.bind(texture.texture_target_hint(), &self.ctx.gl);
+                self.ctx
+                    .bind_texture_to_unit(unit as u32, texture.texture_target_hint(), *texture);
                 self.set_uniform_int1(uniform_name, unit as i32);
             }
         }
     }
 
+    /// Like [`Drawable::apply_textures`], on units reserved with [`ManagedContext::reserve_texture_units`].
+    ///
+    /// ## Panics
+    /// The reservation must cover every texture, and each texture must match the type of its sampler.
+    pub fn apply_reserved_textures(
+        &self,
+        reservation: &TextureUnitReservation,
+        textures: &[(&dyn BindableTexture, &str)],
+    ) {
+        assert!(
+            textures.len() <= reservation.units().len(),
+            "too many textures for the reservation!"
+        );
+        for (unit, (texture, uniform_name)) in reservation.units().zip(textures) {
+            self.check_sampler(uniform_name, *texture);
+            unsafe {
+                self.ctx
+                    .bind_texture_to_unit(unit, texture.texture_target_hint(), *texture);
+                self.set_uniform_int1(uniform_name, unit as i32);
+            }
+        }
+    }
+
+    /// Bind uniform buffers and assign them to the corresponding uniform blocks.
+    ///
+    /// Buffers already bound to their binding point are not re-bound.
+    pub fn apply_uniform_buffers(&self, buffers: &[(&BufferHandle, &str)]) {
+        for (binding, (buffer, block_name)) in buffers.iter().enumerate() {
+            assert_eq!(
+                buffer.ty(),
+                BufferType::UniformBuffer,
+                "Attempted to bind buffer to invalid binding point"
+            );
+            self.current_program
+                .bind_uniform_block(block_name, binding as u32);
+            unsafe {
+                self.ctx
+                    .bind_uniform_buffer(binding as u32, buffer.buffer, 0, 0);
+            }
+        }
+    }
+
+    /// The element type to draw with: `ty` if given, otherwise the applied index buffer's.
+    ///
+    /// ## Panics
+    /// The type must be known, and must match the index buffer's if both are.
+    fn resolve_index_type(&self, ty: Option<DataType>) -> DataType {
+        match (ty, self.index_type) {
+            (Some(ty), Some(index_type)) => {
+                assert_eq!(
+                    ty, index_type,
+                    "index type does not match the index buffer!"
+                );
+                ty
+            }
+            (Some(ty), None) | (None, Some(ty)) => ty,
+            (None, None) => panic!("index buffer has no index type!"),
+        }
+    }
+
     /// Render primitives using bound vertex data & index data.
-    pub fn draw_elements(&mut self, mode: DrawMode, count: u32, ty: DataType, offset: i32) {
+    ///
+    /// Pass `None` as `ty` to use the index buffer's element type, see [`BufferHandle::index_buffer_from`].
+    ///
+    /// ## Panics
+    /// `ty` must match the index buffer's element type if it is known, and the type must be known if `ty` is `None`.
+    pub fn draw_elements(
+        &mut self,
+        mode: DrawMode,
+        count: u32,
+        ty: impl Into<Option<DataType>>,
+        offset: i32,
+    ) {
+        span!("draw_elements", ?mode, count);
         assert!(self.vertices_applied, "no buffers were applied");
         assert!(self.has_index_buffer, "no index buffer was applied");
+        let ty = self.resolve_index_type(ty.into());
         unsafe {
             self.ctx
                 .gl
@@ -353,15 +792,28 @@ impl<'a> Drawable<'a> {
     }
 
     /// Render primitives using bound vertex data & index data, with instancing.
+    ///
+    /// See [`Drawable::draw_elements`] for `ty`, and [`ManagedContext::set_instancing_emulation`] for contexts without
+    /// instanced draws.
     pub fn draw_elements_instanced(
         &self,
         mode: DrawMode,
         count: u32,
-        ty: DataType,
+        ty: impl Into<Option<DataType>>,
         offset: i32,
         instances: u32,
     ) {
+        span!("draw_elements_instanced", ?mode, count, instances);
         assert!(self.vertices_applied, "no buffers were applied");
+        let ty = self.resolve_index_type(ty.into());
+        let draw = || unsafe {
+            self.ctx
+                .gl
+                .draw_elements(mode.to_gl(), count as i32, ty as u32, offset)
+        };
+        if self.emulate_instances(instances, draw) {
+            return;
+        }
         unsafe {
             self.ctx.gl.draw_elements_instanced(
                 mode.to_gl(),
@@ -373,21 +825,110 @@ impl<'a> Drawable<'a> {
         }
     }
 
+    /// Render the indices in `range`, offset by `base_vertex`, computing the byte offset from the index buffer's
+    /// element type, see [`BufferHandle::with_index_type`].
+    ///
+    /// A non-zero `base_vertex` requires OpenGL 3.2 or OpenGL ES 3.2. More than one instance is emulated where
+    /// enabled, see [`ManagedContext::set_instancing_emulation`].
+    ///
+    /// ## Panics
+    /// The applied index buffer must have a known element type.
+    pub fn draw_indexed(
+        &self,
+        mode: DrawMode,
+        range: Range<u32>,
+        base_vertex: i32,
+        instances: u32,
+    ) {
+        span!("draw_indexed", ?mode, ?range, base_vertex, instances);
+        assert!(self.vertices_applied, "no buffers were applied");
+        assert!(self.has_index_buffer, "no index buffer was applied");
+        let ty = self.index_type.expect("index buffer has no index type!");
+        let count = range.len() as i32;
+        let offset = (range.start as usize * ty.sizeof()) as i32;
+        if instances != 1
+            && self.emulate_instances(instances, || {
+                self.draw_indexed(mode, range.clone(), base_vertex, 1)
+            })
+        {
+            return;
+        }
+        unsafe {
+            match (base_vertex, instances) {
+                (0, 1) => self
+                    .ctx
+                    .gl
+                    .draw_elements(mode.to_gl(), count, ty.to_gl(), offset),
+                (0, _) => self.ctx.gl.draw_elements_instanced(
+                    mode.to_gl(),
+                    count,
+                    ty.to_gl(),
+                    offset,
+                    instances as _,
+                ),
+                (_, 1) => self.ctx.gl.draw_elements_base_vertex(
+                    mode.to_gl(),
+                    count,
+                    ty.to_gl(),
+                    offset,
+                    base_vertex,
+                ),
+                _ => self.ctx.gl.draw_elements_instanced_base_vertex(
+                    mode.to_gl(),
+                    count,
+                    ty.to_gl(),
+                    offset,
+                    instances as _,
+                    base_vertex,
+                ),
+            }
+        }
+    }
+
     /// Render primitives using previously applied vertex and texture data.
     pub fn draw_arrays(&self, mode: DrawMode, first: i32, count: i32) {
+        span!("draw_arrays", ?mode, first, count);
         assert!(self.vertices_applied, "no buffers were applied");
         unsafe {
-            self.ctx.gl.draw_arrays(mode.to_gl(), first as i32, count);
+            self.ctx.gl.draw_arrays(mode.to_gl(), first, count);
         }
     }
 
     /// Render primitives using bound vertex data, with instancing.
+    ///
+    /// See [`ManagedContext::set_instancing_emulation`] for contexts without instanced draws.
     pub fn draw_arrays_instanced(&self, mode: DrawMode, first: i32, count: i32, instances: u32) {
+        span!("draw_arrays_instanced", ?mode, first, count, instances);
         assert!(self.vertices_applied, "no buffers were applied");
+        if self.emulate_instances(instances, || self.draw_arrays(mode, first, count)) {
+            return;
+        }
         unsafe {
             self.ctx
                 .gl
-                .draw_arrays_instanced(mode.to_gl(), first as i32, count, instances as _);
+                .draw_arrays_instanced(mode.to_gl(), first, count, instances as _);
+        }
+    }
+}
+
+impl<'a> Drop for Drawable<'a> {
+    fn drop(&mut self) {
+        // later draws outside a pipeline, e.g. clears & blits, must not be discarded
+        if self.pipeline.rasterizer_discard
+            && self.ctx.features.contains(Features::RASTERIZER_DISCARD)
+        {
+            unsafe { self.ctx.gl.disable(RASTERIZER_DISCARD) };
+        }
+
+        // explicit vertex arrays keep their attributes enabled, but must not pick up later buffer binds
+        if self.explicit_vertex_array {
+            unsafe { self.ctx.gl.bind_vertex_array(Some(self.ctx.default_vao)) };
+            return;
+        }
+
+        // disable vertex attribs
+        for location in attribute_locations(&self.pipeline.vertex_attributes) {
+            unsafe { self.ctx.gl.disable_vertex_attrib_array(location) }
         }
     }
 }
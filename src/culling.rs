@@ -0,0 +1,310 @@
+use super::*;
+
+use std::ffi::c_void;
+
+/// `glMultiDrawElementsIndirect`, which glow does not expose.
+pub(crate) type MultiDrawElementsIndirectFn = unsafe extern "system" fn(
+    mode: u32,
+    ty: u32,
+    indirect: *const c_void,
+    draw_count: i32,
+    stride: i32,
+);
+
+/// A range of the shared index buffer drawn by a [`GpuCuller`], usually one mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CullDraw {
+    pub index_count: u32,
+    pub first_index: u32,
+    pub base_vertex: i32,
+}
+
+/// An instance culled by a [`GpuCuller`]: a world-space bounding box and the index of the [`CullDraw`] it is drawn
+/// with. Laid out like the std430 struct read by the culling shader.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CullInstance {
+    pub aabb_min: [f32; 3],
+    pub draw: u32,
+    pub aabb_max: [f32; 3],
+    pub _padding: u32,
+}
+
+// SAFETY: `CullInstance` is `repr(C)` and consists of 4-byte fields only, so it has no padding.
+unsafe impl bytemuck::Zeroable for CullInstance {}
+unsafe impl bytemuck::Pod for CullInstance {}
+
+/// `DrawElementsIndirectCommand`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct IndirectCommand {
+    count: u32,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    base_instance: u32,
+}
+
+// SAFETY: `IndirectCommand` is `repr(C)` and consists of 4-byte fields only, so it has no padding.
+unsafe impl bytemuck::Zeroable for IndirectCommand {}
+unsafe impl bytemuck::Pod for IndirectCommand {}
+
+const CULL_CS: &str = r#"
+layout(local_size_x = 64) in;
+
+struct Instance {
+    vec3 aabb_min;
+    uint draw;
+    vec3 aabb_max;
+    uint padding;
+};
+
+struct Command {
+    uint count;
+    uint instance_count;
+    uint first_index;
+    int base_vertex;
+    uint base_instance;
+};
+
+layout(std430, binding = 0) readonly buffer Instances { Instance instances[]; };
+layout(std430, binding = 1) buffer Commands { Command commands[]; };
+layout(std430, binding = 2) writeonly buffer Visible { uint visible[]; };
+layout(binding = 0, offset = 0) uniform atomic_uint u_visible_count;
+
+uniform mat4 u_view_projection;
+uniform uint u_instance_count;
+
+bool inside_frustum(vec3 aabb_min, vec3 aabb_max) {
+    mat4 m = transpose(u_view_projection);
+    vec4 planes[6] = vec4[6](m[3] + m[0], m[3] - m[0], m[3] + m[1], m[3] - m[1], m[3] + m[2], m[3] - m[2]);
+    for (int i = 0; i < 6; i++) {
+        // the corner furthest along the plane normal
+        vec3 p = mix(aabb_min, aabb_max, greaterThan(planes[i].xyz, vec3(0.0)));
+        if (dot(planes[i].xyz, p) + planes[i].w < 0.0) {
+            return false;
+        }
+    }
+    return true;
+}
+
+void main() {
+    uint id = gl_GlobalInvocationID.x;
+    if (id >= u_instance_count) {
+        return;
+    }
+    Instance instance = instances[id];
+    if (!inside_frustum(instance.aabb_min, instance.aabb_max)) {
+        return;
+    }
+    uint slot = atomicAdd(commands[instance.draw].instance_count, 1u);
+    visible[commands[instance.draw].base_instance + slot] = id;
+    atomicCounterIncrement(u_visible_count);
+}
+"#;
+
+/// Frustum culling of instance bounding boxes in a compute pass, producing one compacted indirect draw per
+/// [`CullDraw`] that is issued with a single multi-draw call.
+///
+/// Each frame, [`GpuCuller::cull`] writes the indices of visible instances, grouped by draw, into
+/// [`GpuCuller::visible_instances`]. Bind that buffer as a per-instance attribute, see [`GpuCuller::instance_layout`],
+/// and look up per-instance data such as transforms by that index, e.g. in a [`BufferTexture`]. Then call
+/// [`GpuCuller::draw`]; the CPU never reads back what is visible.
+///
+/// Requires [`Features::COMPUTE_SHADERS`], [`Features::MULTI_DRAW_INDIRECT`] and
+/// [`ManagedContext::load_multi_draw_indirect`].
+#[derive(Debug)]
+pub struct GpuCuller {
+    program: ShaderProgram,
+    draws: Vec<CullDraw>,
+    commands: Vec<IndirectCommand>,
+    instances: BufferHandle,
+    instance_count: u32,
+    indirect: BufferHandle,
+    visible: BufferHandle,
+    counter: BufferHandle,
+}
+
+impl ManagedContext {
+    /// Load `glMultiDrawElementsIndirect`, which [`GpuCuller`] needs.
+    ///
+    /// `loader` is the same function used to create the glow context, e.g. `window.get_proc_address`.
+    ///
+    /// # Safety
+    /// `loader` must return valid function pointers for the current context.
+    pub unsafe fn load_multi_draw_indirect(
+        &mut self,
+        mut loader: impl FnMut(&str) -> *const c_void,
+    ) -> Result<(), Error> {
+        self.require_features(Features::MULTI_DRAW_INDIRECT, "multi-draw indirect")
+            .map_err(|err| Error::Unsupported(err.to_string()))?;
+        let multi_draw_elements_indirect = [
+            "glMultiDrawElementsIndirect",
+            "glMultiDrawElementsIndirectEXT",
+        ]
+        .into_iter()
+        .find_map(|name| load_fn(&mut loader, name).ok());
+        self.multi_draw_elements_indirect_fn =
+            Some(multi_draw_elements_indirect.ok_or_else(|| {
+                Error::Unsupported("glMultiDrawElementsIndirect is not available".into())
+            })?);
+        Ok(())
+    }
+}
+
+impl GpuCuller {
+    /// Create a culler for instances of `draws`, which index into the index buffer applied when drawing.
+    pub fn new(ctx: &mut ManagedContext, draws: &[CullDraw]) -> Result<Self, Error> {
+        if ctx.multi_draw_elements_indirect_fn.is_none() {
+            return Err(Error::Unsupported(
+                "multi-draw indirect was not loaded".into(),
+            ));
+        }
+        let header = if ctx.driver_info().is_embedded {
+            "#version 310 es\nprecision highp float;\n"
+        } else {
+            "#version 430 core\n"
+        };
+        let program = ShaderProgram::compute(ctx, &format!("{}{}", header, CULL_CS))?;
+
+        let commands = vec![IndirectCommand::default(); draws.len()];
+        let mut culler = Self {
+            program,
+            draws: draws.to_vec(),
+            instances: BufferHandle::array_buffer(ctx, BufferUsage::Dynamic, &[])?,
+            instance_count: 0,
+            indirect: BufferHandle::array_buffer(
+                ctx,
+                BufferUsage::Dynamic,
+                bytemuck::cast_slice(&commands),
+            )?,
+            commands,
+            visible: BufferHandle::array_buffer(ctx, BufferUsage::Dynamic, &[])?,
+            counter: BufferHandle::array_buffer(ctx, BufferUsage::Dynamic, &[0; 4])?,
+        };
+        culler.set_instances(ctx, &[]);
+        Ok(culler)
+    }
+
+    /// Replace the instances to cull.
+    ///
+    /// ## Panics
+    /// Every instance must refer to one of the culler's draws.
+    pub fn set_instances(&mut self, ctx: &ManagedContext, instances: &[CullInstance]) {
+        span!("cull_set_instances", instances = instances.len());
+        let mut counts = vec![0; self.draws.len()];
+        for instance in instances {
+            assert!(
+                (instance.draw as usize) < self.draws.len(),
+                "instance refers to a missing draw!"
+            );
+            counts[instance.draw as usize] += 1;
+        }
+
+        // each draw gets a slot for every one of its instances, so all of them can be visible
+        let mut base_instance = 0;
+        for ((command, draw), count) in self.commands.iter_mut().zip(&self.draws).zip(counts) {
+            *command = IndirectCommand {
+                count: draw.index_count,
+                instance_count: 0,
+                first_index: draw.first_index,
+                base_vertex: draw.base_vertex,
+                base_instance,
+            };
+            base_instance += count;
+        }
+
+        self.instances
+            .realloc(ctx, BufferUsage::Dynamic, bytemuck::cast_slice(instances));
+        self.visible
+            .realloc(ctx, BufferUsage::Dynamic, &vec![0; instances.len() * 4]);
+        self.instance_count = instances.len() as u32;
+    }
+
+    /// Cull the instances against the frustum of `view_projection`, a column-major matrix, and write the visible
+    /// instances and draw commands for [`GpuCuller::draw`].
+    pub fn cull(&self, ctx: &ManagedContext, view_projection: &[f32; 16]) {
+        span!("gpu_cull", instances = self.instance_count);
+        self.indirect
+            .update(ctx, 0, bytemuck::cast_slice(&self.commands));
+        self.counter.clear(ctx, &[0; 4]);
+        if self.instance_count == 0 {
+            return;
+        }
+
+        let gl = &ctx.gl;
+        unsafe {
+            gl.use_program(Some(self.program.program));
+            let location = gl.get_uniform_location(self.program.program, "u_view_projection");
+            gl.uniform_matrix_4_f32_slice(location.as_ref(), false, view_projection);
+            let location = gl.get_uniform_location(self.program.program, "u_instance_count");
+            gl.uniform_1_u32(location.as_ref(), self.instance_count);
+
+            gl.bind_buffer_base(SHADER_STORAGE_BUFFER, 0, Some(self.instances.buffer));
+            gl.bind_buffer_base(SHADER_STORAGE_BUFFER, 1, Some(self.indirect.buffer));
+            gl.bind_buffer_base(SHADER_STORAGE_BUFFER, 2, Some(self.visible.buffer));
+            gl.bind_buffer_base(ATOMIC_COUNTER_BUFFER, 0, Some(self.counter.buffer));
+            gl.dispatch_compute(self.instance_count.div_ceil(64), 1, 1);
+            gl.memory_barrier(
+                COMMAND_BARRIER_BIT | VERTEX_ATTRIB_ARRAY_BARRIER_BIT | BUFFER_UPDATE_BARRIER_BIT,
+            );
+            gl.use_program(None);
+        }
+    }
+
+    /// The indices of the visible instances, grouped by draw. Bind it as a per-instance vertex buffer with
+    /// [`GpuCuller::instance_layout`].
+    pub fn visible_instances(&self) -> &BufferHandle {
+        &self.visible
+    }
+
+    /// The layout of [`GpuCuller::visible_instances`] at `buffer_index`: one `float` per instance holding the
+    /// instance index, exact for up to 2^24 instances.
+    pub fn instance_layout(buffer_index: usize) -> VertexLayout {
+        VertexLayout::new(buffer_index)
+            .with_attribute(1, DataType::UnsignedInt, false)
+            .with_divisor(1)
+    }
+
+    /// Draw the visible instances of every draw with a single multi-draw call, after [`GpuCuller::cull`].
+    ///
+    /// ## Panics
+    /// The applied index buffer must have a known element type.
+    pub fn draw(&self, dctx: &mut Drawable, mode: DrawMode) {
+        span!("gpu_cull_draw", draws = self.draws.len());
+        assert!(dctx.vertices_applied, "no buffers were applied");
+        assert!(dctx.has_index_buffer, "no index buffer was applied");
+        let ty = dctx.index_type.expect("index buffer has no index type!");
+        let multi_draw_elements_indirect = dctx
+            .ctx
+            .multi_draw_elements_indirect_fn
+            .expect("multi-draw indirect was not loaded!");
+        unsafe {
+            dctx.ctx
+                .gl
+                .bind_buffer(DRAW_INDIRECT_BUFFER, Some(self.indirect.buffer));
+            multi_draw_elements_indirect(
+                mode.to_gl(),
+                ty.to_gl(),
+                std::ptr::null(),
+                self.commands.len() as i32,
+                0,
+            );
+            dctx.ctx.gl.bind_buffer(DRAW_INDIRECT_BUFFER, None);
+        }
+    }
+
+    /// The number of visible instances after the last [`GpuCuller::cull`], read back from the GPU.
+    ///
+    /// This stalls the pipeline, so only use it for statistics while debugging.
+    pub fn visible_count(&self, ctx: &ManagedContext) -> u32 {
+        let mut count = [0u8; 4];
+        unsafe {
+            ctx.gl
+                .bind_buffer(ATOMIC_COUNTER_BUFFER, Some(self.counter.buffer));
+            ctx.gl
+                .get_buffer_sub_data(ATOMIC_COUNTER_BUFFER, 0, &mut count);
+        }
+        u32::from_ne_bytes(count)
+    }
+}
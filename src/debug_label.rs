@@ -0,0 +1,52 @@
+use super::*;
+
+impl ManagedContext {
+    /// Name an object for graphics debuggers and driver messages, if the context has [`Features::DEBUG_LABELS`].
+    /// Labels are a debugging aid, so they are skipped without a capability warning otherwise.
+    fn label_object(&self, identifier: u32, name: u32, label: &str) {
+        if self.features.contains(Features::DEBUG_LABELS) {
+            unsafe { self.gl.object_label(identifier, name, Some(label)) };
+        }
+    }
+}
+
+impl BufferHandle {
+    /// Name the buffer in graphics debuggers, e.g. RenderDoc, and in driver messages. See [`Features::DEBUG_LABELS`].
+    pub fn with_debug_label(self, ctx: &ManagedContext, label: &str) -> Self {
+        ctx.label_object(BUFFER, self.buffer.0.get(), label);
+        self
+    }
+}
+
+impl TextureHandle {
+    /// Name the texture in graphics debuggers, e.g. RenderDoc, and in driver messages. See [`Features::DEBUG_LABELS`].
+    pub fn with_debug_label(self, ctx: &ManagedContext, label: &str) -> Self {
+        ctx.label_object(TEXTURE, self.texture.0.get(), label);
+        self
+    }
+}
+
+impl Texture2D {
+    /// Name the texture in graphics debuggers, see [`TextureHandle::with_debug_label`].
+    pub fn with_debug_label(self, ctx: &ManagedContext, label: &str) -> Self {
+        ctx.label_object(TEXTURE, self.handle.texture.0.get(), label);
+        self
+    }
+}
+
+impl ShaderProgram {
+    /// Name the program in graphics debuggers, e.g. RenderDoc, and in driver messages. See [`Features::DEBUG_LABELS`].
+    pub fn with_debug_label(self, ctx: &ManagedContext, label: &str) -> Self {
+        ctx.label_object(PROGRAM, self.program.0.get(), label);
+        self
+    }
+}
+
+impl Framebuffer {
+    /// Name the framebuffer in graphics debuggers, e.g. RenderDoc, and in driver messages. See
+    /// [`Features::DEBUG_LABELS`].
+    pub fn with_debug_label(self, ctx: &ManagedContext, label: &str) -> Self {
+        ctx.label_object(FRAMEBUFFER, self.framebuffer.0.get(), label);
+        self
+    }
+}
@@ -0,0 +1,63 @@
+use super::*;
+
+use std::any::Any;
+use std::collections::VecDeque;
+
+/// Resources kept alive until the GPU is done with them, see [`ManagedContext::defer_delete`].
+#[derive(Debug)]
+pub(crate) struct DeletionQueue {
+    frame: u64,
+    latency: u64,
+    pending: VecDeque<(u64, Box<dyn Any>)>,
+}
+
+impl DeletionQueue {
+    pub(crate) fn new() -> Self {
+        Self {
+            frame: 0,
+            latency: 2,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl ManagedContext {
+    /// Drop `resource` once the frames that may still use it have finished, see [`ManagedContext::end_frame`].
+    pub fn defer_delete<T: 'static>(&mut self, resource: T) {
+        let frame = self.deletions.frame;
+        self.deletions
+            .pending
+            .push_back((frame, Box::new(resource)));
+    }
+
+    /// Mark the end of a frame, dropping resources deferred at least the deletion latency ago.
+    ///
+    /// Call this once per frame, e.g. after swapping buffers.
+    pub fn end_frame(&mut self) {
+        span!("end_frame", pending = self.deletions.pending.len());
+        if let Some(interval) = self.state_verification {
+            if self.deletions.frame % interval == 0 {
+                self.verify_state();
+            }
+        }
+        self.deletions.frame += 1;
+        let queue = &mut self.deletions;
+        while queue
+            .pending
+            .front()
+            .is_some_and(|(frame, _)| queue.frame - frame >= queue.latency)
+        {
+            queue.pending.pop_front();
+        }
+    }
+
+    /// Set how many frames deferred deletions wait, 2 by default. Use the number of frames the driver may queue.
+    pub fn set_deletion_latency(&mut self, frames: u64) {
+        self.deletions.latency = frames;
+    }
+
+    /// The number of resources waiting to be dropped.
+    pub fn pending_deletions(&self) -> usize {
+        self.deletions.pending.len()
+    }
+}
@@ -0,0 +1,278 @@
+use super::*;
+
+/// Plain-data description of a [`RenderPipeline`], e.g. for materials stored in asset files.
+///
+/// With the `serde` feature, descriptors can be (de)serialized; omitted optional fields take the defaults of
+/// [`RenderPipeline::new`]. Rebuilding the pipeline with [`RenderPipeline::from_descriptor`] after the file changed
+/// is all hot reloading takes.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PipelineDescriptor {
+    pub vertex_shader: String,
+    pub fragment_shader: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub vertex_attributes: Vec<VertexAttributeDescriptor>,
+    /// Source and destination factors, or `None` to disable blending.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub blend: Option<(BlendFactor, BlendFactor)>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub depth: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub depth_write: bool,
+    #[cfg_attr(feature = "serde", serde(default = "default_color_write"))]
+    pub color_write: [bool; 4],
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stencil: Option<StencilState>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub scissor: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub depth_clamp: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rasterizer_discard: bool,
+    /// The minimum fraction of samples shaded per pixel, see [`RenderPipeline::with_sample_shading`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sample_shading: Option<f32>,
+    /// Per-attachment color write overrides, see [`RenderPipeline::with_color_write_i`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub color_write_attachments: Vec<(u32, [bool; 4])>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub line_smooth: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub point_smooth: bool,
+    /// Features declared on top of those implied by the state, see [`RenderPipeline::with_required_features`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub required_features: Features,
+}
+
+#[cfg(feature = "serde")]
+fn default_color_write() -> [bool; 4] {
+    [true; 4]
+}
+
+impl PipelineDescriptor {
+    /// Describe a pipeline with the default state of [`RenderPipeline::new`].
+    pub fn new(vertex_shader: impl Into<String>, fragment_shader: impl Into<String>) -> Self {
+        Self {
+            vertex_shader: vertex_shader.into(),
+            fragment_shader: fragment_shader.into(),
+            vertex_attributes: vec![],
+            blend: None,
+            depth: false,
+            depth_write: false,
+            color_write: [true; 4],
+            stencil: None,
+            scissor: false,
+            depth_clamp: false,
+            rasterizer_discard: false,
+            sample_shading: None,
+            color_write_attachments: vec![],
+            line_smooth: false,
+            point_smooth: false,
+            required_features: Features::empty(),
+        }
+    }
+}
+
+impl RenderPipeline {
+    /// Compile the shaders of `descriptor` and build a pipeline from it.
+    pub fn from_descriptor(
+        ctx: &ManagedContext,
+        descriptor: &PipelineDescriptor,
+    ) -> Result<Self, Error> {
+        let program =
+            ShaderProgram::new(ctx, &descriptor.vertex_shader, &descriptor.fragment_shader)?;
+        let [r, g, b, a] = descriptor.color_write;
+        let mut pipeline = descriptor
+            .vertex_attributes
+            .iter()
+            .fold(RenderPipeline::new(program), |pipeline, attribute| {
+                pipeline.with_vertex_attribute(attribute.clone())
+            })
+            .with_depth(descriptor.depth)
+            .with_depth_write(descriptor.depth_write)
+            .with_color_write(r, g, b, a)
+            .with_stencil(descriptor.stencil.clone())
+            .with_scissor(descriptor.scissor)
+            .with_depth_clamp(descriptor.depth_clamp)
+            .with_rasterizer_discard(descriptor.rasterizer_discard)
+            .with_line_smooth(descriptor.line_smooth)
+            .with_point_smooth(descriptor.point_smooth)
+            .with_required_features(descriptor.required_features);
+        if let Some((src, dst)) = descriptor.blend {
+            pipeline = pipeline.with_blend(true).with_blend_func(src, dst);
+        }
+        if let Some(min_fraction) = descriptor.sample_shading {
+            pipeline = pipeline.with_sample_shading(min_fraction);
+        }
+        for &(attachment, [r, g, b, a]) in &descriptor.color_write_attachments {
+            pipeline = pipeline.with_color_write_i(attachment, r, g, b, a);
+        }
+        Ok(pipeline)
+    }
+}
+
+/// Plain-data description of how a texture is sampled, see [`TextureHandle::new_with`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct SamplerDescriptor {
+    pub wrapping_mode_s: TextureWrap,
+    pub wrapping_mode_t: TextureWrap,
+    pub min_filter: TextureFilteringMode,
+    pub mag_filter: TextureFilteringMode,
+    pub mipmap_policy: MipmapPolicy,
+    /// The maximum anisotropy, 1.0 to disable anisotropic filtering.
+    pub anisotropy: f32,
+    /// The color sampled outside [`TextureWrap::ClampToBorder`] axes, see [`TextureHandle::set_border_color`].
+    pub border_color: Color,
+    /// Sample the texture with [`shaders::SAMPLE_BORDER`] instead of relying on the driver for
+    /// [`TextureWrap::ClampToBorder`]. Without [`Features::CLAMP_TO_BORDER`] the texture then clamps to its edge
+    /// without a capability warning.
+    pub emulate_border: bool,
+}
+
+impl SamplerDescriptor {
+    /// Crisp, unfiltered pixels that don't bleed at the edges: nearest filtering, clamped, no mipmaps.
+    pub fn pixel_art() -> Self {
+        Self {
+            wrapping_mode_s: TextureWrap::ClampToEdge,
+            wrapping_mode_t: TextureWrap::ClampToEdge,
+            min_filter: TextureFilteringMode::Nearest,
+            mag_filter: TextureFilteringMode::Nearest,
+            mipmap_policy: MipmapPolicy::None,
+            anisotropy: 1.0,
+            border_color: Color::TRANSPARENT,
+            emulate_border: false,
+        }
+    }
+
+    /// Smooth sampling of tiling textures at any distance and angle: trilinear filtering with automatically generated
+    /// mipmaps, repeating, 8x anisotropy.
+    pub fn smooth() -> Self {
+        Self {
+            wrapping_mode_s: TextureWrap::Repeat,
+            wrapping_mode_t: TextureWrap::Repeat,
+            min_filter: TextureFilteringMode::LinearMipmapLinear,
+            mag_filter: TextureFilteringMode::Linear,
+            mipmap_policy: MipmapPolicy::Auto,
+            anisotropy: 8.0,
+            border_color: Color::TRANSPARENT,
+            emulate_border: false,
+        }
+    }
+
+    /// The same sampling without mipmaps, e.g. for atlases that are updated piecewise: mipmapped minification
+    /// filters fall back to the filter within the base level.
+    pub fn without_mipmaps(self) -> Self {
+        let min_filter = match self.min_filter {
+            TextureFilteringMode::NearestMipmapNearest
+            | TextureFilteringMode::NearestMipmapLinear => TextureFilteringMode::Nearest,
+            TextureFilteringMode::LinearMipmapNearest
+            | TextureFilteringMode::LinearMipmapLinear => TextureFilteringMode::Linear,
+            filter => filter,
+        };
+        Self {
+            min_filter,
+            mipmap_policy: MipmapPolicy::None,
+            ..self
+        }
+    }
+}
+
+impl Default for SamplerDescriptor {
+    fn default() -> Self {
+        Self {
+            wrapping_mode_s: TextureWrap::ClampToEdge,
+            wrapping_mode_t: TextureWrap::ClampToEdge,
+            min_filter: TextureFilteringMode::Linear,
+            mag_filter: TextureFilteringMode::Linear,
+            mipmap_policy: MipmapPolicy::None,
+            anisotropy: 1.0,
+            border_color: Color::TRANSPARENT,
+            emulate_border: false,
+        }
+    }
+}
+
+/// Plain-data description of a [`Texture2D`], see [`Texture2D::from_descriptor`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextureDescriptor {
+    pub internal_format: InternalTextureFormat,
+    pub format: TextureFormat,
+    pub ty: DataType,
+    pub width: i32,
+    pub height: i32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sampler: SamplerDescriptor,
+}
+
+impl TextureHandle {
+    /// Create a new 2D texture sampled as described by `descriptor`, e.g. [`SamplerDescriptor::smooth`].
+    pub fn new_with(
+        ctx: &mut ManagedContext,
+        descriptor: &SamplerDescriptor,
+    ) -> Result<Self, Error> {
+        let wrapping_mode = [descriptor.wrapping_mode_s, descriptor.wrapping_mode_t]
+            .map(|wrap| ctx.supported_wrap(wrap, descriptor.emulate_border));
+        let handle = Self::with_target(
+            ctx,
+            TEXTURE_2D,
+            wrapping_mode,
+            descriptor.min_filter,
+            descriptor.mag_filter,
+        )?
+        .with_mipmap_policy(descriptor.mipmap_policy);
+        if descriptor.anisotropy > 1.0 {
+            handle.set_anisotropy(ctx, descriptor.anisotropy);
+        }
+        if wrapping_mode.contains(&TextureWrap::ClampToBorder) {
+            handle.set_border_color(ctx, descriptor.border_color);
+        }
+        Ok(handle)
+    }
+
+    /// Create a new 2D texture sampled as described by [`ManagedContext::default_sampler`].
+    pub fn new_default(ctx: &mut ManagedContext) -> Result<Self, Error> {
+        let descriptor = *ctx.default_sampler();
+        Self::new_with(ctx, &descriptor)
+    }
+}
+
+impl ManagedContext {
+    /// Set how textures are sampled when their creator doesn't say, e.g. [`SamplerDescriptor::pixel_art`] for an
+    /// app-wide pixel art mode or [`SamplerDescriptor::smooth`] for maximum quality.
+    ///
+    /// Consulted by [`TextureHandle::new_default`] and the glyph atlas. Helpers that take filters & wrapping
+    /// explicitly, e.g. [`ManagedContext::create_texture`], take the remaining parameters such as the anisotropy from
+    /// it. Existing textures keep their parameters.
+    pub fn set_default_sampler(&mut self, descriptor: SamplerDescriptor) {
+        self.default_sampler = descriptor;
+    }
+
+    /// The sampler set with [`ManagedContext::set_default_sampler`], [`SamplerDescriptor::default`] unless changed.
+    pub fn default_sampler(&self) -> &SamplerDescriptor {
+        &self.default_sampler
+    }
+}
+
+impl Texture2D {
+    /// Create a texture as described by `descriptor`, filled with `data` if given.
+    pub fn from_descriptor(
+        ctx: &mut ManagedContext,
+        descriptor: &TextureDescriptor,
+        data: Option<&[u8]>,
+    ) -> Result<Self, Error> {
+        Ok(
+            TextureHandle::new_with(ctx, &descriptor.sampler)?.allocate_2d_data(
+                ctx,
+                data,
+                descriptor.internal_format,
+                descriptor.format,
+                descriptor.width,
+                descriptor.height,
+                descriptor.ty,
+            ),
+        )
+    }
+}
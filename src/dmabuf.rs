@@ -0,0 +1,228 @@
+use super::*;
+
+use std::ffi::{c_char, c_void, CStr};
+use std::os::unix::io::RawFd;
+
+const EGL_NONE: i32 = 0x3038;
+const EGL_EXTENSIONS: i32 = 0x3055;
+const EGL_WIDTH: i32 = 0x3057;
+const EGL_HEIGHT: i32 = 0x3056;
+const EGL_LINUX_DMA_BUF_EXT: u32 = 0x3270;
+const EGL_LINUX_DRM_FOURCC_EXT: i32 = 0x3271;
+/// `FD`, `OFFSET` and `PITCH` attributes of each plane.
+const EGL_DMA_BUF_PLANE_ATTRIBS: [[i32; 3]; 3] = [
+    [0x3272, 0x3273, 0x3274],
+    [0x3275, 0x3276, 0x3277],
+    [0x3278, 0x3279, 0x327A],
+];
+/// `MODIFIER_LO` and `MODIFIER_HI` attributes of each plane.
+const EGL_DMA_BUF_PLANE_MODIFIER_ATTRIBS: [[i32; 2]; 3] =
+    [[0x3443, 0x3444], [0x3445, 0x3446], [0x3447, 0x3448]];
+
+type CreateImage = unsafe extern "system" fn(
+    display: *const c_void,
+    context: *const c_void,
+    target: u32,
+    buffer: *const c_void,
+    attrib_list: *const i32,
+) -> *const c_void;
+type DestroyImage = unsafe extern "system" fn(display: *const c_void, image: *const c_void) -> u32;
+type ImageTargetTexture = unsafe extern "system" fn(target: u32, image: *const c_void);
+type QueryString = unsafe extern "system" fn(display: *const c_void, name: i32) -> *const c_char;
+
+/// A single plane of a DMA-BUF image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DmaBufPlane {
+    pub fd: RawFd,
+    pub offset: u32,
+    pub pitch: u32,
+    /// The DRM format modifier, if the producer uses one.
+    pub modifier: Option<u64>,
+}
+
+/// A DMA-BUF image, e.g. a frame exported by a camera or hardware video decoder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DmaBufImage {
+    pub width: i32,
+    pub height: i32,
+    /// The DRM fourcc pixel format code.
+    pub fourcc: u32,
+    /// Up to three planes.
+    pub planes: Vec<DmaBufPlane>,
+}
+
+/// Entry points of the `EGL_KHR_image_base` and `GL_OES_EGL_image` extensions, which glow does not load.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EglImageImport {
+    display: *const c_void,
+    create_image: CreateImage,
+    destroy_image: DestroyImage,
+    image_target_texture: ImageTargetTexture,
+    /// Whether the display supports `EGL_EXT_image_dma_buf_import`.
+    dma_buf_import: bool,
+}
+
+impl EglImageImport {
+    /// Resolve the extension entry points using `eglGetProcAddress`.
+    pub(crate) unsafe fn load(
+        display: *const c_void,
+        mut loader: impl FnMut(&str) -> *const c_void,
+    ) -> Result<Self, Error> {
+        let query_string: QueryString = load_fn(&mut loader, "eglQueryString")?;
+        let extensions = query_string(display, EGL_EXTENSIONS);
+        let dma_buf_import = !extensions.is_null()
+            && CStr::from_ptr(extensions)
+                .to_string_lossy()
+                .split_whitespace()
+                .any(|extension| extension == "EGL_EXT_image_dma_buf_import");
+        Ok(Self {
+            display,
+            create_image: load_fn(&mut loader, "eglCreateImageKHR")?,
+            destroy_image: load_fn(&mut loader, "eglDestroyImageKHR")?,
+            image_target_texture: load_fn(&mut loader, "glEGLImageTargetTexture2DOES")?,
+            dma_buf_import,
+        })
+    }
+}
+
+impl ManagedContext {
+    /// Enable importing EGL images and DMA-BUFs.
+    ///
+    /// `display` is the `EGLDisplay` the context was created on, and `loader` is `eglGetProcAddress`.
+    ///
+    /// # Safety
+    /// `display` must be a valid `EGLDisplay` and `loader` must return valid function pointers for the current context.
+    pub unsafe fn load_egl_image_import(
+        &mut self,
+        display: *const c_void,
+        loader: impl FnMut(&str) -> *const c_void,
+    ) -> Result<(), Error> {
+        if !self.has_extension("GL_OES_EGL_image") {
+            return Err(Error::Unsupported(
+                "GL_OES_EGL_image is not supported".into(),
+            ));
+        }
+
+        self.egl_image = Some(EglImageImport::load(display, loader)?);
+        Ok(())
+    }
+
+    /// Returns true if EGL image import was successfully loaded.
+    pub fn supports_egl_image_import(&self) -> bool {
+        self.egl_image.is_some()
+    }
+
+    fn egl_image(&self) -> Result<EglImageImport, Error> {
+        self.egl_image
+            .ok_or_else(|| Error::Unsupported("EGL image import is not loaded".into()))
+    }
+}
+
+impl Texture2D {
+    /// Create a texture sharing the storage of an `EGLImage`.
+    ///
+    /// `target` is usually [`TEXTURE_EXTERNAL_OES`], which must be sampled through `samplerExternalOES` in shaders.
+    ///
+    /// # Safety
+    /// `image` must be a valid `EGLImage` created on the display passed to [`ManagedContext::load_egl_image_import`].
+    pub unsafe fn from_egl_image(
+        ctx: &mut ManagedContext,
+        image: *const c_void,
+        target: u32,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, Error> {
+        let egl = ctx.egl_image()?;
+        let texture = ctx.gl.create_texture().map_err(Error::TextureAlloc)?;
+        ctx.bind_scratch_texture(target, texture);
+        ctx.gl
+            .tex_parameter_i32(target, TEXTURE_MIN_FILTER, LINEAR as _);
+        ctx.gl
+            .tex_parameter_i32(target, TEXTURE_MAG_FILTER, LINEAR as _);
+        ctx.gl
+            .tex_parameter_i32(target, TEXTURE_WRAP_S, CLAMP_TO_EDGE as _);
+        ctx.gl
+            .tex_parameter_i32(target, TEXTURE_WRAP_T, CLAMP_TO_EDGE as _);
+        (egl.image_target_texture)(target, image);
+        ctx.release_scratch_texture(target);
+
+        Ok(Self::from_raw_gl(ctx, texture, target, width, height))
+    }
+
+    /// Import a DMA-BUF without copying it, as a [`TEXTURE_EXTERNAL_OES`] texture.
+    ///
+    /// The file descriptors are not closed; the texture keeps its own reference to the buffer. Requires the
+    /// `EGL_EXT_image_dma_buf_import` and `GL_OES_EGL_image_external` extensions.
+    pub fn import_dmabuf(ctx: &mut ManagedContext, image: &DmaBufImage) -> Result<Self, Error> {
+        span!("import_dmabuf", width = image.width, height = image.height);
+        assert!(
+            !image.planes.is_empty() && image.planes.len() <= 3,
+            "DMA-BUF images have 1 to 3 planes!"
+        );
+        let egl = ctx.egl_image()?;
+        if !egl.dma_buf_import {
+            return Err(Error::Unsupported(
+                "EGL_EXT_image_dma_buf_import is not supported".into(),
+            ));
+        }
+        if !ctx.has_extension("GL_OES_EGL_image_external") {
+            return Err(Error::Unsupported(
+                "GL_OES_EGL_image_external is not supported".into(),
+            ));
+        }
+
+        let mut attribs = vec![
+            EGL_WIDTH,
+            image.width,
+            EGL_HEIGHT,
+            image.height,
+            EGL_LINUX_DRM_FOURCC_EXT,
+            image.fourcc as i32,
+        ];
+        for (i, plane) in image.planes.iter().enumerate() {
+            let [fd, offset, pitch] = EGL_DMA_BUF_PLANE_ATTRIBS[i];
+            attribs.extend([
+                fd,
+                plane.fd,
+                offset,
+                plane.offset as i32,
+                pitch,
+                plane.pitch as i32,
+            ]);
+            if let Some(modifier) = plane.modifier {
+                let [lo, hi] = EGL_DMA_BUF_PLANE_MODIFIER_ATTRIBS[i];
+                attribs.extend([
+                    lo,
+                    modifier as u32 as i32,
+                    hi,
+                    (modifier >> 32) as u32 as i32,
+                ]);
+            }
+        }
+        attribs.push(EGL_NONE);
+
+        unsafe {
+            let egl_image = (egl.create_image)(
+                egl.display,
+                std::ptr::null(),
+                EGL_LINUX_DMA_BUF_EXT,
+                std::ptr::null(),
+                attribs.as_ptr(),
+            );
+            if egl_image.is_null() {
+                return Err(Error::Unsupported("failed to import DMA-BUF".into()));
+            }
+
+            let texture = Self::from_egl_image(
+                ctx,
+                egl_image,
+                TEXTURE_EXTERNAL_OES,
+                image.width,
+                image.height,
+            );
+            // the texture holds on to the storage, the image itself is no longer needed
+            (egl.destroy_image)(egl.display, egl_image);
+            texture
+        }
+    }
+}
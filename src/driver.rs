@@ -0,0 +1,112 @@
+use super::*;
+use bitflags::bitflags;
+
+bitflags! {
+    /// Known driver bugs and performance pitfalls, detected from the driver strings.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct DriverQuirks: u32 {
+        /// `CLAMP_TO_BORDER` is emulated in the shader compiler or otherwise very slow. Samplers with
+        /// [`SamplerDescriptor::emulate_border`] clamp to the edge instead.
+        const SLOW_CLAMP_TO_BORDER = 1 << 0;
+        /// sRGB textures and framebuffers do not convert to/from linear correctly. [`Features::SRGB`] is not reported.
+        const BROKEN_SRGB = 1 << 1;
+    }
+}
+
+/// Strings and version numbers reported by the OpenGL driver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DriverInfo {
+    /// The `GL_VENDOR` string.
+    pub vendor: String,
+    /// The `GL_RENDERER` string.
+    pub renderer: String,
+    /// The `GL_VERSION` string.
+    pub version: String,
+    /// The `GL_SHADING_LANGUAGE_VERSION` string.
+    pub shading_language_version: String,
+    /// The parsed major & minor version of the context.
+    pub version_number: (u32, u32),
+    /// Whether the context is an OpenGL ES (or WebGL) context.
+    pub is_embedded: bool,
+    /// Whether the context is a desktop compatibility profile context, which supports fixed-function state.
+    pub is_compatibility_profile: bool,
+}
+
+impl DriverInfo {
+    pub(crate) fn query(gl: &Context) -> Self {
+        let version = gl.version();
+        let is_compatibility_profile = !version.is_embedded
+            && ((version.major, version.minor) < (3, 2)
+                || unsafe { gl.get_parameter_i32(CONTEXT_PROFILE_MASK) } as u32
+                    & CONTEXT_COMPATIBILITY_PROFILE_BIT
+                    != 0);
+        unsafe {
+            Self {
+                vendor: gl.get_parameter_string(VENDOR),
+                renderer: gl.get_parameter_string(RENDERER),
+                version: gl.get_parameter_string(VERSION),
+                shading_language_version: gl.get_parameter_string(SHADING_LANGUAGE_VERSION),
+                version_number: (version.major, version.minor),
+                is_embedded: version.is_embedded,
+                is_compatibility_profile,
+            }
+        }
+    }
+
+    /// Returns true if the context version is at least `major.minor`.
+    pub fn is_at_least(&self, major: u32, minor: u32) -> bool {
+        self.version_number >= (major, minor)
+    }
+
+    /// Look up the quirks that apply to this driver.
+    pub fn quirks(&self) -> DriverQuirks {
+        QUIRKS_TABLE
+            .iter()
+            .filter(|entry| entry.matches(self))
+            .fold(DriverQuirks::empty(), |acc, entry| acc | entry.quirks)
+    }
+}
+
+/// A single entry in the driver workarounds registry.
+struct QuirkEntry {
+    /// Case-insensitive substring of `GL_VENDOR` or `GL_RENDERER` identifying the driver.
+    pattern: &'static str,
+    /// Only match OpenGL ES contexts.
+    embedded_only: bool,
+    quirks: DriverQuirks,
+}
+
+impl QuirkEntry {
+    fn matches(&self, info: &DriverInfo) -> bool {
+        if self.embedded_only && !info.is_embedded {
+            return false;
+        }
+
+        let pattern = self.pattern.to_lowercase();
+        info.vendor.to_lowercase().contains(&pattern)
+            || info.renderer.to_lowercase().contains(&pattern)
+    }
+}
+
+const QUIRKS_TABLE: &[QuirkEntry] = &[
+    QuirkEntry {
+        pattern: "mali",
+        embedded_only: true,
+        quirks: DriverQuirks::SLOW_CLAMP_TO_BORDER,
+    },
+    QuirkEntry {
+        pattern: "powervr",
+        embedded_only: true,
+        quirks: DriverQuirks::SLOW_CLAMP_TO_BORDER,
+    },
+    QuirkEntry {
+        pattern: "adreno",
+        embedded_only: true,
+        quirks: DriverQuirks::BROKEN_SRGB,
+    },
+    QuirkEntry {
+        pattern: "vivante",
+        embedded_only: true,
+        quirks: DriverQuirks::SLOW_CLAMP_TO_BORDER.union(DriverQuirks::BROKEN_SRGB),
+    },
+];
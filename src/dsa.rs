@@ -0,0 +1,115 @@
+use super::*;
+
+use std::ffi::c_void;
+use std::num::NonZeroU32;
+
+type CreateVertexArrays = unsafe extern "system" fn(count: i32, arrays: *mut u32);
+type VertexArrayBindingDivisor =
+    unsafe extern "system" fn(vao: u32, binding_index: u32, divisor: u32);
+type NamedBufferSubData =
+    unsafe extern "system" fn(buffer: u32, offset: isize, size: isize, data: *const c_void);
+type TextureSubImage2D = unsafe extern "system" fn(
+    texture: u32,
+    level: i32,
+    x_offset: i32,
+    y_offset: i32,
+    width: i32,
+    height: i32,
+    format: u32,
+    ty: u32,
+    pixels: *const c_void,
+);
+
+/// Entry points of `ARB_direct_state_access` which glow does not expose.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DirectStateAccess {
+    create_vertex_arrays: CreateVertexArrays,
+    vertex_array_binding_divisor: VertexArrayBindingDivisor,
+    named_buffer_sub_data: NamedBufferSubData,
+    texture_sub_image_2d: TextureSubImage2D,
+}
+
+impl DirectStateAccess {
+    unsafe fn load(mut loader: impl FnMut(&str) -> *const c_void) -> Result<Self, Error> {
+        Ok(Self {
+            create_vertex_arrays: load_fn(&mut loader, "glCreateVertexArrays")?,
+            vertex_array_binding_divisor: load_fn(&mut loader, "glVertexArrayBindingDivisor")?,
+            named_buffer_sub_data: load_fn(&mut loader, "glNamedBufferSubData")?,
+            texture_sub_image_2d: load_fn(&mut loader, "glTextureSubImage2D")?,
+        })
+    }
+
+    /// Create a vertex array that can be edited without binding it first, unlike one from `glGenVertexArrays`.
+    pub(crate) unsafe fn create_vertex_array(&self) -> Option<NativeVertexArray> {
+        let mut vao = 0;
+        (self.create_vertex_arrays)(1, &mut vao);
+        NonZeroU32::new(vao).map(NativeVertexArray)
+    }
+
+    pub(crate) unsafe fn vertex_array_binding_divisor(
+        &self,
+        vao: NativeVertexArray,
+        binding_index: u32,
+        divisor: u32,
+    ) {
+        (self.vertex_array_binding_divisor)(vao.0.get(), binding_index, divisor);
+    }
+
+    pub(crate) unsafe fn named_buffer_sub_data(
+        &self,
+        buffer: NativeBuffer,
+        offset: i32,
+        data: &[u8],
+    ) {
+        (self.named_buffer_sub_data)(
+            buffer.0.get(),
+            offset as isize,
+            data.len() as isize,
+            data.as_ptr() as *const c_void,
+        );
+    }
+
+    pub(crate) unsafe fn texture_sub_image_2d(
+        &self,
+        texture: NativeTexture,
+        rect: Rect,
+        format: TextureFormat,
+        ty: DataType,
+        pixels: &[u8],
+    ) {
+        (self.texture_sub_image_2d)(
+            texture.0.get(),
+            0,
+            rect.x,
+            rect.y,
+            rect.width,
+            rect.height,
+            format as u32,
+            ty as u32,
+            pixels.as_ptr() as *const c_void,
+        );
+    }
+}
+
+impl ManagedContext {
+    /// Load the `ARB_direct_state_access` entry points glow doesn't expose, so buffer updates, texture uploads and
+    /// vertex array setup edit objects without binding them. Without them, these fall back to binding the objects.
+    ///
+    /// `loader` is the same function used to create the glow context, e.g. `window.get_proc_address`.
+    ///
+    /// # Safety
+    /// `loader` must return valid function pointers for the current context.
+    pub unsafe fn load_direct_state_access(
+        &mut self,
+        loader: impl FnMut(&str) -> *const c_void,
+    ) -> Result<(), Error> {
+        self.require_features(Features::DIRECT_STATE_ACCESS, "direct state access")?;
+        self.dsa = Some(DirectStateAccess::load(loader)?);
+        Ok(())
+    }
+
+    /// The direct state access entry points, if [`ManagedContext::load_direct_state_access`] was called.
+    pub(crate) fn dsa(&self) -> Option<&DirectStateAccess> {
+        self.dsa.as_ref()
+    }
+}
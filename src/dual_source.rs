@@ -0,0 +1,72 @@
+use super::*;
+
+use std::ffi::{c_char, c_void, CString};
+
+/// `glBindFragDataLocationIndexed`, which glow does not expose.
+pub(crate) type BindFragDataLocationIndexedFn =
+    unsafe extern "system" fn(program: u32, color_number: u32, index: u32, name: *const c_char);
+
+impl ManagedContext {
+    /// Load `glBindFragDataLocationIndexed` for [`ShaderProgram::with_dual_source_outputs`].
+    ///
+    /// Shaders declaring `layout(location = 0, index = 1)` on their second output don't need this.
+    ///
+    /// `loader` is the same function used to create the glow context, e.g. `window.get_proc_address`.
+    ///
+    /// # Safety
+    /// `loader` must return valid function pointers for the current context.
+    pub unsafe fn load_dual_source_blending(
+        &mut self,
+        mut loader: impl FnMut(&str) -> *const c_void,
+    ) -> Result<(), Error> {
+        self.require_features(Features::DUAL_SOURCE_BLEND, "dual-source blending")
+            .map_err(|err| Error::Unsupported(err.to_string()))?;
+        let bind_frag_data_location_indexed = [
+            "glBindFragDataLocationIndexed",
+            "glBindFragDataLocationIndexedEXT",
+        ]
+        .into_iter()
+        .find_map(|name| load_fn(&mut loader, name).ok());
+        self.bind_frag_data_location_indexed_fn =
+            Some(bind_frag_data_location_indexed.ok_or_else(|| {
+                Error::Unsupported("glBindFragDataLocationIndexed is not available".into())
+            })?);
+        Ok(())
+    }
+}
+
+impl ShaderProgram {
+    /// Create a new program whose fragment shader outputs `color` and `blend_weights` are the first and second source
+    /// of dual-source blending, e.g. for subpixel text where `blend_weights` holds per-channel coverage and the
+    /// pipeline blends with [`BlendFactor::Source1Color`] & [`BlendFactor::OneMinusSource1Color`].
+    ///
+    /// Requires [`ManagedContext::load_dual_source_blending`].
+    pub fn with_dual_source_outputs(
+        ctx: &ManagedContext,
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+        color: &str,
+        blend_weights: &str,
+    ) -> Result<Self, Error> {
+        let Some(bind_frag_data_location_indexed) = ctx.bind_frag_data_location_indexed_fn else {
+            return Err(Error::Unsupported(
+                "dual-source blending was not loaded".into(),
+            ));
+        };
+        let color = CString::new(color).expect("output name contains a NUL byte!");
+        let blend_weights = CString::new(blend_weights).expect("output name contains a NUL byte!");
+
+        let shader = compile_shader_with(
+            &ctx.gl,
+            &[
+                (ShaderStage::Vertex, vertex_shader_source),
+                (ShaderStage::Fragment, fragment_shader_source),
+            ],
+            |program| unsafe {
+                bind_frag_data_location_indexed(program.0.get(), 0, 0, color.as_ptr());
+                bind_frag_data_location_indexed(program.0.get(), 0, 1, blend_weights.as_ptr());
+            },
+        )?;
+        Ok(Self::from_linked(ctx, shader))
+    }
+}
@@ -0,0 +1,64 @@
+use super::*;
+
+/// The shader stage a compile error originated from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ShaderStage {
+    Vertex = VERTEX_SHADER,
+    Geometry = GEOMETRY_SHADER,
+    Fragment = FRAGMENT_SHADER,
+    Compute = COMPUTE_SHADER,
+}
+
+/// Errors returned by rapax.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    /// A shader failed to compile. Contains the shader info log.
+    #[error("failed to compile {stage:?} shader: {log}")]
+    ShaderCompile { stage: ShaderStage, log: String },
+    /// A shader program failed to link. Contains the program info log.
+    #[error("failed to link shader program: {0}")]
+    ShaderLink(String),
+    /// A buffer object could not be created.
+    #[error("failed to allocate buffer: {0}")]
+    BufferAlloc(String),
+    /// A texture object could not be created.
+    #[error("failed to allocate texture: {0}")]
+    TextureAlloc(String),
+    /// A framebuffer object could not be created.
+    #[error("failed to allocate framebuffer: {0}")]
+    FramebufferAlloc(String),
+    /// A texture atlas has no space left for a new entry.
+    #[error("texture atlas is full")]
+    AtlasFull,
+    /// Pixel data uploaded to a texture is shorter than the region it is written to.
+    #[error("pixel data too short: expected {expected} bytes, got {actual}")]
+    PixelDataSize { expected: usize, actual: usize },
+    /// A vector path could not be tessellated.
+    #[cfg(feature = "lyon")]
+    #[error("failed to tessellate path: {0}")]
+    Tessellation(String),
+    /// A model or one of its resources could not be loaded.
+    #[cfg(feature = "asset")]
+    #[error("failed to load asset: {0}")]
+    Asset(String),
+    /// A requested feature is not supported by the driver.
+    #[error("unsupported capability: {0}")]
+    Unsupported(String),
+    /// A declared feature requirement is not met, see [`Features`].
+    #[error(transparent)]
+    Capability(#[from] CapabilityError),
+    /// An error reported by `glGetError`.
+    #[error("OpenGL error {0:#x}")]
+    Gl(u32),
+}
+
+impl ManagedContext {
+    /// Check for errors reported by `glGetError`, returning the first one.
+    pub fn check_error(&self) -> Result<(), Error> {
+        match unsafe { self.gl.get_error() } {
+            NO_ERROR => Ok(()),
+            error => Err(Error::Gl(error)),
+        }
+    }
+}
@@ -0,0 +1,414 @@
+use super::*;
+use bitflags::bitflags;
+
+bitflags! {
+    /// Optional features a pipeline or texture may depend on.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+    pub struct Features: u32 {
+        /// Per-instance vertex attributes (`glVertexAttribDivisor`).
+        const INSTANCING = 1 << 0;
+        /// sRGB texture formats that convert correctly.
+        const SRGB = 1 << 1;
+        /// Multisampled textures (`TEXTURE_2D_MULTISAMPLE`).
+        const MULTISAMPLE_TEXTURES = 1 << 2;
+        /// Geometry shaders and layered rendering.
+        const GEOMETRY_SHADERS = 1 << 3;
+        /// Direct state access (`ARB_direct_state_access`), used to modify objects without binding them. Buffer
+        /// updates, sub-image uploads and vertex array setup also need [`ManagedContext::load_direct_state_access`].
+        const DIRECT_STATE_ACCESS = 1 << 4;
+        /// Floating point color attachments, e.g. for HDR rendering.
+        const FLOAT_RENDER_TARGETS = 1 << 5;
+        /// Unsized `ALPHA`, `LUMINANCE` and `LUMINANCE_ALPHA` textures, which core profiles removed.
+        const LUMINANCE_FORMATS = 1 << 6;
+        /// Normalized formats only desktop GL has, e.g. `RGB16` or `R3_G3_B2`.
+        const DESKTOP_TEXTURE_FORMATS = 1 << 7;
+        /// Anisotropic texture filtering (`TEXTURE_MAX_ANISOTROPY`).
+        const ANISOTROPIC_FILTERING = 1 << 8;
+        /// Separate vertex attribute formats and vertex buffer bindings (`glBindVertexBuffer`).
+        const VERTEX_ATTRIB_BINDING = 1 << 9;
+        /// [`TextureWrap::ClampToBorder`] and texture border colors, which OpenGL ES only has from 3.2 on.
+        const CLAMP_TO_BORDER = 1 << 10;
+        /// Clamping fragment depth instead of clipping at the near & far planes (`GL_DEPTH_CLAMP`).
+        const DEPTH_CLAMP = 1 << 11;
+        /// Color write masks per color attachment (`glColorMaski`).
+        const DRAW_BUFFERS_INDEXED = 1 << 12;
+        /// Running the fragment shader per sample on multisampled targets (`GL_SAMPLE_SHADING`).
+        const SAMPLE_SHADING = 1 << 13;
+        /// Blend factors reading a second fragment shader output, e.g. [`BlendFactor::Source1Color`].
+        const DUAL_SOURCE_BLEND = 1 << 14;
+        /// Buffers read as textures (`GL_TEXTURE_BUFFER`), see [`BufferTexture`].
+        const BUFFER_TEXTURES = 1 << 15;
+        /// Compute shaders reading & writing shader storage buffers.
+        const COMPUTE_SHADERS = 1 << 16;
+        /// Issuing many indirect draws with one call (`glMultiDrawElementsIndirect`).
+        const MULTI_DRAW_INDIRECT = 1 << 17;
+        /// Instanced draw calls (`glDrawArraysInstanced`), see [`ManagedContext::set_instancing_emulation`].
+        const INSTANCED_DRAWS = 1 << 18;
+        /// Discarding primitives before rasterization (`GL_RASTERIZER_DISCARD`), e.g. for transform feedback passes.
+        const RASTERIZER_DISCARD = 1 << 19;
+        /// Naming objects for graphics debuggers and driver messages (`glObjectLabel`, `KHR_debug`).
+        const DEBUG_LABELS = 1 << 20;
+    }
+}
+
+impl Features {
+    pub(crate) fn query(info: &DriverInfo, gl: &Context, quirks: DriverQuirks) -> Self {
+        let extensions = gl.supported_extensions();
+        let has = |name: &str| extensions.contains(name);
+        let (desktop, es) = if info.is_embedded {
+            (false, true)
+        } else {
+            (true, false)
+        };
+
+        let mut features = Self::empty();
+        features.set(
+            Self::INSTANCING,
+            (desktop && (info.is_at_least(3, 3) || has("GL_ARB_instanced_arrays")))
+                || (es && info.is_at_least(3, 0)),
+        );
+        features.set(
+            Self::INSTANCED_DRAWS,
+            (desktop
+                && (info.is_at_least(3, 1)
+                    || has("GL_ARB_draw_instanced")
+                    || has("GL_EXT_draw_instanced")))
+                || (es && (info.is_at_least(3, 0) || has("GL_EXT_draw_instanced"))),
+        );
+        features.set(
+            Self::SRGB,
+            !quirks.contains(DriverQuirks::BROKEN_SRGB)
+                && (info.is_at_least(3, 0) || has("GL_EXT_texture_sRGB") || has("GL_EXT_sRGB")),
+        );
+        features.set(
+            Self::MULTISAMPLE_TEXTURES,
+            (desktop && (info.is_at_least(3, 2) || has("GL_ARB_texture_multisample")))
+                || (es && info.is_at_least(3, 1)),
+        );
+        features.set(
+            Self::GEOMETRY_SHADERS,
+            info.is_at_least(3, 2) || (es && has("GL_EXT_geometry_shader")),
+        );
+        features.set(
+            Self::DIRECT_STATE_ACCESS,
+            desktop && (info.is_at_least(4, 5) || has("GL_ARB_direct_state_access")),
+        );
+        features.set(
+            Self::FLOAT_RENDER_TARGETS,
+            (desktop && info.is_at_least(3, 0)) || (es && has("GL_EXT_color_buffer_float")),
+        );
+        features.set(
+            Self::LUMINANCE_FORMATS,
+            es || info.is_compatibility_profile || !info.is_at_least(3, 2),
+        );
+        features.set(Self::DESKTOP_TEXTURE_FORMATS, desktop);
+        features.set(
+            Self::ANISOTROPIC_FILTERING,
+            (desktop && info.is_at_least(4, 6))
+                || has("GL_ARB_texture_filter_anisotropic")
+                || has("GL_EXT_texture_filter_anisotropic"),
+        );
+        features.set(
+            Self::VERTEX_ATTRIB_BINDING,
+            (desktop && (info.is_at_least(4, 3) || has("GL_ARB_vertex_attrib_binding")))
+                || (es && info.is_at_least(3, 1)),
+        );
+        features.set(
+            Self::CLAMP_TO_BORDER,
+            desktop
+                || (es
+                    && (info.is_at_least(3, 2)
+                        || has("GL_EXT_texture_border_clamp")
+                        || has("GL_OES_texture_border_clamp"))),
+        );
+        features.set(
+            Self::DEBUG_LABELS,
+            (desktop && info.is_at_least(4, 3))
+                || (es && info.is_at_least(3, 2))
+                || has("GL_KHR_debug"),
+        );
+        features.set(
+            Self::DEPTH_CLAMP,
+            (desktop && (info.is_at_least(3, 2) || has("GL_ARB_depth_clamp")))
+                || (es && has("GL_EXT_depth_clamp")),
+        );
+        features.set(
+            Self::RASTERIZER_DISCARD,
+            info.is_at_least(3, 0) || (desktop && has("GL_EXT_transform_feedback")),
+        );
+        features.set(
+            Self::DRAW_BUFFERS_INDEXED,
+            (desktop && info.is_at_least(3, 0))
+                || (es
+                    && (info.is_at_least(3, 2)
+                        || has("GL_EXT_draw_buffers_indexed")
+                        || has("GL_OES_draw_buffers_indexed"))),
+        );
+        features.set(
+            Self::SAMPLE_SHADING,
+            (desktop && (info.is_at_least(4, 0) || has("GL_ARB_sample_shading")))
+                || (es && (info.is_at_least(3, 2) || has("GL_OES_sample_shading"))),
+        );
+        features.set(
+            Self::DUAL_SOURCE_BLEND,
+            (desktop && (info.is_at_least(3, 3) || has("GL_ARB_blend_func_extended")))
+                || (es && has("GL_EXT_blend_func_extended")),
+        );
+        features.set(
+            Self::BUFFER_TEXTURES,
+            (desktop && (info.is_at_least(3, 1) || has("GL_ARB_texture_buffer_object")))
+                || (es
+                    && (info.is_at_least(3, 2)
+                        || has("GL_EXT_texture_buffer")
+                        || has("GL_OES_texture_buffer"))),
+        );
+        features.set(
+            Self::COMPUTE_SHADERS,
+            (desktop
+                && (info.is_at_least(4, 3)
+                    || (has("GL_ARB_compute_shader")
+                        && has("GL_ARB_shader_storage_buffer_object"))))
+                || (es && info.is_at_least(3, 1)),
+        );
+        features.set(
+            Self::MULTI_DRAW_INDIRECT,
+            (desktop && (info.is_at_least(4, 3) || has("GL_ARB_multi_draw_indirect")))
+                || (es && has("GL_EXT_multi_draw_indirect")),
+        );
+        features
+    }
+}
+
+/// A requested feature is missing from the context.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("missing {missing:?}: {what}")]
+pub struct CapabilityError {
+    /// The missing features.
+    pub missing: Features,
+    /// What requested them, or how the request was downgraded.
+    pub what: String,
+}
+
+/// Called when rapax downgrades a request because of missing features, see
+/// [`ManagedContext::set_capability_warning_handler`].
+pub struct CapabilityWarningHandler(Box<dyn Fn(&CapabilityError)>);
+
+impl std::fmt::Debug for CapabilityWarningHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CapabilityWarningHandler")
+    }
+}
+
+impl ManagedContext {
+    /// The optional features supported by the context.
+    pub fn features(&self) -> Features {
+        self.features
+    }
+
+    pub(crate) fn supports_dsa(&self) -> bool {
+        self.features.contains(Features::DIRECT_STATE_ACCESS)
+    }
+
+    /// Check that all `required` features are supported.
+    pub fn require_features(&self, required: Features, what: &str) -> Result<(), CapabilityError> {
+        let missing = required - self.features;
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(CapabilityError {
+                missing,
+                what: what.to_owned(),
+            })
+        }
+    }
+
+    /// Check that the context supports every feature the pipeline requires.
+    ///
+    /// [`ManagedContext::bind_pipeline`] downgrades unsupported pipelines on a best-effort basis instead, e.g. instanced
+    /// attributes are read per vertex.
+    pub fn validate_pipeline(&self, pipeline: &RenderPipeline) -> Result<(), CapabilityError> {
+        self.require_features(pipeline.required_features(), "render pipeline")
+    }
+
+    /// Receive a warning whenever a request is downgraded because of missing features.
+    ///
+    /// Without a handler, downgrades are only reported as `tracing` warnings when the `tracing` feature is enabled.
+    pub fn set_capability_warning_handler(&mut self, handler: impl Fn(&CapabilityError) + 'static) {
+        self.capability_warning = Some(CapabilityWarningHandler(Box::new(handler)));
+    }
+
+    /// Report a best-effort downgrade if any of the `required` features are missing.
+    ///
+    /// Returns true if everything is supported.
+    pub(crate) fn warn_missing_features(&self, required: Features, downgrade: &str) -> bool {
+        let Err(err) = self.require_features(required, downgrade) else {
+            return true;
+        };
+
+        #[cfg(feature = "tracing")]
+        tracing::warn!(missing = ?err.missing, "{}", err.what);
+        if let Some(handler) = &self.capability_warning {
+            (handler.0)(&err);
+        }
+        false
+    }
+
+    /// Report each feature `pipeline` depends on but the context lacks, once per program and feature, so pipelines
+    /// bound every frame don't repeat the warning.
+    pub(crate) fn warn_missing_pipeline_features(&mut self, pipeline: &RenderPipeline) {
+        let missing = pipeline.required_features().difference(self.features);
+        for feature in missing.iter() {
+            if !self
+                .pipeline_warnings
+                .insert((pipeline.program.program, feature))
+            {
+                continue;
+            }
+            let downgrade = match feature {
+                Features::INSTANCING => "instanced vertex attributes are read per vertex",
+                Features::DEPTH_CLAMP => "depth is clipped at the near & far planes",
+                Features::RASTERIZER_DISCARD => "primitives are rasterized",
+                Features::DRAW_BUFFERS_INDEXED => "color write masks apply to every attachment",
+                Features::DUAL_SOURCE_BLEND => "dual-source blend factors read the first output",
+                _ => "render pipeline bound without a feature it requires",
+            };
+            self.warn_missing_features(feature, downgrade);
+        }
+    }
+
+    /// Downgrade a texture format the context can't allocate, warning about it.
+    ///
+    /// Pixel data must be adapted with [`ManagedContext::pixel_transfer`] and luminance formats swizzled with
+    /// [`ManagedContext::apply_format_swizzle`].
+    pub(crate) fn supported_format(&self, format: InternalTextureFormat) -> InternalTextureFormat {
+        let downgrade = match format {
+            InternalTextureFormat::Srgb8 | InternalTextureFormat::Srgb8Alpha8 => {
+                "sRGB texture format downgraded to a linear format"
+            }
+            InternalTextureFormat::Alpha
+            | InternalTextureFormat::Luminance
+            | InternalTextureFormat::LuminanceAlpha => {
+                "luminance texture format replaced by a swizzled red/green format"
+            }
+            _ => "texture format downgraded to the closest supported format",
+        };
+        if self.warn_missing_features(format.required_features(), downgrade) {
+            format
+        } else {
+            format.downgrade(self.features)
+        }
+    }
+}
+
+impl ManagedContext {
+    /// Downgrade [`TextureWrap::ClampToBorder`] to [`TextureWrap::ClampToEdge`] if the context lacks it, warning about
+    /// it unless the border is emulated in the shader. Emulated borders also clamp to the edge on drivers with
+    /// [`DriverQuirks::SLOW_CLAMP_TO_BORDER`], as the shader gives the same result.
+    pub(crate) fn supported_wrap(&self, wrap: TextureWrap, emulated: bool) -> TextureWrap {
+        if wrap != TextureWrap::ClampToBorder {
+            return wrap;
+        }
+        let slow = self.quirks().contains(DriverQuirks::SLOW_CLAMP_TO_BORDER);
+        if self.features.contains(Features::CLAMP_TO_BORDER) && !(emulated && slow) {
+            return wrap;
+        }
+        if !emulated {
+            self.warn_missing_features(
+                Features::CLAMP_TO_BORDER,
+                "clamp to border wrapping replaced by clamp to edge",
+            );
+        }
+        TextureWrap::ClampToEdge
+    }
+}
+
+impl RenderPipeline {
+    /// Declare features the pipeline depends on, in addition to those implied by its state.
+    pub fn with_required_features(self, features: Features) -> Self {
+        Self {
+            required_features: features,
+            ..self
+        }
+    }
+
+    /// Every feature the pipeline depends on, including instancing if any attribute has a divisor, and depth clamping,
+    /// rasterizer discard and per-attachment color writes if used.
+    pub fn required_features(&self) -> Features {
+        let mut features = self.required_features;
+        if self.vertex_attributes.iter().any(|attr| attr.divisor != 0) {
+            features |= Features::INSTANCING;
+        }
+        if self.depth_clamp {
+            features |= Features::DEPTH_CLAMP;
+        }
+        if self.rasterizer_discard {
+            features |= Features::RASTERIZER_DISCARD;
+        }
+        if !self.color_write_attachments.is_empty() {
+            features |= Features::DRAW_BUFFERS_INDEXED;
+        }
+        if self.blend_enabled
+            && (is_dual_source(self.blend_func.0) || is_dual_source(self.blend_func.1))
+        {
+            features |= Features::DUAL_SOURCE_BLEND;
+        }
+        features
+    }
+}
+
+impl InternalTextureFormat {
+    /// The features required to allocate textures of this format.
+    pub fn required_features(&self) -> Features {
+        match self {
+            Self::Srgb8 | Self::Srgb8Alpha8 => Features::SRGB,
+            Self::R16F | Self::Rg16F | Self::Rgba16F | Self::Rgba32F => {
+                Features::FLOAT_RENDER_TARGETS
+            }
+            Self::Alpha | Self::Luminance | Self::LuminanceAlpha => Features::LUMINANCE_FORMATS,
+            Self::R3G3B2
+            | Self::Rgb4
+            | Self::Rgb5
+            | Self::Rgb10
+            | Self::Rgb12
+            | Self::Rgb16
+            | Self::Rgba2
+            | Self::Rgba12
+            | Self::R16
+            | Self::Rg16
+            | Self::Rgba16 => Features::DESKTOP_TEXTURE_FORMATS,
+            _ => Features::empty(),
+        }
+    }
+
+    /// The closest format that does not require `features`, used as a best-effort downgrade.
+    pub(crate) fn downgrade(self, features: Features) -> Self {
+        match self {
+            Self::Srgb8 if !features.contains(Features::SRGB) => Self::Rgb8,
+            Self::Srgb8Alpha8 if !features.contains(Features::SRGB) => Self::Rgba8,
+            Self::Rgba16F | Self::Rgba32F if !features.contains(Features::FLOAT_RENDER_TARGETS) => {
+                Self::Rgba8
+            }
+            Self::R16F if !features.contains(Features::FLOAT_RENDER_TARGETS) => Self::R8,
+            Self::Rg16F if !features.contains(Features::FLOAT_RENDER_TARGETS) => Self::Rg8,
+            Self::Alpha | Self::Luminance if !features.contains(Features::LUMINANCE_FORMATS) => {
+                Self::R8
+            }
+            Self::LuminanceAlpha if !features.contains(Features::LUMINANCE_FORMATS) => Self::Rg8,
+            Self::R3G3B2 | Self::Rgb4 | Self::Rgb5 | Self::Rgb10 | Self::Rgb12 | Self::Rgb16
+                if !features.contains(Features::DESKTOP_TEXTURE_FORMATS) =>
+            {
+                Self::Rgb8
+            }
+            Self::Rgba2 if !features.contains(Features::DESKTOP_TEXTURE_FORMATS) => Self::Rgba4,
+            Self::R16 if !features.contains(Features::DESKTOP_TEXTURE_FORMATS) => Self::R8,
+            Self::Rg16 if !features.contains(Features::DESKTOP_TEXTURE_FORMATS) => Self::Rg8,
+            Self::Rgba12 | Self::Rgba16
+                if !features.contains(Features::DESKTOP_TEXTURE_FORMATS) =>
+            {
+                Self::Rgba8
+            }
+            format => format,
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use super::*;
+
+use std::cell::Cell;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+
+/// A framebuffer attachment point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attachment {
+    /// The color attachment with the given index.
+    Color(u32),
+    Depth,
+    Stencil,
+    DepthStencil,
+}
+
+impl Attachment {
+    pub fn to_gl(&self) -> u32 {
+        match *self {
+            Self::Color(index) => COLOR_ATTACHMENT0 + index,
+            Self::Depth => DEPTH_ATTACHMENT,
+            Self::Stencil => STENCIL_ATTACHMENT,
+            Self::DepthStencil => DEPTH_STENCIL_ATTACHMENT,
+        }
+    }
+}
+
+/// A handle to an OpenGL framebuffer object. The internal OpenGL framebuffer object will be automatically freed on drop.
+#[derive(Debug)]
+pub struct Framebuffer {
+    pub(crate) framebuffer: NativeFramebuffer,
+    srgb: Cell<bool>,
+    gl: Arc<Context>,
+}
+
+impl Framebuffer {
+    /// Create a new framebuffer without any attachments.
+    pub fn new(ctx: &ManagedContext) -> Result<Self, Error> {
+        let framebuffer = unsafe {
+            ctx.gl
+                .create_framebuffer()
+                .map_err(Error::FramebufferAlloc)?
+        };
+
+        Ok(Self {
+            framebuffer,
+            srgb: Cell::new(false),
+            gl: ctx.gl.clone(),
+        })
+    }
+
+    /// Attach a 2D texture.
+    pub fn attach_texture_2d(
+        &self,
+        ctx: &mut ManagedContext,
+        attachment: Attachment,
+        texture: &Texture2D,
+    ) {
+        if attachment == Attachment::Color(0) {
+            self.srgb.set(matches!(
+                texture.internal_format,
+                InternalTextureFormat::Srgb8 | InternalTextureFormat::Srgb8Alpha8
+            ));
+        }
+        self.edit(ctx, |gl| unsafe {
+            gl.framebuffer_texture_2d(
+                FRAMEBUFFER,
+                attachment.to_gl(),
+                TEXTURE_2D,
+                Some(texture.handle.texture),
+                0,
+            );
+        });
+    }
+
+    /// Attach a single face of a cube map.
+    pub fn attach_cube_face(
+        &self,
+        ctx: &mut ManagedContext,
+        attachment: Attachment,
+        texture: &TextureCube,
+        face: CubeFace,
+    ) {
+        self.edit(ctx, |gl| unsafe {
+            gl.framebuffer_texture_2d(
+                FRAMEBUFFER,
+                attachment.to_gl(),
+                face.to_gl(),
+                Some(texture.handle.texture),
+                0,
+            );
+        });
+    }
+
+    /// Attach every layer of a cube map or array texture, so a geometry shader can select the layer via `gl_Layer`.
+    ///
+    /// Requires geometry shader support, see [`ManagedContext::supports_geometry_shaders`].
+    pub fn attach_layered(
+        &self,
+        ctx: &mut ManagedContext,
+        attachment: Attachment,
+        texture: &impl LayeredTexture,
+    ) {
+        self.edit(ctx, |gl| unsafe {
+            gl.framebuffer_texture(
+                FRAMEBUFFER,
+                attachment.to_gl(),
+                Some(texture.native_texture()),
+                0,
+            );
+        });
+    }
+
+    /// Attach a single layer of an array texture.
+    ///
+    /// ## Panics
+    /// The layer must exist.
+    pub fn attach_layer(
+        &self,
+        ctx: &mut ManagedContext,
+        attachment: Attachment,
+        texture: &Texture2DArray,
+        layer: i32,
+    ) {
+        assert!(layer >= 0 && layer < texture.layers, "no such layer!");
+        self.edit(ctx, |gl| unsafe {
+            gl.framebuffer_texture_layer(
+                FRAMEBUFFER,
+                attachment.to_gl(),
+                Some(texture.handle.texture),
+                0,
+                layer,
+            );
+        });
+    }
+
+    /// Remove whatever is attached to the given attachment point.
+    pub fn detach(&self, ctx: &mut ManagedContext, attachment: Attachment) {
+        self.edit(ctx, |gl| unsafe {
+            gl.framebuffer_texture_2d(FRAMEBUFFER, attachment.to_gl(), TEXTURE_2D, None, 0);
+        });
+    }
+
+    /// Returns true if the framebuffer is complete and can be rendered to.
+    pub fn is_complete(&self, ctx: &mut ManagedContext) -> bool {
+        self.edit(ctx, |gl| unsafe {
+            gl.check_framebuffer_status(FRAMEBUFFER)
+        }) == FRAMEBUFFER_COMPLETE
+    }
+
+    /// Bind the framebuffer to run `edit` on it, then bind the previously bound framebuffers again.
+    fn edit<T>(&self, ctx: &ManagedContext, edit: impl FnOnce(&Context) -> T) -> T {
+        let previous = ctx.framebuffer_binding();
+        unsafe { ctx.gl.bind_framebuffer(FRAMEBUFFER, Some(self.framebuffer)) };
+        let result = edit(&ctx.gl);
+        ctx.restore_framebuffer(previous);
+        result
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe { self.gl.delete_framebuffer(self.framebuffer) }
+    }
+}
+
+impl BindableFramebuffer for &Framebuffer {
+    unsafe fn bind(&self, target: u32, gl: &Context) {
+        gl.bind_framebuffer(target, Some(self.framebuffer));
+    }
+
+    fn is_srgb(&self) -> bool {
+        self.srgb.get()
+    }
+}
+
+impl BindableFramebuffer for NativeFramebuffer {
+    unsafe fn bind(&self, target: u32, gl: &Context) {
+        gl.bind_framebuffer(target, Some(*self));
+    }
+}
+
+/// A texture with multiple layers that can be attached to a framebuffer as a whole.
+pub trait LayeredTexture {
+    /// The underlying OpenGL texture.
+    fn native_texture(&self) -> NativeTexture;
+
+    /// The number of layers addressable via `gl_Layer`.
+    fn layer_count(&self) -> i32;
+}
+
+impl LayeredTexture for TextureCube {
+    fn native_texture(&self) -> NativeTexture {
+        self.handle.texture
+    }
+
+    fn layer_count(&self) -> i32 {
+        6
+    }
+}
+
+impl LayeredTexture for Texture2DArray {
+    fn native_texture(&self) -> NativeTexture {
+        self.handle.texture
+    }
+
+    fn layer_count(&self) -> i32 {
+        self.layers
+    }
+}
+
+pub trait BindableFramebuffer {
+    /// Bind the framebuffer to the given target.
+    ///
+    /// # Safety
+    /// The caller must ensure `target` is a valid framebuffer binding point.
+    unsafe fn bind(&self, target: u32, gl: &Context);
+
+    /// Returns true if the first color attachment stores sRGB-encoded colors.
+    fn is_srgb(&self) -> bool {
+        false
+    }
+}
+
+/// The framebuffers bound for drawing & reading, saved with [`ManagedContext::framebuffer_binding`] to restore them
+/// after rendering into another framebuffer.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FramebufferBinding {
+    draw: Option<NativeFramebuffer>,
+    read: Option<NativeFramebuffer>,
+    srgb: bool,
+}
+
+impl ManagedContext {
+    /// Render into the given framebuffer, or the default framebuffer if `None`.
+    pub fn bind_framebuffer(&self, framebuffer: Option<impl BindableFramebuffer>) {
+        self.debug_assert_current();
+        unsafe {
+            match framebuffer {
+                Some(framebuffer) => {
+                    framebuffer.bind(FRAMEBUFFER, &self.gl);
+                    self.framebuffer_srgb.set(framebuffer.is_srgb());
+                }
+                None => self.bind_default_framebuffer(),
+            }
+        }
+    }
+
+    /// Save the bound framebuffers, see [`ManagedContext::restore_framebuffer`].
+    pub(crate) fn framebuffer_binding(&self) -> FramebufferBinding {
+        let binding = |parameter| {
+            let name = unsafe { self.gl.get_parameter_i32(parameter) } as u32;
+            NonZeroU32::new(name).map(NativeFramebuffer)
+        };
+        // GLES 2 has a single framebuffer binding, whose enum is shared with the draw binding
+        let draw = binding(DRAW_FRAMEBUFFER_BINDING);
+        let read = if self.driver_info().is_at_least(3, 0) {
+            binding(READ_FRAMEBUFFER_BINDING)
+        } else {
+            draw
+        };
+        FramebufferBinding {
+            draw,
+            read,
+            srgb: self.framebuffer_srgb.get(),
+        }
+    }
+
+    /// Bind the framebuffers saved by [`ManagedContext::framebuffer_binding`] again.
+    pub(crate) fn restore_framebuffer(&self, binding: FramebufferBinding) {
+        unsafe {
+            if binding.draw == binding.read {
+                self.gl.bind_framebuffer(FRAMEBUFFER, binding.draw);
+            } else {
+                self.gl.bind_framebuffer(DRAW_FRAMEBUFFER, binding.draw);
+                self.gl.bind_framebuffer(READ_FRAMEBUFFER, binding.read);
+            }
+        }
+        self.framebuffer_srgb.set(binding.srgb);
+    }
+
+    /// Render into the default framebuffer.
+    pub fn bind_default_framebuffer(&self) {
+        unsafe { self.gl.bind_framebuffer(FRAMEBUFFER, None) };
+        self.framebuffer_srgb.set(self.default_framebuffer_srgb);
+    }
+
+    /// Declare whether the default framebuffer stores sRGB-encoded colors, e.g. because the window surface was
+    /// created with an sRGB config. GL has no way to query it.
+    pub fn set_default_framebuffer_srgb(&mut self, srgb: bool) {
+        self.default_framebuffer_srgb = srgb;
+        self.framebuffer_srgb.set(srgb);
+    }
+
+    /// Returns true if the bound framebuffer encodes written colors to sRGB, so they must be passed linear.
+    ///
+    /// sRGB attachments always encode on GLES; desktop GL additionally requires [`GlCapability::FRAMEBUFFER_SRGB`].
+    pub fn framebuffer_is_srgb(&self) -> bool {
+        self.framebuffer_srgb.get()
+            && (self.driver_info().is_embedded
+                || self.raw_capability(GlCapability::FRAMEBUFFER_SRGB) == Some(true))
+    }
+
+    /// Returns true if geometry shaders, and therefore layered rendering via `gl_Layer`, are supported.
+    pub fn supports_geometry_shaders(&self) -> bool {
+        let info = self.driver_info();
+        if info.is_embedded {
+            info.is_at_least(3, 2) || self.has_extension("GL_EXT_geometry_shader")
+        } else {
+            info.is_at_least(3, 2)
+        }
+    }
+}
@@ -0,0 +1,72 @@
+/// The six planes of a view frustum, for culling bounding volumes on the CPU before recording draws.
+///
+/// Planes are stored as `[a, b, c, d]` with normalized normals pointing inward, so a point `p` is inside a plane if
+/// `a * p.x + b * p.y + c * p.z + d >= 0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    planes: [[f32; 4]; 6],
+}
+
+impl Frustum {
+    /// Extract the frustum of a column-major view-projection matrix, in the space the matrix transforms from, e.g.
+    /// world space for `projection * view`. Works for perspective & orthographic projections alike.
+    pub fn from_matrix(m: &[f32; 16]) -> Self {
+        let row = |i: usize| [m[i], m[4 + i], m[8 + i], m[12 + i]];
+        let combine = |a: [f32; 4], b: [f32; 4], sign: f32| {
+            let plane = [
+                a[0] + sign * b[0],
+                a[1] + sign * b[1],
+                a[2] + sign * b[2],
+                a[3] + sign * b[3],
+            ];
+            let len = (plane[0] * plane[0] + plane[1] * plane[1] + plane[2] * plane[2]).sqrt();
+            plane.map(|component| component / len)
+        };
+
+        let w = row(3);
+        Self {
+            planes: [
+                combine(w, row(0), 1.0),
+                combine(w, row(0), -1.0),
+                combine(w, row(1), 1.0),
+                combine(w, row(1), -1.0),
+                combine(w, row(2), 1.0),
+                combine(w, row(2), -1.0),
+            ],
+        }
+    }
+
+    /// The left, right, bottom, top, near & far planes.
+    pub fn planes(&self) -> &[[f32; 4]; 6] {
+        &self.planes
+    }
+
+    /// Returns true if the axis-aligned box between `min` and `max` is at least partially inside the frustum.
+    ///
+    /// Conservative: boxes near a corner of the frustum may be reported as intersecting although they are outside.
+    pub fn intersects_aabb(&self, min: [f32; 3], max: [f32; 3]) -> bool {
+        self.planes.iter().all(|plane| {
+            // the corner furthest along the plane normal
+            let corner = [0, 1, 2].map(|i| if plane[i] > 0.0 { max[i] } else { min[i] });
+            distance(plane, corner) >= 0.0
+        })
+    }
+
+    /// Returns true if the sphere around `center` is at least partially inside the frustum.
+    ///
+    /// Conservative like [`Frustum::intersects_aabb`].
+    pub fn intersects_sphere(&self, center: [f32; 3], radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| distance(plane, center) >= -radius)
+    }
+
+    /// Returns true if `point` is inside the frustum.
+    pub fn contains_point(&self, point: [f32; 3]) -> bool {
+        self.intersects_sphere(point, 0.0)
+    }
+}
+
+fn distance(plane: &[f32; 4], point: [f32; 3]) -> f32 {
+    plane[0] * point[0] + plane[1] * point[1] + plane[2] * point[2] + plane[3]
+}
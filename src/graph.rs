@@ -0,0 +1,637 @@
+//! Frame graph for multi-pass renderers.
+//!
+//! Passes declare the textures they read and write. [`RenderGraph::execute`] orders them by their dependencies,
+//! skips passes whose output nothing uses, allocates transient render targets from a [`TargetPool`] and discards
+//! attachments that no later pass reads. Each pass runs through [`ManagedContext::render_pass`].
+
+use super::*;
+
+/// Size and format of a transient render target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetDesc {
+    pub width: i32,
+    pub height: i32,
+    /// A color format or one of the `DepthComponent*` formats.
+    pub format: InternalTextureFormat,
+}
+
+impl TargetDesc {
+    pub fn new(width: i32, height: i32, format: InternalTextureFormat) -> Self {
+        Self {
+            width,
+            height,
+            format,
+        }
+    }
+
+    fn is_depth(&self) -> bool {
+        matches!(
+            self.format,
+            InternalTextureFormat::DepthComponent16
+                | InternalTextureFormat::DepthComponent24
+                | InternalTextureFormat::DepthComponent32F
+        )
+    }
+
+    fn transfer(&self) -> Result<(TextureFormat, DataType), Error> {
+        Ok(match self.format {
+            InternalTextureFormat::DepthComponent16 | InternalTextureFormat::DepthComponent24 => {
+                (TextureFormat::DepthComponent, DataType::UnsignedInt)
+            }
+            InternalTextureFormat::DepthComponent32F => {
+                (TextureFormat::DepthComponent, DataType::Float)
+            }
+            InternalTextureFormat::Depth24Stencil8 => {
+                return Err(Error::Unsupported(
+                    "packed depth stencil targets in a render graph".to_owned(),
+                ))
+            }
+            InternalTextureFormat::Rgba16F | InternalTextureFormat::Rgba32F => {
+                (TextureFormat::Rgba, DataType::Float)
+            }
+            InternalTextureFormat::R16F => (TextureFormat::Red, DataType::Float),
+            InternalTextureFormat::Rg16F => (TextureFormat::Rg, DataType::Float),
+            InternalTextureFormat::R16 => (TextureFormat::Red, DataType::UnsignedShort),
+            InternalTextureFormat::Rg16 => (TextureFormat::Rg, DataType::UnsignedShort),
+            InternalTextureFormat::Rgba16 => (TextureFormat::Rgba, DataType::UnsignedShort),
+            InternalTextureFormat::R8 => (TextureFormat::Red, DataType::UnsignedByte),
+            InternalTextureFormat::Rg8 => (TextureFormat::Rg, DataType::UnsignedByte),
+            InternalTextureFormat::Rgb
+            | InternalTextureFormat::Rgb8
+            | InternalTextureFormat::Srgb8 => (TextureFormat::Rgb, DataType::UnsignedByte),
+            _ => (TextureFormat::Rgba, DataType::UnsignedByte),
+        })
+    }
+}
+
+/// A texture of a [`RenderGraph`], created with [`RenderGraph::create_texture`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GraphTexture(usize);
+
+#[derive(Debug)]
+struct TextureNode {
+    name: String,
+    desc: TargetDesc,
+}
+
+/// The textures and attachment operations of a single pass, see [`RenderGraph::add_pass`].
+#[derive(Debug, Clone)]
+pub struct GraphPass {
+    name: String,
+    reads: Vec<GraphTexture>,
+    color: Vec<(GraphTexture, LoadOp<[f32; 4]>)>,
+    depth: Option<(GraphTexture, LoadOp<f32>)>,
+    backbuffer: Option<LoadOp<[f32; 4]>>,
+    keep: bool,
+}
+
+impl GraphPass {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            reads: vec![],
+            color: vec![],
+            depth: None,
+            backbuffer: None,
+            keep: false,
+        }
+    }
+
+    /// Sample `texture`, which must be written by another pass.
+    pub fn read(self, texture: GraphTexture) -> Self {
+        let mut reads = self.reads;
+        reads.push(texture);
+
+        Self { reads, ..self }
+    }
+
+    /// Render into `texture` as the next color attachment. [`LoadOp::Load`] keeps what earlier passes wrote.
+    pub fn write_color(self, texture: GraphTexture, load: LoadOp<[f32; 4]>) -> Self {
+        let mut color = self.color;
+        color.push((texture, load));
+
+        Self { color, ..self }
+    }
+
+    /// Use `texture` as the depth attachment.
+    pub fn write_depth(self, texture: GraphTexture, load: LoadOp<f32>) -> Self {
+        Self {
+            depth: Some((texture, load)),
+            ..self
+        }
+    }
+
+    /// Render into the default framebuffer instead of graph textures. Such passes are never skipped.
+    pub fn write_backbuffer(self, load: LoadOp<[f32; 4]>) -> Self {
+        Self {
+            backbuffer: Some(load),
+            ..self
+        }
+    }
+
+    /// Never skip this pass, e.g. because it reads back pixels or writes buffers the graph doesn't know about.
+    pub fn keep(self) -> Self {
+        Self { keep: true, ..self }
+    }
+
+    fn writes(&self) -> impl Iterator<Item = (GraphTexture, bool)> + '_ {
+        let color = self
+            .color
+            .iter()
+            .map(|(texture, load)| (*texture, *load == LoadOp::Load));
+        let depth = self
+            .depth
+            .iter()
+            .map(|(texture, load)| (*texture, *load == LoadOp::Load));
+        color.chain(depth)
+    }
+
+    fn uses(&self) -> impl Iterator<Item = GraphTexture> + '_ {
+        self.reads
+            .iter()
+            .copied()
+            .chain(self.writes().map(|(texture, _)| texture))
+    }
+}
+
+type PassFn<'a> = Box<dyn FnOnce(&mut ManagedContext, &GraphTextures) + 'a>;
+
+/// A frame's passes and the transient textures they share. Build one per frame and run it with
+/// [`RenderGraph::execute`].
+pub struct RenderGraph<'a> {
+    textures: Vec<TextureNode>,
+    passes: Vec<(GraphPass, PassFn<'a>)>,
+}
+
+impl std::fmt::Debug for RenderGraph<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RenderGraph")
+            .field("textures", &self.textures)
+            .field(
+                "passes",
+                &self.passes.iter().map(|(pass, _)| pass).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl Default for RenderGraph<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> RenderGraph<'a> {
+    pub fn new() -> Self {
+        Self {
+            textures: vec![],
+            passes: vec![],
+        }
+    }
+
+    /// Declare a transient texture. It is only allocated while passes use it.
+    pub fn create_texture(&mut self, name: &str, desc: TargetDesc) -> GraphTexture {
+        self.textures.push(TextureNode {
+            name: name.to_owned(),
+            desc,
+        });
+        GraphTexture(self.textures.len() - 1)
+    }
+
+    /// Add a pass. `execute` is called inside the pass with its attachments bound and the viewport covering them;
+    /// textures it reads are looked up through [`GraphTextures`].
+    pub fn add_pass(
+        &mut self,
+        pass: GraphPass,
+        execute: impl FnOnce(&mut ManagedContext, &GraphTextures) + 'a,
+    ) {
+        self.passes.push((pass, Box::new(execute)));
+    }
+
+    /// The order passes will execute in, by name. Skipped passes are left out.
+    pub fn execution_order(&self) -> Vec<&str> {
+        self.schedule()
+            .into_iter()
+            .map(|index| self.passes[index].0.name.as_str())
+            .collect()
+    }
+
+    /// Order the passes so that every read sees the texture's nearest earlier write and later writes wait for its
+    /// readers, and skip passes that don't contribute to the backbuffer or a kept pass.
+    ///
+    /// ## Panics
+    ///
+    /// If passes depend on each other in a cycle.
+    fn schedule(&self) -> Vec<usize> {
+        let count = self.passes.len();
+        let writers = |texture: GraphTexture| {
+            (0..count).filter(move |&writer| {
+                self.passes[writer]
+                    .0
+                    .writes()
+                    .any(|(written, _)| written == texture)
+            })
+        };
+        let previous =
+            |index: usize, texture: GraphTexture| writers(texture).rfind(|&writer| writer < index);
+        // the write a pass at `index` sees: the nearest earlier one, or the first one if the texture is only written
+        // by passes declared later
+        let source = |index: usize, texture: GraphTexture| {
+            previous(index, texture).or_else(|| writers(texture).find(|&writer| writer != index))
+        };
+
+        // a pass depends on the writes it reads or loads, and must run after earlier writes of what it overwrites
+        // and after earlier readers of those writes
+        let mut dependencies = vec![vec![]; count];
+        let mut ordering = vec![vec![]; count];
+        for (index, (pass, _)) in self.passes.iter().enumerate() {
+            for &texture in &pass.reads {
+                let Some(source) = source(index, texture) else {
+                    continue;
+                };
+                dependencies[index].push(source);
+                for writer in writers(texture).filter(|&writer| writer > source && writer != index)
+                {
+                    ordering[writer].push(index);
+                }
+            }
+            for (texture, load) in pass.writes() {
+                if let Some(earlier) = previous(index, texture) {
+                    if load {
+                        dependencies[index].push(earlier);
+                    } else {
+                        ordering[index].push(earlier);
+                    }
+                }
+            }
+            if pass.backbuffer.is_some() {
+                if let Some(earlier) =
+                    (0..index).rfind(|&earlier| self.passes[earlier].0.backbuffer.is_some())
+                {
+                    ordering[index].push(earlier);
+                }
+            }
+        }
+
+        let mut live = vec![false; count];
+        let mut stack: Vec<usize> = (0..count)
+            .filter(|&index| {
+                let pass = &self.passes[index].0;
+                pass.keep || pass.backbuffer.is_some()
+            })
+            .collect();
+        while let Some(index) = stack.pop() {
+            if !std::mem::replace(&mut live[index], true) {
+                stack.extend(&dependencies[index]);
+            }
+        }
+
+        // stable topological sort, preferring declaration order; skipped passes don't hold anything up
+        let mut order = Vec::with_capacity(count);
+        let mut done = vec![false; count];
+        while order.len() < live.iter().filter(|live| **live).count() {
+            let next = (0..count)
+                .find(|&index| {
+                    live[index]
+                        && !done[index]
+                        && dependencies[index]
+                            .iter()
+                            .chain(&ordering[index])
+                            .all(|&dependency| !live[dependency] || done[dependency])
+                })
+                .expect("render graph passes depend on each other in a cycle!");
+            done[next] = true;
+            order.push(next);
+        }
+        order
+    }
+
+    /// Run the live passes in dependency order, allocating their textures from `pool`.
+    ///
+    /// Attachments that no later pass uses are invalidated at the end of their pass, and textures go back to the
+    /// pool after their last use, so later passes can reuse them.
+    pub fn execute(self, ctx: &mut ManagedContext, pool: &mut TargetPool) -> Result<(), Error> {
+        span!("render_graph", passes = self.passes.len());
+        let order = self.schedule();
+        let mut last_use = vec![None; self.textures.len()];
+        for (position, &index) in order.iter().enumerate() {
+            for texture in self.passes[index].0.uses() {
+                last_use[texture.0] = Some(position);
+            }
+        }
+
+        let mut passes: Vec<_> = self.passes.into_iter().map(Some).collect();
+        let mut textures = GraphTextures {
+            textures: (0..self.textures.len()).map(|_| None).collect(),
+        };
+        for (position, index) in order.into_iter().enumerate() {
+            let (pass, execute) = passes[index].take().expect("passes run once");
+            span!("graph_pass", name = pass.name.as_str());
+
+            for texture in pass.uses() {
+                if textures.textures[texture.0].is_none() {
+                    let desc = self.textures[texture.0].desc;
+                    textures.textures[texture.0] = Some(pool.acquire(ctx, desc)?);
+                }
+            }
+
+            let store = |texture: GraphTexture| {
+                if last_use[texture.0] > Some(position) {
+                    StoreOp::Store
+                } else {
+                    StoreOp::Discard
+                }
+            };
+
+            if let Some(load) = pass.backbuffer {
+                let desc = RenderPassDescriptor::new(None).with_color(Operations {
+                    load,
+                    store: StoreOp::Store,
+                });
+                ctx.render_pass(&desc, |ctx| execute(ctx, &textures));
+            } else {
+                let framebuffer = pool.acquire_framebuffer(ctx)?;
+                let mut desc = RenderPassDescriptor::new(Some(&framebuffer));
+                let mut attachments = vec![];
+                for (i, (texture, load)) in pass.color.iter().enumerate() {
+                    let attachment = Attachment::Color(i as u32);
+                    framebuffer.attach_texture_2d(ctx, attachment, textures.get(*texture));
+                    attachments.push(attachment);
+                    desc = desc.with_color(Operations {
+                        load: *load,
+                        store: store(*texture),
+                    });
+                }
+                if let Some((texture, load)) = pass.depth {
+                    framebuffer.attach_texture_2d(ctx, Attachment::Depth, textures.get(texture));
+                    attachments.push(Attachment::Depth);
+                    desc = desc.with_depth(Operations {
+                        load,
+                        store: store(texture),
+                    });
+                }
+                let previous = ctx.framebuffer_binding();
+                ctx.bind_framebuffer(Some(&framebuffer));
+                unsafe {
+                    let buffers: Vec<u32> = (0..pass.color.len() as u32)
+                        .map(|i| COLOR_ATTACHMENT0 + i)
+                        .collect();
+                    if buffers.is_empty() {
+                        ctx.gl.draw_buffers(&[NONE]);
+                    } else {
+                        ctx.gl.draw_buffers(&buffers);
+                    }
+                }
+
+                let viewport = ctx.viewport_rect();
+                if let Some(texture) = pass.writes().map(|(texture, _)| texture).next() {
+                    let target = &self.textures[texture.0].desc;
+                    ctx.set_viewport(0, 0, target.width, target.height);
+                }
+                ctx.render_pass(&desc, |ctx| execute(ctx, &textures));
+                for attachment in attachments {
+                    framebuffer.detach(ctx, attachment);
+                }
+                ctx.restore_framebuffer(previous);
+                ctx.set_viewport(viewport.x, viewport.y, viewport.width, viewport.height);
+                pool.framebuffers.push(framebuffer);
+            }
+
+            for texture in pass.uses() {
+                if last_use[texture.0] == Some(position) {
+                    if let Some(allocated) = textures.textures[texture.0].take() {
+                        pool.release(self.textures[texture.0].desc, allocated);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The name given to `texture` in [`RenderGraph::create_texture`].
+    pub fn texture_name(&self, texture: GraphTexture) -> &str {
+        &self.textures[texture.0].name
+    }
+}
+
+/// The textures allocated while a pass executes.
+#[derive(Debug)]
+pub struct GraphTextures {
+    textures: Vec<Option<Texture2D>>,
+}
+
+impl GraphTextures {
+    /// Look up a texture the pass reads or writes.
+    ///
+    /// ## Panics
+    ///
+    /// If `texture` isn't used by the current pass.
+    pub fn get(&self, texture: GraphTexture) -> &Texture2D {
+        self.textures[texture.0]
+            .as_ref()
+            .expect("texture is not used by this pass!")
+    }
+}
+
+/// Render targets and framebuffers reused across frames by [`RenderGraph::execute`].
+#[derive(Debug, Default)]
+pub struct TargetPool {
+    textures: Vec<(TargetDesc, Texture2D)>,
+    framebuffers: Vec<Framebuffer>,
+}
+
+impl TargetPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of idle textures in the pool.
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+
+    /// Free all idle textures and framebuffers, e.g. after the window was resized.
+    pub fn clear(&mut self) {
+        self.textures.clear();
+        self.framebuffers.clear();
+    }
+
+    fn acquire(&mut self, ctx: &mut ManagedContext, desc: TargetDesc) -> Result<Texture2D, Error> {
+        if let Some(index) = self.textures.iter().position(|(pooled, _)| *pooled == desc) {
+            return Ok(self.textures.swap_remove(index).1);
+        }
+
+        let filter = if desc.is_depth() {
+            TextureFilteringMode::Nearest
+        } else {
+            TextureFilteringMode::Linear
+        };
+        let (format, ty) = desc.transfer()?;
+        let texture = TextureHandle::new_with(
+            ctx,
+            &SamplerDescriptor {
+                min_filter: filter,
+                mag_filter: filter,
+                ..Default::default()
+            },
+        )?
+        .with_memory_label("render graph")
+        .allocate_2d_data(ctx, None, desc.format, format, desc.width, desc.height, ty);
+        Ok(texture)
+    }
+
+    fn release(&mut self, desc: TargetDesc, texture: Texture2D) {
+        self.textures.push((desc, texture));
+    }
+
+    fn acquire_framebuffer(&mut self, ctx: &ManagedContext) -> Result<Framebuffer, Error> {
+        match self.framebuffers.pop() {
+            Some(framebuffer) => Ok(framebuffer),
+            None => Framebuffer::new(ctx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> (RenderGraph<'static>, GraphTexture, GraphTexture) {
+        let mut graph = RenderGraph::new();
+        let desc = TargetDesc::new(4, 4, InternalTextureFormat::Rgba8);
+        let a = graph.create_texture("a", desc);
+        let b = graph.create_texture("b", desc);
+        (graph, a, b)
+    }
+
+    #[test]
+    fn reads_see_the_nearest_earlier_write() {
+        let (mut graph, a, b) = graph();
+        let clear = LoadOp::Clear([0.0; 4]);
+        graph.add_pass(GraphPass::new("write").write_color(a, clear), |_, _| {});
+        graph.add_pass(
+            GraphPass::new("read").read(a).write_color(b, clear),
+            |_, _| {},
+        );
+        graph.add_pass(GraphPass::new("rewrite").write_color(a, clear), |_, _| {});
+        graph.add_pass(
+            GraphPass::new("reread")
+                .read(a)
+                .read(b)
+                .write_backbuffer(clear),
+            |_, _| {},
+        );
+
+        assert_eq!(
+            graph.execution_order(),
+            ["write", "read", "rewrite", "reread"]
+        );
+    }
+
+    #[test]
+    fn writes_wait_for_earlier_readers() {
+        let (mut graph, a, b) = graph();
+        let clear = LoadOp::Clear([0.0; 4]);
+        graph.add_pass(GraphPass::new("write").write_color(a, clear), |_, _| {});
+        graph.add_pass(
+            GraphPass::new("present").read(b).write_backbuffer(clear),
+            |_, _| {},
+        );
+        graph.add_pass(
+            GraphPass::new("read").read(a).write_color(b, clear),
+            |_, _| {},
+        );
+        graph.add_pass(GraphPass::new("rewrite").write_color(a, clear), |_, _| {});
+        graph.add_pass(GraphPass::new("reread").read(a).keep(), |_, _| {});
+
+        assert_eq!(
+            graph.execution_order(),
+            ["write", "read", "present", "rewrite", "reread"]
+        );
+    }
+
+    #[test]
+    fn loads_depend_on_the_previous_write() {
+        let (mut graph, a, _) = graph();
+        graph.add_pass(
+            GraphPass::new("accumulate").write_color(a, LoadOp::Load),
+            |_, _| {},
+        );
+        graph.add_pass(
+            GraphPass::new("clear").write_color(a, LoadOp::Clear([0.0; 4])),
+            |_, _| {},
+        );
+        graph.add_pass(
+            GraphPass::new("present")
+                .read(a)
+                .write_backbuffer(LoadOp::Load),
+            |_, _| {},
+        );
+
+        // the clear discards what was accumulated before it
+        assert_eq!(graph.execution_order(), ["clear", "present"]);
+    }
+
+    #[test]
+    fn unused_passes_are_skipped() {
+        let (mut graph, a, b) = graph();
+        let clear = LoadOp::Clear([0.0; 4]);
+        graph.add_pass(GraphPass::new("unused").write_color(b, clear), |_, _| {});
+        graph.add_pass(GraphPass::new("write").write_color(a, clear), |_, _| {});
+        graph.add_pass(
+            GraphPass::new("present").read(a).write_backbuffer(clear),
+            |_, _| {},
+        );
+
+        assert_eq!(graph.execution_order(), ["write", "present"]);
+    }
+
+    #[test]
+    fn reads_of_later_declared_writes_run_after_them() {
+        let (mut graph, a, _) = graph();
+        graph.add_pass(
+            GraphPass::new("present")
+                .read(a)
+                .write_backbuffer(LoadOp::Load),
+            |_, _| {},
+        );
+        graph.add_pass(
+            GraphPass::new("write").write_color(a, LoadOp::Clear([0.0; 4])),
+            |_, _| {},
+        );
+
+        assert_eq!(graph.execution_order(), ["write", "present"]);
+    }
+
+    #[test]
+    fn backbuffer_passes_keep_their_order() {
+        let (mut graph, a, _) = graph();
+        graph.add_pass(
+            GraphPass::new("background").write_backbuffer(LoadOp::Clear([0.0; 4])),
+            |_, _| {},
+        );
+        graph.add_pass(
+            GraphPass::new("overlay")
+                .read(a)
+                .write_backbuffer(LoadOp::Load),
+            |_, _| {},
+        );
+        graph.add_pass(
+            GraphPass::new("write").write_color(a, LoadOp::Clear([0.0; 4])),
+            |_, _| {},
+        );
+
+        assert_eq!(graph.execution_order(), ["background", "write", "overlay"]);
+    }
+
+    #[test]
+    fn packed_depth_stencil_targets_are_unsupported() {
+        let desc = TargetDesc::new(4, 4, InternalTextureFormat::Depth24Stencil8);
+        assert!(matches!(desc.transfer(), Err(Error::Unsupported(_))));
+    }
+}
@@ -0,0 +1,95 @@
+use super::*;
+
+use std::ffi::c_void;
+
+// fixed-function enums glow does not define
+pub(crate) const POINT_SMOOTH: u32 = 0x0B10;
+const POINT_SMOOTH_HINT: u32 = 0x0C51;
+
+/// An implementation-specific behavior that can be tuned with [`ManagedContext::set_hint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum Hint {
+    /// Quality of antialiased points. Compatibility profile only.
+    PointSmooth = POINT_SMOOTH_HINT,
+    /// Quality of antialiased lines.
+    LineSmooth = LINE_SMOOTH_HINT,
+    /// Quality of antialiased polygons.
+    PolygonSmooth = POLYGON_SMOOTH_HINT,
+    /// Quality of texture compression when uploading to a compressed internal format.
+    TextureCompression = TEXTURE_COMPRESSION_HINT,
+    /// Accuracy of `dFdx`/`dFdy` in fragment shaders.
+    FragmentShaderDerivative = FRAGMENT_SHADER_DERIVATIVE_HINT,
+}
+
+impl Hint {
+    /// Returns true if the hint is accepted by a context with the given driver info.
+    pub fn is_supported(&self, info: &DriverInfo) -> bool {
+        match self {
+            Self::PointSmooth => info.is_compatibility_profile,
+            Self::LineSmooth | Self::PolygonSmooth | Self::TextureCompression => !info.is_embedded,
+            Self::FragmentShaderDerivative => !info.is_embedded || info.is_at_least(3, 0),
+        }
+    }
+}
+
+/// The preferred trade-off for a [`Hint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum Quality {
+    Fastest = FASTEST,
+    Nicest = NICEST,
+    DontCare = DONT_CARE,
+}
+
+/// `glHint`, which glow does not expose.
+pub(crate) type HintFn = unsafe extern "system" fn(target: u32, mode: u32);
+
+impl ManagedContext {
+    /// Load `glHint` so [`ManagedContext::set_hint`] can be used.
+    ///
+    /// `loader` is the same function used to create the glow context, e.g. `window.get_proc_address`.
+    ///
+    /// # Safety
+    /// `loader` must return valid function pointers for the current context.
+    pub unsafe fn load_hints(
+        &mut self,
+        mut loader: impl FnMut(&str) -> *const c_void,
+    ) -> Result<(), Error> {
+        self.hint_fn = Some(load_fn(&mut loader, "glHint")?);
+        Ok(())
+    }
+
+    /// Set an implementation hint.
+    ///
+    /// Does nothing if the hint isn't supported by the context, or [`ManagedContext::load_hints`] wasn't called.
+    pub fn set_hint(&self, hint: Hint, quality: Quality) {
+        if let Some(hint_fn) = self.hint_fn {
+            if hint.is_supported(self.driver_info()) {
+                unsafe { hint_fn(hint as _, quality as _) };
+            }
+        }
+    }
+
+    /// Returns true if fixed-function point & line smoothing is available, i.e. on compatibility profiles.
+    pub fn supports_smoothing(&self) -> bool {
+        self.driver_info().is_compatibility_profile
+    }
+
+    pub(crate) unsafe fn apply_smoothing(&self, pipeline: &RenderPipeline) {
+        if !self.supports_smoothing() {
+            return;
+        }
+
+        for (cap, enabled) in [
+            (LINE_SMOOTH, pipeline.line_smooth),
+            (POINT_SMOOTH, pipeline.point_smooth),
+        ] {
+            if enabled {
+                self.gl.enable(cap);
+            } else {
+                self.gl.disable(cap);
+            }
+        }
+    }
+}
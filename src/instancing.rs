@@ -0,0 +1,56 @@
+use super::*;
+
+/// The `int` uniform set to the index of the instance being drawn while instancing is emulated, see
+/// [`ManagedContext::set_instancing_emulation`].
+pub const INSTANCE_INDEX_UNIFORM: &str = "uInstanceIndex";
+
+impl ManagedContext {
+    /// Emulate instanced draws on contexts without [`Features::INSTANCED_DRAWS`], e.g. OpenGL 2.1 or OpenGL ES 2.0.
+    ///
+    /// `draw_*_instanced` calls, and [`Drawable::draw_indexed`] with more than one instance, then issue one draw per
+    /// instance with [`INSTANCE_INDEX_UNIFORM`] set to the instance's index, in place of `gl_InstanceID`. Per-instance
+    /// vertex attributes are not emulated, so shaders look up per-instance data by that index, e.g. in a uniform array.
+    ///
+    /// Every instance costs a draw call and a uniform update, so this only keeps batchers working on old hardware; it
+    /// is not a substitute for real instancing. Has no effect on contexts that support instanced draws.
+    pub fn set_instancing_emulation(&mut self, enabled: bool) {
+        self.instancing_emulation = enabled;
+    }
+
+    /// Returns true if instanced draws are emulated, see [`ManagedContext::set_instancing_emulation`].
+    pub fn emulates_instancing(&self) -> bool {
+        self.instancing_emulation && !self.features.contains(Features::INSTANCED_DRAWS)
+    }
+}
+
+impl Drawable<'_> {
+    /// Call `draw` once per instance with the instance index uniform set, if instancing is emulated.
+    ///
+    /// Returns false without drawing if the context should draw instances natively.
+    pub(crate) fn emulate_instances(&self, instances: u32, draw: impl Fn()) -> bool {
+        if !self.ctx.emulates_instancing() {
+            return false;
+        }
+
+        span!("emulate_instances", instances);
+        let gl = &self.ctx.gl;
+        let location = unsafe {
+            gl.get_uniform_location(self.current_program.program, INSTANCE_INDEX_UNIFORM)
+        };
+        for instance in 0..instances {
+            let instance = instance as i32;
+            // shaders may not use the index; going through the cache keeps it valid for later uniform updates
+            if location.is_some()
+                && self.uniform_changed(
+                    INSTANCE_INDEX_UNIFORM,
+                    bytemuck::bytes_of(&instance),
+                    false,
+                )
+            {
+                unsafe { gl.uniform_1_i32(location.as_ref(), instance) };
+            }
+            draw();
+        }
+        true
+    }
+}
@@ -0,0 +1,36 @@
+/// Enter a `tracing` span for the rest of the enclosing scope.
+///
+/// Expands to nothing unless the `tracing` feature is enabled. With the `call-log` feature, the call and its fields
+/// are also recorded in [`crate::call_log`].
+macro_rules! span {
+    ($name:expr $(, $($fields:tt)*)?) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!($name $(, $($fields)*)?).entered();
+        #[cfg(feature = "call-log")]
+        $crate::call_log::record($name, |_out| {
+            call_fields!(_out; $($($fields)*)?);
+        });
+    };
+}
+
+/// Write `tracing`-style span fields into a call log entry.
+#[cfg(feature = "call-log")]
+macro_rules! call_fields {
+    ($out:ident;) => {};
+    ($out:ident; $key:ident = ?$value:expr $(, $($rest:tt)*)?) => {
+        $crate::call_log::write_field($out, stringify!($key), &$value);
+        call_fields!($out; $($($rest)*)?);
+    };
+    ($out:ident; $key:ident = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::call_log::write_field($out, stringify!($key), &$value);
+        call_fields!($out; $($($rest)*)?);
+    };
+    ($out:ident; ?$key:ident $(, $($rest:tt)*)?) => {
+        $crate::call_log::write_field($out, stringify!($key), &$key);
+        call_fields!($out; $($($rest)*)?);
+    };
+    ($out:ident; $key:ident $(, $($rest:tt)*)?) => {
+        $crate::call_log::write_field($out, stringify!($key), &$key);
+        call_fields!($out; $($($rest)*)?);
+    };
+}
@@ -1,22 +1,212 @@
+#[macro_use]
+mod instrument;
+
+mod error;
+pub use error::*;
+
 mod shader;
 pub use shader::*;
 
+mod variants;
+pub use variants::*;
+
+mod uniform_block;
+pub use uniform_block::*;
+
+mod push_constants;
+pub use push_constants::*;
+
+mod binding_set;
+pub use binding_set::*;
+
+mod sampler;
+pub use sampler::*;
+mod texture_unit;
+pub use texture_unit::*;
+
+mod share;
+pub use share::*;
+
 mod ctx;
 pub use ctx::*;
 
+mod memory;
+pub use memory::*;
+
+mod debug_label;
+
+mod staging;
+pub use staging::*;
+
+mod thread_guard;
+pub use thread_guard::*;
+
+mod deletion;
+pub(crate) use deletion::*;
+
+mod registry;
+pub use registry::*;
+
+mod bundle;
+pub use bundle::*;
+
 mod blend;
 pub use blend::*;
 
+mod color;
+pub use color::*;
+
+mod hint;
+pub use hint::*;
+
+mod instancing;
+pub use instancing::*;
+
+mod sample_shading;
+pub(crate) use sample_shading::*;
+
+mod dual_source;
+pub(crate) use dual_source::*;
+
+mod capability;
+pub use capability::*;
+
+mod raw;
+pub use raw::*;
+
+mod snapshot;
+pub use snapshot::*;
+
 mod buffer;
 pub use buffer::*;
 
 mod pipeline;
 pub use pipeline::*;
 
+mod descriptor;
+pub use descriptor::*;
+
+mod vertex_layout;
+pub use vertex_layout::*;
+
+mod vertex_array;
+pub use vertex_array::*;
+
+mod mesh;
+pub use mesh::*;
+
 mod clearflags;
 pub use clearflags::*;
 
+mod driver;
+pub use driver::*;
+
+mod features;
+pub use features::*;
+
+mod capabilities;
+pub use capabilities::*;
+
+mod rect;
+pub use rect::*;
+
+mod frustum;
+pub use frustum::*;
+
+#[cfg(feature = "sdf")]
+mod sdf;
+#[cfg(feature = "sdf")]
+pub use sdf::*;
+
+#[cfg(feature = "particles")]
+mod particles;
+#[cfg(feature = "particles")]
+pub use particles::*;
+
+#[cfg(feature = "culling")]
+mod culling;
+#[cfg(feature = "culling")]
+pub use culling::*;
+
+mod capture;
+pub use capture::*;
+
+mod upload;
+pub use upload::*;
+
+mod streaming;
+pub use streaming::*;
+
+mod framebuffer;
+pub use framebuffer::*;
+// shadow glow's `Framebuffer` type alias
+pub use framebuffer::Framebuffer;
+
+mod pass;
+pub use pass::*;
+
+mod clip;
+pub use clip::*;
+
+#[cfg(feature = "shadow")]
+mod shadow;
+#[cfg(feature = "shadow")]
+pub use shadow::*;
+
+#[cfg(feature = "shadow")]
+mod cascade;
+#[cfg(feature = "shadow")]
+pub use cascade::*;
+
+#[cfg(feature = "shadow")]
+mod shadow_atlas;
+#[cfg(feature = "shadow")]
+pub use shadow_atlas::*;
+
+#[cfg(feature = "post")]
+mod post;
+#[cfg(feature = "post")]
+pub use post::*;
+
+#[cfg(feature = "post")]
+mod bloom;
+#[cfg(feature = "post")]
+pub use bloom::*;
+
 pub mod texture;
 pub use texture::*;
 
+pub mod shaders;
+
+pub mod util;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+#[cfg(feature = "call-log")]
+pub mod call_log;
+
+#[cfg(feature = "text")]
+pub mod text;
+
+#[cfg(feature = "lyon")]
+pub mod vector;
+
+#[cfg(feature = "asset")]
+pub mod asset;
+
+#[cfg(feature = "graph")]
+pub mod graph;
+
+mod bindless;
+pub use bindless::*;
+
+mod dsa;
+pub(crate) use dsa::*;
+
+#[cfg(all(target_os = "linux", feature = "dmabuf"))]
+mod dmabuf;
+#[cfg(all(target_os = "linux", feature = "dmabuf"))]
+pub use dmabuf::*;
+
 use glow::*;
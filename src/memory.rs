@@ -0,0 +1,187 @@
+use super::*;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Estimated GPU memory held by one kind or label of resources.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Bytes currently allocated.
+    pub bytes: usize,
+    /// The highest `bytes` seen so far.
+    pub peak: usize,
+    /// Live resources.
+    pub count: usize,
+}
+
+impl MemoryUsage {
+    fn add(&mut self, bytes: usize, count: usize) {
+        self.bytes += bytes;
+        self.peak = self.peak.max(self.bytes);
+        self.count += count;
+    }
+
+    fn remove(&mut self, bytes: usize, count: usize) {
+        self.bytes = self.bytes.saturating_sub(bytes);
+        self.count = self.count.saturating_sub(count);
+    }
+}
+
+/// Estimated GPU memory of the resources owned by rapax, see [`ManagedContext::memory_report`].
+///
+/// Sizes are computed from dimensions and formats; drivers add padding, alignment and mipmaps they allocate on their
+/// own, so treat them as a lower bound. Resources imported with `from_raw_gl` count without a size.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    pub textures: MemoryUsage,
+    pub buffers: MemoryUsage,
+    /// Usage per label set with [`TextureHandle::with_memory_label`] or [`BufferHandle::with_memory_label`].
+    pub labels: BTreeMap<String, MemoryUsage>,
+}
+
+impl MemoryReport {
+    /// Bytes allocated by all resources.
+    pub fn total_bytes(&self) -> usize {
+        self.textures.bytes + self.buffers.bytes
+    }
+}
+
+/// Shared between the context and every resource it created, which update it as they are resized and dropped.
+pub(crate) type SharedMemoryReport = Arc<Mutex<MemoryReport>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ResourceKind {
+    Texture,
+    Buffer,
+}
+
+/// The accounted size of a single resource.
+#[derive(Debug)]
+pub(crate) struct MemoryAllocation {
+    report: SharedMemoryReport,
+    kind: ResourceKind,
+    label: Option<String>,
+    bytes: usize,
+}
+
+impl MemoryAllocation {
+    pub(crate) fn new(ctx: &ManagedContext, kind: ResourceKind, bytes: usize) -> Self {
+        let allocation = Self {
+            report: ctx.memory.clone(),
+            kind,
+            label: None,
+            bytes,
+        };
+        allocation.register(1);
+        allocation
+    }
+
+    /// Record a new size, e.g. after reallocating the resource's storage.
+    pub(crate) fn resize(&mut self, bytes: usize) {
+        self.unregister(0);
+        self.bytes = bytes;
+        self.register(0);
+    }
+
+    pub(crate) fn set_label(&mut self, label: &str) {
+        self.unregister(1);
+        self.label = Some(label.to_owned());
+        self.register(1);
+    }
+
+    fn register(&self, count: usize) {
+        let mut report = self.report.lock().unwrap();
+        self.kind_usage(&mut report).add(self.bytes, count);
+        if let Some(label) = &self.label {
+            report
+                .labels
+                .entry(label.clone())
+                .or_default()
+                .add(self.bytes, count);
+        }
+    }
+
+    fn unregister(&self, count: usize) {
+        let mut report = self.report.lock().unwrap();
+        self.kind_usage(&mut report).remove(self.bytes, count);
+        if let Some(usage) = self
+            .label
+            .as_ref()
+            .and_then(|label| report.labels.get_mut(label))
+        {
+            usage.remove(self.bytes, count);
+        }
+    }
+
+    fn kind_usage<'a>(&self, report: &'a mut MemoryReport) -> &'a mut MemoryUsage {
+        match self.kind {
+            ResourceKind::Texture => &mut report.textures,
+            ResourceKind::Buffer => &mut report.buffers,
+        }
+    }
+}
+
+impl Clone for MemoryAllocation {
+    fn clone(&self) -> Self {
+        let allocation = Self {
+            report: self.report.clone(),
+            kind: self.kind,
+            label: self.label.clone(),
+            bytes: self.bytes,
+        };
+        allocation.register(1);
+        allocation
+    }
+}
+
+impl Drop for MemoryAllocation {
+    fn drop(&mut self) {
+        self.unregister(1);
+    }
+}
+
+impl ManagedContext {
+    /// Estimated GPU memory of the textures and buffers created through this context, e.g. for a memory overlay or
+    /// to spot leaks.
+    pub fn memory_report(&self) -> MemoryReport {
+        self.memory.lock().unwrap().clone()
+    }
+}
+
+impl InternalTextureFormat {
+    /// The estimated size of a single pixel, in bytes.
+    pub fn pixel_size(&self) -> usize {
+        match self {
+            Self::Alpha | Self::Luminance | Self::R8 | Self::R3G3B2 => 1,
+            Self::LuminanceAlpha | Self::Rg8 | Self::Rgba2 | Self::Rgba4 | Self::Rgb5A1 => 2,
+            Self::Rgb4 | Self::Rgb5 => 2,
+            Self::DepthComponent16 | Self::R16 | Self::R16F => 2,
+            Self::Rgb | Self::Rgb8 | Self::Srgb8 => 3,
+            Self::DepthComponent24 => 3,
+            Self::Rgba | Self::Rgba8 | Self::Srgb8Alpha8 | Self::Rgb10 | Self::Rgb10A2 => 4,
+            Self::DepthComponent32F | Self::Depth24Stencil8 => 4,
+            Self::Rg16 | Self::Rg16F => 4,
+            Self::Rgb12 => 5,
+            Self::Rgb16 | Self::Rgba12 => 6,
+            Self::Rgba16 | Self::Rgba16F => 8,
+            Self::Rgba32F => 16,
+        }
+    }
+}
+
+/// The estimated size of a texture's storage, including a third more for mipmaps if it samples them.
+pub(crate) fn texture_bytes(
+    handle: &TextureHandle,
+    format: InternalTextureFormat,
+    width: i32,
+    height: i32,
+    layers: i32,
+) -> usize {
+    let bytes = width.max(0) as usize * height.max(0) as usize * layers.max(0) as usize;
+    let bytes = bytes * format.pixel_size();
+    if handle.min_filter.uses_mipmaps() {
+        bytes + bytes / 3
+    } else {
+        bytes
+    }
+}
@@ -0,0 +1,246 @@
+//! CPU-side mesh geometry, uploaded into interleaved vertex buffers with [`MeshData::upload`] or merged with
+//! [`MeshBundle::bake`]. The `asset` feature loads [`MeshData`] from OBJ and glTF files.
+
+use super::*;
+
+/// The attribute location of vertex positions, see [`MeshData::layout`].
+pub const POSITION_LOCATION: u32 = 0;
+/// The attribute location of vertex normals, see [`MeshData::layout`].
+pub const NORMAL_LOCATION: u32 = 1;
+/// The attribute location of texture coordinates, see [`MeshData::layout`].
+pub const TEX_COORD_LOCATION: u32 = 2;
+
+/// CPU-side geometry of a single mesh or glTF primitive, as flat attribute streams.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeshData {
+    pub name: String,
+    /// Three components per vertex.
+    pub positions: Vec<f32>,
+    /// Three components per vertex, or empty if the mesh has no normals.
+    pub normals: Vec<f32>,
+    /// Two components per vertex, or empty if the mesh has no texture coordinates.
+    pub tex_coords: Vec<f32>,
+    /// Triangle list indices.
+    pub indices: Vec<u32>,
+    /// The index of the mesh's material, e.g. in an `asset::Model`'s materials.
+    pub material: Option<usize>,
+}
+
+/// A mesh uploaded into an interleaved vertex buffer and an index buffer, see [`MeshData::upload`].
+#[derive(Debug)]
+pub struct Mesh {
+    vertices: BufferHandle,
+    indices: BufferHandle,
+    index_count: u32,
+    layout: VertexLayout,
+    material: Option<usize>,
+}
+
+/// Meshes merged into a single vertex & index buffer, see [`MeshBundle::bake`].
+#[derive(Debug)]
+pub struct MeshBundle {
+    vertices: BufferHandle,
+    indices: BufferHandle,
+    index_count: u32,
+    layout: VertexLayout,
+    spans: Vec<DrawSpan>,
+}
+
+/// The range of a [`MeshBundle`]'s indices that draws one of its meshes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrawSpan {
+    pub first_index: u32,
+    pub index_count: u32,
+    /// The index of the mesh's material, e.g. in an `asset::Model`'s materials.
+    pub material: Option<usize>,
+}
+
+impl MeshData {
+    /// The number of vertices of the mesh.
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len() / 3
+    }
+
+    /// The interleaved layout of the mesh's vertex buffer at buffer index 0, with attributes at
+    /// [`POSITION_LOCATION`], [`NORMAL_LOCATION`] & [`TEX_COORD_LOCATION`]. Add it to a pipeline with
+    /// [`RenderPipeline::with_vertex_layout`].
+    pub fn layout(&self) -> VertexLayout {
+        let mut layout = VertexLayout::new(0).with_attribute(3, DataType::Float, false);
+        let mut locations = vec![POSITION_LOCATION];
+        if !self.normals.is_empty() {
+            layout = layout.with_attribute(3, DataType::Float, false);
+            locations.push(NORMAL_LOCATION);
+        }
+        if !self.tex_coords.is_empty() {
+            layout = layout.with_attribute(2, DataType::Float, false);
+            locations.push(TEX_COORD_LOCATION);
+        }
+
+        let attributes: Vec<VertexAttributeDescriptor> = layout
+            .attributes()
+            .iter()
+            .zip(locations)
+            .map(|(attr, location)| VertexAttributeDescriptor {
+                location: Some(location),
+                ..attr.clone()
+            })
+            .collect();
+        VertexLayout::from_attributes(0, &attributes)
+    }
+
+    /// Interleave the mesh's attributes and upload them, along with the indices.
+    pub fn upload(&self, ctx: &mut ManagedContext) -> Result<Mesh, Error> {
+        span!(
+            "upload_mesh",
+            vertices = self.vertex_count(),
+            indices = self.indices.len()
+        );
+        let layout = self.layout();
+        let vertices = self.interleave(&layout);
+
+        Ok(Mesh {
+            vertices: BufferHandle::array_buffer(ctx, BufferUsage::Immutable, &vertices)?,
+            indices: BufferHandle::index_buffer(
+                ctx,
+                BufferUsage::Immutable,
+                bytemuck::cast_slice(&self.indices),
+            )?
+            .with_index_type(DataType::UnsignedInt),
+            index_count: self.indices.len() as u32,
+            layout,
+            material: self.material,
+        })
+    }
+
+    fn interleave(&self, layout: &VertexLayout) -> Vec<u8> {
+        let streams: Vec<&[f32]> = [&self.positions, &self.normals, &self.tex_coords]
+            .into_iter()
+            .filter(|stream| !stream.is_empty())
+            .map(Vec::as_slice)
+            .collect();
+        layout.interleave(&streams)
+    }
+}
+
+impl MeshBundle {
+    /// Merge `meshes` into one vertex & index buffer and upload them, e.g. the static scenery of a level.
+    ///
+    /// Each mesh's indices are offset by the mesh's first vertex while baking, so the spans need no base vertex: the
+    /// whole bundle draws with a single call, and consecutive spans, e.g. those sharing a material when the meshes
+    /// are sorted by it, merge into one.
+    ///
+    /// ## Panics
+    /// Every mesh must have the same layout, i.e. either all or none of them have normals, and the same goes for
+    /// texture coordinates.
+    pub fn bake(ctx: &mut ManagedContext, meshes: &[MeshData]) -> Result<Self, Error> {
+        span!("bake_meshes", meshes = meshes.len());
+        let layout = meshes
+            .first()
+            .map(MeshData::layout)
+            .unwrap_or_else(|| VertexLayout::new(0));
+
+        let mut vertices = vec![];
+        let mut indices = vec![];
+        let mut spans = vec![];
+        for mesh in meshes {
+            assert_eq!(mesh.layout(), layout, "meshes have different layouts!");
+            let first_vertex = (vertices.len() / layout.stride()) as u32;
+            spans.push(DrawSpan {
+                first_index: indices.len() as u32,
+                index_count: mesh.indices.len() as u32,
+                material: mesh.material,
+            });
+            vertices.extend(mesh.interleave(&layout));
+            indices.extend(mesh.indices.iter().map(|index| index + first_vertex));
+        }
+
+        Ok(Self {
+            vertices: BufferHandle::array_buffer(ctx, BufferUsage::Immutable, &vertices)?,
+            indices: BufferHandle::index_buffer_from(ctx, BufferUsage::Immutable, &indices)?,
+            index_count: indices.len() as u32,
+            layout,
+            spans,
+        })
+    }
+
+    /// Bind the bundle's buffers and draw every mesh with a single call. The bound pipeline must use
+    /// [`MeshBundle::layout`].
+    pub fn draw(&self, dctx: &mut Drawable) {
+        dctx.apply_bindings(&[&self.vertices], Some(&self.indices));
+        dctx.draw_indexed(DrawMode::Triangles, 0..self.index_count, 0, 1);
+    }
+
+    /// Bind the bundle's buffers and draw `spans`, merging consecutive ones into a single call.
+    pub fn draw_spans(&self, dctx: &mut Drawable, spans: &[DrawSpan]) {
+        dctx.apply_bindings(&[&self.vertices], Some(&self.indices));
+        let mut ranges = spans
+            .iter()
+            .map(|span| span.first_index..span.first_index + span.index_count);
+        let Some(mut range) = ranges.next() else {
+            return;
+        };
+        for next in ranges {
+            if next.start == range.end {
+                range.end = next.end;
+            } else {
+                dctx.draw_indexed(DrawMode::Triangles, range, 0, 1);
+                range = next;
+            }
+        }
+        dctx.draw_indexed(DrawMode::Triangles, range, 0, 1);
+    }
+
+    /// The spans of the baked meshes, in the order they were passed to [`MeshBundle::bake`].
+    pub fn spans(&self) -> &[DrawSpan] {
+        &self.spans
+    }
+
+    /// The layout of the vertex buffer, see [`MeshData::layout`].
+    pub fn layout(&self) -> &VertexLayout {
+        &self.layout
+    }
+
+    /// The index buffer, e.g. to draw the spans with a `GpuCuller` when the `culling` feature is enabled.
+    pub fn indices(&self) -> &BufferHandle {
+        &self.indices
+    }
+
+    /// The interleaved vertex buffer.
+    pub fn vertices(&self) -> &BufferHandle {
+        &self.vertices
+    }
+}
+
+#[cfg(feature = "culling")]
+impl From<DrawSpan> for CullDraw {
+    fn from(span: DrawSpan) -> Self {
+        Self {
+            index_count: span.index_count,
+            first_index: span.first_index,
+            base_vertex: 0,
+        }
+    }
+}
+
+impl Mesh {
+    /// Bind the mesh buffers and draw all triangles. The bound pipeline must use [`Mesh::layout`].
+    pub fn draw(&self, dctx: &mut Drawable) {
+        dctx.apply_bindings(&[&self.vertices], Some(&self.indices));
+        dctx.draw_indexed(DrawMode::Triangles, 0..self.index_count, 0, 1);
+    }
+
+    /// The layout of the vertex buffer, see [`MeshData::layout`].
+    pub fn layout(&self) -> &VertexLayout {
+        &self.layout
+    }
+
+    /// The index of the mesh's material, e.g. in an `asset::Model`'s materials.
+    pub fn material(&self) -> Option<usize> {
+        self.material
+    }
+
+    /// The number of indices of the mesh.
+    pub fn index_count(&self) -> u32 {
+        self.index_count
+    }
+}
@@ -0,0 +1,308 @@
+use super::*;
+
+/// A single particle, stored as the per-instance attributes described by [`ParticleSystem::instance_layout`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Particle {
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+    pub color: [f32; 4],
+    /// Remaining lifetime in seconds. Particles with no life left are removed or, on the GPU, hidden.
+    pub life: f32,
+}
+
+// SAFETY: `Particle` is `repr(C)` and consists of `f32`s only, so it has no padding.
+unsafe impl bytemuck::Zeroable for Particle {}
+unsafe impl bytemuck::Pod for Particle {}
+
+/// Billboard vertex shader for [`ParticleSystem`]. Reads the quad corner at location 0 and the [`Particle`] fields at
+/// locations 1 to 4; `float u_size` is the quad size and `mat4 u_transform` the view-projection.
+pub const PARTICLE_VS: &str = r#"
+layout(location = 0) in vec2 a_corner;
+layout(location = 1) in vec3 a_position;
+layout(location = 2) in vec3 a_velocity;
+layout(location = 3) in vec4 a_color;
+layout(location = 4) in float a_life;
+
+uniform mat4 u_transform;
+uniform float u_size;
+
+out vec2 v_corner;
+out vec4 v_color;
+
+void main() {
+    v_corner = a_corner;
+    v_color = a_color;
+    // dead particles collapse to a degenerate quad
+    float size = a_life > 0.0 ? u_size : 0.0;
+    vec4 center = u_transform * vec4(a_position, 1.0);
+    gl_Position = center + vec4(a_corner * size, 0.0, 0.0);
+}
+"#;
+
+/// Soft round particle, for use with [`PARTICLE_VS`].
+pub const PARTICLE_FS: &str = r#"
+in vec2 v_corner;
+in vec4 v_color;
+
+out vec4 o_color;
+
+void main() {
+    float falloff = 1.0 - smoothstep(0.0, 0.5, length(v_corner));
+    o_color = vec4(v_color.rgb, v_color.a * falloff);
+}
+"#;
+
+/// Transform feedback update shader, see [`ParticleSystem::update_gpu`]. Reads the [`Particle`] fields at locations 0
+/// to 3, integrates them over `float u_delta_time` and applies `vec3 u_gravity`.
+pub const PARTICLE_UPDATE_VS: &str = r#"
+layout(location = 0) in vec3 a_position;
+layout(location = 1) in vec3 a_velocity;
+layout(location = 2) in vec4 a_color;
+layout(location = 3) in float a_life;
+
+uniform float u_delta_time;
+uniform vec3 u_gravity;
+
+out vec3 v_position;
+out vec3 v_velocity;
+out vec4 v_color;
+out float v_life;
+
+void main() {
+    v_velocity = a_velocity + u_gravity * u_delta_time;
+    v_position = a_position + v_velocity * u_delta_time;
+    v_color = a_color;
+    v_life = a_life - u_delta_time;
+}
+"#;
+
+const PARTICLE_UPDATE_FS: &str = r#"
+out vec4 o_color;
+
+void main() {
+    o_color = vec4(0.0);
+}
+"#;
+
+/// The transform feedback outputs of [`PARTICLE_UPDATE_VS`], in [`Particle`] field order.
+pub const PARTICLE_VARYINGS: [&str; 4] = ["v_position", "v_velocity", "v_color", "v_life"];
+
+/// Corners of the billboard quad, drawn as a triangle strip.
+const QUAD_CORNERS: [f32; 8] = [-0.5, -0.5, 0.5, -0.5, -0.5, 0.5, 0.5, 0.5];
+
+/// Instanced particles, updated on the CPU or through transform feedback and drawn as one billboard quad each.
+///
+/// Particles live in a streaming instance buffer of fixed capacity. Once it is full, new particles replace the oldest.
+#[derive(Debug)]
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    capacity: usize,
+    next: usize,
+    quad: BufferHandle,
+    // the front buffer is drawn, the back buffer receives transform feedback
+    instances: [BufferHandle; 2],
+    front: usize,
+    pipeline: RenderPipeline,
+    // CPU particles not uploaded yet
+    cpu_dirty: bool,
+    // GPU particles not read back yet
+    gpu_dirty: bool,
+}
+
+impl ParticleSystem {
+    /// Create an empty particle system drawing with `pipeline`, e.g. [`RenderPipeline::particles`].
+    ///
+    /// `pipeline` must not have vertex attributes yet; the quad corner and the per-instance [`Particle`] attributes are
+    /// added to it, see [`PARTICLE_VS`].
+    pub fn new(
+        ctx: &mut ManagedContext,
+        pipeline: RenderPipeline,
+        capacity: usize,
+    ) -> Result<Self, Error> {
+        let quad = BufferHandle::array_buffer(
+            ctx,
+            BufferUsage::Immutable,
+            bytemuck::cast_slice(&QUAD_CORNERS),
+        )?;
+        let empty = vec![0u8; capacity * std::mem::size_of::<Particle>()];
+        let instances = [
+            BufferHandle::array_buffer(ctx, BufferUsage::Stream, &empty)?,
+            BufferHandle::array_buffer(ctx, BufferUsage::Stream, &empty)?,
+        ];
+        let pipeline = pipeline
+            .with_vertex_layout(&VertexLayout::new(0).with_attribute(2, DataType::Float, false))
+            .with_vertex_layout(&Self::instance_layout(1).with_divisor(1));
+
+        Ok(Self {
+            particles: Vec::with_capacity(capacity),
+            capacity,
+            next: 0,
+            quad,
+            instances,
+            front: 0,
+            pipeline,
+            cpu_dirty: false,
+            gpu_dirty: false,
+        })
+    }
+
+    /// The attributes of a [`Particle`] read from the vertex buffer at `buffer_index`.
+    pub fn instance_layout(buffer_index: usize) -> VertexLayout {
+        VertexLayout::new(buffer_index)
+            .with_attribute(3, DataType::Float, false)
+            .with_attribute(3, DataType::Float, false)
+            .with_attribute(4, DataType::Float, false)
+            .with_attribute(1, DataType::Float, false)
+    }
+
+    /// A pipeline updating particles with [`PARTICLE_UPDATE_VS`], for [`ParticleSystem::update_gpu`].
+    pub fn update_pipeline(ctx: &ManagedContext) -> Result<RenderPipeline, Error> {
+        let program = ShaderProgram::with_transform_feedback(
+            ctx,
+            &shaders::shim(ctx, PARTICLE_UPDATE_VS)?,
+            &shaders::shim(ctx, PARTICLE_UPDATE_FS)?,
+            &PARTICLE_VARYINGS,
+        )?;
+        Ok(RenderPipeline::new(program)
+            .with_vertex_layout(&Self::instance_layout(0))
+            .with_rasterizer_discard(true))
+    }
+
+    /// Add a particle, replacing the oldest one if the system is full.
+    pub fn emit(&mut self, ctx: &ManagedContext, particle: Particle) {
+        let slot = if self.particles.len() < self.capacity {
+            self.particles.push(particle);
+            self.particles.len() - 1
+        } else if self.capacity > 0 {
+            let slot = self.next % self.capacity;
+            self.particles[slot] = particle;
+            self.next = slot + 1;
+            slot
+        } else {
+            return;
+        };
+
+        if self.gpu_dirty {
+            // the CPU copy is stale, so only patch the GPU buffer
+            self.instances[self.front].update(
+                ctx,
+                (slot * std::mem::size_of::<Particle>()) as i32,
+                bytemuck::bytes_of(&particle),
+            );
+        } else {
+            self.cpu_dirty = true;
+        }
+    }
+
+    /// Integrate positions by velocity, age particles by `delta_time` and remove dead ones, on the CPU.
+    pub fn update(&mut self, ctx: &ManagedContext, delta_time: f32) {
+        self.update_with(ctx, |particle| {
+            for axis in 0..3 {
+                particle.position[axis] += particle.velocity[axis] * delta_time;
+            }
+            particle.life -= delta_time;
+        });
+    }
+
+    /// Apply a custom update to every particle on the CPU, then remove dead ones.
+    pub fn update_with(&mut self, ctx: &ManagedContext, mut update: impl FnMut(&mut Particle)) {
+        span!("particles_update", count = self.particles.len());
+        self.read_back(ctx);
+        self.particles.iter_mut().for_each(&mut update);
+        self.particles.retain(|particle| particle.life > 0.0);
+        self.next = 0;
+        self.cpu_dirty = true;
+    }
+
+    /// Update particles on the GPU through transform feedback, using a pipeline like
+    /// [`ParticleSystem::update_pipeline`]. `uniforms` sets the update program's uniforms.
+    ///
+    /// Dead particles stay in the buffer until the next CPU update; [`PARTICLE_VS`] hides them.
+    ///
+    /// ## Panics
+    /// The pipeline must discard primitives before rasterization, see [`RenderPipeline::with_rasterizer_discard`].
+    pub fn update_gpu(
+        &mut self,
+        ctx: &mut ManagedContext,
+        pipeline: &RenderPipeline,
+        uniforms: impl FnOnce(&mut Drawable),
+    ) {
+        assert!(
+            pipeline.rasterizer_discard,
+            "particle update pipeline must discard primitives!"
+        );
+        span!("particles_update_gpu", count = self.particles.len());
+        self.upload(ctx);
+        let (front, back) = (&self.instances[self.front], &self.instances[1 - self.front]);
+        let count = self.particles.len() as i32;
+        ctx.with_pipeline(pipeline, |dctx| {
+            dctx.apply_bindings(&[front], None::<&BufferHandle>);
+            uniforms(dctx);
+            unsafe {
+                let gl = &dctx.ctx.gl;
+                gl.bind_buffer_base(TRANSFORM_FEEDBACK_BUFFER, 0, Some(back.buffer));
+                gl.begin_transform_feedback(POINTS);
+                gl.draw_arrays(POINTS, 0, count);
+                gl.end_transform_feedback();
+                gl.bind_buffer_base(TRANSFORM_FEEDBACK_BUFFER, 0, None);
+            }
+        });
+        self.front = 1 - self.front;
+        self.gpu_dirty = true;
+    }
+
+    /// Draw every particle as an instanced quad. `uniforms` sets the pipeline's uniforms, e.g. `u_transform`.
+    pub fn draw(&mut self, ctx: &mut ManagedContext, uniforms: impl FnOnce(&mut Drawable)) {
+        self.upload(ctx);
+        let (quad, instances) = (&self.quad, &self.instances[self.front]);
+        let count = self.particles.len() as u32;
+        ctx.with_pipeline(&self.pipeline, |dctx| {
+            dctx.apply_bindings(&[quad, instances], None::<&BufferHandle>);
+            uniforms(dctx);
+            dctx.draw_arrays_instanced(DrawMode::TriangleStrip, 0, 4, count);
+        });
+    }
+
+    /// The particles as of the last CPU update or emission.
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// The buffer holding the particles that are drawn, e.g. for a custom update path.
+    pub fn instance_buffer(&self) -> &BufferHandle {
+        &self.instances[self.front]
+    }
+
+    fn upload(&mut self, ctx: &ManagedContext) {
+        if self.cpu_dirty {
+            self.instances[self.front].update(ctx, 0, bytemuck::cast_slice(&self.particles));
+            self.cpu_dirty = false;
+        }
+    }
+
+    fn read_back(&mut self, ctx: &ManagedContext) {
+        if self.gpu_dirty {
+            let buffer = &self.instances[self.front];
+            unsafe {
+                ctx.gl.bind_buffer(ARRAY_BUFFER, Some(buffer.buffer));
+                ctx.gl.get_buffer_sub_data(
+                    ARRAY_BUFFER,
+                    0,
+                    bytemuck::cast_slice_mut(&mut self.particles),
+                );
+            }
+            self.gpu_dirty = false;
+        }
+    }
+}
+
+impl RenderPipeline {
+    /// An additively blended pipeline drawing soft round particles. See [`PARTICLE_VS`] and [`ParticleSystem::new`].
+    pub fn particles(ctx: &ManagedContext) -> Result<Self, Error> {
+        Ok(Self::new(shaders::program(ctx, PARTICLE_VS, PARTICLE_FS)?)
+            .with_blend(true)
+            .with_blend_func(BlendFactor::SourceAlpha, BlendFactor::One)
+            .with_depth_write(false))
+    }
+}
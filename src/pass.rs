@@ -0,0 +1,192 @@
+use super::*;
+
+// default framebuffer attachments for glInvalidateFramebuffer
+const DEFAULT_COLOR: u32 = 0x1800;
+const DEFAULT_DEPTH: u32 = 0x1801;
+const DEFAULT_STENCIL: u32 = 0x1802;
+
+/// What happens to an attachment's contents at the start of a render pass.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadOp<T> {
+    /// Clear the attachment to the given value.
+    Clear(T),
+    /// Keep the previous contents.
+    Load,
+}
+
+/// What happens to an attachment's contents at the end of a render pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreOp {
+    /// Keep the rendered contents.
+    Store,
+    /// The contents are no longer needed, e.g. depth buffers. Saves bandwidth on tiled GPUs.
+    Discard,
+}
+
+/// Load & store operations of a single attachment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Operations<T> {
+    pub load: LoadOp<T>,
+    pub store: StoreOp,
+}
+
+impl<T> Default for Operations<T> {
+    fn default() -> Self {
+        Self {
+            load: LoadOp::Load,
+            store: StoreOp::Store,
+        }
+    }
+}
+
+/// Describes the target and attachment operations of a render pass.
+#[derive(Debug, Clone)]
+pub struct RenderPassDescriptor<'a> {
+    pub(crate) framebuffer: Option<&'a Framebuffer>,
+    pub(crate) color: Vec<Operations<[f32; 4]>>,
+    pub(crate) depth: Option<Operations<f32>>,
+    pub(crate) stencil: Option<Operations<i32>>,
+}
+
+impl<'a> RenderPassDescriptor<'a> {
+    /// Create a new pass rendering into the given framebuffer, or the default framebuffer if `None`.
+    pub fn new(framebuffer: Option<&'a Framebuffer>) -> Self {
+        Self {
+            framebuffer,
+            color: vec![],
+            depth: None,
+            stencil: None,
+        }
+    }
+
+    /// Add the operations of the next color attachment.
+    pub fn with_color(self, ops: Operations<[f32; 4]>) -> Self {
+        let mut color = self.color;
+        color.push(ops);
+
+        Self { color, ..self }
+    }
+
+    /// Set the depth attachment operations.
+    pub fn with_depth(self, ops: Operations<f32>) -> Self {
+        Self {
+            depth: Some(ops),
+            ..self
+        }
+    }
+
+    /// Set the stencil attachment operations.
+    pub fn with_stencil(self, ops: Operations<i32>) -> Self {
+        Self {
+            stencil: Some(ops),
+            ..self
+        }
+    }
+
+    fn attachments_to_discard(&self) -> Vec<u32> {
+        let default = self.framebuffer.is_none();
+        let color = self
+            .color
+            .iter()
+            .enumerate()
+            .filter(|(_, ops)| ops.store == StoreOp::Discard)
+            .map(|(i, _)| {
+                if default {
+                    DEFAULT_COLOR
+                } else {
+                    Attachment::Color(i as u32).to_gl()
+                }
+            });
+        let depth = self
+            .depth
+            .filter(|ops| ops.store == StoreOp::Discard)
+            .map(|_| {
+                if default {
+                    DEFAULT_DEPTH
+                } else {
+                    DEPTH_ATTACHMENT
+                }
+            });
+        let stencil = self
+            .stencil
+            .filter(|ops| ops.store == StoreOp::Discard)
+            .map(|_| {
+                if default {
+                    DEFAULT_STENCIL
+                } else {
+                    STENCIL_ATTACHMENT
+                }
+            });
+
+        color.chain(depth).chain(stencil).collect()
+    }
+}
+
+impl ManagedContext {
+    /// Returns true if `glInvalidateFramebuffer` is available, which [`StoreOp::Discard`] maps to.
+    pub fn supports_invalidate_framebuffer(&self) -> bool {
+        let info = self.driver_info();
+        if info.is_embedded {
+            info.is_at_least(3, 0)
+        } else {
+            info.is_at_least(4, 3) || self.has_extension("GL_ARB_invalidate_subdata")
+        }
+    }
+
+    /// Returns true if `glInvalidateBufferSubData` is available, see [`BufferHandle::invalidate`].
+    pub fn supports_invalidate_subdata(&self) -> bool {
+        let info = self.driver_info();
+        !info.is_embedded
+            && (info.is_at_least(4, 3) || self.has_extension("GL_ARB_invalidate_subdata"))
+    }
+
+    /// Run a render pass: bind its framebuffer, apply the load operations, draw, then apply the store operations.
+    ///
+    /// The previously bound framebuffer is bound again afterwards. [`StoreOp::Discard`] is a no-op if the driver
+    /// doesn't support invalidation.
+    pub fn render_pass(
+        &mut self,
+        desc: &RenderPassDescriptor,
+        draw: impl FnOnce(&mut ManagedContext),
+    ) {
+        span!("render_pass", color_attachments = desc.color.len());
+        let previous = self.framebuffer_binding();
+        self.bind_framebuffer(desc.framebuffer);
+        unsafe {
+            // clears respect the write masks, which are re-applied by the next pipeline bind
+            self.gl.color_mask(true, true, true, true);
+            self.gl.depth_mask(true);
+            self.gl.stencil_mask(0xFF);
+            self.gl.disable(SCISSOR_TEST);
+
+            for (i, ops) in desc.color.iter().enumerate() {
+                if let LoadOp::Clear(color) = ops.load {
+                    self.gl.clear_buffer_f32_slice(COLOR, i as u32, &color);
+                }
+            }
+            if let Some(Operations {
+                load: LoadOp::Clear(depth),
+                ..
+            }) = desc.depth
+            {
+                self.gl.clear_buffer_f32_slice(DEPTH, 0, &[depth]);
+            }
+            if let Some(Operations {
+                load: LoadOp::Clear(stencil),
+                ..
+            }) = desc.stencil
+            {
+                self.gl.clear_buffer_i32_slice(STENCIL, 0, &[stencil]);
+            }
+        }
+
+        draw(self);
+
+        let discard = desc.attachments_to_discard();
+        if !discard.is_empty() && self.supports_invalidate_framebuffer() {
+            self.bind_framebuffer(desc.framebuffer);
+            unsafe { self.gl.invalidate_framebuffer(FRAMEBUFFER, &discard) };
+        }
+        self.restore_framebuffer(previous);
+    }
+}
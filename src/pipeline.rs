@@ -4,6 +4,7 @@ use std::sync::Arc;
 
 /// A stencil function.
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum StencilFunc {
     Never = NEVER,
@@ -18,6 +19,7 @@ pub enum StencilFunc {
 
 /// A stencil operation.
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum StencilOp {
     /// The currently stored stencil value is kept.
@@ -39,7 +41,8 @@ pub enum StencilOp {
 }
 
 /// Vertex attribute descriptor.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VertexAttributeDescriptor {
     pub buffer_index: usize,
     pub size: i32,
@@ -48,10 +51,22 @@ pub struct VertexAttributeDescriptor {
     pub stride: i32,
     pub offset: i32,
     pub divisor: u32,
+    /// The attribute location, for shaders without `layout(location = N)` qualifiers. Defaults to the attribute's
+    /// index in the pipeline, see [`RenderPipeline::with_named_attribute`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub location: Option<u32>,
+}
+
+impl VertexAttributeDescriptor {
+    /// The location of the attribute at `index` in a pipeline.
+    pub fn location_at(&self, index: usize) -> u32 {
+        self.location.unwrap_or(index as u32)
+    }
 }
 
 /// Stencil function state.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StencilFuncState {
     pub mask: u32,
     pub func: StencilFunc,
@@ -70,6 +85,7 @@ impl Default for StencilFuncState {
 
 /// Stencil state descriptor.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StencilState {
     pub front_mask: u32,
     pub back_mask: u32,
@@ -110,20 +126,39 @@ pub struct RenderPipeline {
 
     // depth test
     pub(crate) depth_enabled: bool,
+    pub(crate) depth_clamp: bool,
 
     // depth write & color write
     pub(crate) depth_write: bool,
     pub(crate) color_write: [bool; 4],
+    pub(crate) color_write_attachments: Vec<(u32, [bool; 4])>,
+
+    // rasterization
+    pub(crate) rasterizer_discard: bool,
+    // f32 bits of the minimum sample shading fraction, so the pipeline stays `Eq`
+    pub(crate) sample_shading: Option<u32>,
+
+    // fixed-function smoothing
+    pub(crate) line_smooth: bool,
+    pub(crate) point_smooth: bool,
 
     // pipeline program
     pub(crate) program: Arc<ShaderProgram>,
 
     pub(crate) vertex_attributes: Vec<VertexAttributeDescriptor>,
+
+    pub(crate) required_features: Features,
 }
 
 impl RenderPipeline {
     /// Create a new pipeline using the given shader program.
+    #[allow(clippy::arc_with_non_send_sync)]
     pub fn new(program: ShaderProgram) -> Self {
+        Self::new_shared(Arc::new(program))
+    }
+
+    /// Create a new pipeline using a shader program shared with other pipelines, e.g. from [`ShaderVariants::get`].
+    pub fn new_shared(program: Arc<ShaderProgram>) -> Self {
         Self {
             blend_enabled: false,
             blend_func: (0, 0),
@@ -132,17 +167,61 @@ impl RenderPipeline {
             stencil_state: None,
 
             depth_enabled: false,
+            depth_clamp: false,
             depth_write: false,
             color_write: [true, true, true, true],
+            color_write_attachments: vec![],
+
+            rasterizer_discard: false,
+            sample_shading: None,
+
+            line_smooth: false,
+            point_smooth: false,
 
-            program: Arc::new(program),
+            program,
 
             vertex_attributes: vec![],
+
+            required_features: Features::empty(),
         }
     }
 
+    /// Create a pipeline for depth-only passes such as shadow maps: depth test & write on, color writes off.
+    ///
+    /// See [`ShaderProgram::vertex_only`] to skip the fragment shader.
+    pub fn depth_only(program: ShaderProgram) -> Self {
+        Self::new(program)
+            .with_depth(true)
+            .with_depth_write(true)
+            .with_color_write(false, false, false, false)
+    }
+
+    /// Create a pipeline that only writes color: depth test & write off, color writes on.
+    pub fn color_only(program: ShaderProgram) -> Self {
+        Self::new(program)
+            .with_depth(false)
+            .with_depth_write(false)
+            .with_color_write(true, true, true, true)
+    }
+
+    /// Create a copy of this pipeline to be further configured using the `with_*` builder methods.
+    ///
+    /// The shader program is shared with the original pipeline, so variants such as
+    /// "same material but additive blend" don't need to repeat every vertex attribute.
+    pub fn derive(&self) -> Self {
+        self.clone()
+    }
+
     /// Add a vertex attribute to the pipeline.
+    ///
+    /// ## Panics
+    /// The attribute's location must not be used by another attribute of the pipeline.
     pub fn with_vertex_attribute(self, attr: VertexAttributeDescriptor) -> Self {
+        let location = attr.location_at(self.vertex_attributes.len());
+        assert!(
+            !attribute_locations(&self.vertex_attributes).contains(&location),
+            "vertex attribute location {location} is already used!"
+        );
         let mut vertex_attributes = self.vertex_attributes;
         vertex_attributes.push(attr);
 
@@ -152,6 +231,48 @@ impl RenderPipeline {
         }
     }
 
+    /// Add a vertex attribute at the location of the shader's attribute `name`, see
+    /// [`ShaderProgram::attribute_location`].
+    ///
+    /// ## Panics
+    /// The program must have an active attribute `name`, whose location no other attribute of the pipeline uses.
+    pub fn with_named_attribute(self, name: &str, attr: VertexAttributeDescriptor) -> Self {
+        let location = self
+            .program
+            .attribute_location(name)
+            .expect("No such vertex attribute!");
+        self.with_vertex_attribute(VertexAttributeDescriptor {
+            location: Some(location),
+            ..attr
+        })
+    }
+
+    /// Add a `mat4` vertex attribute, e.g. a per-instance transform, as four `vec4` column attributes at consecutive
+    /// locations, starting at the location the next attribute would get.
+    ///
+    /// `offset` is the byte offset of the first column; the columns are tightly packed after it.
+    pub fn with_mat4_attribute(
+        self,
+        buffer_index: usize,
+        offset: i32,
+        stride: i32,
+        divisor: u32,
+    ) -> Self {
+        let column_size = 4 * DataType::Float.sizeof() as i32;
+        (0..4).fold(self, |pipeline, column| {
+            pipeline.with_vertex_attribute(VertexAttributeDescriptor {
+                buffer_index,
+                size: 4,
+                ty: DataType::Float,
+                normalized: false,
+                stride,
+                offset: offset + column * column_size,
+                divisor,
+                location: None,
+            })
+        })
+    }
+
     /// Set the blend state.
     pub fn with_blend(self, enabled: bool) -> Self {
         Self {
@@ -168,6 +289,14 @@ impl RenderPipeline {
         }
     }
 
+    /// Set the scissor test state. The scissor rect is set while drawing, see [`Drawable::set_scissor`].
+    pub fn with_scissor(self, enabled: bool) -> Self {
+        Self {
+            scissor_enabled: enabled,
+            ..self
+        }
+    }
+
     /// Set the depth state.
     pub fn with_depth(self, enabled: bool) -> Self {
         Self {
@@ -184,6 +313,28 @@ impl RenderPipeline {
         }
     }
 
+    /// Set the depth clamp state. Fragments outside the near & far planes are kept with their depth clamped instead of
+    /// being clipped, e.g. so shadow casters behind the light's near plane still cast shadows.
+    ///
+    /// Requires [`Features::DEPTH_CLAMP`].
+    pub fn with_depth_clamp(self, enabled: bool) -> Self {
+        Self {
+            depth_clamp: enabled,
+            ..self
+        }
+    }
+
+    /// Set the rasterizer discard state. Primitives are discarded before rasterization, so no fragments are
+    /// generated, e.g. for passes that only capture vertex shader output with transform feedback.
+    ///
+    /// Requires [`Features::RASTERIZER_DISCARD`].
+    pub fn with_rasterizer_discard(self, enabled: bool) -> Self {
+        Self {
+            rasterizer_discard: enabled,
+            ..self
+        }
+    }
+
     /// Set the color write state, per channel.
     pub fn with_color_write(self, r: bool, g: bool, b: bool, a: bool) -> Self {
         Self {
@@ -192,6 +343,38 @@ impl RenderPipeline {
         }
     }
 
+    /// Set the color write state of a single color attachment, per channel, overriding
+    /// [`RenderPipeline::with_color_write`] for it. E.g. an MRT pass can write velocity but leave albedo untouched.
+    ///
+    /// `attachment` is the index of the draw buffer, 0 for `COLOR_ATTACHMENT0`. Requires
+    /// [`Features::DRAW_BUFFERS_INDEXED`].
+    pub fn with_color_write_i(self, attachment: u32, r: bool, g: bool, b: bool, a: bool) -> Self {
+        let mut color_write_attachments = self.color_write_attachments;
+        color_write_attachments.retain(|(index, _)| *index != attachment);
+        color_write_attachments.push((attachment, [r, g, b, a]));
+
+        Self {
+            color_write_attachments,
+            ..self
+        }
+    }
+
+    /// Set the line smoothing state. Only has an effect on compatibility profiles, see [`ManagedContext::supports_smoothing`].
+    pub fn with_line_smooth(self, enabled: bool) -> Self {
+        Self {
+            line_smooth: enabled,
+            ..self
+        }
+    }
+
+    /// Set the point smoothing state. Only has an effect on compatibility profiles, see [`ManagedContext::supports_smoothing`].
+    pub fn with_point_smooth(self, enabled: bool) -> Self {
+        Self {
+            point_smooth: enabled,
+            ..self
+        }
+    }
+
     /// Get a reference to the shader program. Useful for setting uniforms.
     pub fn program(&self) -> &ShaderProgram {
         &self.program
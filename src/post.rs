@@ -0,0 +1,371 @@
+use super::*;
+
+/// Scale linear colors by `exp2(float u_exposure)`, for use with [`shaders::FULLSCREEN_VS`].
+pub const EXPOSURE_FS: &str = r#"
+uniform sampler2D u_texture;
+uniform float u_exposure;
+
+in vec2 v_uv;
+
+out vec4 o_color;
+
+void main() {
+    vec4 color = texture(u_texture, v_uv);
+    o_color = vec4(color.rgb * exp2(u_exposure), color.a);
+}
+"#;
+
+/// Reinhard tone mapping to gamma-encoded colors, for use with [`shaders::FULLSCREEN_VS`].
+pub const TONEMAP_REINHARD_FS: &str = r#"
+uniform sampler2D u_texture;
+
+in vec2 v_uv;
+
+out vec4 o_color;
+
+void main() {
+    vec3 hdr = texture(u_texture, v_uv).rgb;
+    vec3 ldr = hdr / (hdr + vec3(1.0));
+    o_color = vec4(pow(ldr, vec3(1.0 / 2.2)), 1.0);
+}
+"#;
+
+/// Narkowicz's fit of the ACES filmic curve, tone mapping to gamma-encoded colors. For use with
+/// [`shaders::FULLSCREEN_VS`].
+pub const TONEMAP_ACES_FS: &str = r#"
+uniform sampler2D u_texture;
+
+in vec2 v_uv;
+
+out vec4 o_color;
+
+void main() {
+    vec3 x = texture(u_texture, v_uv).rgb;
+    vec3 ldr = clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+    o_color = vec4(pow(ldr, vec3(1.0 / 2.2)), 1.0);
+}
+"#;
+
+/// Fast approximate antialiasing of gamma-encoded colors, for use with [`shaders::FULLSCREEN_VS`] after tone mapping.
+pub const FXAA_FS: &str = r#"
+uniform sampler2D u_texture;
+
+in vec2 v_uv;
+
+out vec4 o_color;
+
+float luma(vec3 color) {
+    return dot(color, vec3(0.299, 0.587, 0.114));
+}
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(u_texture, 0));
+    vec3 rgb_m = texture(u_texture, v_uv).rgb;
+    float luma_nw = luma(texture(u_texture, v_uv + vec2(-1.0, -1.0) * texel).rgb);
+    float luma_ne = luma(texture(u_texture, v_uv + vec2(1.0, -1.0) * texel).rgb);
+    float luma_sw = luma(texture(u_texture, v_uv + vec2(-1.0, 1.0) * texel).rgb);
+    float luma_se = luma(texture(u_texture, v_uv + vec2(1.0, 1.0) * texel).rgb);
+    float luma_m = luma(rgb_m);
+    float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    vec2 dir = vec2(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se)
+    );
+    float dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * (1.0 / 8.0), 1.0 / 128.0);
+    float dir_scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * dir_scale, vec2(-8.0), vec2(8.0)) * texel;
+
+    vec3 rgb_a = 0.5 * (
+        texture(u_texture, v_uv + dir * (1.0 / 3.0 - 0.5)).rgb +
+        texture(u_texture, v_uv + dir * (2.0 / 3.0 - 0.5)).rgb);
+    vec3 rgb_b = rgb_a * 0.5 + 0.25 * (
+        texture(u_texture, v_uv - dir * 0.5).rgb +
+        texture(u_texture, v_uv + dir * 0.5).rgb);
+    float luma_b = luma(rgb_b);
+    o_color = vec4((luma_b < luma_min || luma_b > luma_max) ? rgb_a : rgb_b, 1.0);
+}
+"#;
+
+/// A tone mapping curve, see [`PostChain::tonemap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tonemap {
+    /// See [`TONEMAP_REINHARD_FS`].
+    Reinhard,
+    /// See [`TONEMAP_ACES_FS`].
+    Aces,
+}
+
+/// A floating point render target of a [`PostChain`] or [`Bloom`].
+#[derive(Debug)]
+pub(crate) struct PostTarget {
+    pub(crate) framebuffer: Framebuffer,
+    pub(crate) color: Texture2D,
+}
+
+impl PostTarget {
+    pub(crate) fn new(ctx: &mut ManagedContext, width: i32, height: i32) -> Result<Self, Error> {
+        let color = TextureHandle::new_with(
+            ctx,
+            &SamplerDescriptor {
+                wrapping_mode_s: TextureWrap::ClampToEdge,
+                wrapping_mode_t: TextureWrap::ClampToEdge,
+                min_filter: TextureFilteringMode::Linear,
+                mag_filter: TextureFilteringMode::Linear,
+                ..Default::default()
+            },
+        )?
+        .allocate_2d_data(
+            ctx,
+            None,
+            InternalTextureFormat::Rgba16F,
+            TextureFormat::Rgba,
+            width,
+            height,
+            DataType::Float,
+        );
+        let framebuffer = Framebuffer::new(ctx)?;
+        framebuffer.attach_texture_2d(ctx, Attachment::Color(0), &color);
+        Ok(Self { framebuffer, color })
+    }
+
+    /// Draw a fullscreen triangle into the whole target, restoring the default framebuffer and previous viewport
+    /// afterwards.
+    pub(crate) fn draw(
+        &self,
+        ctx: &mut ManagedContext,
+        pipeline: &RenderPipeline,
+        triangle: &BufferHandle,
+        textures: &[(&dyn BindableTexture, &str)],
+        uniforms: impl FnOnce(&mut Drawable),
+    ) {
+        let viewport = ctx.viewport_rect();
+        ctx.bind_framebuffer(Some(&self.framebuffer));
+        ctx.set_viewport(0, 0, self.color.width, self.color.height);
+        draw_fullscreen(ctx, pipeline, triangle, textures, uniforms);
+        ctx.bind_default_framebuffer();
+        ctx.set_viewport(viewport.x, viewport.y, viewport.width, viewport.height);
+    }
+}
+
+/// A chain of fullscreen post-processing passes over an HDR scene.
+///
+/// The scene is rendered into a floating point target, then each pass reads the previous result and writes into the
+/// other of two internal targets. Floating point targets fall back to RGBA8 without
+/// [`Features::FLOAT_RENDER_TARGETS`].
+#[derive(Debug)]
+pub struct PostChain {
+    pub(crate) targets: [PostTarget; 2],
+    depth: Texture2D,
+    pub(crate) current: usize,
+    width: i32,
+    height: i32,
+    pub(crate) triangle: BufferHandle,
+    exposure: RenderPipeline,
+    reinhard: RenderPipeline,
+    aces: RenderPipeline,
+    fxaa: RenderPipeline,
+    blur: RenderPipeline,
+    blit: RenderPipeline,
+}
+
+impl PostChain {
+    /// Create the render targets and built-in passes for a `width` x `height` viewport.
+    pub fn new(ctx: &mut ManagedContext, width: i32, height: i32) -> Result<Self, Error> {
+        let targets = [
+            PostTarget::new(ctx, width, height)?,
+            PostTarget::new(ctx, width, height)?,
+        ];
+        let depth = TextureHandle::new_with(
+            ctx,
+            &SamplerDescriptor {
+                wrapping_mode_s: TextureWrap::ClampToEdge,
+                wrapping_mode_t: TextureWrap::ClampToEdge,
+                min_filter: TextureFilteringMode::Nearest,
+                mag_filter: TextureFilteringMode::Nearest,
+                ..Default::default()
+            },
+        )?
+        .allocate_2d_data(
+            ctx,
+            None,
+            InternalTextureFormat::DepthComponent24,
+            TextureFormat::DepthComponent,
+            width,
+            height,
+            DataType::UnsignedInt,
+        );
+        targets[0]
+            .framebuffer
+            .attach_texture_2d(ctx, Attachment::Depth, &depth);
+        if !targets[0].framebuffer.is_complete(ctx) {
+            return Err(Error::Unsupported(
+                "incomplete post chain framebuffer".into(),
+            ));
+        }
+
+        let pass = |ctx: &ManagedContext, fs: &str| {
+            RenderPipeline::built_in(
+                ctx,
+                shaders::FULLSCREEN_VS,
+                fs,
+                VertexLayout::new(0).with_attribute(2, DataType::Float, false),
+            )
+        };
+
+        Ok(Self {
+            targets,
+            depth,
+            current: 0,
+            width,
+            height,
+            triangle: BufferHandle::array_buffer(
+                ctx,
+                BufferUsage::Immutable,
+                bytemuck::cast_slice(&shaders::FULLSCREEN_TRIANGLE),
+            )?,
+            exposure: pass(ctx, EXPOSURE_FS)?,
+            reinhard: pass(ctx, TONEMAP_REINHARD_FS)?,
+            aces: pass(ctx, TONEMAP_ACES_FS)?,
+            fxaa: pass(ctx, FXAA_FS)?,
+            blur: RenderPipeline::gaussian_blur(ctx)?,
+            blit: RenderPipeline::blit(ctx)?,
+        })
+    }
+
+    /// Resize the render targets, e.g. when the window is resized.
+    pub fn resize(&mut self, ctx: &mut ManagedContext, width: i32, height: i32) {
+        for target in self.targets.iter_mut() {
+            target.color.resize(ctx, width, height);
+        }
+        self.depth.resize(ctx, width, height);
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Render the scene into the HDR target, which has a depth attachment. The target is cleared beforehand.
+    pub fn render_scene(
+        &mut self,
+        ctx: &mut ManagedContext,
+        draw: impl FnOnce(&mut ManagedContext),
+    ) {
+        // the depth texture stays attached to the first target
+        self.current = 0;
+        let viewport = ctx.viewport_rect();
+        ctx.bind_framebuffer(Some(&self.targets[0].framebuffer));
+        ctx.set_viewport(0, 0, self.width, self.height);
+        unsafe {
+            // clears respect the write masks, which are re-applied by the next pipeline bind
+            ctx.gl.color_mask(true, true, true, true);
+            ctx.gl.depth_mask(true);
+        }
+        ctx.clear(ClearFlags::COLOR | ClearFlags::DEPTH);
+        draw(ctx);
+        ctx.bind_default_framebuffer();
+        ctx.set_viewport(viewport.x, viewport.y, viewport.width, viewport.height);
+    }
+
+    /// Apply a fullscreen pass. The pipeline reads the previous result from `sampler2D u_texture`, see
+    /// [`shaders::FULLSCREEN_VS`]; `uniforms` sets any other uniforms.
+    pub fn apply(
+        &mut self,
+        ctx: &mut ManagedContext,
+        pipeline: &RenderPipeline,
+        uniforms: impl FnOnce(&mut Drawable),
+    ) {
+        self.draw_pass(ctx, pipeline, uniforms);
+        self.current = 1 - self.current;
+    }
+
+    /// Scale the scene brightness by `2^exposure`. See [`EXPOSURE_FS`].
+    pub fn exposure(&mut self, ctx: &mut ManagedContext, exposure: f32) {
+        self.draw_pass(ctx, &self.exposure, |dctx| {
+            dctx.set_uniform_float1("u_exposure", exposure)
+        });
+        self.current = 1 - self.current;
+    }
+
+    /// Map HDR colors to gamma-encoded display colors.
+    pub fn tonemap(&mut self, ctx: &mut ManagedContext, tonemap: Tonemap) {
+        let pipeline = match tonemap {
+            Tonemap::Reinhard => &self.reinhard,
+            Tonemap::Aces => &self.aces,
+        };
+        self.draw_pass(ctx, pipeline, |_| {});
+        self.current = 1 - self.current;
+    }
+
+    /// Antialias edges of the tone mapped image. See [`FXAA_FS`].
+    pub fn fxaa(&mut self, ctx: &mut ManagedContext) {
+        self.draw_pass(ctx, &self.fxaa, |_| {});
+        self.current = 1 - self.current;
+    }
+
+    /// Blur the image with two passes of [`shaders::GAUSSIAN_BLUR_FS`]. `radius` scales the distance between taps, so
+    /// the kernel reaches `4 * radius` texels in each direction.
+    pub fn blur(&mut self, ctx: &mut ManagedContext, radius: f32) {
+        let (width, height) = (self.width as f32, self.height as f32);
+        self.draw_pass(ctx, &self.blur, |dctx| {
+            dctx.set_uniform_float2("u_direction", &[radius / width, 0.0])
+        });
+        self.current = 1 - self.current;
+        self.draw_pass(ctx, &self.blur, |dctx| {
+            dctx.set_uniform_float2("u_direction", &[0.0, radius / height])
+        });
+        self.current = 1 - self.current;
+    }
+
+    /// Draw from the current target into the other one.
+    fn draw_pass(
+        &self,
+        ctx: &mut ManagedContext,
+        pipeline: &RenderPipeline,
+        uniforms: impl FnOnce(&mut Drawable),
+    ) {
+        let source = &self.targets[self.current];
+        self.targets[1 - self.current].draw(
+            ctx,
+            pipeline,
+            &self.triangle,
+            &[(&source.color, "u_texture")],
+            uniforms,
+        );
+    }
+
+    /// Copy the result into the currently bound framebuffer, covering the current viewport.
+    pub fn present(&self, ctx: &mut ManagedContext) {
+        let source = &self.targets[self.current];
+        draw_fullscreen(
+            ctx,
+            &self.blit,
+            &self.triangle,
+            &[(&source.color, "u_texture")],
+            |_| {},
+        );
+    }
+
+    /// The texture holding the result of the last pass.
+    pub fn output(&self) -> &Texture2D {
+        &self.targets[self.current].color
+    }
+}
+
+pub(crate) fn draw_fullscreen(
+    ctx: &mut ManagedContext,
+    pipeline: &RenderPipeline,
+    triangle: &BufferHandle,
+    textures: &[(&dyn BindableTexture, &str)],
+    uniforms: impl FnOnce(&mut Drawable),
+) {
+    // the chain runs in the middle of user rendering, so keep clear of the units user draws bind
+    let units = ctx
+        .reserve_texture_units(textures.len() as u32)
+        .expect("reserved texture units exhausted!");
+    ctx.with_pipeline(pipeline, |dctx| {
+        dctx.apply_bindings(&[triangle], None::<&BufferHandle>);
+        dctx.apply_reserved_textures(&units, textures);
+        uniforms(dctx);
+        dctx.draw_arrays(DrawMode::Triangles, 0, 3);
+    });
+}
@@ -0,0 +1,80 @@
+use super::*;
+
+/// The uniform buffer binding point reserved for [`Drawable::push_constants`]. It is the last one every GL 3.3 and
+/// GLES 3.0 driver provides, so it doesn't collide with [`Drawable::apply_uniform_buffers`].
+pub const PUSH_CONSTANT_BINDING: u32 = 23;
+
+/// The name of the uniform block receiving [`Drawable::push_constants`].
+pub const PUSH_CONSTANT_BLOCK: &str = "PushConstants";
+
+/// The largest value accepted by [`Drawable::push_constants`], in bytes.
+pub const MAX_PUSH_CONSTANTS_SIZE: usize = 256;
+
+const RING_SIZE: usize = 64 * 1024;
+
+/// A streaming uniform buffer that push constants are appended to, wrapping around when full.
+#[derive(Debug)]
+pub(crate) struct PushConstantRing {
+    buffer: BufferHandle,
+    offset: usize,
+    alignment: usize,
+}
+
+impl PushConstantRing {
+    fn new(ctx: &ManagedContext) -> Result<Self, Error> {
+        let alignment = unsafe { ctx.gl.get_parameter_i32(UNIFORM_BUFFER_OFFSET_ALIGNMENT) };
+        Ok(Self {
+            buffer: BufferHandle::uniform_buffer(ctx, BufferUsage::Stream, &[0; RING_SIZE])?,
+            offset: 0,
+            alignment: alignment.max(1) as usize,
+        })
+    }
+
+    /// Write `data` into the next free slot, returning its offset.
+    fn push(&mut self, ctx: &ManagedContext, data: &[u8]) -> usize {
+        let mut offset = self.offset.div_ceil(self.alignment) * self.alignment;
+        if offset + data.len() > self.buffer.capacity() {
+            // orphan the storage, so draws still reading the old contents don't stall
+            self.buffer.invalidate(ctx);
+            offset = 0;
+        }
+        self.buffer.update(ctx, offset as i32, data);
+        self.offset = offset + data.len();
+        offset
+    }
+}
+
+impl<'a> Drawable<'a> {
+    /// Set small per-draw data, like Vulkan push constants.
+    ///
+    /// `value` is appended to a ring buffer shared by all draws and bound to the program's `PushConstants` uniform
+    /// block at [`PUSH_CONSTANT_BINDING`]. `T` must match the block's `std140` layout, padding included.
+    ///
+    /// ## Panics
+    /// The program must contain the `PushConstants` block, and `value` must not exceed [`MAX_PUSH_CONSTANTS_SIZE`].
+    pub fn push_constants<T: bytemuck::Pod>(&mut self, value: &T) {
+        let data = bytemuck::bytes_of(value);
+        assert!(
+            data.len() <= MAX_PUSH_CONSTANTS_SIZE,
+            "push constants too large!"
+        );
+
+        let mut ring = match self.ctx.push_constants.take() {
+            Some(ring) => ring,
+            None => PushConstantRing::new(self.ctx).expect("push constant buffer is required"),
+        };
+        let offset = ring.push(self.ctx, data);
+
+        self.current_program
+            .bind_uniform_block(PUSH_CONSTANT_BLOCK, PUSH_CONSTANT_BINDING);
+        unsafe {
+            self.ctx.bind_uniform_buffer(
+                PUSH_CONSTANT_BINDING,
+                ring.buffer.buffer,
+                offset as i32,
+                data.len() as i32,
+            );
+        }
+        self.ctx.push_constants = Some(ring);
+    }
+}
@@ -0,0 +1,48 @@
+use super::*;
+
+use std::ops::Deref;
+
+/// Scoped access to the underlying glow context, see [`ManagedContext::raw_gl`].
+///
+/// Any GL state may be changed through it; the state tracked by rapax is invalidated on drop.
+#[derive(Debug)]
+pub struct RawScope<'a> {
+    ctx: &'a mut ManagedContext,
+}
+
+impl<'a> RawScope<'a> {
+    /// The underlying glow context.
+    pub fn gl(&self) -> &glow::Context {
+        &self.ctx.gl
+    }
+}
+
+impl<'a> Deref for RawScope<'a> {
+    type Target = glow::Context;
+
+    fn deref(&self) -> &Self::Target {
+        &self.ctx.gl
+    }
+}
+
+impl<'a> Drop for RawScope<'a> {
+    fn drop(&mut self) {
+        self.ctx.invalidate_raw_capabilities();
+        self.ctx.invalidate_texture_units();
+        self.ctx.invalidate_buffer_bindings();
+        self.ctx.invalidate_clear_state();
+        self.ctx.invalidate_uniform_caches();
+    }
+}
+
+impl ManagedContext {
+    /// Use raw glow calls for things rapax doesn't wrap.
+    ///
+    /// Pipeline state is fully re-applied on the next [`ManagedContext::bind_pipeline`]. Once the scope ends, rapax
+    /// forgets the capabilities set through [`ManagedContext::set_raw_capability`], the texture unit and uniform buffer
+    /// bindings, the clear values and the cached uniform values.
+    pub fn raw_gl(&mut self) -> RawScope<'_> {
+        self.debug_assert_current();
+        RawScope { ctx: self }
+    }
+}
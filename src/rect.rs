@@ -0,0 +1,30 @@
+/// An integer rectangle, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    /// Create a new rectangle.
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns true if this rectangle lies inside a `width` x `height` area at the origin.
+    pub fn fits_within(&self, width: i32, height: i32) -> bool {
+        self.x >= 0
+            && self.y >= 0
+            && self.width >= 0
+            && self.height >= 0
+            && self.x + self.width <= width
+            && self.y + self.height <= height
+    }
+}
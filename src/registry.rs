@@ -0,0 +1,271 @@
+use super::*;
+
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+/// A plain, copyable reference to a resource owned by a [`Registry`].
+///
+/// Ids of removed resources are never reused: the slot's generation is bumped, so stale ids return `None` instead of
+/// a newer resource.
+pub struct Id<T> {
+    index: u32,
+    generation: u32,
+    marker: PhantomData<fn() -> T>,
+}
+
+/// A texture owned by the context, see [`ManagedContext::create_texture`].
+pub type TextureId = Id<Texture2D>;
+/// A buffer owned by the context, see [`ManagedContext::create_buffer`].
+pub type BufferId = Id<BufferHandle>;
+
+impl<T> Id<T> {
+    /// The slot index, e.g. to pack the id into an ECS component or a shader constant.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// The slot generation, bumped each time the slot's resource is removed.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl<T> Clone for Id<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Id<T> {}
+
+impl<T> PartialEq for Id<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for Id<T> {}
+
+impl<T> Hash for Id<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<T> std::fmt::Debug for Id<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Id({}v{})", self.index, self.generation)
+    }
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+/// Resources addressed by generational [`Id`]s, for engines that store plain ids in components rather than owning
+/// resources.
+#[derive(Debug)]
+pub struct Registry<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+    len: usize,
+}
+
+impl<T> Default for Registry<T> {
+    fn default() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> Registry<T> {
+    pub(crate) fn insert(&mut self, value: T) -> Id<T> {
+        self.len += 1;
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.value = Some(value);
+            return Id {
+                index,
+                generation: slot.generation,
+                marker: PhantomData,
+            };
+        }
+
+        self.slots.push(Slot {
+            generation: 0,
+            value: Some(value),
+        });
+        Id {
+            index: self.slots.len() as u32 - 1,
+            generation: 0,
+            marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn remove(&mut self, id: Id<T>) -> Option<T> {
+        let slot = self.slots.get_mut(id.index as usize)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(id.index);
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// The resource of `id`, or `None` if it was destroyed.
+    pub fn get(&self, id: Id<T>) -> Option<&T> {
+        self.slots
+            .get(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.value.as_ref())
+    }
+
+    /// The mutable resource of `id`, or `None` if it was destroyed.
+    pub fn get_mut(&mut self, id: Id<T>) -> Option<&mut T> {
+        self.slots
+            .get_mut(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.value.as_mut())
+    }
+
+    /// Whether `id` refers to a live resource.
+    pub fn contains(&self, id: Id<T>) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// The number of live resources.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the registry holds no live resources.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterate over all live resources and their ids.
+    pub fn iter(&self) -> impl Iterator<Item = (Id<T>, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let id = Id {
+                index: index as u32,
+                generation: slot.generation,
+                marker: PhantomData,
+            };
+            slot.value.as_ref().map(|value| (id, value))
+        })
+    }
+}
+
+impl ManagedContext {
+    /// Create a texture owned by the context, returning an id to look it up with [`ManagedContext::textures`].
+    pub fn create_texture(&mut self, desc: &PendingTexture) -> Result<TextureId, Error> {
+        let texture = self.create_pending_texture(desc)?;
+        Ok(self.textures.insert(texture))
+    }
+
+    /// Transfer ownership of an existing texture to the context.
+    pub fn insert_texture(&mut self, texture: Texture2D) -> TextureId {
+        self.textures.insert(texture)
+    }
+
+    /// Remove a texture, deleting it once in-flight frames are done with it, see [`ManagedContext::defer_delete`].
+    ///
+    /// Returns `false` if the id was already destroyed.
+    pub fn destroy_texture(&mut self, id: TextureId) -> bool {
+        let Some(texture) = self.textures.remove(id) else {
+            return false;
+        };
+        self.defer_delete(texture);
+        true
+    }
+
+    /// The textures owned by the context, to look up [`TextureId`]s.
+    pub fn textures(&self) -> &Registry<Texture2D> {
+        &self.textures
+    }
+
+    /// The textures owned by the context, to modify them through their [`TextureId`]s.
+    pub fn textures_mut(&mut self) -> &mut Registry<Texture2D> {
+        &mut self.textures
+    }
+
+    /// Create a buffer owned by the context, returning an id to look it up with [`ManagedContext::buffers`].
+    pub fn create_buffer(&mut self, desc: &PendingBuffer) -> Result<BufferId, Error> {
+        let buffer = self.create_pending_buffer(desc)?;
+        Ok(self.buffers.insert(buffer))
+    }
+
+    /// Transfer ownership of an existing buffer to the context.
+    pub fn insert_buffer(&mut self, buffer: BufferHandle) -> BufferId {
+        self.buffers.insert(buffer)
+    }
+
+    /// Remove a buffer, deleting it once in-flight frames are done with it, see [`ManagedContext::defer_delete`].
+    ///
+    /// Returns `false` if the id was already destroyed.
+    pub fn destroy_buffer(&mut self, id: BufferId) -> bool {
+        let Some(buffer) = self.buffers.remove(id) else {
+            return false;
+        };
+        self.defer_delete(buffer);
+        true
+    }
+
+    /// The buffers owned by the context, to look up [`BufferId`]s.
+    pub fn buffers(&self) -> &Registry<BufferHandle> {
+        &self.buffers
+    }
+
+    /// The buffers owned by the context, to modify them through their [`BufferId`]s.
+    pub fn buffers_mut(&mut self) -> &mut Registry<BufferHandle> {
+        &mut self.buffers
+    }
+}
+
+impl Drawable<'_> {
+    /// Like [`Drawable::apply_bindings`], with buffers owned by the context.
+    ///
+    /// ## Panics
+    ///
+    /// If an id was destroyed.
+    pub fn apply_buffer_ids(
+        &mut self,
+        vertex_buffers: &[BufferId],
+        index_buffer: Option<BufferId>,
+    ) {
+        let buffers = &self.ctx.buffers;
+        let vertex: Vec<&BufferHandle> = vertex_buffers
+            .iter()
+            .map(|id| buffers.get(*id).expect("stale buffer id!"))
+            .collect();
+        let index = index_buffer.map(|id| buffers.get(id).expect("stale buffer id!"));
+        // the registry stays borrowed while binding, so only record the bindings afterwards
+        let index_type = self.bind_buffers(&vertex, index);
+        self.record_bindings(index_type);
+    }
+
+    /// Like [`Drawable::apply_textures`], with textures owned by the context.
+    ///
+    /// ## Panics
+    ///
+    /// If an id was destroyed, more than [`ManagedContext::user_texture_units`] textures are applied, or a texture
+    /// doesn't match the type of its sampler.
+    pub fn apply_texture_ids(&self, textures: &[(TextureId, &str)]) {
+        let textures: Vec<(&dyn BindableTexture, &str)> = textures
+            .iter()
+            .map(|(id, uniform_name)| {
+                let texture = self.ctx.textures.get(*id).expect("stale texture id!");
+                (texture as &dyn BindableTexture, *uniform_name)
+            })
+            .collect();
+        self.apply_textures(&textures);
+    }
+}
@@ -0,0 +1,69 @@
+use super::*;
+
+use std::ffi::c_void;
+
+/// `glMinSampleShading`, which glow does not expose.
+pub(crate) type MinSampleShadingFn = unsafe extern "system" fn(value: f32);
+
+impl ManagedContext {
+    /// Load `glMinSampleShading` so pipelines using [`RenderPipeline::with_sample_shading`] shade per sample.
+    ///
+    /// `loader` is the same function used to create the glow context, e.g. `window.get_proc_address`.
+    ///
+    /// # Safety
+    /// `loader` must return valid function pointers for the current context.
+    pub unsafe fn load_sample_shading(
+        &mut self,
+        mut loader: impl FnMut(&str) -> *const c_void,
+    ) -> Result<(), Error> {
+        self.require_features(Features::SAMPLE_SHADING, "sample shading")
+            .map_err(|err| Error::Unsupported(err.to_string()))?;
+        let min_sample_shading = [
+            "glMinSampleShading",
+            "glMinSampleShadingARB",
+            "glMinSampleShadingOES",
+        ]
+        .into_iter()
+        .find_map(|name| load_fn(&mut loader, name).ok());
+        self.min_sample_shading_fn = Some(
+            min_sample_shading
+                .ok_or_else(|| Error::Unsupported("glMinSampleShading is not available".into()))?,
+        );
+        Ok(())
+    }
+
+    /// Apply a pipeline's sample shading state, if sample shading was loaded.
+    pub(crate) unsafe fn apply_sample_shading(&self, pipeline: &RenderPipeline) {
+        let Some(min_sample_shading) = self.min_sample_shading_fn else {
+            return;
+        };
+
+        match pipeline.sample_shading() {
+            Some(min_fraction) => {
+                self.gl.enable(SAMPLE_SHADING);
+                min_sample_shading(min_fraction);
+            }
+            None => self.gl.disable(SAMPLE_SHADING),
+        }
+    }
+}
+
+impl RenderPipeline {
+    /// Run the fragment shader for at least `min_fraction` of the samples of each pixel on multisampled targets,
+    /// instead of once per pixel, e.g. 1.0 so alpha-tested foliage is antialiased like geometry edges. 0.0 disables
+    /// sample shading.
+    ///
+    /// Requires [`Features::SAMPLE_SHADING`] and [`ManagedContext::load_sample_shading`]; otherwise fragments are
+    /// shaded once per pixel.
+    pub fn with_sample_shading(self, min_fraction: f32) -> Self {
+        Self {
+            sample_shading: (min_fraction > 0.0).then(|| min_fraction.min(1.0).to_bits()),
+            ..self
+        }
+    }
+
+    /// The minimum fraction of samples shaded per pixel, see [`RenderPipeline::with_sample_shading`].
+    pub fn sample_shading(&self) -> Option<f32> {
+        self.sample_shading.map(f32::from_bits)
+    }
+}
@@ -0,0 +1,175 @@
+use super::*;
+
+// sampler types glow does not define
+const SAMPLER_EXTERNAL_OES: u32 = 0x8D66;
+
+/// A sampler uniform of a shader program and the texture unit(s) assigned to it.
+///
+/// Units are assigned automatically in the order the driver reports the samplers; arrays take consecutive units.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SamplerUniform {
+    /// The uniform name, without any `[0]` suffix.
+    pub name: String,
+    /// The first texture unit.
+    pub unit: u32,
+    /// The array length, 1 for non-array samplers.
+    pub count: u32,
+    /// The texture target the sampler type reads from, e.g. `TEXTURE_CUBE_MAP` for `samplerCube`.
+    pub target: u32,
+    /// Whether the sampler is a shadow sampler, e.g. `sampler2DShadow`, which compares against a depth texture.
+    pub shadow: bool,
+}
+
+/// The texture target sampled by a GLSL sampler type, or `None` for non-sampler types.
+fn sampler_target(ty: u32) -> Option<u32> {
+    match ty {
+        SAMPLER_2D | SAMPLER_2D_SHADOW | INT_SAMPLER_2D | UNSIGNED_INT_SAMPLER_2D => {
+            Some(TEXTURE_2D)
+        }
+        SAMPLER_CUBE | SAMPLER_CUBE_SHADOW | INT_SAMPLER_CUBE | UNSIGNED_INT_SAMPLER_CUBE => {
+            Some(TEXTURE_CUBE_MAP)
+        }
+        SAMPLER_2D_ARRAY
+        | SAMPLER_2D_ARRAY_SHADOW
+        | INT_SAMPLER_2D_ARRAY
+        | UNSIGNED_INT_SAMPLER_2D_ARRAY => Some(TEXTURE_2D_ARRAY),
+        SAMPLER_3D | INT_SAMPLER_3D | UNSIGNED_INT_SAMPLER_3D => Some(TEXTURE_3D),
+        SAMPLER_BUFFER | INT_SAMPLER_BUFFER | UNSIGNED_INT_SAMPLER_BUFFER => Some(TEXTURE_BUFFER),
+        SAMPLER_EXTERNAL_OES => Some(TEXTURE_EXTERNAL_OES),
+        _ => None,
+    }
+}
+
+fn is_shadow_sampler(ty: u32) -> bool {
+    matches!(
+        ty,
+        SAMPLER_2D_SHADOW | SAMPLER_CUBE_SHADOW | SAMPLER_2D_ARRAY_SHADOW
+    )
+}
+
+/// The GLSL name of the sampler type reading from `target`, for error messages.
+fn sampler_type_name(target: u32, shadow: bool) -> String {
+    let ty = match target {
+        TEXTURE_2D => "sampler2D",
+        TEXTURE_CUBE_MAP => "samplerCube",
+        TEXTURE_2D_ARRAY => "sampler2DArray",
+        TEXTURE_3D => "sampler3D",
+        TEXTURE_EXTERNAL_OES => "samplerExternalOES",
+        TEXTURE_BUFFER => "samplerBuffer",
+        _ => return format!("sampler of target {:#06x}", target),
+    };
+    if shadow {
+        format!("{}Shadow", ty)
+    } else {
+        ty.to_owned()
+    }
+}
+
+/// Find the program's sampler uniforms and assign each of them its own texture unit(s).
+pub(crate) unsafe fn assign_sampler_units(
+    gl: &Context,
+    program: NativeProgram,
+) -> Vec<SamplerUniform> {
+    let count = gl.get_active_uniforms(program);
+    let mut samplers = vec![];
+    let mut next_unit = 0;
+    for index in 0..count {
+        let Some(uniform) = gl.get_active_uniform(program, index) else {
+            continue;
+        };
+        let Some(target) = sampler_target(uniform.utype) else {
+            continue;
+        };
+
+        samplers.push(SamplerUniform {
+            name: uniform.name.trim_end_matches("[0]").to_owned(),
+            unit: next_unit,
+            count: uniform.size as u32,
+            target,
+            shadow: is_shadow_sampler(uniform.utype),
+        });
+        next_unit += uniform.size as u32;
+    }
+
+    if !samplers.is_empty() {
+        gl.use_program(Some(program));
+        for sampler in &samplers {
+            let units: Vec<i32> = (sampler.unit..sampler.unit + sampler.count)
+                .map(|unit| unit as i32)
+                .collect();
+            let loc = gl.get_uniform_location(program, &sampler.name);
+            gl.uniform_1_i32_slice(loc.as_ref(), &units);
+        }
+        gl.use_program(None);
+    }
+
+    samplers
+}
+
+impl ShaderProgram {
+    /// The program's sampler uniforms and their automatically assigned texture units.
+    pub fn samplers(&self) -> &[SamplerUniform] {
+        &self.samplers
+    }
+
+    /// The first texture unit assigned to the sampler uniform `name`.
+    pub fn sampler_unit(&self, name: &str) -> Option<u32> {
+        self.samplers
+            .iter()
+            .find(|sampler| sampler.name == name)
+            .map(|sampler| sampler.unit)
+    }
+}
+
+impl<'a> Drawable<'a> {
+    /// Bind a texture to a texture unit assigned to one of the program's samplers, see [`ShaderProgram::samplers`].
+    ///
+    /// ## Panics
+    /// A sampler must use the unit, and the texture must match the sampler type, see
+    /// [`Drawable::apply_textures`].
+    pub fn bind_texture_unit(&self, unit: u32, texture: &dyn BindableTexture) {
+        let sampler = self
+            .current_program
+            .samplers
+            .iter()
+            .find(|sampler| (sampler.unit..sampler.unit + sampler.count).contains(&unit));
+        let sampler = sampler.expect("No sampler uses this texture unit!");
+        check_sampler_texture(sampler, texture);
+
+        unsafe {
+            self.ctx.bind_texture_to_unit(unit, sampler.target, texture);
+        }
+    }
+
+    /// Check that `texture` can be sampled by the program's sampler uniform `name`, if the program has one.
+    pub(crate) fn check_sampler(&self, name: &str, texture: &dyn BindableTexture) {
+        if let Some(sampler) = self
+            .current_program
+            .samplers
+            .iter()
+            .find(|sampler| sampler.name == name)
+        {
+            check_sampler_texture(sampler, texture);
+        }
+    }
+}
+
+/// Panic if `texture` doesn't match the type of `sampler`, which would otherwise sample black.
+fn check_sampler_texture(sampler: &SamplerUniform, texture: &dyn BindableTexture) {
+    let target = texture.texture_target_hint();
+    assert!(
+        target == sampler.target,
+        "sampler {} is a {}, but the texture bound to it is sampled as a {}",
+        sampler.name,
+        sampler_type_name(sampler.target, sampler.shadow),
+        sampler_type_name(target, false)
+    );
+    if let Some(depth) = texture.is_depth() {
+        assert!(
+            depth || !sampler.shadow,
+            "sampler {} is a {}, but the texture bound to it has no depth format",
+            sampler.name,
+            sampler_type_name(sampler.target, sampler.shadow)
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use super::*;
+
+use crate::util::RectPacker;
+use std::collections::HashMap;
+
+/// A glyph or icon stored in an [`SdfAtlas`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SdfGlyph {
+    /// Normalized atlas coordinates: u0, v0, u1, v1.
+    pub uv: [f32; 4],
+    /// Offset and size of the quad relative to the pen position, at the size the field was generated at: x, y, width,
+    /// height.
+    pub bounds: [f32; 4],
+    /// Horizontal advance at the size the field was generated at.
+    pub advance: f32,
+}
+
+/// A texture of signed distance fields for glyphs and icons that scale without re-rasterizing.
+///
+/// Draw entries with [`RenderPipeline::sdf_2d`]. Atlases generated by external tools such as msdf-atlas-gen can be
+/// imported with [`SdfAtlas::from_texture`]; single-channel fields generated by [`SdfAtlas::add_coverage`] are
+/// stored in every channel, so the same shader handles both.
+#[derive(Debug)]
+pub struct SdfAtlas {
+    texture: Texture2D,
+    distance_range: f32,
+    packer: Option<RectPacker>,
+    glyphs: HashMap<u32, SdfGlyph>,
+}
+
+impl SdfAtlas {
+    /// Create an empty RGBA atlas of `size` x `size` pixels.
+    ///
+    /// `distance_range` is the width of the distance field around each edge, in atlas pixels.
+    pub fn new(ctx: &mut ManagedContext, size: i32, distance_range: f32) -> Result<Self, Error> {
+        let texture = TextureHandle::new_with(
+            ctx,
+            &SamplerDescriptor {
+                wrapping_mode_s: TextureWrap::ClampToEdge,
+                wrapping_mode_t: TextureWrap::ClampToEdge,
+                min_filter: TextureFilteringMode::Linear,
+                mag_filter: TextureFilteringMode::Linear,
+                ..Default::default()
+            },
+        )?
+        .allocate_2d_data(
+            ctx,
+            None,
+            InternalTextureFormat::Rgba8,
+            TextureFormat::Rgba,
+            size,
+            size,
+            DataType::UnsignedByte,
+        );
+
+        Ok(Self {
+            texture,
+            distance_range,
+            packer: Some(RectPacker::new(size, size)),
+            glyphs: HashMap::new(),
+        })
+    }
+
+    /// Import a pre-generated (M)SDF atlas. Entries are added with [`SdfAtlas::insert`].
+    ///
+    /// The texture must use linear filtering and no mipmaps, and store the distance with 0.5 on the edge.
+    pub fn from_texture(texture: Texture2D, distance_range: f32) -> Self {
+        Self {
+            texture,
+            distance_range,
+            packer: None,
+            glyphs: HashMap::new(),
+        }
+    }
+
+    /// Register an entry of the atlas under `key`, e.g. a code point or an icon id.
+    pub fn insert(&mut self, key: u32, glyph: SdfGlyph) {
+        self.glyphs.insert(key, glyph);
+    }
+
+    /// Generate a distance field from an 8-bit coverage mask, e.g. a rasterized glyph, and add it under `key`.
+    ///
+    /// Imported atlases have no free space to add to.
+    ///
+    /// ## Panics
+    /// `coverage` must contain `width * height` values.
+    pub fn add_coverage(
+        &mut self,
+        ctx: &mut ManagedContext,
+        key: u32,
+        coverage: &[u8],
+        width: i32,
+        height: i32,
+        advance: f32,
+    ) -> Result<SdfGlyph, Error> {
+        assert_eq!(
+            coverage.len(),
+            (width.max(0) * height.max(0)) as usize,
+            "out of bounds read!"
+        );
+        let padding = ((self.distance_range * 0.5).ceil() as i32).max(1);
+        let (field_width, field_height) = (width + 2 * padding, height + 2 * padding);
+        let rect = self
+            .packer
+            .as_mut()
+            .and_then(|packer| packer.allocate(field_width + 1, field_height + 1))
+            .ok_or(Error::AtlasFull)?;
+
+        let field = signed_distance_field(coverage, width, height, padding, self.distance_range);
+        self.texture.write_subimage(
+            ctx,
+            rect.x,
+            rect.y,
+            field_width,
+            field_height,
+            TextureFormat::Rgba,
+            DataType::UnsignedByte,
+            &field,
+        )?;
+
+        let size = self.texture.width() as f32;
+        let glyph = SdfGlyph {
+            uv: [
+                rect.x as f32 / size,
+                rect.y as f32 / size,
+                (rect.x + field_width) as f32 / size,
+                (rect.y + field_height) as f32 / size,
+            ],
+            bounds: [
+                -padding as f32,
+                -padding as f32,
+                field_width as f32,
+                field_height as f32,
+            ],
+            advance,
+        };
+        self.glyphs.insert(key, glyph);
+        Ok(glyph)
+    }
+
+    /// Forget every entry, making the whole space of a generated atlas available again.
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+        if let Some(packer) = self.packer.as_mut() {
+            packer.clear();
+        }
+    }
+
+    /// The entry stored under `key`.
+    pub fn glyph(&self, key: u32) -> Option<&SdfGlyph> {
+        self.glyphs.get(&key)
+    }
+
+    /// The atlas texture.
+    pub fn texture(&self) -> &Texture2D {
+        &self.texture
+    }
+
+    /// The width of the distance field around each edge, in atlas pixels. Set it as `u_distance_range` of
+    /// [`shaders::SDF_FS`].
+    pub fn distance_range(&self) -> f32 {
+        self.distance_range
+    }
+}
+
+/// Brute-force signed distance to the nearest texel on the other side of the edge, mapped to RGBA with 0.5 on the edge.
+fn signed_distance_field(
+    coverage: &[u8],
+    width: i32,
+    height: i32,
+    padding: i32,
+    distance_range: f32,
+) -> Vec<u8> {
+    let inside = |x: i32, y: i32| {
+        x >= 0 && y >= 0 && x < width && y < height && coverage[(y * width + x) as usize] >= 128
+    };
+    let (field_width, field_height) = (width + 2 * padding, height + 2 * padding);
+    let mut field = Vec::with_capacity((field_width * field_height * 4) as usize);
+    for y in 0..field_height {
+        for x in 0..field_width {
+            let (cx, cy) = (x - padding, y - padding);
+            let state = inside(cx, cy);
+            let mut nearest = (padding * padding * 2) as f32;
+            for dy in -padding..=padding {
+                for dx in -padding..=padding {
+                    if inside(cx + dx, cy + dy) != state {
+                        nearest = nearest.min((dx * dx + dy * dy) as f32);
+                    }
+                }
+            }
+            // the edge lies halfway between the two texels
+            let distance = nearest.sqrt() - 0.5;
+            let signed = if state { distance } else { -distance };
+            let value = ((0.5 + signed / distance_range).clamp(0.0, 1.0) * 255.0).round() as u8;
+            field.extend_from_slice(&[value, value, value, value]);
+        }
+    }
+    field
+}
@@ -1,11 +1,19 @@
 use super::*;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::Mutex;
 
 /// A handle to an OpenGL shader program. The internal OpenGL program object will be automatically freed on drop.
 #[derive(Debug)]
 pub struct ShaderProgram {
     pub(crate) program: NativeProgram,
-    gl: Arc<Context>,
+    pub(crate) samplers: Vec<SamplerUniform>,
+    /// The binding point last assigned to each uniform block, by name.
+    pub(crate) block_bindings: Mutex<HashMap<String, u32>>,
+    /// Hashes of the last value set to each uniform, by name.
+    #[cfg(feature = "uniform-cache")]
+    pub(crate) uniform_cache: Mutex<UniformCache>,
+    pub(crate) gl: Arc<Context>,
 }
 
 impl ShaderProgram {
@@ -14,13 +22,174 @@ impl ShaderProgram {
         ctx: &ManagedContext,
         vertex_shader_source: &str,
         fragment_shader_source: &str,
-    ) -> Self {
-        let shader = compile_shader(&ctx.gl, vertex_shader_source, fragment_shader_source);
+    ) -> Result<Self, Error> {
+        let shader = compile_shader(
+            &ctx.gl,
+            &[
+                (ShaderStage::Vertex, vertex_shader_source),
+                (ShaderStage::Fragment, fragment_shader_source),
+            ],
+            &[],
+            &[],
+        )?;
+        Ok(Self::from_linked(ctx, shader))
+    }
+
+    /// Create a new program with a geometry shader, using sources passed in as strings.
+    pub fn with_geometry_shader(
+        ctx: &ManagedContext,
+        vertex_shader_source: &str,
+        geometry_shader_source: &str,
+        fragment_shader_source: &str,
+    ) -> Result<Self, Error> {
+        if !ctx.supports_geometry_shaders() {
+            return Err(Error::Unsupported("geometry shaders".into()));
+        }
+
+        let shader = compile_shader(
+            &ctx.gl,
+            &[
+                (ShaderStage::Vertex, vertex_shader_source),
+                (ShaderStage::Geometry, geometry_shader_source),
+                (ShaderStage::Fragment, fragment_shader_source),
+            ],
+            &[],
+            &[],
+        )?;
+        Ok(Self::from_linked(ctx, shader))
+    }
+
+    /// Create a new compute program, using a source passed in as a string.
+    ///
+    /// Requires [`Features::COMPUTE_SHADERS`].
+    pub fn compute(ctx: &ManagedContext, compute_shader_source: &str) -> Result<Self, Error> {
+        ctx.require_features(Features::COMPUTE_SHADERS, "compute shaders")
+            .map_err(|err| Error::Unsupported(err.to_string()))?;
+
+        let shader = compile_shader(
+            &ctx.gl,
+            &[(ShaderStage::Compute, compute_shader_source)],
+            &[],
+            &[],
+        )?;
+        Ok(Self::from_linked(ctx, shader))
+    }
+
+    /// Create a new program whose vertex shader outputs `varyings` are captured by transform feedback, interleaved in
+    /// the given order.
+    pub fn with_transform_feedback(
+        ctx: &ManagedContext,
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+        varyings: &[&str],
+    ) -> Result<Self, Error> {
+        let shader = compile_shader(
+            &ctx.gl,
+            &[
+                (ShaderStage::Vertex, vertex_shader_source),
+                (ShaderStage::Fragment, fragment_shader_source),
+            ],
+            varyings,
+            &[],
+        )?;
+        Ok(Self::from_linked(ctx, shader))
+    }
+
+    /// Create a new program whose fragment shader outputs are written to the given color attachments, for MRT shaders
+    /// without `layout(location = N)` qualifiers on older GL versions.
+    ///
+    /// Outputs with a layout qualifier keep their location. Unsupported on OpenGL ES, which requires the qualifiers.
+    pub fn with_frag_data_locations(
+        ctx: &ManagedContext,
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+        locations: &[(&str, u32)],
+    ) -> Result<Self, Error> {
+        if ctx.driver_info().is_embedded {
+            return Err(Error::Unsupported("fragment data location binding".into()));
+        }
+
+        let shader = compile_shader(
+            &ctx.gl,
+            &[
+                (ShaderStage::Vertex, vertex_shader_source),
+                (ShaderStage::Fragment, fragment_shader_source),
+            ],
+            &[],
+            locations,
+        )?;
+        Ok(Self::from_linked(ctx, shader))
+    }
+
+    /// Create a new program with only a vertex stage, for depth-only passes such as a depth pre-pass or shadow maps.
+    ///
+    /// OpenGL ES requires a fragment stage, so an empty fragment shader using the vertex shader's `#version` is linked
+    /// there. The same fallback is used on drivers that fail to link a program without a fragment stage.
+    pub fn vertex_only(ctx: &ManagedContext, vertex_shader_source: &str) -> Result<Self, Error> {
+        let embedded = ctx.driver_info().is_embedded;
+        if !embedded {
+            match compile_shader(
+                &ctx.gl,
+                &[(ShaderStage::Vertex, vertex_shader_source)],
+                &[],
+                &[],
+            ) {
+                Ok(shader) => return Ok(Self::from_linked(ctx, shader)),
+                Err(Error::ShaderLink(_)) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        let version = version_directive(vertex_shader_source).unwrap_or(if embedded {
+            "#version 300 es"
+        } else {
+            "#version 330 core"
+        });
+        let fragment_shader_source = format!("{}\nvoid main() {{}}\n", version);
+        let shader = compile_shader(
+            &ctx.gl,
+            &[
+                (ShaderStage::Vertex, vertex_shader_source),
+                (ShaderStage::Fragment, &fragment_shader_source),
+            ],
+            &[],
+            &[],
+        )?;
+        Ok(Self::from_linked(ctx, shader))
+    }
+
+    pub(crate) fn from_linked(ctx: &ManagedContext, program: NativeProgram) -> Self {
         Self {
-            program: shader,
+            program,
+            samplers: unsafe { assign_sampler_units(&ctx.gl, program) },
+            block_bindings: Mutex::new(HashMap::new()),
+            #[cfg(feature = "uniform-cache")]
+            uniform_cache: Mutex::new(UniformCache::default()),
             gl: ctx.gl.clone(),
         }
     }
+
+    /// Forget the cached uniform values, e.g. after setting uniforms of this program through raw GL calls.
+    ///
+    /// With the `uniform-cache` feature, setting a uniform to the value it already has skips the driver call.
+    pub fn invalidate_uniform_cache(&self) {
+        #[cfg(feature = "uniform-cache")]
+        self.uniform_cache.lock().unwrap().values.clear();
+    }
+
+    /// The location the linker assigned to the active vertex attribute `name`.
+    pub fn attribute_location(&self, name: &str) -> Option<u32> {
+        unsafe { self.gl.get_attrib_location(self.program, name) }
+    }
+}
+
+/// The uniform values last set to a program, see [`ShaderProgram::invalidate_uniform_cache`].
+#[cfg(feature = "uniform-cache")]
+#[derive(Debug, Default)]
+pub(crate) struct UniformCache {
+    /// The [`ShareGroup`] generation the values were set in; older values are stale.
+    pub(crate) generation: u64,
+    pub(crate) values: HashMap<String, u64>,
 }
 
 impl PartialEq for ShaderProgram {
@@ -55,38 +224,102 @@ impl Drop for ShaderProgram {
     }
 }
 
+/// The `#version` directive of a GLSL source, if any.
+fn version_directive(source: &str) -> Option<&str> {
+    source
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with("#version"))
+}
+
+/// Insert a `#define` for each of `defines` after the `#version` directive of `source`, or at its start if it has
+/// none. A define of the form `NAME=VALUE` is defined to `VALUE`.
+pub(crate) fn inject_defines(source: &str, defines: &[&str]) -> String {
+    let mut directives = String::new();
+    for define in defines {
+        match define.split_once('=') {
+            Some((name, value)) => directives += &format!("#define {} {}\n", name, value),
+            None => directives += &format!("#define {}\n", define),
+        }
+    }
+
+    let mut out = String::with_capacity(source.len() + directives.len());
+    let mut lines = source.split_inclusive('\n');
+    if version_directive(source).is_some() {
+        for line in lines.by_ref() {
+            out += line;
+            if line.trim().starts_with("#version") {
+                if !line.ends_with('\n') {
+                    out.push('\n');
+                }
+                break;
+            }
+        }
+    }
+    out += &directives;
+    out.extend(lines);
+    out
+}
+
 fn compile_shader(
     gl: &glow::Context,
-    vertex_shader_source: &str,
-    fragment_shader_source: &str,
-) -> NativeProgram {
-    unsafe {
-        let program = gl.create_program().expect("Cannot create program"); // compile and link shader program
+    shader_sources: &[(ShaderStage, &str)],
+    feedback_varyings: &[&str],
+    frag_data_locations: &[(&str, u32)],
+) -> Result<NativeProgram, Error> {
+    compile_shader_with(gl, shader_sources, |program| unsafe {
+        if !feedback_varyings.is_empty() {
+            gl.transform_feedback_varyings(program, feedback_varyings, INTERLEAVED_ATTRIBS);
+        }
+        for (name, color_number) in frag_data_locations {
+            gl.bind_frag_data_location(program, *color_number, name);
+        }
+    })
+}
 
-        let shader_sources = [
-            (glow::VERTEX_SHADER, vertex_shader_source),
-            (glow::FRAGMENT_SHADER, fragment_shader_source),
-        ];
+/// Compile & link a program, calling `before_link` once the shaders are attached, e.g. to bind output locations.
+pub(crate) fn compile_shader_with(
+    gl: &glow::Context,
+    shader_sources: &[(ShaderStage, &str)],
+    before_link: impl FnOnce(NativeProgram),
+) -> Result<NativeProgram, Error> {
+    unsafe {
+        let program = gl.create_program().map_err(Error::ShaderLink)?; // compile and link shader program
 
         let mut shaders = Vec::with_capacity(shader_sources.len());
 
-        for (shader_type, shader_source) in shader_sources.iter() {
-            let shader = gl
-                .create_shader(*shader_type)
-                .expect("Cannot create shader");
+        let delete_all = |shaders: &[NativeShader]| {
+            for shader in shaders {
+                gl.delete_shader(*shader);
+            }
+            gl.delete_program(program);
+        };
+
+        for (stage, shader_source) in shader_sources.iter() {
+            let shader = match gl.create_shader(*stage as u32) {
+                Ok(shader) => shader,
+                Err(log) => {
+                    delete_all(&shaders);
+                    return Err(Error::ShaderCompile { stage: *stage, log });
+                }
+            };
+            shaders.push(shader);
             gl.shader_source(shader, shader_source);
             gl.compile_shader(shader);
             if !gl.get_shader_compile_status(shader) {
-                // TODO: use Result instead of panicking
-                std::panic::panic_any(gl.get_shader_info_log(shader));
+                let log = gl.get_shader_info_log(shader);
+                delete_all(&shaders);
+                return Err(Error::ShaderCompile { stage: *stage, log });
             }
             gl.attach_shader(program, shader);
-            shaders.push(shader);
         }
 
+        before_link(program);
         gl.link_program(program);
         if !gl.get_program_link_status(program) {
-            std::panic::panic_any(gl.get_program_info_log(program));
+            let log = gl.get_program_info_log(program);
+            delete_all(&shaders);
+            return Err(Error::ShaderLink(log));
         }
 
         for shader in shaders {
@@ -94,6 +327,6 @@ fn compile_shader(
             gl.delete_shader(shader);
         }
 
-        program
+        Ok(program)
     }
 }
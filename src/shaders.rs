@@ -0,0 +1,422 @@
+//! Built-in shaders for common needs.
+//!
+//! Sources are written without a `#version` line; [`shim`] prepends the right header for the context, so the same
+//! source works on desktop GL 3.3+ and OpenGL ES 3.0+.
+//!
+//! 2D vertex shaders read a `vec2` position at location 0 and, where used, a `vec2` texture coordinate at location 1,
+//! and transform positions by the `mat4 u_transform` uniform. Set `vec4 u_color` uniforms to
+//! [`ManagedContext::framebuffer_color`], or [`ManagedContext::encode_color`] to override the framebuffer's encoding,
+//! so colors match on sRGB and non-sRGB targets.
+
+use super::*;
+
+/// Vertex shader for [`FLAT_COLOR_FS`].
+pub const FLAT_COLOR_VS: &str = r#"
+layout(location = 0) in vec2 a_position;
+
+uniform mat4 u_transform;
+
+void main() {
+    gl_Position = u_transform * vec4(a_position, 0.0, 1.0);
+}
+"#;
+
+/// Fill with the `vec4 u_color` uniform.
+pub const FLAT_COLOR_FS: &str = r#"
+uniform vec4 u_color;
+
+out vec4 o_color;
+
+void main() {
+    o_color = u_color;
+}
+"#;
+
+/// Vertex shader passing the texture coordinate through as `v_uv`.
+pub const TEXTURED_VS: &str = r#"
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in vec2 a_uv;
+
+uniform mat4 u_transform;
+
+out vec2 v_uv;
+
+void main() {
+    v_uv = a_uv;
+    gl_Position = u_transform * vec4(a_position, 0.0, 1.0);
+}
+"#;
+
+/// Sample `sampler2D u_texture`, tinted by the `vec4 u_color` uniform.
+pub const TEXTURED_FS: &str = r#"
+uniform sampler2D u_texture;
+uniform vec4 u_color;
+
+in vec2 v_uv;
+
+out vec4 o_color;
+
+void main() {
+    o_color = texture(u_texture, v_uv) * u_color;
+}
+"#;
+
+/// Antialiased rounded rectangle filling the quad, for use with [`TEXTURED_VS`].
+///
+/// `vec2 u_size` is the rectangle size in pixels, `float u_radius` the corner radius and `vec4 u_color` the fill color.
+/// A circle is a square with a radius of half its size.
+pub const SDF_ROUNDED_RECT_FS: &str = r#"
+uniform vec2 u_size;
+uniform float u_radius;
+uniform vec4 u_color;
+
+in vec2 v_uv;
+
+out vec4 o_color;
+
+float rounded_rect(vec2 p, vec2 half_size, float radius) {
+    vec2 q = abs(p) - half_size + radius;
+    return min(max(q.x, q.y), 0.0) + length(max(q, 0.0)) - radius;
+}
+
+void main() {
+    float d = rounded_rect((v_uv - 0.5) * u_size, u_size * 0.5, u_radius);
+    float coverage = clamp(0.5 - d / max(fwidth(d), 1e-4), 0.0, 1.0);
+    o_color = vec4(u_color.rgb, u_color.a * coverage);
+}
+"#;
+
+/// Signed distance field glyphs and icons, for use with [`TEXTURED_VS`] and an [`SdfAtlas`].
+///
+/// Takes the median of the RGB channels, so both multi-channel and single-channel fields work. `float u_distance_range`
+/// is [`SdfAtlas::distance_range`]; edges are antialiased over one screen pixel using screen-space derivatives.
+#[cfg(feature = "sdf")]
+pub const SDF_FS: &str = r#"
+uniform sampler2D u_texture;
+uniform float u_distance_range;
+uniform vec4 u_color;
+
+in vec2 v_uv;
+
+out vec4 o_color;
+
+float median(float r, float g, float b) {
+    return max(min(r, g), min(max(r, g), b));
+}
+
+float screen_px_range() {
+    vec2 unit_range = vec2(u_distance_range) / vec2(textureSize(u_texture, 0));
+    vec2 screen_tex_size = vec2(1.0) / fwidth(v_uv);
+    return max(0.5 * dot(unit_range, screen_tex_size), 1.0);
+}
+
+void main() {
+    vec3 msd = texture(u_texture, v_uv).rgb;
+    float sd = median(msd.r, msd.g, msd.b);
+    float coverage = clamp(screen_px_range() * (sd - 0.5) + 0.5, 0.0, 1.0);
+    o_color = vec4(u_color.rgb, u_color.a * coverage);
+}
+"#;
+
+/// Vertex shader for fullscreen passes. Positions are in clip space, `v_uv` is derived from them.
+pub const FULLSCREEN_VS: &str = r#"
+layout(location = 0) in vec2 a_position;
+
+out vec2 v_uv;
+
+void main() {
+    v_uv = a_position * 0.5 + 0.5;
+    gl_Position = vec4(a_position, 0.0, 1.0);
+}
+"#;
+
+/// Clip space positions of a single triangle covering the viewport, for use with [`FULLSCREEN_VS`].
+pub const FULLSCREEN_TRIANGLE: [f32; 6] = [-1.0, -1.0, 3.0, -1.0, -1.0, 3.0];
+
+/// Copy `sampler2D u_texture`, for use with [`FULLSCREEN_VS`].
+pub const BLIT_FS: &str = r#"
+uniform sampler2D u_texture;
+
+in vec2 v_uv;
+
+out vec4 o_color;
+
+void main() {
+    o_color = texture(u_texture, v_uv);
+}
+"#;
+
+/// One direction of a separable 9-tap gaussian blur of `sampler2D u_texture`, for use with [`FULLSCREEN_VS`].
+///
+/// `vec2 u_direction` is the step between taps in texture coordinates, e.g. `(1 / width, 0)` for the horizontal pass.
+pub const GAUSSIAN_BLUR_FS: &str = r#"
+uniform sampler2D u_texture;
+uniform vec2 u_direction;
+
+in vec2 v_uv;
+
+out vec4 o_color;
+
+void main() {
+    float weights[5] = float[](0.2270270270, 0.1945945946, 0.1216216216, 0.0540540541, 0.0162162162);
+    vec4 sum = texture(u_texture, v_uv) * weights[0];
+    for (int i = 1; i < 5; i++) {
+        vec2 offset = u_direction * float(i);
+        sum += texture(u_texture, v_uv + offset) * weights[i];
+        sum += texture(u_texture, v_uv - offset) * weights[i];
+    }
+    o_color = sum;
+}
+"#;
+
+/// GLSL function emulating [`TextureWrap::ClampToBorder`] for contexts without [`Features::CLAMP_TO_BORDER`], to
+/// paste into fragment shaders sampling textures created with [`SamplerDescriptor::emulate_border`].
+///
+/// `sample_border(tex, uv, border)` samples the texture clamped to its edge inside `[0, 1]` and returns `border`
+/// outside it, so it gives the same result whether or not the driver clamps to the border. Unlike the other sources
+/// here, it also compiles as GLSL ES 1.00, where `texture2D` replaces `texture`.
+pub const SAMPLE_BORDER: &str = r#"
+#if __VERSION__ < 130
+#define sample_border_texture texture2D
+#else
+#define sample_border_texture texture
+#endif
+
+vec4 sample_border(sampler2D tex, vec2 uv, vec4 border) {
+    vec2 inside = step(vec2(0.0), uv) * step(uv, vec2(1.0));
+    return mix(border, sample_border_texture(tex, uv), inside.x * inside.y);
+}
+"#;
+
+/// The `#version` header (and default precision on ES) for the context.
+pub fn version_header(ctx: &ManagedContext) -> Result<&'static str, Error> {
+    let info = ctx.driver_info();
+    if info.is_embedded && info.is_at_least(3, 0) {
+        Ok("#version 300 es\nprecision highp float;\n")
+    } else if !info.is_embedded && info.is_at_least(3, 3) {
+        Ok("#version 330 core\n")
+    } else {
+        Err(Error::Unsupported(
+            "built-in shaders need GL 3.3 or GLES 3.0".into(),
+        ))
+    }
+}
+
+/// Prepend the version header for the context to a shader source.
+pub fn shim(ctx: &ManagedContext, source: &str) -> Result<String, Error> {
+    Ok(format!("{}{}", version_header(ctx)?, source))
+}
+
+/// Compile a program from version-less sources, see [`shim`].
+pub fn program(ctx: &ManagedContext, vs: &str, fs: &str) -> Result<ShaderProgram, Error> {
+    ShaderProgram::new(ctx, &shim(ctx, vs)?, &shim(ctx, fs)?)
+}
+
+impl RenderPipeline {
+    pub(crate) fn built_in(
+        ctx: &ManagedContext,
+        vs: &str,
+        fs: &str,
+        layout: VertexLayout,
+    ) -> Result<Self, Error> {
+        Ok(Self::new(program(ctx, vs, fs)?).with_vertex_layout(&layout))
+    }
+
+    pub(crate) fn alpha_blended(self) -> Self {
+        self.with_blend(true)
+            .with_blend_func(BlendFactor::SourceAlpha, BlendFactor::OneMinusSourceAlpha)
+    }
+
+    /// A pipeline filling 2D geometry with a single color. See [`FLAT_COLOR_FS`].
+    pub fn flat_color_2d(ctx: &ManagedContext) -> Result<Self, Error> {
+        Self::built_in(ctx, FLAT_COLOR_VS, FLAT_COLOR_FS, position_layout())
+    }
+
+    /// An alpha blended pipeline drawing textured 2D geometry. See [`TEXTURED_FS`].
+    pub fn textured_2d(ctx: &ManagedContext) -> Result<Self, Error> {
+        Self::built_in(ctx, TEXTURED_VS, TEXTURED_FS, textured_layout()).map(Self::alpha_blended)
+    }
+
+    /// An alpha blended pipeline drawing antialiased rounded rectangles and circles. See [`SDF_ROUNDED_RECT_FS`].
+    pub fn sdf_rounded_rect_2d(ctx: &ManagedContext) -> Result<Self, Error> {
+        Self::built_in(ctx, TEXTURED_VS, SDF_ROUNDED_RECT_FS, textured_layout())
+            .map(Self::alpha_blended)
+    }
+
+    /// An alpha blended pipeline drawing glyphs and icons from an [`SdfAtlas`]. See [`SDF_FS`].
+    #[cfg(feature = "sdf")]
+    pub fn sdf_2d(ctx: &ManagedContext) -> Result<Self, Error> {
+        Self::built_in(ctx, TEXTURED_VS, SDF_FS, textured_layout()).map(Self::alpha_blended)
+    }
+
+    /// A pipeline copying a texture to the whole viewport. See [`FULLSCREEN_VS`].
+    pub fn blit(ctx: &ManagedContext) -> Result<Self, Error> {
+        Self::built_in(ctx, FULLSCREEN_VS, BLIT_FS, position_layout())
+    }
+
+    /// A pipeline applying one direction of a gaussian blur to the whole viewport. See [`GAUSSIAN_BLUR_FS`].
+    pub fn gaussian_blur(ctx: &ManagedContext) -> Result<Self, Error> {
+        Self::built_in(ctx, FULLSCREEN_VS, GAUSSIAN_BLUR_FS, position_layout())
+    }
+}
+
+fn position_layout() -> VertexLayout {
+    VertexLayout::new(0).with_attribute(2, DataType::Float, false)
+}
+
+fn textured_layout() -> VertexLayout {
+    position_layout().with_attribute(2, DataType::Float, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every vertex & fragment shader pair a pipeline constructor links, with the vertex layout it uses.
+    fn programs() -> Vec<(&'static str, &'static str, VertexLayout)> {
+        vec![
+            (FLAT_COLOR_VS, FLAT_COLOR_FS, position_layout()),
+            (TEXTURED_VS, TEXTURED_FS, textured_layout()),
+            (TEXTURED_VS, SDF_ROUNDED_RECT_FS, textured_layout()),
+            #[cfg(feature = "sdf")]
+            (TEXTURED_VS, SDF_FS, textured_layout()),
+            (FULLSCREEN_VS, BLIT_FS, position_layout()),
+            (FULLSCREEN_VS, GAUSSIAN_BLUR_FS, position_layout()),
+        ]
+    }
+
+    /// The `layout` location, type & name of every global declared with `qualifier`, e.g. `in` or `out`.
+    fn declarations(source: &str, qualifier: &str) -> Vec<(Option<u32>, String, String)> {
+        source
+            .lines()
+            .filter_map(|line| {
+                let (location, line) = match line.strip_prefix("layout(location = ") {
+                    Some(rest) => {
+                        let (location, rest) = rest.split_once(") ")?;
+                        (Some(location.parse().unwrap()), rest)
+                    }
+                    None => (None, line),
+                };
+                let declaration = line.strip_prefix(qualifier)?.strip_prefix(' ')?;
+                let (ty, name) = declaration.strip_suffix(';')?.split_once(' ')?;
+                Some((location, ty.to_string(), name.to_string()))
+            })
+            .collect()
+    }
+
+    /// Keep the lines of the active `#if __VERSION__ < N` branches and expand `#define`s, like the GLSL preprocessor
+    /// with `__VERSION__` set to `version`.
+    fn preprocess(source: &str, version: u32) -> String {
+        let mut active = vec![true];
+        let mut defines = vec![];
+        let mut out = String::new();
+        for line in source.lines() {
+            let directive = line.trim();
+            if let Some(bound) = directive.strip_prefix("#if __VERSION__ < ") {
+                active.push(version < bound.parse::<u32>().unwrap());
+            } else if directive == "#else" {
+                let branch = active.pop().unwrap();
+                active.push(!branch);
+            } else if directive == "#endif" {
+                active.pop();
+            } else if !active.iter().all(|&active| active) {
+                continue;
+            } else if let Some(define) = directive.strip_prefix("#define ") {
+                let (name, value) = define.split_once(' ').unwrap();
+                defines.push((name.to_string(), value.to_string()));
+            } else {
+                let line = defines
+                    .iter()
+                    .fold(line.to_string(), |line, (name, value)| {
+                        line.replace(name, value)
+                    });
+                out.push_str(&line);
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn sources_are_complete_and_version_less() {
+        for (vs, fs, _) in programs() {
+            for source in [vs, fs] {
+                assert!(!source.contains("#version"), "{}", source);
+                assert!(source.contains("void main() {"), "{}", source);
+                assert_eq!(source.matches('{').count(), source.matches('}').count());
+                assert_eq!(source.matches('(').count(), source.matches(')').count());
+            }
+        }
+    }
+
+    #[test]
+    fn fragment_inputs_match_vertex_outputs() {
+        for (vs, fs, _) in programs() {
+            let outputs = declarations(vs, "out");
+            for input in declarations(fs, "in") {
+                assert!(
+                    outputs.contains(&input),
+                    "{:?} is not written by\n{}",
+                    input,
+                    vs
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn vertex_inputs_match_the_layouts() {
+        for (vs, _, layout) in programs() {
+            let inputs = declarations(vs, "in");
+            assert_eq!(inputs.len(), layout.attributes().len(), "{}", vs);
+            for (index, ((location, ty, _), attribute)) in
+                inputs.iter().zip(layout.attributes()).enumerate()
+            {
+                assert_eq!(*location, Some(index as u32));
+                assert_eq!(*ty, format!("vec{}", attribute.size));
+                assert_eq!(attribute.ty, DataType::Float);
+            }
+        }
+    }
+
+    #[test]
+    fn fullscreen_triangle_covers_the_viewport() {
+        let [ax, ay, bx, by, cx, cy] = FULLSCREEN_TRIANGLE;
+        let edge = |(x0, y0): (f32, f32), (x1, y1): (f32, f32), (x, y): (f32, f32)| {
+            (x1 - x0) * (y - y0) - (y1 - y0) * (x - x0)
+        };
+        for corner in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+            assert!(edge((ax, ay), (bx, by), corner) >= 0.0);
+            assert!(edge((bx, by), (cx, cy), corner) >= 0.0);
+            assert!(edge((cx, cy), (ax, ay), corner) >= 0.0);
+        }
+    }
+
+    #[test]
+    fn gaussian_weights_sum_to_one() {
+        let weights = GAUSSIAN_BLUR_FS
+            .split_once("float[](")
+            .and_then(|(_, rest)| rest.split_once(')'))
+            .unwrap()
+            .0;
+        let weights: Vec<f32> = weights
+            .split(',')
+            .map(|weight| weight.trim().parse().unwrap())
+            .collect();
+        assert_eq!(weights.len(), 5);
+        let sum = weights[0] + 2.0 * weights[1..].iter().sum::<f32>();
+        assert!((sum - 1.0).abs() < 1e-6, "{}", sum);
+    }
+
+    #[test]
+    fn sample_border_samples_with_the_version_specific_function() {
+        let es_1 = preprocess(SAMPLE_BORDER, 100);
+        assert!(es_1.contains("texture2D(tex, uv)"), "{}", es_1);
+        assert!(!es_1.contains("#"), "{}", es_1);
+        for version in [300, 330] {
+            let source = preprocess(SAMPLE_BORDER, version);
+            assert!(source.contains(" texture(tex, uv)"), "{}", source);
+            assert!(!source.contains("texture2D"), "{}", source);
+        }
+    }
+}
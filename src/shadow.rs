@@ -0,0 +1,84 @@
+use super::*;
+
+/// A pass of [`ManagedContext::render_depth_cubemap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeMapPass {
+    /// All faces are attached at once. The pipeline must use a geometry shader that routes
+    /// each primitive to its face by writing [`CubeFace::layer`] to `gl_Layer`.
+    Layered,
+    /// Only the given face is attached.
+    Face(CubeFace),
+}
+
+impl ManagedContext {
+    /// Render depth into every face of a cube map, e.g. for omni-directional point light shadows.
+    ///
+    /// The scene is drawn in a single [`CubeMapPass::Layered`] pass when geometry shaders are supported,
+    /// otherwise `draw` is called once per face. The depth of every face is cleared beforehand, and the
+    /// previous framebuffer and viewport are restored afterwards.
+    pub fn render_depth_cubemap(
+        &mut self,
+        framebuffer: &Framebuffer,
+        cubemap: &TextureCube,
+        mut draw: impl FnMut(&mut ManagedContext, CubeMapPass),
+    ) {
+        if !self.supports_geometry_shaders() {
+            self.render_depth_cubemap_per_face(framebuffer, cubemap, |ctx, face| {
+                draw(ctx, CubeMapPass::Face(face))
+            });
+            return;
+        }
+
+        let (previous, viewport) = (self.framebuffer_binding(), self.viewport_rect());
+        framebuffer.attach_layered(self, Attachment::Depth, cubemap);
+        self.begin_depth_pass(framebuffer, Rect::new(0, 0, cubemap.size, cubemap.size));
+        self.clear(ClearFlags::DEPTH);
+
+        draw(self, CubeMapPass::Layered);
+
+        self.restore_framebuffer(previous);
+        self.set_viewport(viewport.x, viewport.y, viewport.width, viewport.height);
+    }
+
+    /// Render depth into every face of a cube map, calling `draw` once per face.
+    ///
+    /// See [`CubeFace::view_matrix`] for building the per-face view matrices.
+    pub fn render_depth_cubemap_per_face(
+        &mut self,
+        framebuffer: &Framebuffer,
+        cubemap: &TextureCube,
+        mut draw: impl FnMut(&mut ManagedContext, CubeFace),
+    ) {
+        let (previous, viewport) = (self.framebuffer_binding(), self.viewport_rect());
+        for face in CubeFace::ALL {
+            self.begin_depth_pass(framebuffer, Rect::new(0, 0, cubemap.size, cubemap.size));
+            unsafe {
+                self.gl.framebuffer_texture_2d(
+                    FRAMEBUFFER,
+                    DEPTH_ATTACHMENT,
+                    face.to_gl(),
+                    Some(cubemap.handle.texture),
+                    0,
+                );
+            }
+            self.clear(ClearFlags::DEPTH);
+            draw(self, face);
+        }
+
+        self.restore_framebuffer(previous);
+        self.set_viewport(viewport.x, viewport.y, viewport.width, viewport.height);
+    }
+
+    /// Bind `framebuffer` for rendering depth only into `rect`: no color buffers are drawn or read, depth writes are
+    /// enabled and the scissor test is disabled, so clearing depth isn't masked or clipped by the last pipeline.
+    pub(crate) fn begin_depth_pass(&self, framebuffer: &Framebuffer, rect: Rect) {
+        self.bind_framebuffer(Some(framebuffer));
+        unsafe {
+            self.gl.draw_buffers(&[NONE]);
+            self.gl.read_buffer(NONE);
+            self.gl.depth_mask(true);
+            self.gl.disable(SCISSOR_TEST);
+        }
+        self.set_viewport(rect.x, rect.y, rect.width, rect.height);
+    }
+}
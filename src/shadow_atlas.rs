@@ -0,0 +1,152 @@
+use super::*;
+
+use crate::util::RectPacker;
+
+/// A tile of a [`ShadowAtlas`], holding the shadow map of one light.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowTile {
+    /// The tile's texels in the atlas, used as viewport & scissor rect when rendering into it.
+    pub rect: Rect,
+    /// Scale & offset mapping the tile's `[0, 1]` shadow map coordinates into the atlas: `uv * xy + zw`.
+    pub uv_transform: [f32; 4],
+}
+
+impl ShadowTile {
+    /// Map a column-major light view-projection into the atlas: the result transforms positions to atlas coordinates
+    /// in x & y and `[0, 1]` depth in z, ready for shadow lookups after the perspective divide.
+    pub fn atlas_matrix(&self, view_projection: &[f32; 16]) -> [f32; 16] {
+        let [scale_x, scale_y, offset_x, offset_y] = self.uv_transform;
+        // clip space [-1, 1] to the tile's part of [0, 1]
+        let scale = [0.5 * scale_x, 0.5 * scale_y, 0.5];
+        let offset = [0.5 * scale_x + offset_x, 0.5 * scale_y + offset_y, 0.5];
+        let mut out = *view_projection;
+        for (src, dst) in view_projection.chunks_exact(4).zip(out.chunks_exact_mut(4)) {
+            for row in 0..3 {
+                dst[row] = scale[row] * src[row] + offset[row] * src[3];
+            }
+        }
+        out
+    }
+}
+
+/// Slices one large depth texture into per-light shadow map tiles, for scenes with many shadowed lights that can't
+/// afford a framebuffer and texture per light.
+///
+/// Tiles are packed with a [`RectPacker`]; allocate them again after [`ShadowAtlas::clear`] whenever the set of lights
+/// or their resolutions change. Render with [`ShadowAtlas::render`] and transform shadow lookups with
+/// [`ShadowTile::atlas_matrix`].
+#[derive(Debug, Clone)]
+pub struct ShadowAtlas {
+    packer: RectPacker,
+    size: i32,
+    padding: i32,
+    tiles: Vec<ShadowTile>,
+}
+
+impl ShadowAtlas {
+    /// Create an atlas for a depth texture of `size` x `size` texels.
+    pub fn new(size: i32) -> Self {
+        Self {
+            packer: RectPacker::new(size, size),
+            size,
+            padding: 0,
+            tiles: Vec::new(),
+        }
+    }
+
+    /// Leave `padding` texels between tiles, so filtered shadow lookups near a tile's edge don't sample its
+    /// neighbors.
+    pub fn with_padding(self, padding: i32) -> Self {
+        Self { padding, ..self }
+    }
+
+    /// Allocate a `resolution` x `resolution` tile, returning its index, or `None` if the atlas is full.
+    pub fn allocate(&mut self, resolution: i32) -> Option<usize> {
+        let padded = self
+            .packer
+            .allocate(resolution + 2 * self.padding, resolution + 2 * self.padding)?;
+        let rect = Rect::new(
+            padded.x + self.padding,
+            padded.y + self.padding,
+            resolution,
+            resolution,
+        );
+        let size = self.size as f32;
+        self.tiles.push(ShadowTile {
+            rect,
+            uv_transform: [
+                rect.width as f32 / size,
+                rect.height as f32 / size,
+                rect.x as f32 / size,
+                rect.y as f32 / size,
+            ],
+        });
+        Some(self.tiles.len() - 1)
+    }
+
+    /// Free every tile.
+    pub fn clear(&mut self) {
+        self.packer.clear();
+        self.tiles.clear();
+    }
+
+    /// The allocated tiles, in allocation order.
+    pub fn tiles(&self) -> &[ShadowTile] {
+        &self.tiles
+    }
+
+    /// The size of the atlas, in texels.
+    pub fn size(&self) -> i32 {
+        self.size
+    }
+
+    /// Render depth into every tile of `depth`, calling `draw` with the tile index.
+    ///
+    /// The whole atlas depth is cleared beforehand. The viewport & scissor rect are set to each tile, so pipelines with
+    /// scissor testing enabled can't draw outside it. The previous framebuffer, viewport and scissor rect are restored
+    /// afterwards.
+    ///
+    /// ## Panics
+    /// `depth` must be as large as the atlas.
+    pub fn render(
+        &self,
+        ctx: &mut ManagedContext,
+        framebuffer: &Framebuffer,
+        depth: &Texture2D,
+        mut draw: impl FnMut(&mut ManagedContext, usize, &ShadowTile),
+    ) {
+        assert_eq!(
+            depth.size(),
+            (self.size, self.size),
+            "depth texture doesn't match the atlas size!"
+        );
+        span!("render_shadow_atlas", tiles = self.tiles.len());
+        let previous = ctx.framebuffer_binding();
+        let (viewport, scissor) = (ctx.viewport_rect(), ctx.scissor_rect());
+        framebuffer.attach_texture_2d(ctx, Attachment::Depth, depth);
+        // begin_depth_pass disables the scissor test, so the old scissor box doesn't clip the clear of the whole atlas
+        ctx.begin_depth_pass(framebuffer, Rect::new(0, 0, self.size, self.size));
+        ctx.clear(ClearFlags::DEPTH);
+        for (index, tile) in self.tiles.iter().enumerate() {
+            ctx.begin_depth_pass(framebuffer, tile.rect);
+            ctx.set_scissor_rect(tile.rect);
+            draw(ctx, index, tile);
+        }
+
+        ctx.restore_framebuffer(previous);
+        ctx.set_viewport(viewport.x, viewport.y, viewport.width, viewport.height);
+        ctx.set_scissor_rect(scissor);
+    }
+}
+
+impl ManagedContext {
+    fn scissor_rect(&self) -> Rect {
+        let mut scissor = [0; 4];
+        unsafe { self.gl.get_parameter_i32_slice(SCISSOR_BOX, &mut scissor) };
+        Rect::new(scissor[0], scissor[1], scissor[2], scissor[3])
+    }
+
+    fn set_scissor_rect(&self, rect: Rect) {
+        unsafe { self.gl.scissor(rect.x, rect.y, rect.width, rect.height) };
+    }
+}
@@ -0,0 +1,67 @@
+use super::*;
+
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+/// A group of [`ManagedContext`]s whose GL contexts share objects, e.g. one per window of a tool with multiple
+/// viewports. See [`ManagedContext::new_shared`].
+///
+/// Shaders, buffers, textures, samplers and renderbuffers created on one context of the group can be used on every
+/// other. Container objects are not shareable: [`Framebuffer`]s and [`VertexArrayObject`]s must be created per
+/// context. Every context also keeps its own cache of vertex arrays, and its own texture unit and uniform buffer
+/// bindings. Shared objects are deleted through the context that created them, so it must outlive them.
+#[derive(Debug, Clone, Default)]
+pub struct ShareGroup {
+    members: Rc<RefCell<Vec<Weak<RefCell<BindingTables>>>>>,
+    /// Bumped whenever uniforms may have been set behind the uniform caches' back, as programs are shared.
+    pub(crate) uniform_cache_generation: Rc<Cell<u64>>,
+}
+
+impl ShareGroup {
+    /// Create an empty group. Contexts join it through [`ManagedContext::new_shared`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of live contexts in the group.
+    pub fn len(&self) -> usize {
+        self.members
+            .borrow()
+            .iter()
+            .filter(|units| units.strong_count() > 0)
+            .count()
+    }
+
+    /// Returns true if no live context belongs to the group.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn join(&self, units: &SharedBindingTables) {
+        let mut members = self.members.borrow_mut();
+        members.retain(|units| units.strong_count() > 0);
+        members.push(Rc::downgrade(units));
+    }
+
+    /// Forget `texture` in the texture unit bindings of every context, because it was deleted and its name may be
+    /// reused.
+    pub(crate) fn forget_texture(&self, texture: NativeTexture) {
+        for units in self.members.borrow().iter().filter_map(Weak::upgrade) {
+            units.borrow_mut().forget(texture);
+        }
+    }
+
+    /// Forget the cached uniform values of every program used by the group.
+    pub(crate) fn invalidate_uniform_caches(&self) {
+        self.uniform_cache_generation
+            .set(self.uniform_cache_generation.get() + 1);
+    }
+
+    /// Forget `buffer` in the uniform buffer bindings of every context, because it was deleted and its name may be
+    /// reused.
+    pub(crate) fn forget_buffer(&self, buffer: NativeBuffer) {
+        for units in self.members.borrow().iter().filter_map(Weak::upgrade) {
+            units.borrow_mut().forget_buffer(buffer);
+        }
+    }
+}
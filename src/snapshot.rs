@@ -0,0 +1,237 @@
+use super::*;
+
+use std::fmt;
+
+/// State tracked by rapax whose value in GL differs from the value rapax last set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateDivergence {
+    /// What the state is, e.g. `texture unit 2`.
+    pub state: String,
+    /// The value rapax last set.
+    pub expected: String,
+    /// The value read back from GL.
+    pub actual: String,
+}
+
+impl StateDivergence {
+    pub(crate) fn new(
+        state: impl Into<String>,
+        expected: impl fmt::Debug,
+        actual: impl fmt::Debug,
+    ) -> Self {
+        Self {
+            state: state.into(),
+            expected: format!("{:?}", expected),
+            actual: format!("{:?}", actual),
+        }
+    }
+}
+
+impl fmt::Display for StateDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: rapax expects {}, GL has {}",
+            self.state, self.expected, self.actual
+        )
+    }
+}
+
+/// GL state read back by [`ManagedContext::dump_state`], to attach to bug reports about state leaks.
+///
+/// Object names are raw GL names, 0 for none. `Display` prints a report that highlights divergences.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateSnapshot {
+    pub driver: DriverInfo,
+    pub viewport: Rect,
+    /// The scissor rectangle, or `None` if the scissor test is disabled.
+    pub scissor: Option<Rect>,
+    /// The RGB source & destination blend factors, or `None` if blending is disabled.
+    pub blend: Option<(u32, u32)>,
+    /// The depth function, or `None` if the depth test is disabled.
+    pub depth_test: Option<u32>,
+    pub depth_write: bool,
+    pub color_write: [bool; 4],
+    pub stencil_test: bool,
+    pub cull_face: bool,
+    pub program: u32,
+    pub vertex_array: u32,
+    pub draw_framebuffer: u32,
+    pub read_framebuffer: u32,
+    pub active_texture_unit: u32,
+    pub clear_color: [f32; 4],
+    pub clear_depth: f32,
+    pub clear_stencil: i32,
+    /// Tracked state that differs from what rapax expects, usually because raw GL calls were made outside
+    /// [`ManagedContext::raw_gl`].
+    pub divergences: Vec<StateDivergence>,
+}
+
+impl fmt::Display for StateSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rect = |rect: Rect| format!("{} {} {}x{}", rect.x, rect.y, rect.width, rect.height);
+        writeln!(
+            f,
+            "driver: {} / {} / {}",
+            self.driver.vendor, self.driver.renderer, self.driver.version
+        )?;
+        writeln!(f, "viewport: {}", rect(self.viewport))?;
+        match self.scissor {
+            Some(scissor) => writeln!(f, "scissor: {}", rect(scissor))?,
+            None => writeln!(f, "scissor: off")?,
+        }
+        match self.blend {
+            Some((src, dst)) => writeln!(f, "blend: {:#06x} {:#06x}", src, dst)?,
+            None => writeln!(f, "blend: off")?,
+        }
+        match self.depth_test {
+            Some(func) => writeln!(f, "depth test: {:#06x}", func)?,
+            None => writeln!(f, "depth test: off")?,
+        }
+        writeln!(f, "depth write: {}", self.depth_write)?;
+        writeln!(f, "color write: {:?}", self.color_write)?;
+        writeln!(f, "stencil test: {}", self.stencil_test)?;
+        writeln!(f, "cull face: {}", self.cull_face)?;
+        writeln!(f, "program: {}", self.program)?;
+        writeln!(f, "vertex array: {}", self.vertex_array)?;
+        writeln!(
+            f,
+            "framebuffers: draw {}, read {}",
+            self.draw_framebuffer, self.read_framebuffer
+        )?;
+        writeln!(f, "active texture unit: {}", self.active_texture_unit)?;
+        writeln!(
+            f,
+            "clear: color {:?}, depth {}, stencil {}",
+            self.clear_color, self.clear_depth, self.clear_stencil
+        )?;
+        if self.divergences.is_empty() {
+            writeln!(f, "no divergences from tracked state")
+        } else {
+            writeln!(
+                f,
+                "{} divergences from tracked state:",
+                self.divergences.len()
+            )?;
+            for divergence in &self.divergences {
+                writeln!(f, "! {}", divergence)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+impl ManagedContext {
+    /// Read back the current GL state, next to the state rapax tracks, e.g. to attach to a bug report.
+    ///
+    /// This stalls the pipeline, so only call it while diagnosing problems.
+    pub fn dump_state(&self) -> StateSnapshot {
+        self.debug_assert_current();
+        let gl = &self.gl;
+        unsafe {
+            let rect = |parameter| {
+                let mut rect = [0; 4];
+                gl.get_parameter_i32_slice(parameter, &mut rect);
+                Rect::new(rect[0], rect[1], rect[2], rect[3])
+            };
+            let name = |parameter| gl.get_parameter_i32(parameter) as u32;
+            let mut color_write = [0; 4];
+            gl.get_parameter_i32_slice(COLOR_WRITEMASK, &mut color_write);
+            let mut clear_color = [0.0; 4];
+            gl.get_parameter_f32_slice(COLOR_CLEAR_VALUE, &mut clear_color);
+
+            StateSnapshot {
+                driver: self.driver_info().clone(),
+                viewport: rect(VIEWPORT),
+                scissor: gl.is_enabled(SCISSOR_TEST).then(|| rect(SCISSOR_BOX)),
+                blend: gl
+                    .is_enabled(BLEND)
+                    .then(|| (name(BLEND_SRC_RGB), name(BLEND_DST_RGB))),
+                depth_test: gl.is_enabled(DEPTH_TEST).then(|| name(DEPTH_FUNC)),
+                depth_write: gl.get_parameter_i32(DEPTH_WRITEMASK) != 0,
+                color_write: color_write.map(|write| write != 0),
+                stencil_test: gl.is_enabled(STENCIL_TEST),
+                cull_face: gl.is_enabled(CULL_FACE),
+                program: name(CURRENT_PROGRAM),
+                vertex_array: name(VERTEX_ARRAY_BINDING),
+                draw_framebuffer: name(DRAW_FRAMEBUFFER_BINDING),
+                read_framebuffer: name(READ_FRAMEBUFFER_BINDING),
+                active_texture_unit: name(ACTIVE_TEXTURE).wrapping_sub(TEXTURE0),
+                clear_color,
+                clear_depth: gl.get_parameter_f32(DEPTH_CLEAR_VALUE),
+                clear_stencil: gl.get_parameter_i32(STENCIL_CLEAR_VALUE),
+                divergences: self.state_divergences(),
+            }
+        }
+    }
+
+    /// Panic in debug builds if GL state tracked by rapax, e.g. texture bindings and clear values, was changed behind
+    /// its back, for example by raw GL calls outside [`ManagedContext::raw_gl`] or by external code sharing the
+    /// context.
+    ///
+    /// Reading GL state back stalls the pipeline; see [`ManagedContext::set_state_verification_interval`] to verify
+    /// periodically instead.
+    #[track_caller]
+    pub fn verify_state(&self) {
+        if cfg!(debug_assertions) {
+            let divergences = self.state_divergences();
+            assert!(
+                divergences.is_empty(),
+                "GL state was changed behind rapax's back; use ManagedContext::raw_gl for raw GL calls:\n{}",
+                divergences
+                    .iter()
+                    .map(|divergence| format!("! {}", divergence))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            );
+        }
+    }
+
+    /// Call [`ManagedContext::verify_state`] from [`ManagedContext::end_frame`] every `frames` frames, or never with
+    /// `None`, the default. Only has an effect in debug builds.
+    pub fn set_state_verification_interval(&mut self, frames: Option<u64>) {
+        self.state_verification = frames.filter(|frames| *frames > 0);
+    }
+
+    /// Compare the state rapax tracks, e.g. texture bindings and clear values, with the state read back from GL.
+    pub(crate) fn state_divergences(&self) -> Vec<StateDivergence> {
+        let mut divergences = vec![];
+        unsafe {
+            self.binding_divergences(&mut divergences);
+
+            let clear = self.clear_state.get();
+            if let Some(color) = clear.color {
+                let mut actual = [0.0; 4];
+                self.gl
+                    .get_parameter_f32_slice(COLOR_CLEAR_VALUE, &mut actual);
+                if actual != color {
+                    divergences.push(StateDivergence::new("clear color", color, actual));
+                }
+            }
+            if let Some(depth) = clear.depth {
+                let actual = self.gl.get_parameter_f32(DEPTH_CLEAR_VALUE);
+                if actual != depth {
+                    divergences.push(StateDivergence::new("clear depth", depth, actual));
+                }
+            }
+            if let Some(stencil) = clear.stencil {
+                let actual = self.gl.get_parameter_i32(STENCIL_CLEAR_VALUE);
+                if actual != stencil {
+                    divergences.push(StateDivergence::new("clear stencil", stencil, actual));
+                }
+            }
+
+            for (cap, enabled) in &self.raw_capabilities {
+                let actual = self.gl.is_enabled(cap.0);
+                if actual != *enabled {
+                    divergences.push(StateDivergence::new(
+                        format!("capability {:#06x}", cap.0),
+                        enabled,
+                        actual,
+                    ));
+                }
+            }
+        }
+        divergences
+    }
+}
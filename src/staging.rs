@@ -0,0 +1,88 @@
+use super::*;
+
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+/// Allocator for rapax's CPU-side staging buffers, e.g. pixel data converted for upload and rasterized glyphs.
+///
+/// Staging buffers only live until the upload they stage, so an allocator can hand out recycled buffers, e.g. from a
+/// pool reset every frame, instead of going through the global allocator. Set with
+/// [`ManagedContext::set_staging_allocator`].
+pub trait RapaxAlloc: Send + Sync {
+    /// An empty buffer with capacity for at least `capacity` bytes.
+    fn acquire(&self, capacity: usize) -> Vec<u8>;
+
+    /// Take back a buffer from [`RapaxAlloc::acquire`] once its data was uploaded.
+    fn release(&self, buffer: Vec<u8>);
+}
+
+/// The default [`RapaxAlloc`], allocating every staging buffer from the global allocator.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeapAlloc;
+
+impl RapaxAlloc for HeapAlloc {
+    fn acquire(&self, capacity: usize) -> Vec<u8> {
+        Vec::with_capacity(capacity)
+    }
+
+    fn release(&self, _buffer: Vec<u8>) {}
+}
+
+#[derive(Clone)]
+pub(crate) struct StagingAlloc(Arc<dyn RapaxAlloc>);
+
+impl Default for StagingAlloc {
+    fn default() -> Self {
+        Self(Arc::new(HeapAlloc))
+    }
+}
+
+impl std::fmt::Debug for StagingAlloc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("StagingAlloc")
+    }
+}
+
+/// A buffer from the context's [`RapaxAlloc`], released back to it on drop.
+#[derive(Debug)]
+pub(crate) struct StagingBuffer {
+    data: Vec<u8>,
+    alloc: StagingAlloc,
+}
+
+impl Deref for StagingBuffer {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.data
+    }
+}
+
+impl DerefMut for StagingBuffer {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.data
+    }
+}
+
+impl Drop for StagingBuffer {
+    fn drop(&mut self) {
+        self.alloc.0.release(std::mem::take(&mut self.data));
+    }
+}
+
+impl ManagedContext {
+    /// Allocate CPU-side staging buffers with `alloc` instead of the global allocator.
+    pub fn set_staging_allocator(&mut self, alloc: impl RapaxAlloc + 'static) {
+        self.staging = StagingAlloc(Arc::new(alloc));
+    }
+
+    /// An empty staging buffer with capacity for at least `capacity` bytes.
+    pub(crate) fn staging_buffer(&self, capacity: usize) -> StagingBuffer {
+        let mut data = self.staging.0.acquire(capacity);
+        data.clear();
+        StagingBuffer {
+            data,
+            alloc: self.staging.clone(),
+        }
+    }
+}
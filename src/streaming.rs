@@ -0,0 +1,293 @@
+use super::*;
+
+use std::collections::HashMap;
+
+/// The label streamed textures are accounted under in [`ManagedContext::memory_report`].
+pub const STREAMING_MEMORY_LABEL: &str = "streaming";
+
+/// What a call to [`TextureStreamer::update`] did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamingStats {
+    /// Estimated bytes of the resident levels of every streamed texture.
+    pub resident_bytes: usize,
+    /// Textures that gained a higher resolution level.
+    pub uploads: usize,
+    /// Levels dropped from the GPU to stay within the budget.
+    pub evictions: usize,
+}
+
+#[derive(Debug)]
+struct StreamedTexture {
+    /// Every level, the base level first.
+    levels: Vec<MipLevel>,
+    internal_format: InternalTextureFormat,
+    /// The highest resolution level on the GPU.
+    resident: usize,
+    /// The resident level the texture was last uploaded with.
+    uploaded: usize,
+    last_used: u64,
+    priority: f32,
+}
+
+impl StreamedTexture {
+    /// The estimated GPU size of the levels from `level` down.
+    fn bytes_from(&self, level: usize) -> usize {
+        let pixel_size = self.internal_format.pixel_size();
+        self.levels[level..]
+            .iter()
+            .map(|level| level.width as usize * level.height as usize * pixel_size)
+            .sum()
+    }
+}
+
+/// Keeps streamed textures within a GPU memory budget by only uploading the levels they need.
+///
+/// Streamed textures live in the context's registry, see [`ManagedContext::textures`], and keep their full mip chain
+/// in CPU memory. Every frame, [`TextureStreamer::touch`] the textures that are drawn, then call
+/// [`TextureStreamer::update`]: while over budget, the least recently used textures drop their highest resolution level
+/// and are re-uploaded from the lower levels; textures touched this frame then gain levels back, the highest priority
+/// first, as far as the budget allows.
+///
+/// Resident levels are uploaded as the texture's level 0, so a texture's size changes as it streams in & out; sample
+/// it with normalized coordinates only.
+#[derive(Debug)]
+pub struct TextureStreamer {
+    budget: usize,
+    max_uploads: usize,
+    frame: u64,
+    textures: HashMap<TextureId, StreamedTexture>,
+}
+
+impl TextureStreamer {
+    /// Create a streamer keeping the streamed textures within `budget` bytes.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            max_uploads: 4,
+            frame: 0,
+            textures: HashMap::new(),
+        }
+    }
+
+    /// Set the maximum number of textures gaining a level per [`TextureStreamer::update`], to spread uploads over
+    /// frames. Defaults to 4.
+    pub fn with_max_uploads(self, max_uploads: usize) -> Self {
+        Self {
+            max_uploads: max_uploads.max(1),
+            ..self
+        }
+    }
+
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Create a streamed texture owned by the context. Its mip chain is generated on the CPU with `filter`, and it
+    /// starts out with only its smallest level resident.
+    ///
+    /// Textures without pixels, or whose data can't be filtered on the CPU, see [`Texture2D::generate_mipmaps_cpu`],
+    /// are always fully resident. The texture's mipmap policy is ignored.
+    pub fn insert(
+        &mut self,
+        ctx: &mut ManagedContext,
+        desc: &PendingTexture,
+        filter: MipmapFilter,
+    ) -> Result<TextureId, Error> {
+        span!("stream_insert", width = desc.width, height = desc.height);
+        let alignment = ctx.unpack_alignment();
+        let (width, pixel_size) = (desc.width.max(0) as usize, desc.format.pixel_size(desc.ty));
+        let row_len = width * pixel_size;
+        let stride = row_stride(width, pixel_size, alignment);
+        // levels are kept tightly packed, independent of the unpack alignment when they're uploaded
+        let mut levels = vec![MipLevel {
+            width: desc.width,
+            height: desc.height,
+            data: desc
+                .pixels
+                .chunks(stride)
+                .flat_map(|row| &row[..row_len.min(row.len())])
+                .copied()
+                .collect(),
+        }];
+        if !desc.pixels.is_empty() {
+            levels.extend(
+                generate_mip_chain(
+                    &desc.pixels,
+                    ctx.supported_format(desc.internal_format),
+                    desc.format,
+                    desc.ty,
+                    desc.width,
+                    desc.height,
+                    alignment,
+                    filter,
+                )
+                .unwrap_or_default(),
+            );
+        }
+
+        let texture = allocate_streamed(ctx, desc)?;
+        let id = ctx.insert_texture(texture);
+        let mut streamed = StreamedTexture {
+            internal_format: ctx.supported_format(desc.internal_format),
+            resident: levels.len() - 1,
+            uploaded: 0,
+            levels,
+            last_used: self.frame,
+            priority: 0.0,
+        };
+        upload_resident_levels(ctx, id, &mut streamed);
+        self.textures.insert(id, streamed);
+        Ok(id)
+    }
+
+    /// Stop streaming a texture and destroy it, see [`ManagedContext::destroy_texture`].
+    pub fn remove(&mut self, ctx: &mut ManagedContext, id: TextureId) -> bool {
+        self.textures.remove(&id).is_some() && ctx.destroy_texture(id)
+    }
+
+    /// Mark a texture as drawn this frame. Textures with a higher `priority`, e.g. their approximate size on screen,
+    /// are streamed in first and evicted last.
+    pub fn touch(&mut self, id: TextureId, priority: f32) {
+        if let Some(texture) = self.textures.get_mut(&id) {
+            if texture.last_used != self.frame {
+                texture.priority = priority;
+            } else {
+                texture.priority = texture.priority.max(priority);
+            }
+            texture.last_used = self.frame;
+        }
+    }
+
+    /// The index of the highest resolution level of a texture on the GPU, 0 if it is fully resident.
+    pub fn resident_level(&self, id: TextureId) -> Option<usize> {
+        self.textures.get(&id).map(|texture| texture.resident)
+    }
+
+    /// Estimated bytes of the resident levels of every streamed texture.
+    pub fn resident_bytes(&self) -> usize {
+        self.textures
+            .values()
+            .map(|texture| texture.bytes_from(texture.resident))
+            .sum()
+    }
+
+    /// Evict & upload levels for the textures touched since the last update, then start a new frame.
+    pub fn update(&mut self, ctx: &mut ManagedContext) -> StreamingStats {
+        span!("stream_update", textures = self.textures.len());
+        let mut stats = StreamingStats {
+            resident_bytes: self.resident_bytes(),
+            ..Default::default()
+        };
+
+        // least recently used & lowest priority first
+        let mut order: Vec<TextureId> = self.textures.keys().copied().collect();
+        order.sort_by(|a, b| {
+            let (a, b) = (&self.textures[a], &self.textures[b]);
+            a.last_used
+                .cmp(&b.last_used)
+                .then(a.priority.total_cmp(&b.priority))
+        });
+
+        for id in &order {
+            if stats.resident_bytes <= self.budget {
+                break;
+            }
+            let texture = self.textures.get_mut(id).unwrap();
+            while stats.resident_bytes > self.budget && texture.resident + 1 < texture.levels.len()
+            {
+                stats.resident_bytes -=
+                    texture.bytes_from(texture.resident) - texture.bytes_from(texture.resident + 1);
+                texture.resident += 1;
+                stats.evictions += 1;
+            }
+        }
+
+        for id in order.iter().rev() {
+            if stats.uploads == self.max_uploads {
+                break;
+            }
+            let texture = self.textures.get_mut(id).unwrap();
+            if texture.last_used != self.frame || texture.resident == 0 {
+                continue;
+            }
+            let cost =
+                texture.bytes_from(texture.resident - 1) - texture.bytes_from(texture.resident);
+            if stats.resident_bytes + cost > self.budget {
+                continue;
+            }
+            stats.resident_bytes += cost;
+            texture.resident -= 1;
+            stats.uploads += 1;
+        }
+
+        for (id, texture) in &mut self.textures {
+            if texture.resident != texture.uploaded {
+                upload_resident_levels(ctx, *id, texture);
+            }
+        }
+
+        self.frame += 1;
+        stats
+    }
+}
+
+/// Create an empty texture sampling like `desc`, to be filled by [`upload_resident_levels`].
+fn allocate_streamed(ctx: &mut ManagedContext, desc: &PendingTexture) -> Result<Texture2D, Error> {
+    let mut texture = ctx.create_pending_texture(&PendingTexture {
+        pixels: vec![],
+        width: 0,
+        height: 0,
+        mipmap_policy: MipmapPolicy::None,
+        ..desc.clone()
+    })?;
+    texture.handle.memory.set_label(STREAMING_MEMORY_LABEL);
+    Ok(texture)
+}
+
+/// Re-upload a streamed texture from its resident level down.
+fn upload_resident_levels(ctx: &mut ManagedContext, id: TextureId, streamed: &mut StreamedTexture) {
+    let mut textures = std::mem::take(&mut ctx.textures);
+    if let Some(texture) = textures.get_mut(id) {
+        let base = &streamed.levels[streamed.resident];
+        span!(
+            "stream_upload",
+            texture = texture.handle.texture.0.get(),
+            width = base.width,
+            height = base.height
+        );
+        let alignment = ctx.unpack_alignment();
+        unsafe { ctx.gl.pixel_store_i32(UNPACK_ALIGNMENT, 1) };
+        let data = (!base.data.is_empty()).then_some(base.data.as_slice());
+        texture.reallocate(ctx, data, base.width, base.height);
+        let (format, ty) = texture.pixel_format();
+        for (index, level) in streamed.levels[streamed.resident + 1..].iter().enumerate() {
+            let transfer = ctx.pixel_transfer(texture.format(), format, ty, Some(&level.data));
+            unsafe {
+                ctx.bind_scratch_texture(TEXTURE_2D, texture.handle.texture);
+                ctx.gl.tex_image_2d(
+                    TEXTURE_2D,
+                    index as i32 + 1,
+                    texture.format() as _,
+                    level.width,
+                    level.height,
+                    0,
+                    transfer.format as _,
+                    transfer.ty as _,
+                    transfer.data.as_deref(),
+                );
+                ctx.release_scratch_texture(TEXTURE_2D);
+            }
+        }
+        unsafe { ctx.gl.pixel_store_i32(UNPACK_ALIGNMENT, alignment as i32) };
+        texture
+            .handle
+            .memory
+            .resize(streamed.bytes_from(streamed.resident));
+    }
+    ctx.textures = textures;
+    streamed.uploaded = streamed.resident;
+}
@@ -0,0 +1,294 @@
+//! Golden-image regression testing.
+//!
+//! Frames are compared against PNGs stored in the directory named by the `RAPAX_GOLDEN_DIR` environment variable,
+//! `tests/golden` by default. Set `RAPAX_UPDATE_GOLDEN=1` to (re)write the stored images instead of comparing.
+//! When a comparison fails, `<name>.actual.png` and `<name>.diff.png` are written next to the stored image.
+
+use super::*;
+
+use std::path::{Path, PathBuf};
+
+/// A framebuffer with an RGBA8 color and a 24-bit depth texture, for rendering without a window.
+#[derive(Debug)]
+pub struct OffscreenTarget {
+    framebuffer: Framebuffer,
+    color: Texture2D,
+    _depth: Texture2D,
+}
+
+impl OffscreenTarget {
+    /// Create a new offscreen target.
+    pub fn new(ctx: &mut ManagedContext, width: i32, height: i32) -> Result<Self, Error> {
+        let allocate = |ctx: &mut ManagedContext, internal_format, format, ty| {
+            TextureHandle::new_with(ctx, &SamplerDescriptor::pixel_art()).map(|handle| {
+                handle.allocate_2d_data(ctx, None, internal_format, format, width, height, ty)
+            })
+        };
+        let color = allocate(
+            ctx,
+            InternalTextureFormat::Rgba8,
+            TextureFormat::Rgba,
+            DataType::UnsignedByte,
+        )?;
+        let depth = allocate(
+            ctx,
+            InternalTextureFormat::DepthComponent24,
+            TextureFormat::DepthComponent,
+            DataType::UnsignedInt,
+        )?;
+
+        let framebuffer = Framebuffer::new(ctx)?;
+        framebuffer.attach_texture_2d(ctx, Attachment::Color(0), &color);
+        framebuffer.attach_texture_2d(ctx, Attachment::Depth, &depth);
+        if !framebuffer.is_complete(ctx) {
+            return Err(Error::Unsupported(
+                "incomplete offscreen framebuffer".into(),
+            ));
+        }
+
+        Ok(Self {
+            framebuffer,
+            color,
+            _depth: depth,
+        })
+    }
+
+    /// The color texture.
+    pub fn color(&self) -> &Texture2D {
+        &self.color
+    }
+
+    /// Render into the target and read the result back.
+    ///
+    /// The previously bound framebuffers and viewport are restored afterwards.
+    pub fn render(
+        &self,
+        ctx: &mut ManagedContext,
+        draw: impl FnOnce(&mut ManagedContext),
+    ) -> ImageData {
+        let previous = ctx.framebuffer_binding();
+        let viewport = ctx.viewport_rect();
+        let rect = Rect::new(0, 0, self.color.width, self.color.height);
+        ctx.bind_framebuffer(Some(&self.framebuffer));
+        ctx.set_viewport(rect.x, rect.y, rect.width, rect.height);
+
+        draw(ctx);
+        let image = ctx.capture_rect(rect);
+
+        ctx.restore_framebuffer(previous);
+        ctx.set_viewport(viewport.x, viewport.y, viewport.width, viewport.height);
+        image
+    }
+
+    /// Render into the target and compare the result against the stored image `name`.
+    ///
+    /// ## Panics
+    /// See [`assert_image_matches`].
+    pub fn assert_matches(
+        &self,
+        ctx: &mut ManagedContext,
+        name: &str,
+        tolerance: u8,
+        draw: impl FnOnce(&mut ManagedContext),
+    ) {
+        assert_image_matches(&self.render(ctx, draw), name, tolerance);
+    }
+}
+
+/// Compare the current viewport of the bound framebuffer against the stored image `name`.
+///
+/// ## Panics
+/// See [`assert_image_matches`].
+pub fn assert_frame_matches(ctx: &ManagedContext, name: &str, tolerance: u8) {
+    assert_image_matches(&ctx.capture_frame(), name, tolerance);
+}
+
+/// Compare an image against the stored image `name`. Channels may differ by up to `tolerance`.
+///
+/// ## Panics
+/// The stored image must exist and match in size, and no channel may differ by more than `tolerance`.
+pub fn assert_image_matches(image: &ImageData, name: &str, tolerance: u8) {
+    assert_image_matches_in(&golden_dir(), image, name, tolerance);
+}
+
+fn assert_image_matches_in(dir: &Path, image: &ImageData, name: &str, tolerance: u8) {
+    let path = dir.join(format!("{}.png", name));
+    let actual = to_rgba_image(image);
+
+    if std::env::var_os("RAPAX_UPDATE_GOLDEN").is_some_and(|value| value == "1") {
+        std::fs::create_dir_all(dir).expect("failed to create golden image directory");
+        actual.save(&path).expect("failed to write golden image");
+        return;
+    }
+
+    let expected = match image::open(&path) {
+        Ok(expected) => expected.into_rgba8(),
+        Err(err) => panic!(
+            "failed to load golden image {}: {} (run with RAPAX_UPDATE_GOLDEN=1 to create it)",
+            path.display(),
+            err
+        ),
+    };
+    assert_eq!(
+        expected.dimensions(),
+        actual.dimensions(),
+        "frame size does not match golden image {}",
+        name
+    );
+
+    let (mismatched, diff) = diff_images(&expected, &actual, tolerance);
+    if mismatched > 0 {
+        let actual_path = dir.join(format!("{}.actual.png", name));
+        let diff_path = dir.join(format!("{}.diff.png", name));
+        let _ = actual.save(&actual_path);
+        let _ = diff.save(&diff_path);
+        panic!(
+            "{} pixels differ from golden image {} by more than {}, see {}",
+            mismatched,
+            name,
+            tolerance,
+            diff_path.display()
+        );
+    }
+}
+
+/// Count the pixels of two equally sized images with a channel differing by more than `tolerance`. The returned diff
+/// image marks them red, over a darkened copy of `actual`.
+fn diff_images(
+    expected: &image::RgbaImage,
+    actual: &image::RgbaImage,
+    tolerance: u8,
+) -> (usize, image::RgbaImage) {
+    let mut diff = image::RgbaImage::new(actual.width(), actual.height());
+    let mut mismatched = 0;
+    for ((expected, actual), diff) in expected
+        .pixels()
+        .zip(actual.pixels())
+        .zip(diff.pixels_mut())
+    {
+        let delta = expected
+            .0
+            .iter()
+            .zip(actual.0.iter())
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap_or(0);
+        if delta > tolerance {
+            mismatched += 1;
+            *diff = image::Rgba([255, 0, 0, 255]);
+        } else {
+            *diff = image::Rgba([actual[0] / 4, actual[1] / 4, actual[2] / 4, 255]);
+        }
+    }
+    (mismatched, diff)
+}
+
+fn golden_dir() -> PathBuf {
+    std::env::var_os("RAPAX_GOLDEN_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("tests/golden"))
+}
+
+fn to_rgba_image(image: &ImageData) -> image::RgbaImage {
+    image::RgbaImage::from_raw(image.width as u32, image.height as u32, image.rgba.clone())
+        .expect("image data does not match its size")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The 4x4 red & blue checkerboard stored as `tests/golden/checkerboard.png`.
+    fn checkerboard() -> ImageData {
+        let rgba = (0..16)
+            .flat_map(|i| match (i % 4 + i / 4) % 2 {
+                0 => [255, 0, 0, 255],
+                _ => [0, 0, 255, 255],
+            })
+            .collect();
+        ImageData {
+            width: 4,
+            height: 4,
+            rgba,
+        }
+    }
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+    }
+
+    /// A scratch golden directory holding a copy of the checkerboard, for tests writing failure images.
+    fn scratch_dir(test: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("rapax-golden-{}-{}", test, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::copy(
+            fixtures().join("checkerboard.png"),
+            dir.join("checkerboard.png"),
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn identical_images_have_no_mismatches() {
+        let image = to_rgba_image(&checkerboard());
+        let (mismatched, diff) = diff_images(&image, &image, 0);
+        assert_eq!(mismatched, 0);
+        assert_eq!(diff.get_pixel(0, 0).0, [63, 0, 0, 255]);
+    }
+
+    #[test]
+    fn channels_may_differ_by_the_tolerance() {
+        let expected = to_rgba_image(&checkerboard());
+        let mut actual = expected.clone();
+        actual.get_pixel_mut(1, 0).0[2] -= 3;
+        actual.get_pixel_mut(2, 3).0[3] -= 4;
+
+        assert_eq!(diff_images(&expected, &actual, 4).0, 0);
+        let (mismatched, diff) = diff_images(&expected, &actual, 3);
+        assert_eq!(mismatched, 1);
+        assert_eq!(diff.get_pixel(2, 3).0, [255, 0, 0, 255]);
+        assert_ne!(diff.get_pixel(1, 0).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn matches_the_stored_fixture() {
+        let mut image = checkerboard();
+        assert_image_matches_in(&fixtures(), &image, "checkerboard", 0);
+        image.rgba[0] = 250;
+        assert_image_matches_in(&fixtures(), &image, "checkerboard", 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "1 pixels differ from golden image checkerboard by more than 5")]
+    fn mismatches_write_the_actual_and_diff_images() {
+        let dir = scratch_dir("mismatch");
+        let mut image = checkerboard();
+        image.rgba[0] = 249;
+        let result = std::panic::catch_unwind(|| {
+            assert_image_matches_in(&dir, &image, "checkerboard", 5);
+        });
+        assert!(dir.join("checkerboard.actual.png").exists());
+        assert!(dir.join("checkerboard.diff.png").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+        std::panic::resume_unwind(result.unwrap_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "frame size does not match golden image checkerboard")]
+    fn size_mismatches_fail() {
+        let image = ImageData {
+            width: 2,
+            height: 2,
+            rgba: vec![0; 16],
+        };
+        assert_image_matches_in(&fixtures(), &image, "checkerboard", 255);
+    }
+
+    #[test]
+    #[should_panic(expected = "run with RAPAX_UPDATE_GOLDEN=1 to create it")]
+    fn missing_images_fail() {
+        assert_image_matches_in(&fixtures(), &checkerboard(), "missing", 255);
+    }
+}
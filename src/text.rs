@@ -0,0 +1,661 @@
+//! Text rendering interop with [`cosmic_text`].
+//!
+//! Glyphs are rasterized into a [`GlyphAtlas`] texture, while the resulting [`TextLayout`] is plain data so
+//! applications can hit-test it. Glyphs are drawn as one instanced quad each with [`RenderPipeline::glyphs`], so
+//! editing text only uploads the instances of the changed layout. Carets & selections are drawn under them from
+//! [`TextLayout::highlights`] with a [`HighlightBuffer`] and [`RenderPipeline::text_highlights`], and IME input is
+//! shown inline with a [`Composition`].
+
+use super::*;
+
+use crate::util::RectPacker;
+use cosmic_text::{
+    AttrsList, Buffer, CacheKey, Color, Cursor, Edit, FontSystem, SwashCache, SwashContent,
+};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Vertex shader for [`GLYPH_FS`], drawing a 4 vertex triangle strip per instance. Reads the glyph rect at location
+/// 0, its atlas coordinates at location 1, its normalized color at location 2 and whether it is a color glyph at
+/// location 3, see [`TextLayout::instance_data`]. `mat4 u_transform` maps layout pixels to clip space.
+pub const GLYPH_VS: &str = r#"
+layout(location = 0) in vec4 a_rect;
+layout(location = 1) in vec4 a_uv;
+layout(location = 2) in vec4 a_color;
+layout(location = 3) in float a_is_color;
+
+uniform mat4 u_transform;
+
+out vec2 v_uv;
+out vec4 v_color;
+out float v_is_color;
+
+void main() {
+    vec2 corner = vec2(float(gl_VertexID & 1), float(gl_VertexID >> 1));
+    v_uv = mix(a_uv.xy, a_uv.zw, corner);
+    v_color = a_color;
+    v_is_color = a_is_color;
+    gl_Position = u_transform * vec4(a_rect.xy + a_rect.zw * corner, 0.0, 1.0);
+}
+"#;
+
+/// Sample glyphs from the `sampler2D u_atlas` of a [`GlyphAtlas`], tinting coverage masks by the glyph color.
+///
+/// Glyph colors and color glyphs are sRGB-encoded, and decoded to linear if `int u_srgb_target` is non-zero, see
+/// [`GlyphAtlas::apply`].
+pub const GLYPH_FS: &str = r#"
+uniform sampler2D u_atlas;
+uniform int u_srgb_target;
+
+in vec2 v_uv;
+in vec4 v_color;
+in float v_is_color;
+
+out vec4 o_color;
+
+vec3 decode(vec3 color) {
+    if (u_srgb_target == 0) {
+        return color;
+    }
+    return mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), step(0.04045, color));
+}
+
+void main() {
+    vec4 texel = texture(u_atlas, v_uv);
+    o_color = v_is_color > 0.5 ? vec4(decode(texel.rgb), texel.a) : vec4(decode(v_color.rgb), v_color.a * texel.a);
+}
+"#;
+
+/// Vertex shader for [`HIGHLIGHT_FS`], drawing a 4 vertex triangle strip per instance. Reads the quad rect at
+/// location 0 and its normalized color at location 1, see [`HighlightBuffer`]. `mat4 u_transform` maps layout pixels
+/// to clip space.
+pub const HIGHLIGHT_VS: &str = r#"
+layout(location = 0) in vec4 a_rect;
+layout(location = 1) in vec4 a_color;
+
+uniform mat4 u_transform;
+
+out vec4 v_color;
+
+void main() {
+    vec2 corner = vec2(float(gl_VertexID & 1), float(gl_VertexID >> 1));
+    v_color = a_color;
+    gl_Position = u_transform * vec4(a_rect.xy + a_rect.zw * corner, 0.0, 1.0);
+}
+"#;
+
+/// Fill carets & selections with their sRGB-encoded color, decoded to linear if `int u_srgb_target` is non-zero.
+pub const HIGHLIGHT_FS: &str = r#"
+uniform int u_srgb_target;
+
+in vec4 v_color;
+
+out vec4 o_color;
+
+void main() {
+    vec3 color = v_color.rgb;
+    if (u_srgb_target != 0) {
+        color = mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), step(0.04045, color));
+    }
+    o_color = vec4(color, v_color.a);
+}
+"#;
+
+/// The size of a single glyph instance of [`TextLayout::instance_data`], in bytes.
+pub const GLYPH_INSTANCE_SIZE: usize = 40;
+
+/// The size of a single [`HighlightQuad`] instance, in bytes.
+pub const HIGHLIGHT_INSTANCE_SIZE: usize = 20;
+
+/// A quad covering one glyph, in layout pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphQuad {
+    /// Position and size of the quad: x, y, width, height.
+    pub rect: [f32; 4],
+    /// Normalized atlas coordinates of the glyph: u0, v0, u1, v1.
+    pub uv: [f32; 4],
+    /// The glyph color. Color glyphs, e.g. emoji, should be sampled as-is.
+    pub color: [u8; 4],
+    /// Whether the atlas holds the glyph's own colors rather than a white coverage mask.
+    pub is_color: bool,
+    /// The index of the text line the glyph belongs to.
+    pub line: usize,
+    /// The byte range of the glyph's cluster in the line's text.
+    pub byte_range: Range<usize>,
+}
+
+/// A position the caret can be placed at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaretPosition {
+    /// The index of the text line.
+    pub line: usize,
+    /// The byte index in the line's text.
+    pub index: usize,
+    pub x: f32,
+    /// The top of the layout line.
+    pub y: f32,
+    pub height: f32,
+}
+
+/// One rectangle of a highlighted selection. Selections spanning several layout lines produce one per line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelectionRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// A solid quad behind the glyphs, for a caret or a selection rectangle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HighlightQuad {
+    /// Position and size of the quad: x, y, width, height.
+    pub rect: [f32; 4],
+    pub color: [u8; 4],
+}
+
+impl CaretPosition {
+    /// A caret `width` pixels wide, centered on the position.
+    pub fn quad(&self, width: f32, color: Color) -> HighlightQuad {
+        HighlightQuad {
+            rect: [self.x - width * 0.5, self.y, width, self.height],
+            color: color.as_rgba(),
+        }
+    }
+}
+
+impl SelectionRect {
+    pub fn quad(&self, color: Color) -> HighlightQuad {
+        HighlightQuad {
+            rect: [self.x, self.y, self.width, self.height],
+            color: color.as_rgba(),
+        }
+    }
+}
+
+/// The layout of a [`Buffer`], see [`GlyphAtlas::layout`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextLayout {
+    pub glyphs: Vec<GlyphQuad>,
+    /// Every caret position, in visual order per layout line.
+    pub carets: Vec<CaretPosition>,
+    pub selection: Vec<SelectionRect>,
+}
+
+impl TextLayout {
+    /// The caret position closest to a point, e.g. for placing the caret on click.
+    pub fn hit(&self, x: f32, y: f32) -> Option<CaretPosition> {
+        let distance = |caret: &CaretPosition| {
+            let dy = if y < caret.y {
+                caret.y - y
+            } else {
+                (y - caret.y - caret.height).max(0.0)
+            };
+            (dy, (x - caret.x).abs())
+        };
+        self.carets.iter().copied().min_by(|a, b| {
+            distance(a)
+                .partial_cmp(&distance(b))
+                .unwrap_or(Ordering::Equal)
+        })
+    }
+
+    /// Per-glyph instance data for [`RenderPipeline::glyphs`]; draw it with
+    /// `draw_arrays_instanced(DrawMode::TriangleStrip, 0, 4, glyphs.len() as u32)`.
+    pub fn instance_data(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.glyphs.len() * GLYPH_INSTANCE_SIZE);
+        for glyph in &self.glyphs {
+            data.extend_from_slice(bytemuck::cast_slice(&glyph.rect));
+            data.extend_from_slice(bytemuck::cast_slice(&glyph.uv));
+            data.extend_from_slice(&glyph.color);
+            let is_color: f32 = if glyph.is_color { 1.0 } else { 0.0 };
+            data.extend_from_slice(bytemuck::bytes_of(&is_color));
+        }
+        data
+    }
+
+    /// Quads for the selection and, if given and visible, the caret of `cursor`, e.g. for a text editor. Draw them
+    /// under the glyphs with a [`HighlightBuffer`].
+    pub fn highlights(
+        &self,
+        cursor: Option<Cursor>,
+        caret_width: f32,
+        caret_color: Color,
+        selection_color: Color,
+    ) -> Vec<HighlightQuad> {
+        let selection = self.selection.iter().map(|rect| rect.quad(selection_color));
+        let caret = cursor
+            .and_then(|cursor| self.caret(cursor))
+            .map(|caret| caret.quad(caret_width, caret_color));
+        selection.chain(caret).collect()
+    }
+
+    /// Quads underlining the text between two cursors, e.g. the [`Composition::range`] of an IME composition.
+    /// Ranges spanning several lines produce one quad per line.
+    pub fn underline(
+        &self,
+        (start, end): (Cursor, Cursor),
+        thickness: f32,
+        color: Color,
+    ) -> Vec<HighlightQuad> {
+        let (start, end) = if start <= end {
+            (start, end)
+        } else {
+            (end, start)
+        };
+        let mut quads: Vec<HighlightQuad> = vec![];
+        let mut line: Option<(usize, f32, f32, f32)> = None;
+        let in_range = |caret: &&CaretPosition| {
+            let position = (caret.line, caret.index);
+            (start.line, start.index) <= position && position <= (end.line, end.index)
+        };
+        // carets are grouped by layout line, so wrapped lines get separate quads
+        for caret in self.carets.iter().filter(in_range) {
+            let bottom = caret.y + caret.height;
+            match &mut line {
+                Some((index, left, right, y)) if *index == caret.line && *y == bottom => {
+                    *left = left.min(caret.x);
+                    *right = right.max(caret.x);
+                }
+                _ => {
+                    quads.extend(line.map(|line| underline_quad(line, thickness, color)));
+                    line = Some((caret.line, caret.x, caret.x, bottom));
+                }
+            }
+        }
+        quads.extend(line.map(|line| underline_quad(line, thickness, color)));
+        quads.retain(|quad| quad.rect[2] > 0.0);
+        quads
+    }
+
+    /// The caret position of a cursor, if it is visible.
+    pub fn caret(&self, cursor: Cursor) -> Option<CaretPosition> {
+        self.carets
+            .iter()
+            .copied()
+            .find(|caret| caret.line == cursor.line && caret.index == cursor.index)
+    }
+}
+
+fn underline_quad(
+    (_, left, right, bottom): (usize, f32, f32, f32),
+    thickness: f32,
+    color: Color,
+) -> HighlightQuad {
+    HighlightQuad {
+        rect: [left, bottom - thickness, right - left, thickness],
+        color: color.as_rgba(),
+    }
+}
+
+/// An IME composition in progress, e.g. pinyin being typed before it is converted to Chinese characters.
+///
+/// The composing (preedit) text is inserted into the editor, so it is laid out & wrapped with the surrounding text,
+/// and replaced on every update until it is committed. Feed the windowing library's IME events into
+/// [`Composition::preedit`] and [`Composition::commit`], e.g. winit's `Ime::Preedit` and `Ime::Commit`, instead of
+/// inserting characters one by one, and underline [`Composition::range`] with [`TextLayout::underline`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Composition {
+    color: Option<Color>,
+    range: Option<(Cursor, Cursor)>,
+    show_caret: bool,
+}
+
+impl Composition {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draw composing text in `color` instead of the color of the text around it.
+    pub fn with_color(self, color: Color) -> Self {
+        Self {
+            color: Some(color),
+            ..self
+        }
+    }
+
+    /// Replace the composing text with `text`, placing the caret at the byte offset `caret` of it, or hiding the
+    /// caret if `None`. An empty `text` ends the composition without inserting anything.
+    ///
+    /// Starting a composition replaces the editor's selection.
+    pub fn preedit(&mut self, editor: &mut impl Edit, text: &str, caret: Option<usize>) {
+        self.clear(editor);
+        editor.delete_selection();
+        if text.is_empty() {
+            return;
+        }
+
+        let start = editor.cursor();
+        let attrs_list = self.color.map(|color| {
+            let line = &editor.buffer().lines[start.line];
+            AttrsList::new(
+                line.attrs_list()
+                    .get_span(start.index.saturating_sub(1))
+                    .color(color),
+            )
+        });
+        editor.insert_string(text, attrs_list);
+        let end = editor.cursor();
+        self.range = Some((start, end));
+        self.show_caret = caret.is_some();
+        // composing text is a single line, unless the IME inserts line breaks
+        if let Some(caret) = caret.filter(|_| start.line == end.line) {
+            editor.set_cursor(Cursor::new(start.line, start.index + caret.min(text.len())));
+        }
+    }
+
+    /// End the composition, inserting the final `text` in place of the composing text.
+    pub fn commit(&mut self, editor: &mut impl Edit, text: &str) {
+        self.clear(editor);
+        editor.insert_string(text, None);
+    }
+
+    /// Remove the composing text, e.g. when the IME is disabled.
+    pub fn clear(&mut self, editor: &mut impl Edit) {
+        if let Some((start, end)) = self.range.take() {
+            editor.set_cursor(end);
+            editor.set_select_opt(Some(start));
+            editor.delete_selection();
+        }
+        self.show_caret = false;
+    }
+
+    /// Returns true while composing text is shown.
+    pub fn is_composing(&self) -> bool {
+        self.range.is_some()
+    }
+
+    /// The start & end of the composing text in the editor's buffer.
+    pub fn range(&self) -> Option<(Cursor, Cursor)> {
+        self.range
+    }
+
+    /// Returns false if the IME asked to hide the caret while composing.
+    pub fn shows_caret(&self) -> bool {
+        self.show_caret || self.range.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AtlasEntry {
+    rect: Rect,
+    left: i32,
+    top: i32,
+    is_color: bool,
+}
+
+/// A texture holding rasterized glyphs, filled on demand by [`GlyphAtlas::layout`].
+#[derive(Debug)]
+pub struct GlyphAtlas {
+    texture: Texture2D,
+    packer: RectPacker,
+    // glyphs without coverage, e.g. spaces, have no entry
+    entries: HashMap<CacheKey, Option<AtlasEntry>>,
+}
+
+/// Padding between glyphs, so linear filtering doesn't bleed into neighbours.
+const GLYPH_PADDING: i32 = 1;
+
+impl GlyphAtlas {
+    /// Create an empty RGBA atlas of `size` x `size` pixels, filtered like [`ManagedContext::default_sampler`]
+    /// without mipmaps, e.g. nearest for pixel fonts.
+    pub fn new(ctx: &mut ManagedContext, size: i32) -> Result<Self, Error> {
+        let sampler = SamplerDescriptor {
+            wrapping_mode_s: TextureWrap::ClampToEdge,
+            wrapping_mode_t: TextureWrap::ClampToEdge,
+            ..ctx.default_sampler().without_mipmaps()
+        };
+        let texture = TextureHandle::new_with(ctx, &sampler)?.allocate_2d_data(
+            ctx,
+            None,
+            InternalTextureFormat::Rgba8,
+            TextureFormat::Rgba,
+            size,
+            size,
+            DataType::UnsignedByte,
+        );
+
+        Ok(Self {
+            texture,
+            packer: RectPacker::new(size, size),
+            entries: HashMap::new(),
+        })
+    }
+
+    /// The atlas texture, to be sampled with the coordinates of each [`GlyphQuad`].
+    pub fn texture(&self) -> &Texture2D {
+        &self.texture
+    }
+
+    /// Bind the atlas to the `u_atlas` sampler of a [`RenderPipeline::glyphs`] pipeline, decoding glyph colors to
+    /// linear if the framebuffer encodes them to sRGB, see [`ManagedContext::encodes_srgb`].
+    pub fn apply(&self, dctx: &Drawable, encoding: FramebufferEncoding) {
+        dctx.apply_textures(&[(&self.texture, "u_atlas")]);
+        dctx.set_uniform_int1("u_srgb_target", dctx.ctx.encodes_srgb(encoding) as i32);
+    }
+
+    /// Forget every rasterized glyph, e.g. once the atlas is full. Layouts created before are invalidated.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.packer.clear();
+    }
+
+    /// Lay out the visible lines of `buffer`, rasterizing glyphs missing from the atlas.
+    ///
+    /// `selection` is the range between two cursors to produce [`SelectionRect`]s for. Glyphs without a color
+    /// attribute use `default_color`.
+    pub fn layout(
+        &mut self,
+        ctx: &mut ManagedContext,
+        font_system: &mut FontSystem,
+        swash_cache: &mut SwashCache,
+        buffer: &Buffer,
+        selection: Option<(Cursor, Cursor)>,
+        default_color: Color,
+    ) -> Result<TextLayout, Error> {
+        span!("text_layout");
+        let line_height = buffer.metrics().line_height;
+        let size = self.texture.width() as f32;
+        let mut layout = TextLayout::default();
+
+        for run in buffer.layout_runs() {
+            let caret = |index: usize, x: f32| CaretPosition {
+                line: run.line_i,
+                index,
+                x,
+                y: run.line_top,
+                height: line_height,
+            };
+            if run.glyphs.is_empty() {
+                layout.carets.push(caret(0, 0.0));
+            }
+
+            for (i, glyph) in run.glyphs.iter().enumerate() {
+                let (left, right) = if glyph.level.is_rtl() {
+                    (glyph.x + glyph.w, glyph.x)
+                } else {
+                    (glyph.x, glyph.x + glyph.w)
+                };
+                layout.carets.push(caret(glyph.start, left));
+                if i + 1 == run.glyphs.len() {
+                    layout.carets.push(caret(glyph.end, right));
+                }
+
+                let physical = glyph.physical((0.0, 0.0), 1.0);
+                let Some(entry) = self.entry(ctx, font_system, swash_cache, physical.cache_key)?
+                else {
+                    continue;
+                };
+                let x = (physical.x + entry.left) as f32;
+                let y = run.line_y + (physical.y - entry.top) as f32;
+                layout.glyphs.push(GlyphQuad {
+                    rect: [x, y, entry.rect.width as f32, entry.rect.height as f32],
+                    uv: [
+                        entry.rect.x as f32 / size,
+                        entry.rect.y as f32 / size,
+                        (entry.rect.x + entry.rect.width) as f32 / size,
+                        (entry.rect.y + entry.rect.height) as f32 / size,
+                    ],
+                    color: glyph.color_opt.unwrap_or(default_color).as_rgba(),
+                    is_color: entry.is_color,
+                    line: run.line_i,
+                    byte_range: glyph.start..glyph.end,
+                });
+            }
+
+            if let Some((start, end)) = selection {
+                let (start, end) = if start <= end {
+                    (start, end)
+                } else {
+                    (end, start)
+                };
+                if let Some((x, width)) = run.highlight(start, end) {
+                    layout.selection.push(SelectionRect {
+                        x,
+                        y: run.line_top,
+                        width,
+                        height: line_height,
+                    });
+                }
+            }
+        }
+
+        Ok(layout)
+    }
+
+    fn entry(
+        &mut self,
+        ctx: &mut ManagedContext,
+        font_system: &mut FontSystem,
+        swash_cache: &mut SwashCache,
+        key: CacheKey,
+    ) -> Result<Option<AtlasEntry>, Error> {
+        if let Some(entry) = self.entries.get(&key) {
+            return Ok(*entry);
+        }
+
+        let entry = match swash_cache.get_image(font_system, key) {
+            Some(image) if image.placement.width > 0 && image.placement.height > 0 => {
+                let width = image.placement.width as i32;
+                let height = image.placement.height as i32;
+                let rect = self
+                    .packer
+                    .allocate(width + GLYPH_PADDING, height + GLYPH_PADDING)
+                    .ok_or(Error::AtlasFull)?;
+                let is_color = image.content == SwashContent::Color;
+                let mut pixels = ctx.staging_buffer((width * height * 4) as usize);
+                match image.content {
+                    SwashContent::Mask => {
+                        pixels.extend(image.data.iter().flat_map(|&alpha| [255, 255, 255, alpha]))
+                    }
+                    SwashContent::Color => pixels.extend_from_slice(&image.data),
+                    SwashContent::SubpixelMask => {
+                        pixels.extend(image.data.chunks_exact(4).flat_map(|rgba| {
+                            let coverage = (rgba[0] as u16 + rgba[1] as u16 + rgba[2] as u16) / 3;
+                            [255, 255, 255, coverage as u8]
+                        }))
+                    }
+                }
+                self.texture.write_subimage(
+                    ctx,
+                    rect.x,
+                    rect.y,
+                    width,
+                    height,
+                    TextureFormat::Rgba,
+                    DataType::UnsignedByte,
+                    &pixels,
+                )?;
+                Some(AtlasEntry {
+                    rect: Rect::new(rect.x, rect.y, width, height),
+                    left: image.placement.left,
+                    top: image.placement.top,
+                    is_color,
+                })
+            }
+            _ => None,
+        };
+        self.entries.insert(key, entry);
+        Ok(entry)
+    }
+}
+
+/// Caret & selection quads uploaded for [`RenderPipeline::text_highlights`].
+#[derive(Debug)]
+pub struct HighlightBuffer {
+    instances: BufferHandle,
+    count: u32,
+}
+
+impl HighlightBuffer {
+    pub fn new(ctx: &ManagedContext) -> Result<Self, Error> {
+        Ok(Self {
+            instances: BufferHandle::array_buffer(ctx, BufferUsage::Dynamic, &[])?,
+            count: 0,
+        })
+    }
+
+    /// Replace the quads, e.g. with [`TextLayout::highlights`].
+    pub fn update(&mut self, ctx: &ManagedContext, quads: &[HighlightQuad]) {
+        let mut data = Vec::with_capacity(quads.len() * HIGHLIGHT_INSTANCE_SIZE);
+        for quad in quads {
+            data.extend_from_slice(bytemuck::cast_slice(&quad.rect));
+            data.extend_from_slice(&quad.color);
+        }
+        self.instances.realloc(ctx, BufferUsage::Dynamic, &data);
+        self.count = quads.len() as u32;
+    }
+
+    /// Draw the quads with a [`RenderPipeline::text_highlights`] pipeline, whose `u_transform` must already be set.
+    /// Colors are decoded to linear for sRGB framebuffers, like [`GlyphAtlas::apply`].
+    pub fn draw(&self, dctx: &mut Drawable, encoding: FramebufferEncoding) {
+        if self.count == 0 {
+            return;
+        }
+        dctx.apply_bindings(&[&self.instances], None::<&BufferHandle>);
+        dctx.set_uniform_int1("u_srgb_target", dctx.ctx.encodes_srgb(encoding) as i32);
+        dctx.draw_arrays_instanced(DrawMode::TriangleStrip, 0, 4, self.count);
+    }
+
+    pub fn len(&self) -> usize {
+        self.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+impl RenderPipeline {
+    /// An alpha blended pipeline drawing [`HighlightBuffer`] quads, carets and selections, to be drawn before the
+    /// glyphs. See [`HIGHLIGHT_VS`].
+    pub fn text_highlights(ctx: &ManagedContext) -> Result<Self, Error> {
+        Self::built_in(
+            ctx,
+            HIGHLIGHT_VS,
+            HIGHLIGHT_FS,
+            VertexLayout::new(0)
+                .with_attribute(4, DataType::Float, false)
+                .with_attribute(4, DataType::UnsignedByte, true)
+                .with_divisor(1),
+        )
+        .map(Self::alpha_blended)
+    }
+
+    /// An alpha blended pipeline drawing the glyphs of a [`TextLayout`] as instanced quads. See [`GLYPH_VS`].
+    pub fn glyphs(ctx: &ManagedContext) -> Result<Self, Error> {
+        Self::built_in(
+            ctx,
+            GLYPH_VS,
+            GLYPH_FS,
+            VertexLayout::new(0)
+                .with_attribute(4, DataType::Float, false)
+                .with_attribute(4, DataType::Float, false)
+                .with_attribute(4, DataType::UnsignedByte, true)
+                .with_attribute(1, DataType::Float, false)
+                .with_divisor(1),
+        )
+        .map(Self::alpha_blended)
+    }
+}
@@ -4,10 +4,37 @@ use std::sync::Arc;
 mod tex_2d;
 pub use tex_2d::*;
 
+mod tex_cube;
+pub use tex_cube::*;
+
+mod tex_2d_array;
+pub use tex_2d_array::*;
+
+mod tex_dynamic;
+pub use tex_dynamic::*;
+
+mod tex_buffer;
+pub use tex_buffer::*;
+
+mod mipmap;
+pub use mipmap::*;
+
+mod conversion;
+pub(crate) use conversion::{row_stride, PixelTransfer};
+
+#[cfg(feature = "image")]
+mod tex_image;
+#[cfg(feature = "image")]
+pub use tex_image::*;
+
+/// The texture target for externally produced images, e.g. video frames (`GL_OES_EGL_image_external`).
+pub const TEXTURE_EXTERNAL_OES: u32 = 0x8D65;
+
 /// Specifies an internal OpenGL texture format.
 ///
 /// The availability of texture formats depends on the platform being used.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum InternalTextureFormat {
     /// Alpha format.
@@ -16,6 +43,14 @@ pub enum InternalTextureFormat {
     Luminance = LUMINANCE,
     /// Luminance alpha format.
     LuminanceAlpha = LUMINANCE_ALPHA,
+    /// 8-bit red format.
+    R8 = R8,
+    /// 8-bit red & green format.
+    Rg8 = RG8,
+    /// 16-bit red format.
+    R16 = R16,
+    /// 16-bit red & green format.
+    Rg16 = RG16,
     /// 3-bit red, 3-bit green, 2-bit blue format.
     R3G3B2 = R3_G3_B2,
     /// RGB format.
@@ -48,12 +83,33 @@ pub enum InternalTextureFormat {
     Rgba12 = RGBA12,
     /// 16-bit RGBA format.
     Rgba16 = RGBA16,
+    /// 16-bit depth format.
+    DepthComponent16 = DEPTH_COMPONENT16,
+    /// 24-bit depth format.
+    DepthComponent24 = DEPTH_COMPONENT24,
+    /// 32-bit floating point depth format.
+    DepthComponent32F = DEPTH_COMPONENT32F,
+    /// 24-bit depth format with 8-bit stencil.
+    Depth24Stencil8 = DEPTH24_STENCIL8,
+    /// 8-bit sRGB format. Requires [`Features::SRGB`].
+    Srgb8 = SRGB8,
+    /// 8-bit sRGB format with linear 8-bit alpha. Requires [`Features::SRGB`].
+    Srgb8Alpha8 = SRGB8_ALPHA8,
+    /// 16-bit floating point red format. Rendering to it requires [`Features::FLOAT_RENDER_TARGETS`].
+    R16F = R16F,
+    /// 16-bit floating point red & green format. Rendering to it requires [`Features::FLOAT_RENDER_TARGETS`].
+    Rg16F = RG16F,
+    /// 16-bit floating point RGBA format. Rendering to it requires [`Features::FLOAT_RENDER_TARGETS`].
+    Rgba16F = RGBA16F,
+    /// 32-bit floating point RGBA format. Rendering to it requires [`Features::FLOAT_RENDER_TARGETS`].
+    Rgba32F = RGBA32F,
 }
 
 /// Specifies a supported OpenGL texture format.
 ///
 /// The availability of texture formats depends on the platform being used.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum TextureFormat {
     /// Red channel format.
@@ -64,6 +120,8 @@ pub enum TextureFormat {
     Blue = BLUE,
     /// Alpha channel format.
     Alpha = ALPHA,
+    /// Red & green channel format.
+    Rg = RG,
     /// RGB format.
     Rgb = RGB,
     /// RGBA format.
@@ -72,18 +130,48 @@ pub enum TextureFormat {
     Luminance = LUMINANCE,
     /// Luminance alpha format.
     LuminanceAlpha = LUMINANCE_ALPHA,
+    /// Depth format.
+    DepthComponent = DEPTH_COMPONENT,
+    /// Packed depth & stencil format.
+    DepthStencil = DEPTH_STENCIL,
+}
+
+impl TextureFormat {
+    /// The number of components per pixel.
+    pub fn components(&self) -> usize {
+        match *self {
+            Self::Red | Self::Green | Self::Blue | Self::Alpha => 1,
+            Self::Luminance | Self::DepthComponent => 1,
+            Self::Rg | Self::LuminanceAlpha | Self::DepthStencil => 2,
+            Self::Rgb => 3,
+            Self::Rgba => 4,
+        }
+    }
+
+    /// The size of a single pixel of this format stored as `ty`, in bytes.
+    pub fn pixel_size(&self, ty: DataType) -> usize {
+        if ty.is_packed() {
+            ty.sizeof()
+        } else {
+            self.components() * ty.sizeof()
+        }
+    }
 }
 
 /// Specifies the wrapping behavior of an axis of a texture.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum TextureWrap {
+    Repeat = REPEAT,
     MirroredRepeat = MIRRORED_REPEAT,
+    ClampToEdge = CLAMP_TO_EDGE,
     ClampToBorder = CLAMP_TO_BORDER,
 }
 
 /// Specifies a potential texture filtering mode.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum TextureFilteringMode {
     /// Picks the nearest pixel.
@@ -92,55 +180,231 @@ pub enum TextureFilteringMode {
     Linear = LINEAR,
     /// Takes the nearest mipmap to match the pixel size and uses nearest neighbor interpolation for texture sampling.
     NearestMipmapNearest = NEAREST_MIPMAP_NEAREST,
+    /// Takes the nearest mipmap and averages pixels in it.
+    LinearMipmapNearest = LINEAR_MIPMAP_NEAREST,
+    /// Picks the nearest pixel in the two closest mipmaps and blends between them.
+    NearestMipmapLinear = NEAREST_MIPMAP_LINEAR,
+    /// Trilinear filtering: averages pixels in the two closest mipmaps and blends between them.
+    LinearMipmapLinear = LINEAR_MIPMAP_LINEAR,
+}
+
+impl TextureFilteringMode {
+    /// Returns true if this filtering mode samples from mipmap levels.
+    pub fn uses_mipmaps(&self) -> bool {
+        matches!(
+            self,
+            Self::NearestMipmapNearest
+                | Self::LinearMipmapNearest
+                | Self::NearestMipmapLinear
+                | Self::LinearMipmapLinear
+        )
+    }
+}
+
+/// Specifies when texture mipmaps are generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MipmapPolicy {
+    /// Mipmaps are only generated by calling [`Texture2D::generate_mipmaps`].
+    None,
+    /// Mipmaps are regenerated whenever texture data changes, if the minification filter uses mipmaps.
+    Auto,
+    /// Mipmaps are generated on the CPU with the given filter whenever the whole texture is uploaded, if the
+    /// minification filter uses mipmaps. Partial writes leave them stale; see [`Texture2D::generate_mipmaps_cpu`].
+    Cpu(MipmapFilter),
+}
+
+/// Options for uploading pixel data with [`TextureHandle::allocate_2d_data_with`], [`Texture2D::reallocate_with`]
+/// and [`Texture2D::write_subimage_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UploadOptions {
+    /// Flip the data so its first row ends up at the bottom, matching OpenGL's bottom-left origin. Use this for images
+    /// stored top-down, which is how almost every image format stores them.
+    pub flip_y: bool,
 }
 
 /// A handle to an OpenGL texture object. The internal OpenGL program object will be automatically freed on drop.
 #[derive(Debug)]
 pub struct TextureHandle {
     pub(crate) texture: NativeTexture,
+    pub(crate) target: u32,
+    pub(crate) min_filter: TextureFilteringMode,
+    pub(crate) mipmap_policy: MipmapPolicy,
     gl: Arc<Context>,
+    share_group: ShareGroup,
+    pub(crate) memory: MemoryAllocation,
 }
 
 impl TextureHandle {
     /// Create a new texture.
+    #[deprecated(
+        note = "easy to misorder, use `TextureHandle::new_with` with a `SamplerDescriptor`"
+    )]
     pub fn new(
         ctx: &mut ManagedContext,
         wrapping_mode_s: TextureWrap,
         wrapping_mode_t: TextureWrap,
         min_filter: TextureFilteringMode,
         mag_filter: TextureFilteringMode,
-    ) -> Result<Self, String> {
+    ) -> Result<Self, Error> {
+        Self::with_target(
+            ctx,
+            TEXTURE_2D,
+            [wrapping_mode_s, wrapping_mode_t],
+            min_filter,
+            mag_filter,
+        )
+    }
+
+    /// Create a new cube map texture. Every axis uses the same wrapping mode.
+    pub fn new_cube(
+        ctx: &mut ManagedContext,
+        wrapping_mode: TextureWrap,
+        min_filter: TextureFilteringMode,
+        mag_filter: TextureFilteringMode,
+    ) -> Result<Self, Error> {
+        let wrapping_mode = ctx.supported_wrap(wrapping_mode, false);
+        let handle = Self::with_target(
+            ctx,
+            TEXTURE_CUBE_MAP,
+            [wrapping_mode, wrapping_mode],
+            min_filter,
+            mag_filter,
+        )?;
+        unsafe {
+            if ctx.supports_dsa() {
+                ctx.gl
+                    .texture_parameter_i32(handle.texture, TEXTURE_WRAP_R, wrapping_mode as _);
+            } else {
+                ctx.bind_scratch_texture(TEXTURE_CUBE_MAP, handle.texture);
+                ctx.gl
+                    .tex_parameter_i32(TEXTURE_CUBE_MAP, TEXTURE_WRAP_R, wrapping_mode as _);
+                ctx.release_scratch_texture(TEXTURE_CUBE_MAP);
+            }
+        }
+        Ok(handle)
+    }
+
+    pub(crate) fn with_target(
+        ctx: &mut ManagedContext,
+        target: u32,
+        wrapping_mode: [TextureWrap; 2],
+        min_filter: TextureFilteringMode,
+        mag_filter: TextureFilteringMode,
+    ) -> Result<Self, Error> {
+        let wrapping_mode = wrapping_mode.map(|wrap| ctx.supported_wrap(wrap, false));
         let texture = unsafe {
-            let texture = ctx.gl.create_texture()?;
-            ctx.gl.bind_texture(TEXTURE_2D, Some(texture));
-            ctx.gl
-                .tex_parameter_i32(TEXTURE_2D, TEXTURE_WRAP_S, wrapping_mode_s as _);
-            ctx.gl
-                .tex_parameter_i32(TEXTURE_2D, TEXTURE_WRAP_T, wrapping_mode_t as _);
-            ctx.gl
-                .tex_parameter_i32(TEXTURE_2D, TEXTURE_MAG_FILTER, mag_filter as _);
-            ctx.gl
-                .tex_parameter_i32(TEXTURE_2D, TEXTURE_MIN_FILTER, min_filter as _);
-            ctx.gl.bind_texture(TEXTURE_2D, None);
-            texture
+            if ctx.supports_dsa() {
+                let texture = ctx
+                    .gl
+                    .create_named_texture(target)
+                    .map_err(Error::TextureAlloc)?;
+                ctx.gl
+                    .texture_parameter_i32(texture, TEXTURE_WRAP_S, wrapping_mode[0] as _);
+                ctx.gl
+                    .texture_parameter_i32(texture, TEXTURE_WRAP_T, wrapping_mode[1] as _);
+                ctx.gl
+                    .texture_parameter_i32(texture, TEXTURE_MAG_FILTER, mag_filter as _);
+                ctx.gl
+                    .texture_parameter_i32(texture, TEXTURE_MIN_FILTER, min_filter as _);
+                texture
+            } else {
+                let texture = ctx.gl.create_texture().map_err(Error::TextureAlloc)?;
+                ctx.bind_scratch_texture(target, texture);
+                ctx.gl
+                    .tex_parameter_i32(target, TEXTURE_WRAP_S, wrapping_mode[0] as _);
+                ctx.gl
+                    .tex_parameter_i32(target, TEXTURE_WRAP_T, wrapping_mode[1] as _);
+                ctx.gl
+                    .tex_parameter_i32(target, TEXTURE_MAG_FILTER, mag_filter as _);
+                ctx.gl
+                    .tex_parameter_i32(target, TEXTURE_MIN_FILTER, min_filter as _);
+                ctx.release_scratch_texture(target);
+                texture
+            }
         };
         Ok(Self {
-            texture: texture,
+            texture,
+            target,
+            min_filter,
+            mipmap_policy: MipmapPolicy::None,
             gl: ctx.gl.clone(),
+            share_group: ctx.share_group.clone(),
+            memory: MemoryAllocation::new(ctx, ResourceKind::Texture, 0),
         })
     }
 
-    /// Set the TEXTURE_BORDER_COLOR texture parameter.
-    pub fn set_border_color(&self, ctx: &mut ManagedContext, color: [f32; 4]) {
+    /// Take ownership of a texture object created outside of rapax. It will be freed on drop.
+    pub fn from_raw_gl(ctx: &ManagedContext, texture: NativeTexture, target: u32) -> Self {
+        Self {
+            texture,
+            target,
+            min_filter: TextureFilteringMode::Linear,
+            mipmap_policy: MipmapPolicy::None,
+            gl: ctx.gl.clone(),
+            share_group: ctx.share_group.clone(),
+            memory: MemoryAllocation::new(ctx, ResourceKind::Texture, 0),
+        }
+    }
+
+    /// Account the texture's memory under `label` in [`ManagedContext::memory_report`], e.g. "ui" or "shadows".
+    pub fn with_memory_label(mut self, label: &str) -> Self {
+        self.memory.set_label(label);
+        self
+    }
+
+    /// Set the maximum anisotropy, 1.0 to disable anisotropic filtering. Clamped to the driver's maximum.
+    ///
+    /// Skipped with a capability warning without [`Features::ANISOTROPIC_FILTERING`].
+    pub fn set_anisotropy(&self, ctx: &mut ManagedContext, anisotropy: f32) {
+        if !ctx.warn_missing_features(
+            Features::ANISOTROPIC_FILTERING,
+            "anisotropic filtering ignored",
+        ) {
+            return;
+        }
         unsafe {
-            ctx.gl.bind_texture(TEXTURE_2D, Some(self.texture));
+            let anisotropy = anisotropy.clamp(1.0, ctx.capabilities.max_anisotropy.max(1.0));
+            ctx.bind_scratch_texture(self.target, self.texture);
             ctx.gl
-                .tex_parameter_f32_slice(TEXTURE_2D, TEXTURE_BORDER_COLOR, &color);
-            ctx.gl.bind_texture(TEXTURE_2D, None);
+                .tex_parameter_f32(self.target, TEXTURE_MAX_ANISOTROPY, anisotropy);
+            ctx.release_scratch_texture(self.target);
+        }
+    }
+
+    /// Set the mipmap generation policy.
+    pub fn with_mipmap_policy(mut self, policy: MipmapPolicy) -> Self {
+        self.mipmap_policy = policy;
+        self
+    }
+
+    /// Set the color sampled outside [`TextureWrap::ClampToBorder`] axes. The components are used as they are, like
+    /// texel values, so for sRGB textures they are sRGB-encoded and for linear textures linear.
+    ///
+    /// Skipped with a capability warning without [`Features::CLAMP_TO_BORDER`]; sample with
+    /// [`shaders::SAMPLE_BORDER`] to emulate the border instead.
+    pub fn set_border_color(&self, ctx: &mut ManagedContext, color: impl Into<Color>) {
+        if !ctx.warn_missing_features(Features::CLAMP_TO_BORDER, "texture border color ignored") {
+            return;
+        }
+        unsafe {
+            ctx.bind_scratch_texture(self.target, self.texture);
+            ctx.gl.tex_parameter_f32_slice(
+                self.target,
+                TEXTURE_BORDER_COLOR,
+                &color.into().to_srgb(),
+            );
+            ctx.release_scratch_texture(self.target);
         }
     }
 
     /// Upload/allocate 2D texture data and receive a [`Texture2D`] instance.
+    ///
+    /// Formats requiring unsupported [`Features`] are downgraded, see [`ManagedContext::set_capability_warning_handler`].
+    ///
+    /// ## Panics
+    /// The texture must have been created using [`TextureHandle::new`].
+    #[allow(clippy::too_many_arguments)]
     pub fn allocate_2d_data(
         self,
         ctx: &mut ManagedContext,
@@ -151,8 +415,52 @@ impl TextureHandle {
         height: i32,
         ty: DataType,
     ) -> Texture2D {
+        self.allocate_2d_data_with(
+            ctx,
+            data,
+            internal_format,
+            format,
+            width,
+            height,
+            ty,
+            UploadOptions::default(),
+        )
+    }
+
+    /// Upload/allocate 2D texture data like [`TextureHandle::allocate_2d_data`], e.g. flipping it vertically.
+    ///
+    /// ## Panics
+    /// The texture must have been created using [`TextureHandle::new`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn allocate_2d_data_with(
+        mut self,
+        ctx: &mut ManagedContext,
+        data: Option<&[u8]>,
+        internal_format: InternalTextureFormat,
+        format: TextureFormat,
+        width: i32,
+        height: i32,
+        ty: DataType,
+        options: UploadOptions,
+    ) -> Texture2D {
+        span!(
+            "allocate_2d_data",
+            texture = self.texture.0.get(),
+            width,
+            height,
+            bytes = data.map(|data| data.len()).unwrap_or(0)
+        );
+        assert_eq!(self.target, TEXTURE_2D, "texture is not a 2D texture");
+        let requested_format = internal_format;
+        let internal_format = ctx.supported_format(internal_format);
+        let mut transfer = ctx.pixel_transfer(internal_format, format, ty, data);
+        if options.flip_y {
+            transfer.flip_rows(ctx, width, height);
+        }
+        self.memory
+            .resize(texture_bytes(&self, internal_format, width, height, 1));
         unsafe {
-            ctx.gl.bind_texture(TEXTURE_2D, Some(self.texture));
+            ctx.bind_scratch_texture(TEXTURE_2D, self.texture);
             ctx.gl.tex_image_2d(
                 TEXTURE_2D,
                 0,
@@ -160,12 +468,52 @@ impl TextureHandle {
                 width,
                 height,
                 0,
-                format as _,
-                ty as _,
-                data,
+                transfer.format as _,
+                transfer.ty as _,
+                transfer.data.as_deref(),
             );
-            ctx.gl.bind_texture(TEXTURE_2D, None);
-            Texture2D(self)
+            ctx.apply_format_swizzle(TEXTURE_2D, requested_format, internal_format);
+            if data.is_some() {
+                self.apply_mipmap_policy(ctx);
+                self.apply_cpu_mipmap_policy(ctx, internal_format, &transfer, width, height);
+            }
+            ctx.release_scratch_texture(TEXTURE_2D);
+            Texture2D {
+                handle: self,
+                internal_format,
+                format,
+                ty,
+                width,
+                height,
+            }
+        }
+    }
+
+    /// Regenerate mipmaps of the bound texture if required by the mipmap policy.
+    pub(crate) unsafe fn apply_mipmap_policy(&self, ctx: &ManagedContext) {
+        if self.mipmap_policy == MipmapPolicy::Auto && self.min_filter.uses_mipmaps() {
+            if ctx.supports_dsa() {
+                ctx.gl.generate_texture_mipmap(self.texture);
+            } else {
+                ctx.gl.generate_mipmap(self.target);
+            }
+        }
+    }
+
+    /// Generate mipmaps of the bound 2D texture on the CPU from a whole-texture upload, if required by the mipmap
+    /// policy.
+    pub(crate) unsafe fn apply_cpu_mipmap_policy(
+        &self,
+        ctx: &ManagedContext,
+        internal_format: InternalTextureFormat,
+        transfer: &PixelTransfer,
+        width: i32,
+        height: i32,
+    ) {
+        if let MipmapPolicy::Cpu(filter) = self.mipmap_policy {
+            if self.min_filter.uses_mipmaps() {
+                upload_cpu_mipmaps(ctx, internal_format, transfer, width, height, filter);
+            }
         }
     }
 }
@@ -194,9 +542,23 @@ impl BindableTexture for &BindableNativeTexture {
 }
 
 pub trait BindableTexture {
+    /// Bind the texture to the given target.
+    ///
+    /// # Safety
+    /// The caller must ensure `target` is compatible with the underlying texture object.
     unsafe fn bind(&self, target: u32, gl: &Context);
 
     fn texture_target_hint(&self) -> u32;
+
+    /// The texture object bound by [`BindableTexture::bind`], used to skip redundant binds.
+    fn native_texture(&self) -> Option<NativeTexture> {
+        None
+    }
+
+    /// Whether the texture has a depth format, so it can be sampled by shadow samplers. `None` if unknown.
+    fn is_depth(&self) -> Option<bool> {
+        None
+    }
 }
 
 impl Drop for TextureHandle {
@@ -204,5 +566,6 @@ impl Drop for TextureHandle {
         unsafe {
             self.gl.delete_texture(self.texture);
         }
+        self.share_group.forget_texture(self.texture);
     }
 }
@@ -0,0 +1,165 @@
+use super::*;
+
+use std::ops::Deref;
+
+/// Pixel data adapted to the format a texture was actually allocated with, see [`ManagedContext::pixel_transfer`].
+pub(crate) struct PixelTransfer<'a> {
+    pub(crate) format: TextureFormat,
+    pub(crate) ty: DataType,
+    pub(crate) data: Option<TransferData<'a>>,
+}
+
+/// The caller's pixel data, or a converted copy in a staging buffer.
+pub(crate) enum TransferData<'a> {
+    Borrowed(&'a [u8]),
+    Staged(StagingBuffer),
+}
+
+impl Deref for TransferData<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Borrowed(data) => data,
+            Self::Staged(buffer) => buffer,
+        }
+    }
+}
+
+impl PixelTransfer<'_> {
+    /// Reverse the row order of `width` x `height` pixels, for uploads with [`UploadOptions::flip_y`].
+    ///
+    /// Rows are padded to the current unpack alignment, except for the last one, as GL reads them.
+    ///
+    /// ## Panics
+    /// The data must contain every row.
+    pub(crate) fn flip_rows(&mut self, ctx: &ManagedContext, width: i32, height: i32) {
+        let Some(data) = self.data.take() else {
+            return;
+        };
+        let (width, height) = (width.max(0) as usize, height.max(0) as usize);
+        let row_len = width * self.format.pixel_size(self.ty);
+        let stride = row_stride(
+            width,
+            self.format.pixel_size(self.ty),
+            ctx.unpack_alignment(),
+        );
+        let mut buffer = ctx.staging_buffer(data.len());
+        for row in (0..height).rev() {
+            buffer.extend_from_slice(&data[row * stride..row * stride + row_len]);
+            if row > 0 {
+                let padded = buffer.len() + stride - row_len;
+                buffer.resize(padded, 0);
+            }
+        }
+        self.data = Some(TransferData::Staged(buffer));
+    }
+}
+
+/// The length in bytes of a row of `width` pixels, padded to the pack or unpack `alignment`.
+pub(crate) fn row_stride(width: usize, pixel_size: usize, alignment: usize) -> usize {
+    (width * pixel_size).next_multiple_of(alignment.max(1))
+}
+
+impl ManagedContext {
+    /// The current `UNPACK_ALIGNMENT`, which rows of uploaded pixel data are padded to.
+    pub(crate) fn unpack_alignment(&self) -> usize {
+        unsafe { self.gl.get_parameter_i32(UNPACK_ALIGNMENT) as usize }
+    }
+
+    /// Adapt the transfer format and pixel data of an upload to a texture whose format was downgraded by
+    /// [`ManagedContext::supported_format`].
+    ///
+    /// Luminance data is uploaded as red/green data, and 16-bit or floating point data for formats only desktop GL
+    /// has is converted to 8 bits.
+    pub(crate) fn pixel_transfer<'a>(
+        &self,
+        internal_format: InternalTextureFormat,
+        format: TextureFormat,
+        ty: DataType,
+        data: Option<&'a [u8]>,
+    ) -> PixelTransfer<'a> {
+        let format = match format {
+            TextureFormat::Alpha | TextureFormat::Luminance
+                if internal_format == InternalTextureFormat::R8 =>
+            {
+                TextureFormat::Red
+            }
+            TextureFormat::LuminanceAlpha if internal_format == InternalTextureFormat::Rg8 => {
+                TextureFormat::Rg
+            }
+            format => format,
+        };
+
+        let narrow = !self.features.contains(Features::DESKTOP_TEXTURE_FORMATS)
+            && matches!(
+                internal_format,
+                InternalTextureFormat::R8
+                    | InternalTextureFormat::Rg8
+                    | InternalTextureFormat::Rgb8
+                    | InternalTextureFormat::Rgba8
+                    | InternalTextureFormat::Rgba4
+            )
+            && matches!(ty, DataType::UnsignedShort | DataType::Float);
+        if !narrow {
+            return PixelTransfer {
+                format,
+                ty,
+                data: data.map(TransferData::Borrowed),
+            };
+        }
+
+        PixelTransfer {
+            format,
+            ty: DataType::UnsignedByte,
+            data: data.map(|data| {
+                let mut buffer = self.staging_buffer(data.len() / ty.sizeof());
+                to_unsigned_bytes(data, ty, &mut buffer);
+                TransferData::Staged(buffer)
+            }),
+        }
+    }
+
+    /// Make textures of a luminance format downgraded to [`InternalTextureFormat::R8`] or
+    /// [`InternalTextureFormat::Rg8`] sample like the `requested` format. The texture must be bound to `target`.
+    pub(crate) unsafe fn apply_format_swizzle(
+        &self,
+        target: u32,
+        requested: InternalTextureFormat,
+        allocated: InternalTextureFormat,
+    ) {
+        if requested == allocated {
+            return;
+        }
+        let swizzle = match requested {
+            InternalTextureFormat::Alpha => [ZERO, ZERO, ZERO, RED],
+            InternalTextureFormat::Luminance => [RED, RED, RED, ONE],
+            InternalTextureFormat::LuminanceAlpha => [RED, RED, RED, GREEN],
+            _ => return,
+        };
+        let parameters = [
+            TEXTURE_SWIZZLE_R,
+            TEXTURE_SWIZZLE_G,
+            TEXTURE_SWIZZLE_B,
+            TEXTURE_SWIZZLE_A,
+        ];
+        for (parameter, source) in parameters.into_iter().zip(swizzle) {
+            self.gl.tex_parameter_i32(target, parameter, source as i32);
+        }
+    }
+}
+
+/// Convert normalized 16-bit or floating point components to 8 bits, appending them to `out`.
+fn to_unsigned_bytes(data: &[u8], ty: DataType, out: &mut Vec<u8>) {
+    match ty {
+        DataType::UnsignedShort => out.extend(
+            data.chunks_exact(2)
+                .map(|c| (u16::from_ne_bytes([c[0], c[1]]) >> 8) as u8),
+        ),
+        DataType::Float => out.extend(data.chunks_exact(4).map(|c| {
+            let value = f32::from_ne_bytes([c[0], c[1], c[2], c[3]]);
+            (value.clamp(0.0, 1.0) * 255.0).round() as u8
+        })),
+        _ => out.extend_from_slice(data),
+    }
+}
@@ -0,0 +1,317 @@
+use super::*;
+
+use std::f32::consts::PI;
+
+/// The filter used to generate mipmaps on the CPU, see [`MipmapPolicy::Cpu`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MipmapFilter {
+    /// Averages the pixels each level pixel covers. Fast, but distant detail gets blurry.
+    Box,
+    /// A Kaiser-windowed sinc filter. Keeps distant detail sharper than [`MipmapFilter::Box`] at the cost of slight
+    /// ringing around hard edges.
+    Kaiser,
+}
+
+/// The radius of the Kaiser filter, in pixels of the smaller level.
+const KAISER_RADIUS: f32 = 3.0;
+/// The shape parameter of the Kaiser window; higher values trade sharpness for less ringing.
+const KAISER_ALPHA: f32 = 4.0;
+
+impl Texture2D {
+    /// Generate texture mipmaps on the CPU from `data`, the texture's base level in the pixel format it was allocated
+    /// with, see [`Texture2D::pixel_format`].
+    ///
+    /// Useful where `glGenerateMipmap` produces poor results, e.g. box filtering sRGB textures in sRGB space, or
+    /// doesn't support the texture's format. sRGB textures are filtered in linear space. Only
+    /// [`DataType::UnsignedByte`] and [`DataType::Float`] data is filtered on the CPU; other types fall back to
+    /// [`Texture2D::generate_mipmaps`].
+    ///
+    /// ## Panics
+    /// `data` must contain the whole base level.
+    pub fn generate_mipmaps_cpu(
+        &self,
+        ctx: &mut ManagedContext,
+        data: &[u8],
+        filter: MipmapFilter,
+    ) {
+        let transfer = ctx.pixel_transfer(self.internal_format, self.format, self.ty, Some(data));
+        unsafe {
+            ctx.bind_scratch_texture(TEXTURE_2D, self.handle.texture);
+            upload_cpu_mipmaps(
+                ctx,
+                self.internal_format,
+                &transfer,
+                self.width,
+                self.height,
+                filter,
+            );
+            ctx.release_scratch_texture(TEXTURE_2D);
+        }
+    }
+}
+
+/// A mipmap level generated on the CPU, tightly packed.
+#[derive(Debug, Clone)]
+pub(crate) struct MipLevel {
+    pub(crate) width: i32,
+    pub(crate) height: i32,
+    pub(crate) data: Vec<u8>,
+}
+
+/// Filter the base level in `data`, whose rows are padded to `alignment`, down to 1x1, returning every level after it,
+/// or `None` if `ty` can't be filtered on the CPU.
+///
+/// ## Panics
+/// `data` must contain the whole base level.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn generate_mip_chain(
+    data: &[u8],
+    internal_format: InternalTextureFormat,
+    format: TextureFormat,
+    ty: DataType,
+    width: i32,
+    height: i32,
+    alignment: usize,
+    filter: MipmapFilter,
+) -> Option<Vec<MipLevel>> {
+    if !matches!(ty, DataType::UnsignedByte | DataType::Float) {
+        return None;
+    }
+    span!("generate_mip_chain", width, height);
+
+    let components = format.components();
+    let pixel_size = format.pixel_size(ty);
+    let (width, height) = (width.max(1) as usize, height.max(1) as usize);
+    let row_len = width * pixel_size;
+    let stride = row_stride(width, pixel_size, alignment);
+    assert!(
+        data.len() >= (height - 1) * stride + row_len,
+        "data doesn't contain the whole base level!"
+    );
+    // sRGB color components are filtered in linear space, alpha is linear already
+    let srgb_components = match internal_format {
+        InternalTextureFormat::Srgb8 | InternalTextureFormat::Srgb8Alpha8 if components >= 3 => 3,
+        _ => 0,
+    };
+
+    let mut level: Vec<f32> = data
+        .chunks(stride)
+        .take(height)
+        .flat_map(|row| decode(&row[..row_len], ty))
+        .collect();
+    for (i, value) in level.iter_mut().enumerate() {
+        if i % components < srgb_components {
+            *value = srgb_to_linear(*value);
+        }
+    }
+
+    let (mut width, mut height) = (width, height);
+    let mut levels = vec![];
+    while width > 1 || height > 1 {
+        let next_width = (width / 2).max(1);
+        let next_height = (height / 2).max(1);
+        let rows = resample(&level, width, height, components, next_width, 1, filter);
+        level = resample(
+            &rows,
+            next_width,
+            height,
+            components,
+            next_height,
+            next_width,
+            filter,
+        );
+        (width, height) = (next_width, next_height);
+        levels.push(MipLevel {
+            width: width as i32,
+            height: height as i32,
+            data: encode(&level, components, srgb_components, ty),
+        });
+    }
+    Some(levels)
+}
+
+/// Filter the base level in `transfer` down to 1x1 and upload every level after it to the bound 2D texture.
+pub(crate) unsafe fn upload_cpu_mipmaps(
+    ctx: &ManagedContext,
+    internal_format: InternalTextureFormat,
+    transfer: &PixelTransfer,
+    width: i32,
+    height: i32,
+    filter: MipmapFilter,
+) {
+    let Some(data) = transfer.data.as_deref() else {
+        return;
+    };
+    let alignment = ctx.unpack_alignment();
+    let Some(levels) = generate_mip_chain(
+        data,
+        internal_format,
+        transfer.format,
+        transfer.ty,
+        width,
+        height,
+        alignment,
+        filter,
+    ) else {
+        ctx.gl.generate_mipmap(TEXTURE_2D);
+        return;
+    };
+
+    ctx.gl.pixel_store_i32(UNPACK_ALIGNMENT, 1);
+    for (index, level) in levels.iter().enumerate() {
+        ctx.gl.tex_image_2d(
+            TEXTURE_2D,
+            index as i32 + 1,
+            internal_format as _,
+            level.width,
+            level.height,
+            0,
+            transfer.format as _,
+            transfer.ty as _,
+            Some(&level.data),
+        );
+    }
+    ctx.gl.pixel_store_i32(UNPACK_ALIGNMENT, alignment as i32);
+}
+
+/// Resample an image along one axis from `len` to `next_len` pixels. `step` is the distance between neighboring pixels
+/// along the axis, in pixels: 1 for rows, the image width for columns.
+fn resample(
+    src: &[f32],
+    width: usize,
+    height: usize,
+    components: usize,
+    next_len: usize,
+    step: usize,
+    filter: MipmapFilter,
+) -> Vec<f32> {
+    let (len, lines, line_step) = if step == 1 {
+        (width, height, width)
+    } else {
+        (height, width, 1)
+    };
+    if len == next_len {
+        return src.to_vec();
+    }
+
+    let weights: Vec<Vec<(usize, f32)>> = (0..next_len)
+        .map(|i| filter_weights(filter, len, next_len, i))
+        .collect();
+    let (next_width, next_height) = if step == 1 {
+        (next_len, height)
+    } else {
+        (width, next_len)
+    };
+    let mut dst = vec![0.0; next_width * next_height * components];
+    for line in 0..lines {
+        for (i, taps) in weights.iter().enumerate() {
+            let dst_pixel = if step == 1 {
+                line * next_width + i
+            } else {
+                i * next_width + line
+            };
+            for (j, weight) in taps {
+                let src_pixel = line * line_step + j * step;
+                for c in 0..components {
+                    dst[dst_pixel * components + c] += weight * src[src_pixel * components + c];
+                }
+            }
+        }
+    }
+    dst
+}
+
+/// The normalized source pixels & weights contributing to pixel `i` when resampling `len` pixels to `next_len`.
+fn filter_weights(
+    filter: MipmapFilter,
+    len: usize,
+    next_len: usize,
+    i: usize,
+) -> Vec<(usize, f32)> {
+    let scale = len as f32 / next_len as f32;
+    let center = (i as f32 + 0.5) * scale;
+    let radius = match filter {
+        MipmapFilter::Box => 0.5,
+        MipmapFilter::Kaiser => KAISER_RADIUS,
+    } * scale;
+
+    let first = (center - radius).floor() as isize;
+    let last = (center + radius).ceil() as isize;
+    let mut taps: Vec<(usize, f32)> = (first..last)
+        .filter_map(|j| {
+            let weight = match filter {
+                // the part of the source pixel inside the box
+                MipmapFilter::Box => {
+                    let overlap =
+                        (j as f32 + 1.0).min(center + radius) - (j as f32).max(center - radius);
+                    overlap.max(0.0)
+                }
+                MipmapFilter::Kaiser => kaiser((j as f32 + 0.5 - center) / scale),
+            };
+            // clamp to the edge
+            (weight != 0.0).then(|| (j.clamp(0, len as isize - 1) as usize, weight))
+        })
+        .collect();
+
+    let sum: f32 = taps.iter().map(|(_, weight)| weight).sum();
+    for (_, weight) in &mut taps {
+        *weight /= sum;
+    }
+    taps
+}
+
+/// The Kaiser-windowed sinc at `x`, in pixels of the smaller level.
+fn kaiser(x: f32) -> f32 {
+    if x.abs() >= KAISER_RADIUS {
+        return 0.0;
+    }
+    let sinc = if x == 0.0 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    };
+    let t = x / KAISER_RADIUS;
+    sinc * bessel_i0(KAISER_ALPHA * (1.0 - t * t).sqrt()) / bessel_i0(KAISER_ALPHA)
+}
+
+/// The zeroth-order modified Bessel function of the first kind, by its power series.
+fn bessel_i0(x: f32) -> f32 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let quarter_x2 = x * x / 4.0;
+    for k in 1..32 {
+        term *= quarter_x2 / (k * k) as f32;
+        sum += term;
+        if term < sum * 1e-7 {
+            break;
+        }
+    }
+    sum
+}
+
+fn decode(row: &[u8], ty: DataType) -> Vec<f32> {
+    match ty {
+        DataType::Float => row
+            .chunks_exact(4)
+            .map(|bytes| f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect(),
+        _ => row.iter().map(|&byte| byte as f32 / 255.0).collect(),
+    }
+}
+
+fn encode(level: &[f32], components: usize, srgb_components: usize, ty: DataType) -> Vec<u8> {
+    let values = level.iter().enumerate().map(|(i, &value)| {
+        if i % components < srgb_components {
+            linear_to_srgb(value.max(0.0))
+        } else {
+            value
+        }
+    });
+    match ty {
+        DataType::Float => values.flat_map(f32::to_ne_bytes).collect(),
+        _ => values
+            .map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect(),
+    }
+}
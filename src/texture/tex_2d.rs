@@ -2,21 +2,60 @@ use super::*;
 
 /// A 2D texture in GPU memory.
 #[derive(Debug)]
-pub struct Texture2D(pub(crate) TextureHandle);
+pub struct Texture2D {
+    pub(crate) handle: TextureHandle,
+    pub(crate) internal_format: InternalTextureFormat,
+    pub(crate) format: TextureFormat,
+    pub(crate) ty: DataType,
+    pub(crate) width: i32,
+    pub(crate) height: i32,
+}
 
 impl Texture2D {
+    /// Wrap an externally produced texture, e.g. from a video decoder, taking ownership of it.
+    ///
+    /// `target` is usually `TEXTURE_2D` or [`TEXTURE_EXTERNAL_OES`]. The pixel format is assumed to be RGBA8; external
+    /// textures can only be sampled, not written.
+    pub fn from_raw_gl(
+        ctx: &ManagedContext,
+        texture: NativeTexture,
+        target: u32,
+        width: i32,
+        height: i32,
+    ) -> Self {
+        Self {
+            handle: TextureHandle::from_raw_gl(ctx, texture, target),
+            internal_format: InternalTextureFormat::Rgba8,
+            format: TextureFormat::Rgba,
+            ty: DataType::UnsignedByte,
+            width,
+            height,
+        }
+    }
+
     /// Generate texture mipmaps, should be called when texture data changes.
     pub fn generate_mipmaps(&self, ctx: &mut ManagedContext) {
         unsafe {
-            ctx.gl.bind_texture(TEXTURE_2D, Some(self.0.texture));
+            if ctx.supports_dsa() {
+                ctx.gl.generate_texture_mipmap(self.handle.texture);
+                return;
+            }
+
+            ctx.bind_scratch_texture(TEXTURE_2D, self.handle.texture);
             ctx.gl.generate_mipmap(TEXTURE_2D);
-            ctx.gl.bind_texture(TEXTURE_2D, None);
+            ctx.release_scratch_texture(TEXTURE_2D);
         }
     }
 
     /// Upload a sub-image.
     ///
-    /// Mipmaps should be regenerated after the texture is modified.
+    /// Mipmaps should be regenerated after the texture is modified, unless the texture uses [`MipmapPolicy::Auto`].
+    ///
+    /// Returns [`Error::PixelDataSize`] if `data` doesn't cover the region, with rows padded to the unpack alignment.
+    ///
+    /// ## Panics
+    /// The region being written must lie inside the texture. External textures, see [`Texture2D::from_raw_gl`], can't be written.
+    #[allow(clippy::too_many_arguments)]
     pub fn write_subimage(
         &self,
         ctx: &mut ManagedContext,
@@ -27,25 +66,165 @@ impl Texture2D {
         format: TextureFormat,
         ty: DataType,
         data: &[u8],
+    ) -> Result<(), Error> {
+        self.write_subimage_with(
+            ctx,
+            Rect::new(x_offset, y_offset, width, height),
+            format,
+            ty,
+            data,
+            UploadOptions::default(),
+        )
+    }
+
+    /// Upload a sub-image like [`Texture2D::write_subimage`], e.g. flipping it vertically.
+    ///
+    /// `rect` is the destination region and uses OpenGL's bottom-left origin even when flipping. Fails like
+    /// [`Texture2D::write_subimage`] if `data` is too short.
+    ///
+    /// ## Panics
+    /// The region being written must lie inside the texture. External textures, see [`Texture2D::from_raw_gl`], can't be written.
+    pub fn write_subimage_with(
+        &self,
+        ctx: &mut ManagedContext,
+        rect: Rect,
+        format: TextureFormat,
+        ty: DataType,
+        data: &[u8],
+        options: UploadOptions,
+    ) -> Result<(), Error> {
+        span!(
+            "write_subimage",
+            texture = self.handle.texture.0.get(),
+            width = rect.width,
+            height = rect.height,
+            bytes = data.len()
+        );
+        self.assert_writable();
+        assert!(
+            rect.fits_within(self.width, self.height),
+            "out of bounds write!"
+        );
+        let (width, height) = (rect.width as usize, rect.height as usize);
+        let pixel_size = format.pixel_size(ty);
+        let expected = match height {
+            0 => 0,
+            _ => {
+                (height - 1) * row_stride(width, pixel_size, ctx.unpack_alignment())
+                    + width * pixel_size
+            }
+        };
+        if data.len() < expected {
+            return Err(Error::PixelDataSize {
+                expected,
+                actual: data.len(),
+            });
+        }
+
+        let mut transfer = ctx.pixel_transfer(self.internal_format, format, ty, Some(data));
+        if options.flip_y {
+            transfer.flip_rows(ctx, rect.width, rect.height);
+        }
+        unsafe { self.tex_sub_image_2d(ctx, rect, &transfer) };
+        Ok(())
+    }
+
+    /// Upload the region `dst_rect` of a larger CPU-side image that covers the texture, without copying it into a temporary buffer first.
+    ///
+    /// `src_pixels` starts at the image origin and uses the texture's pixel format; `src_stride_bytes` is the length of one image row.
+    ///
+    /// ## Panics
+    /// The region being written must lie inside the texture, and `src_pixels` must contain it. External textures, see [`Texture2D::from_raw_gl`], can't be written.
+    pub fn write_subimage_strided(
+        &self,
+        ctx: &mut ManagedContext,
+        dst_rect: Rect,
+        src_pixels: &[u8],
+        src_stride_bytes: usize,
     ) {
+        span!(
+            "write_subimage_strided",
+            texture = self.handle.texture.0.get(),
+            width = dst_rect.width,
+            height = dst_rect.height
+        );
+        self.assert_writable();
+        assert!(
+            dst_rect.fits_within(self.width, self.height),
+            "out of bounds write!"
+        );
+        let pixel_size = self.format.pixel_size(self.ty);
+        assert!(
+            src_stride_bytes % pixel_size == 0,
+            "stride must be a multiple of the pixel size!"
+        );
+        let row_length = src_stride_bytes / pixel_size;
+        let right = (dst_rect.x + dst_rect.width) as usize;
+        let bottom = (dst_rect.y + dst_rect.height) as usize;
+        assert!(
+            right <= row_length
+                && src_pixels.len()
+                    >= bottom.saturating_sub(1) * src_stride_bytes + right * pixel_size,
+            "out of bounds read!"
+        );
+
+        let transfer =
+            ctx.pixel_transfer(self.internal_format, self.format, self.ty, Some(src_pixels));
         unsafe {
-            ctx.gl.bind_texture(TEXTURE_2D, Some(self.0.texture));
-            ctx.gl.tex_sub_image_2d(
-                TEXTURE_2D,
-                0,
-                x_offset,
-                y_offset,
-                width,
-                height,
-                format as _,
-                ty as _,
-                PixelUnpackData::Slice(data),
+            ctx.gl.pixel_store_i32(UNPACK_ALIGNMENT, 1);
+            ctx.gl.pixel_store_i32(UNPACK_ROW_LENGTH, row_length as i32);
+            ctx.gl.pixel_store_i32(UNPACK_SKIP_PIXELS, dst_rect.x);
+            ctx.gl.pixel_store_i32(UNPACK_SKIP_ROWS, dst_rect.y);
+            self.tex_sub_image_2d(ctx, dst_rect, &transfer);
+            ctx.gl.pixel_store_i32(UNPACK_SKIP_ROWS, 0);
+            ctx.gl.pixel_store_i32(UNPACK_SKIP_PIXELS, 0);
+            ctx.gl.pixel_store_i32(UNPACK_ROW_LENGTH, 0);
+            ctx.gl.pixel_store_i32(UNPACK_ALIGNMENT, 4);
+        }
+    }
+
+    fn assert_writable(&self) {
+        assert_ne!(
+            self.handle.target, TEXTURE_EXTERNAL_OES,
+            "external textures can only be sampled!"
+        );
+    }
+
+    /// Write `transfer` into `rect` of the base level, without binding the texture if direct state access is loaded.
+    unsafe fn tex_sub_image_2d(&self, ctx: &ManagedContext, rect: Rect, transfer: &PixelTransfer) {
+        let data = transfer.data.as_deref().unwrap_or_default();
+        if let Some(dsa) = ctx.dsa() {
+            dsa.texture_sub_image_2d(
+                self.handle.texture,
+                rect,
+                transfer.format,
+                transfer.ty,
+                data,
             );
-            ctx.gl.bind_texture(TEXTURE_2D, None);
+            self.handle.apply_mipmap_policy(ctx);
+            return;
         }
+
+        ctx.bind_scratch_texture(TEXTURE_2D, self.handle.texture);
+        ctx.gl.tex_sub_image_2d(
+            TEXTURE_2D,
+            0,
+            rect.x,
+            rect.y,
+            rect.width,
+            rect.height,
+            transfer.format as _,
+            transfer.ty as _,
+            PixelUnpackData::Slice(data),
+        );
+        self.handle.apply_mipmap_policy(ctx);
+        ctx.release_scratch_texture(TEXTURE_2D);
     }
 
     /// Reallocate 2D texture data and receive a [`Texture2D`] instance.
+    ///
+    /// Prefer [`Texture2D::reallocate`] when the formats don't change.
+    #[allow(clippy::too_many_arguments)]
     pub fn reallocate_2d_data(
         &mut self,
         ctx: &mut ManagedContext,
@@ -56,30 +235,330 @@ impl Texture2D {
         height: i32,
         ty: DataType,
     ) {
+        self.internal_format = ctx.supported_format(internal_format);
+        self.format = format;
+        self.ty = ty;
+        self.reallocate(ctx, data, width, height);
+        unsafe {
+            ctx.bind_scratch_texture(TEXTURE_2D, self.handle.texture);
+            ctx.apply_format_swizzle(TEXTURE_2D, internal_format, self.internal_format);
+            ctx.release_scratch_texture(TEXTURE_2D);
+        }
+    }
+
+    /// Reallocate 2D texture data, reusing the formats the texture was allocated with.
+    ///
+    /// ## Panics
+    /// External textures, see [`Texture2D::from_raw_gl`], can't be written.
+    pub fn reallocate(
+        &mut self,
+        ctx: &mut ManagedContext,
+        data: Option<&[u8]>,
+        width: i32,
+        height: i32,
+    ) {
+        self.reallocate_with(ctx, data, width, height, UploadOptions::default());
+    }
+
+    /// Reallocate 2D texture data like [`Texture2D::reallocate`], e.g. flipping it vertically.
+    ///
+    /// ## Panics
+    /// External textures, see [`Texture2D::from_raw_gl`], can't be written.
+    pub fn reallocate_with(
+        &mut self,
+        ctx: &mut ManagedContext,
+        data: Option<&[u8]>,
+        width: i32,
+        height: i32,
+        options: UploadOptions,
+    ) {
+        span!(
+            "reallocate",
+            texture = self.handle.texture.0.get(),
+            width,
+            height,
+            bytes = data.map(|data| data.len()).unwrap_or(0)
+        );
+        self.assert_writable();
+        let mut transfer = ctx.pixel_transfer(self.internal_format, self.format, self.ty, data);
+        if options.flip_y {
+            transfer.flip_rows(ctx, width, height);
+        }
+        let bytes = texture_bytes(&self.handle, self.internal_format, width, height, 1);
+        self.handle.memory.resize(bytes);
         unsafe {
-            ctx.gl.bind_texture(TEXTURE_2D, Some(self.0.texture));
+            ctx.bind_scratch_texture(TEXTURE_2D, self.handle.texture);
             ctx.gl.tex_image_2d(
                 TEXTURE_2D,
                 0,
-                internal_format as _,
+                self.internal_format as _,
                 width,
                 height,
                 0,
-                format as _,
-                ty as _,
-                data,
+                transfer.format as _,
+                transfer.ty as _,
+                transfer.data.as_deref(),
             );
-            ctx.gl.bind_texture(TEXTURE_2D, None);
+            if data.is_some() {
+                self.handle.apply_mipmap_policy(ctx);
+                self.handle.apply_cpu_mipmap_policy(
+                    ctx,
+                    self.internal_format,
+                    &transfer,
+                    width,
+                    height,
+                );
+            }
+            ctx.release_scratch_texture(TEXTURE_2D);
+        }
+
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Copy a region of another texture into this texture, without a CPU round trip.
+    ///
+    /// Uses `glCopyImageSubData` when available, otherwise falls back to a framebuffer blit, after which the previously
+    /// bound framebuffers are restored.
+    ///
+    /// ## Panics
+    /// `src_rect` must lie inside `src`, and the destination region must lie inside this texture.
+    pub fn copy_from(
+        &self,
+        ctx: &mut ManagedContext,
+        src: &Texture2D,
+        src_rect: Rect,
+        dst_offset: (i32, i32),
+    ) {
+        assert!(
+            src_rect.fits_within(src.width, src.height),
+            "out of bounds read!"
+        );
+        assert!(
+            Rect::new(dst_offset.0, dst_offset.1, src_rect.width, src_rect.height)
+                .fits_within(self.width, self.height),
+            "out of bounds write!"
+        );
+
+        let info = ctx.driver_info();
+        let copy_image = if info.is_embedded {
+            info.is_at_least(3, 2)
+        } else {
+            info.is_at_least(4, 3)
+        } || ctx.has_extension("GL_ARB_copy_image")
+            || ctx.has_extension("GL_EXT_copy_image");
+
+        unsafe {
+            if copy_image {
+                ctx.gl.copy_image_sub_data(
+                    src.handle.texture,
+                    TEXTURE_2D,
+                    0,
+                    src_rect.x,
+                    src_rect.y,
+                    0,
+                    self.handle.texture,
+                    TEXTURE_2D,
+                    0,
+                    dst_offset.0,
+                    dst_offset.1,
+                    0,
+                    src_rect.width,
+                    src_rect.height,
+                    1,
+                );
+            } else {
+                let previous = ctx.framebuffer_binding();
+                let read_fbo = ctx
+                    .gl
+                    .create_framebuffer()
+                    .expect("framebuffer is required");
+                let draw_fbo = ctx
+                    .gl
+                    .create_framebuffer()
+                    .expect("framebuffer is required");
+                ctx.gl.bind_framebuffer(READ_FRAMEBUFFER, Some(read_fbo));
+                ctx.gl.framebuffer_texture_2d(
+                    READ_FRAMEBUFFER,
+                    COLOR_ATTACHMENT0,
+                    TEXTURE_2D,
+                    Some(src.handle.texture),
+                    0,
+                );
+                ctx.gl.bind_framebuffer(DRAW_FRAMEBUFFER, Some(draw_fbo));
+                ctx.gl.framebuffer_texture_2d(
+                    DRAW_FRAMEBUFFER,
+                    COLOR_ATTACHMENT0,
+                    TEXTURE_2D,
+                    Some(self.handle.texture),
+                    0,
+                );
+                ctx.gl.blit_framebuffer(
+                    src_rect.x,
+                    src_rect.y,
+                    src_rect.x + src_rect.width,
+                    src_rect.y + src_rect.height,
+                    dst_offset.0,
+                    dst_offset.1,
+                    dst_offset.0 + src_rect.width,
+                    dst_offset.1 + src_rect.height,
+                    COLOR_BUFFER_BIT,
+                    NEAREST,
+                );
+                ctx.restore_framebuffer(previous);
+                ctx.gl.delete_framebuffer(read_fbo);
+                ctx.gl.delete_framebuffer(draw_fbo);
+            }
+
+            ctx.bind_scratch_texture(TEXTURE_2D, self.handle.texture);
+            self.handle.apply_mipmap_policy(ctx);
+            ctx.release_scratch_texture(TEXTURE_2D);
         }
     }
+
+    /// Copy a region of the currently bound read framebuffer into this texture.
+    ///
+    /// ## Panics
+    /// The destination region must lie inside this texture.
+    pub fn copy_from_framebuffer(
+        &self,
+        ctx: &mut ManagedContext,
+        src_rect: Rect,
+        dst_offset: (i32, i32),
+    ) {
+        assert!(
+            Rect::new(dst_offset.0, dst_offset.1, src_rect.width, src_rect.height)
+                .fits_within(self.width, self.height),
+            "out of bounds write!"
+        );
+
+        unsafe {
+            ctx.bind_scratch_texture(TEXTURE_2D, self.handle.texture);
+            ctx.gl.copy_tex_sub_image_2d(
+                TEXTURE_2D,
+                0,
+                dst_offset.0,
+                dst_offset.1,
+                src_rect.x,
+                src_rect.y,
+                src_rect.width,
+                src_rect.height,
+            );
+            self.handle.apply_mipmap_policy(ctx);
+            ctx.release_scratch_texture(TEXTURE_2D);
+        }
+    }
+
+    /// Fill the whole texture with a single color.
+    ///
+    /// `glClearTexImage` is not exposed by glow, so the texture is cleared through a temporary framebuffer; the
+    /// previously bound framebuffers are restored afterwards.
+    pub fn clear(&self, ctx: &mut ManagedContext, color: [f32; 4]) {
+        unsafe {
+            let fbo = ctx
+                .gl
+                .create_framebuffer()
+                .expect("framebuffer is required");
+            let previous = ctx.framebuffer_binding();
+            ctx.gl.bind_framebuffer(DRAW_FRAMEBUFFER, Some(fbo));
+            ctx.gl.framebuffer_texture_2d(
+                DRAW_FRAMEBUFFER,
+                COLOR_ATTACHMENT0,
+                TEXTURE_2D,
+                Some(self.handle.texture),
+                0,
+            );
+            // clears respect the write mask & scissor test, which are re-applied by the next pipeline bind
+            ctx.gl.color_mask(true, true, true, true);
+            ctx.gl.disable(SCISSOR_TEST);
+            ctx.gl.clear_buffer_f32_slice(COLOR, 0, &color);
+            ctx.restore_framebuffer(previous);
+            ctx.gl.delete_framebuffer(fbo);
+
+            ctx.bind_scratch_texture(TEXTURE_2D, self.handle.texture);
+            self.handle.apply_mipmap_policy(ctx);
+            ctx.release_scratch_texture(TEXTURE_2D);
+        }
+    }
+
+    /// Tell the driver the contents of a mipmap level are no longer needed, before overwriting them.
+    ///
+    /// glow doesn't expose `glInvalidateTexImage`, so the level's storage is re-specified instead, which lets the
+    /// driver orphan the old storage rather than waiting for draws that still sample it.
+    ///
+    /// ## Panics
+    /// The level must exist. External textures, see [`Texture2D::from_raw_gl`], can't be written.
+    pub fn invalidate(&self, ctx: &mut ManagedContext, level: u32) {
+        self.assert_writable();
+        let width = self.width.checked_shr(level).unwrap_or(0);
+        let height = self.height.checked_shr(level).unwrap_or(0);
+        assert!(width > 0 || height > 0, "no such mipmap level!");
+        let transfer = ctx.pixel_transfer(self.internal_format, self.format, self.ty, None);
+        unsafe {
+            ctx.bind_scratch_texture(TEXTURE_2D, self.handle.texture);
+            ctx.gl.tex_image_2d(
+                TEXTURE_2D,
+                level as i32,
+                self.internal_format as _,
+                width.max(1),
+                height.max(1),
+                0,
+                transfer.format as _,
+                transfer.ty as _,
+                None,
+            );
+            ctx.release_scratch_texture(TEXTURE_2D);
+        }
+    }
+
+    /// Resize the texture, discarding its contents.
+    pub fn resize(&mut self, ctx: &mut ManagedContext, width: i32, height: i32) {
+        self.reallocate(ctx, None, width, height);
+    }
+
+    /// The width of the texture, in pixels.
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// The height of the texture, in pixels.
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// The width & height of the texture, in pixels.
+    pub fn size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+
+    /// The internal format the texture was allocated with.
+    pub fn format(&self) -> InternalTextureFormat {
+        self.internal_format
+    }
+
+    /// The pixel format & data type used when the texture was last allocated.
+    pub fn pixel_format(&self) -> (TextureFormat, DataType) {
+        (self.format, self.ty)
+    }
 }
 
 impl BindableTexture for Texture2D {
     unsafe fn bind(&self, target: u32, gl: &Context) {
-        gl.bind_texture(target, Some(self.0.texture));
+        gl.bind_texture(target, Some(self.handle.texture));
     }
 
     fn texture_target_hint(&self) -> u32 {
-        TEXTURE_2D
+        self.handle.target
+    }
+
+    fn native_texture(&self) -> Option<NativeTexture> {
+        Some(self.handle.texture)
+    }
+
+    fn is_depth(&self) -> Option<bool> {
+        Some(matches!(
+            self.format,
+            TextureFormat::DepthComponent | TextureFormat::DepthStencil
+        ))
     }
 }
@@ -0,0 +1,178 @@
+use super::*;
+
+/// An array of 2D textures of the same size in GPU memory.
+#[derive(Debug)]
+pub struct Texture2DArray {
+    pub(crate) handle: TextureHandle,
+    pub(crate) internal_format: InternalTextureFormat,
+    pub(crate) width: i32,
+    pub(crate) height: i32,
+    pub(crate) layers: i32,
+}
+
+impl TextureHandle {
+    /// Create a new 2D array texture.
+    pub fn new_array(
+        ctx: &mut ManagedContext,
+        wrapping_mode_s: TextureWrap,
+        wrapping_mode_t: TextureWrap,
+        min_filter: TextureFilteringMode,
+        mag_filter: TextureFilteringMode,
+    ) -> Result<Self, Error> {
+        Self::with_target(
+            ctx,
+            TEXTURE_2D_ARRAY,
+            [wrapping_mode_s, wrapping_mode_t],
+            min_filter,
+            mag_filter,
+        )
+    }
+
+    /// Upload/allocate 2D array texture data and receive a [`Texture2DArray`] instance.
+    ///
+    /// ## Panics
+    /// The texture must have been created using [`TextureHandle::new_array`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn allocate_2d_array_data(
+        mut self,
+        ctx: &mut ManagedContext,
+        data: Option<&[u8]>,
+        internal_format: InternalTextureFormat,
+        format: TextureFormat,
+        width: i32,
+        height: i32,
+        layers: i32,
+        ty: DataType,
+    ) -> Texture2DArray {
+        assert_eq!(
+            self.target, TEXTURE_2D_ARRAY,
+            "texture is not a 2D array texture"
+        );
+        let requested_format = internal_format;
+        let internal_format = ctx.supported_format(internal_format);
+        let transfer = ctx.pixel_transfer(internal_format, format, ty, data);
+        self.memory
+            .resize(texture_bytes(&self, internal_format, width, height, layers));
+        unsafe {
+            ctx.bind_scratch_texture(TEXTURE_2D_ARRAY, self.texture);
+            ctx.gl.tex_image_3d(
+                TEXTURE_2D_ARRAY,
+                0,
+                internal_format as _,
+                width,
+                height,
+                layers,
+                0,
+                transfer.format as _,
+                transfer.ty as _,
+                transfer.data.as_deref(),
+            );
+            ctx.apply_format_swizzle(TEXTURE_2D_ARRAY, requested_format, internal_format);
+            if data.is_some() {
+                self.apply_mipmap_policy(ctx);
+            }
+            ctx.release_scratch_texture(TEXTURE_2D_ARRAY);
+        }
+
+        Texture2DArray {
+            handle: self,
+            internal_format,
+            width,
+            height,
+            layers,
+        }
+    }
+}
+
+impl Texture2DArray {
+    /// The width of each layer, in pixels.
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// The height of each layer, in pixels.
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// The number of layers.
+    pub fn layers(&self) -> i32 {
+        self.layers
+    }
+
+    /// The internal format the texture was allocated with.
+    pub fn format(&self) -> InternalTextureFormat {
+        self.internal_format
+    }
+
+    /// Upload pixel data to a single layer.
+    ///
+    /// ## Panics
+    /// The layer must exist.
+    pub fn write_layer(
+        &self,
+        ctx: &mut ManagedContext,
+        layer: i32,
+        format: TextureFormat,
+        ty: DataType,
+        data: &[u8],
+    ) {
+        assert!(layer >= 0 && layer < self.layers, "out of bounds write!");
+        let transfer = ctx.pixel_transfer(self.internal_format, format, ty, Some(data));
+        let (format, ty, data) = (
+            transfer.format,
+            transfer.ty,
+            transfer.data.as_deref().unwrap_or_default(),
+        );
+        unsafe {
+            if ctx.supports_dsa() {
+                ctx.gl.texture_sub_image_3d(
+                    self.handle.texture,
+                    0,
+                    0,
+                    0,
+                    layer,
+                    self.width,
+                    self.height,
+                    1,
+                    format as _,
+                    ty as _,
+                    PixelUnpackData::Slice(data),
+                );
+                self.handle.apply_mipmap_policy(ctx);
+                return;
+            }
+
+            ctx.bind_scratch_texture(TEXTURE_2D_ARRAY, self.handle.texture);
+            ctx.gl.tex_sub_image_3d(
+                TEXTURE_2D_ARRAY,
+                0,
+                0,
+                0,
+                layer,
+                self.width,
+                self.height,
+                1,
+                format as _,
+                ty as _,
+                PixelUnpackData::Slice(data),
+            );
+            self.handle.apply_mipmap_policy(ctx);
+            ctx.release_scratch_texture(TEXTURE_2D_ARRAY);
+        }
+    }
+}
+
+impl BindableTexture for Texture2DArray {
+    unsafe fn bind(&self, target: u32, gl: &Context) {
+        gl.bind_texture(target, Some(self.handle.texture));
+    }
+
+    fn texture_target_hint(&self) -> u32 {
+        TEXTURE_2D_ARRAY
+    }
+
+    fn native_texture(&self) -> Option<NativeTexture> {
+        Some(self.handle.texture)
+    }
+}
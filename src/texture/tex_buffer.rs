@@ -0,0 +1,158 @@
+use super::*;
+
+use std::ffi::c_void;
+
+/// `glTexBuffer`, which glow does not expose.
+pub(crate) type TexBufferFn =
+    unsafe extern "system" fn(target: u32, internal_format: u32, buffer: u32);
+
+/// The sized formats a [`BufferTexture`] can interpret its buffer as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum BufferTextureFormat {
+    R8 = R8,
+    R16F = R16F,
+    R32F = R32F,
+    R32I = R32I,
+    R32UI = R32UI,
+    Rg8 = RG8,
+    Rg16F = RG16F,
+    Rg32F = RG32F,
+    Rg32I = RG32I,
+    Rg32UI = RG32UI,
+    Rgba8 = RGBA8,
+    Rgba16F = RGBA16F,
+    Rgba32F = RGBA32F,
+    Rgba32I = RGBA32I,
+    Rgba32UI = RGBA32UI,
+}
+
+impl BufferTextureFormat {
+    /// The size of a single texel in bytes.
+    pub fn texel_size(&self) -> usize {
+        match self {
+            Self::R8 => 1,
+            Self::R16F | Self::Rg8 => 2,
+            Self::R32F | Self::R32I | Self::R32UI | Self::Rg16F | Self::Rgba8 => 4,
+            Self::Rg32F | Self::Rg32I | Self::Rg32UI | Self::Rgba16F => 8,
+            Self::Rgba32F | Self::Rgba32I | Self::Rgba32UI => 16,
+        }
+    }
+}
+
+/// A buffer read as a one-dimensional texture (`GL_TEXTURE_BUFFER`), e.g. for per-instance or skinning data larger
+/// than a uniform buffer, fetched with `texelFetch` from a `samplerBuffer` where storage buffers aren't available.
+///
+/// Update the data through [`BufferTexture::buffer`]; the texture always sees the buffer's current contents.
+#[derive(Debug)]
+pub struct BufferTexture {
+    texture: NativeTexture,
+    buffer: BufferHandle,
+    format: BufferTextureFormat,
+    gl: Arc<Context>,
+    share_group: ShareGroup,
+}
+
+impl ManagedContext {
+    /// Load `glTexBuffer`, which [`BufferTexture::new`] needs.
+    ///
+    /// `loader` is the same function used to create the glow context, e.g. `window.get_proc_address`.
+    ///
+    /// # Safety
+    /// `loader` must return valid function pointers for the current context.
+    pub unsafe fn load_buffer_textures(
+        &mut self,
+        mut loader: impl FnMut(&str) -> *const c_void,
+    ) -> Result<(), Error> {
+        self.require_features(Features::BUFFER_TEXTURES, "buffer textures")
+            .map_err(|err| Error::Unsupported(err.to_string()))?;
+        let tex_buffer = ["glTexBuffer", "glTexBufferEXT", "glTexBufferOES"]
+            .into_iter()
+            .find_map(|name| load_fn(&mut loader, name).ok());
+        self.tex_buffer_fn = Some(
+            tex_buffer.ok_or_else(|| Error::Unsupported("glTexBuffer is not available".into()))?,
+        );
+        Ok(())
+    }
+}
+
+impl BufferTexture {
+    /// Create a texture reading `buffer` as texels of `format`, taking ownership of the buffer.
+    ///
+    /// Requires [`ManagedContext::load_buffer_textures`]. The number of texels must not exceed
+    /// `GL_MAX_TEXTURE_BUFFER_SIZE`, which is at least 65536.
+    pub fn new(
+        ctx: &ManagedContext,
+        buffer: BufferHandle,
+        format: BufferTextureFormat,
+    ) -> Result<Self, Error> {
+        let Some(tex_buffer) = ctx.tex_buffer_fn else {
+            return Err(Error::Unsupported("buffer textures were not loaded".into()));
+        };
+        span!("buffer_texture", bytes = buffer.capacity(), ?format);
+        let texture = unsafe {
+            let texture = ctx.gl.create_texture().map_err(Error::TextureAlloc)?;
+            ctx.bind_scratch_texture(TEXTURE_BUFFER, texture);
+            tex_buffer(TEXTURE_BUFFER, format as u32, buffer.buffer.0.get());
+            ctx.release_scratch_texture(TEXTURE_BUFFER);
+            texture
+        };
+
+        Ok(Self {
+            texture,
+            buffer,
+            format,
+            gl: ctx.gl.clone(),
+            share_group: ctx.share_group.clone(),
+        })
+    }
+
+    /// The buffer holding the texels, e.g. to update it with [`BufferHandle::update`].
+    pub fn buffer(&self) -> &BufferHandle {
+        &self.buffer
+    }
+
+    /// Like [`BufferTexture::buffer`], e.g. to grow it with [`BufferHandle::realloc`].
+    ///
+    /// The texture keeps reading the same buffer object, so reallocations are picked up without rebinding.
+    pub fn buffer_mut(&mut self) -> &mut BufferHandle {
+        &mut self.buffer
+    }
+
+    pub fn format(&self) -> BufferTextureFormat {
+        self.format
+    }
+
+    /// The number of texels in the buffer.
+    pub fn len(&self) -> usize {
+        self.buffer.capacity() / self.format.texel_size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl BindableTexture for BufferTexture {
+    unsafe fn bind(&self, target: u32, gl: &Context) {
+        gl.bind_texture(target, Some(self.texture));
+    }
+
+    fn texture_target_hint(&self) -> u32 {
+        TEXTURE_BUFFER
+    }
+
+    fn native_texture(&self) -> Option<NativeTexture> {
+        Some(self.texture)
+    }
+}
+
+impl Drop for BufferTexture {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_texture(self.texture);
+        }
+        self.share_group.forget_texture(self.texture);
+    }
+}
@@ -0,0 +1,183 @@
+use super::*;
+
+/// A face of a cube map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum CubeFace {
+    PositiveX = TEXTURE_CUBE_MAP_POSITIVE_X,
+    NegativeX = TEXTURE_CUBE_MAP_NEGATIVE_X,
+    PositiveY = TEXTURE_CUBE_MAP_POSITIVE_Y,
+    NegativeY = TEXTURE_CUBE_MAP_NEGATIVE_Y,
+    PositiveZ = TEXTURE_CUBE_MAP_POSITIVE_Z,
+    NegativeZ = TEXTURE_CUBE_MAP_NEGATIVE_Z,
+}
+
+impl CubeFace {
+    /// All faces, in layer order.
+    pub const ALL: [CubeFace; 6] = [
+        Self::PositiveX,
+        Self::NegativeX,
+        Self::PositiveY,
+        Self::NegativeY,
+        Self::PositiveZ,
+        Self::NegativeZ,
+    ];
+
+    pub fn to_gl(&self) -> u32 {
+        *self as u32
+    }
+
+    /// The layer index of the face, as used by `gl_Layer`.
+    pub fn layer(&self) -> i32 {
+        (self.to_gl() - TEXTURE_CUBE_MAP_POSITIVE_X) as i32
+    }
+
+    /// The direction the face looks towards, and the up vector used by OpenGL's cube map conventions.
+    pub fn orientation(&self) -> ([f32; 3], [f32; 3]) {
+        match self {
+            Self::PositiveX => ([1.0, 0.0, 0.0], [0.0, -1.0, 0.0]),
+            Self::NegativeX => ([-1.0, 0.0, 0.0], [0.0, -1.0, 0.0]),
+            Self::PositiveY => ([0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+            Self::NegativeY => ([0.0, -1.0, 0.0], [0.0, 0.0, -1.0]),
+            Self::PositiveZ => ([0.0, 0.0, 1.0], [0.0, -1.0, 0.0]),
+            Self::NegativeZ => ([0.0, 0.0, -1.0], [0.0, -1.0, 0.0]),
+        }
+    }
+
+    /// A column-major view matrix looking from `eye` through this face.
+    pub fn view_matrix(&self, eye: [f32; 3]) -> [f32; 16] {
+        let (f, up) = self.orientation();
+        // s = f x up, u = s x f
+        let s = [
+            f[1] * up[2] - f[2] * up[1],
+            f[2] * up[0] - f[0] * up[2],
+            f[0] * up[1] - f[1] * up[0],
+        ];
+        let u = [
+            s[1] * f[2] - s[2] * f[1],
+            s[2] * f[0] - s[0] * f[2],
+            s[0] * f[1] - s[1] * f[0],
+        ];
+        let dot = |a: [f32; 3], b: [f32; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+
+        #[rustfmt::skip]
+        let view = [
+            s[0], u[0], -f[0], 0.0,
+            s[1], u[1], -f[1], 0.0,
+            s[2], u[2], -f[2], 0.0,
+            -dot(s, eye), -dot(u, eye), dot(f, eye), 1.0,
+        ];
+        view
+    }
+}
+
+/// A cube map texture in GPU memory.
+#[derive(Debug)]
+pub struct TextureCube {
+    pub(crate) handle: TextureHandle,
+    pub(crate) internal_format: InternalTextureFormat,
+    pub(crate) size: i32,
+}
+
+impl TextureHandle {
+    /// Allocate storage for all six faces of a cube map and receive a [`TextureCube`] instance.
+    ///
+    /// ## Panics
+    /// The texture must have been created using [`TextureHandle::new_cube`].
+    pub fn allocate_cube_data(
+        mut self,
+        ctx: &mut ManagedContext,
+        internal_format: InternalTextureFormat,
+        format: TextureFormat,
+        size: i32,
+        ty: DataType,
+    ) -> TextureCube {
+        assert_eq!(
+            self.target, TEXTURE_CUBE_MAP,
+            "texture is not a cube map texture"
+        );
+        let requested_format = internal_format;
+        let internal_format = ctx.supported_format(internal_format);
+        let transfer = ctx.pixel_transfer(internal_format, format, ty, None);
+        self.memory
+            .resize(texture_bytes(&self, internal_format, size, size, 6));
+        unsafe {
+            ctx.bind_scratch_texture(TEXTURE_CUBE_MAP, self.texture);
+            ctx.apply_format_swizzle(TEXTURE_CUBE_MAP, requested_format, internal_format);
+            for face in CubeFace::ALL {
+                ctx.gl.tex_image_2d(
+                    face.to_gl(),
+                    0,
+                    internal_format as _,
+                    size,
+                    size,
+                    0,
+                    transfer.format as _,
+                    transfer.ty as _,
+                    None,
+                );
+            }
+            ctx.release_scratch_texture(TEXTURE_CUBE_MAP);
+        }
+
+        TextureCube {
+            handle: self,
+            internal_format,
+            size,
+        }
+    }
+}
+
+impl TextureCube {
+    /// The width & height of each face, in pixels.
+    pub fn size(&self) -> i32 {
+        self.size
+    }
+
+    /// The internal format the texture was allocated with.
+    pub fn format(&self) -> InternalTextureFormat {
+        self.internal_format
+    }
+
+    /// Upload pixel data to a single face.
+    pub fn write_face(
+        &self,
+        ctx: &mut ManagedContext,
+        face: CubeFace,
+        format: TextureFormat,
+        ty: DataType,
+        data: &[u8],
+    ) {
+        let transfer = ctx.pixel_transfer(self.internal_format, format, ty, Some(data));
+        unsafe {
+            ctx.bind_scratch_texture(TEXTURE_CUBE_MAP, self.handle.texture);
+            ctx.gl.tex_sub_image_2d(
+                face.to_gl(),
+                0,
+                0,
+                0,
+                self.size,
+                self.size,
+                transfer.format as _,
+                transfer.ty as _,
+                PixelUnpackData::Slice(transfer.data.as_deref().unwrap_or_default()),
+            );
+            self.handle.apply_mipmap_policy(ctx);
+            ctx.release_scratch_texture(TEXTURE_CUBE_MAP);
+        }
+    }
+}
+
+impl BindableTexture for TextureCube {
+    unsafe fn bind(&self, target: u32, gl: &Context) {
+        gl.bind_texture(target, Some(self.handle.texture));
+    }
+
+    fn texture_target_hint(&self) -> u32 {
+        TEXTURE_CUBE_MAP
+    }
+
+    fn native_texture(&self) -> Option<NativeTexture> {
+        Some(self.handle.texture)
+    }
+}
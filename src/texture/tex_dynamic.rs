@@ -0,0 +1,131 @@
+use super::*;
+
+/// A 2D texture that is updated every frame while being sampled.
+///
+/// Two textures are kept internally: writes go to the back texture while the front texture is sampled, so uploads
+/// don't have to wait for draws that still read the previous contents.
+#[derive(Debug)]
+pub struct DynamicTexture2D {
+    textures: [Texture2D; 2],
+    front: usize,
+}
+
+impl DynamicTexture2D {
+    /// Create a new double-buffered texture. Both textures use the same parameters.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        ctx: &mut ManagedContext,
+        wrapping_mode_s: TextureWrap,
+        wrapping_mode_t: TextureWrap,
+        min_filter: TextureFilteringMode,
+        mag_filter: TextureFilteringMode,
+        internal_format: InternalTextureFormat,
+        format: TextureFormat,
+        width: i32,
+        height: i32,
+        ty: DataType,
+    ) -> Result<Self, Error> {
+        let allocate = |ctx: &mut ManagedContext| {
+            TextureHandle::new_with(
+                ctx,
+                &SamplerDescriptor {
+                    wrapping_mode_s,
+                    wrapping_mode_t,
+                    min_filter,
+                    mag_filter,
+                    mipmap_policy: MipmapPolicy::None,
+                    ..*ctx.default_sampler()
+                },
+            )
+            .map(|handle| {
+                handle.allocate_2d_data(ctx, None, internal_format, format, width, height, ty)
+            })
+        };
+        let textures = [allocate(ctx)?, allocate(ctx)?];
+
+        Ok(Self { textures, front: 0 })
+    }
+
+    /// The texture that is currently sampled.
+    pub fn front(&self) -> &Texture2D {
+        &self.textures[self.front]
+    }
+
+    /// The texture that is currently written.
+    pub fn back(&self) -> &Texture2D {
+        &self.textures[1 - self.front]
+    }
+
+    /// Make the back texture the one that is sampled.
+    pub fn swap(&mut self) {
+        self.front = 1 - self.front;
+    }
+
+    /// Upload a full image to the back texture, then swap.
+    ///
+    /// Returns [`Error::PixelDataSize`] without swapping if `data` doesn't cover the whole texture.
+    pub fn update(&mut self, ctx: &mut ManagedContext, data: &[u8]) -> Result<(), Error> {
+        let back = self.back();
+        back.write_subimage(
+            ctx,
+            0,
+            0,
+            back.width,
+            back.height,
+            back.format,
+            back.ty,
+            data,
+        )?;
+        self.swap();
+        Ok(())
+    }
+
+    /// Upload the region `dst_rect` of a larger CPU-side image to the back texture, then swap.
+    ///
+    /// As the back texture holds the contents of two frames ago, the region must contain every change made since then.
+    ///
+    /// ## Panics
+    /// See [`Texture2D::write_subimage_strided`].
+    pub fn update_strided(
+        &mut self,
+        ctx: &mut ManagedContext,
+        dst_rect: Rect,
+        src_pixels: &[u8],
+        src_stride_bytes: usize,
+    ) {
+        self.back()
+            .write_subimage_strided(ctx, dst_rect, src_pixels, src_stride_bytes);
+        self.swap();
+    }
+
+    /// Resize both textures. Their contents become undefined.
+    pub fn resize(&mut self, ctx: &mut ManagedContext, width: i32, height: i32) {
+        for texture in &mut self.textures {
+            texture.resize(ctx, width, height);
+        }
+    }
+
+    /// The width of the texture, in pixels.
+    pub fn width(&self) -> i32 {
+        self.front().width
+    }
+
+    /// The height of the texture, in pixels.
+    pub fn height(&self) -> i32 {
+        self.front().height
+    }
+}
+
+impl BindableTexture for DynamicTexture2D {
+    unsafe fn bind(&self, target: u32, gl: &Context) {
+        self.front().bind(target, gl);
+    }
+
+    fn texture_target_hint(&self) -> u32 {
+        TEXTURE_2D
+    }
+
+    fn native_texture(&self) -> Option<NativeTexture> {
+        Some(self.front().handle.texture)
+    }
+}
@@ -0,0 +1,119 @@
+use super::*;
+
+use image::DynamicImage;
+
+/// Options for [`Texture2D::from_image`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureOptions {
+    pub wrapping_mode_s: TextureWrap,
+    pub wrapping_mode_t: TextureWrap,
+    pub min_filter: TextureFilteringMode,
+    pub mag_filter: TextureFilteringMode,
+    /// Flip the image so its first row ends up at the bottom, matching OpenGL's texture coordinates.
+    pub flip_vertically: bool,
+    pub generate_mipmaps: bool,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self {
+            wrapping_mode_s: TextureWrap::ClampToEdge,
+            wrapping_mode_t: TextureWrap::ClampToEdge,
+            min_filter: TextureFilteringMode::Linear,
+            mag_filter: TextureFilteringMode::Linear,
+            flip_vertically: false,
+            generate_mipmaps: true,
+        }
+    }
+}
+
+impl TextureOptions {
+    /// Options sampling like `sampler`, e.g. [`ManagedContext::default_sampler`]. Mipmaps are generated if its
+    /// minification filter uses them.
+    pub fn from_sampler(sampler: &SamplerDescriptor) -> Self {
+        Self {
+            wrapping_mode_s: sampler.wrapping_mode_s,
+            wrapping_mode_t: sampler.wrapping_mode_t,
+            min_filter: sampler.min_filter,
+            mag_filter: sampler.mag_filter,
+            generate_mipmaps: sampler.min_filter.uses_mipmaps(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Texture2D {
+    /// Create a texture from an image, picking the texture format from the image's color type.
+    ///
+    /// 8-bit images are uploaded as they are; luminance images are expanded to RGB(A) on core profiles, which lack
+    /// luminance formats. Images with more than 8 bits per channel are converted to RGBA8.
+    pub fn from_image(
+        ctx: &mut ManagedContext,
+        image: &DynamicImage,
+        options: TextureOptions,
+    ) -> Result<Self, Error> {
+        span!("from_image", width = image.width(), height = image.height());
+        let supports_luminance =
+            ctx.driver_info().is_embedded || ctx.driver_info().is_compatibility_profile;
+        let flipped;
+        let image = if options.flip_vertically {
+            flipped = image.flipv();
+            &flipped
+        } else {
+            image
+        };
+
+        let (internal_format, format, pixels) = match image {
+            DynamicImage::ImageLuma8(image) if supports_luminance => (
+                InternalTextureFormat::Luminance,
+                TextureFormat::Luminance,
+                image.as_raw().clone(),
+            ),
+            DynamicImage::ImageLumaA8(image) if supports_luminance => (
+                InternalTextureFormat::LuminanceAlpha,
+                TextureFormat::LuminanceAlpha,
+                image.as_raw().clone(),
+            ),
+            DynamicImage::ImageLuma8(_) | DynamicImage::ImageRgb8(_) => (
+                InternalTextureFormat::Rgb8,
+                TextureFormat::Rgb,
+                image.to_rgb8().into_raw(),
+            ),
+            _ => (
+                InternalTextureFormat::Rgba8,
+                TextureFormat::Rgba,
+                image.to_rgba8().into_raw(),
+            ),
+        };
+
+        let texture = TextureHandle::new_with(
+            ctx,
+            &SamplerDescriptor {
+                wrapping_mode_s: options.wrapping_mode_s,
+                wrapping_mode_t: options.wrapping_mode_t,
+                min_filter: options.min_filter,
+                mag_filter: options.mag_filter,
+                mipmap_policy: MipmapPolicy::None,
+                ..*ctx.default_sampler()
+            },
+        )?;
+        // rows of 1- and 3-channel images are not 4-byte aligned
+        unsafe { ctx.gl.pixel_store_i32(UNPACK_ALIGNMENT, 1) };
+        let texture = texture.allocate_2d_data(
+            ctx,
+            Some(&pixels),
+            internal_format,
+            format,
+            image.width() as i32,
+            image.height() as i32,
+            DataType::UnsignedByte,
+        );
+        unsafe { ctx.gl.pixel_store_i32(UNPACK_ALIGNMENT, 4) };
+
+        if options.generate_mipmaps {
+            texture.generate_mipmaps(ctx);
+        }
+
+        Ok(texture)
+    }
+}
@@ -0,0 +1,259 @@
+use super::*;
+
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::Rc;
+
+/// Texture bindings per unit and uniform buffer bindings per binding point, as last set by rapax.
+#[derive(Debug)]
+pub(crate) struct BindingTables {
+    active: u32,
+    bound: Vec<Option<(u32, NativeTexture)>>,
+    scratch: u32,
+    /// The first unit kept for internal passes, and which of those units are reserved, one bit per unit.
+    reserved: u32,
+    reserved_in_use: u32,
+    uniform_buffers: Vec<Option<(NativeBuffer, i32, i32)>>,
+}
+
+/// Shared between the context and its [`ShareGroup`], so deleted textures and buffers are forgotten.
+pub(crate) type SharedBindingTables = Rc<RefCell<BindingTables>>;
+
+impl BindingTables {
+    pub(crate) fn query(gl: &Context) -> SharedBindingTables {
+        let max_units = unsafe { gl.get_parameter_i32(MAX_COMBINED_TEXTURE_IMAGE_UNITS) };
+        let scratch = max_units.max(1) as u32 - 1;
+        // an eighth of the units, but at least the two the post-processing chain binds at once
+        let reserved_count = (max_units.max(0) as u32 / 8).clamp(2, 4);
+        Rc::new(RefCell::new(Self {
+            active: 0,
+            bound: Vec::new(),
+            scratch,
+            reserved: scratch.saturating_sub(reserved_count),
+            reserved_in_use: 0,
+            uniform_buffers: Vec::new(),
+        }))
+    }
+
+    /// Forget every unit bound to `texture`, e.g. because it was deleted and its name may be reused.
+    pub(crate) fn forget(&mut self, texture: NativeTexture) {
+        for binding in self.bound.iter_mut() {
+            if matches!(binding, Some((_, bound)) if *bound == texture) {
+                *binding = None;
+            }
+        }
+    }
+
+    /// Forget every binding point bound to `buffer`, e.g. because it was deleted and its name may be reused.
+    pub(crate) fn forget_buffer(&mut self, buffer: NativeBuffer) {
+        for binding in self.uniform_buffers.iter_mut() {
+            if matches!(binding, Some((bound, ..)) if *bound == buffer) {
+                *binding = None;
+            }
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.bound.clear();
+    }
+}
+
+/// Consecutive texture units reserved with [`ManagedContext::reserve_texture_units`], released on drop.
+#[derive(Debug)]
+pub struct TextureUnitReservation {
+    tables: SharedBindingTables,
+    units: Range<u32>,
+}
+
+impl TextureUnitReservation {
+    /// The reserved texture units.
+    pub fn units(&self) -> Range<u32> {
+        self.units.clone()
+    }
+}
+
+impl Drop for TextureUnitReservation {
+    fn drop(&mut self) {
+        let mut tables = self.tables.borrow_mut();
+        let reserved = tables.reserved;
+        for unit in self.units.clone() {
+            tables.reserved_in_use &= !(1 << (unit - reserved));
+        }
+    }
+}
+
+impl ManagedContext {
+    /// The number of texture units [`Drawable::apply_textures`] may use. The units above them are kept for internal
+    /// passes and for mutating textures, so binding textures there never disturbs the textures of a draw.
+    pub fn user_texture_units(&self) -> u32 {
+        self.bindings.borrow().reserved
+    }
+
+    /// The number of texture units below the scratch unit that are kept for internal passes, such as the
+    /// post-processing chain: an eighth of `MAX_COMBINED_TEXTURE_IMAGE_UNITS`, between 2 and 4.
+    pub fn reserved_texture_units(&self) -> u32 {
+        let tables = self.bindings.borrow();
+        tables.scratch - tables.reserved
+    }
+
+    /// Reserve `count` consecutive units of the [`ManagedContext::reserved_texture_units`] kept for internal passes,
+    /// until the reservation is dropped. Returns `None` if there aren't enough free units.
+    ///
+    /// Helpers that draw in the middle of user rendering, like rapax's own post-processing chain, bind their textures
+    /// there with [`Drawable::apply_reserved_textures`].
+    pub fn reserve_texture_units(&self, count: u32) -> Option<TextureUnitReservation> {
+        let mut tables = self.bindings.borrow_mut();
+        let available = tables.scratch - tables.reserved;
+        let mask = 1u32.checked_shl(count).map_or(u32::MAX, |bit| bit - 1);
+        let start = (0..=available.checked_sub(count)?)
+            .find(|start| tables.reserved_in_use & (mask << start) == 0)?;
+        tables.reserved_in_use |= mask << start;
+        let first = tables.reserved + start;
+        Some(TextureUnitReservation {
+            tables: self.bindings.clone(),
+            units: first..first + count,
+        })
+    }
+
+    /// Bind a texture to a unit, skipping the GL calls if it is already bound there.
+    pub(crate) unsafe fn bind_texture_to_unit(
+        &self,
+        unit: u32,
+        target: u32,
+        texture: &dyn BindableTexture,
+    ) {
+        self.debug_assert_current();
+        let mut units = self.bindings.borrow_mut();
+        let native = texture.native_texture();
+        let index = unit as usize;
+        if native.is_some()
+            && units.bound.get(index).copied().flatten() == native.map(|n| (target, n))
+        {
+            return;
+        }
+        if units.active != unit {
+            self.gl.active_texture(TEXTURE0 + unit);
+            units.active = unit;
+        }
+        texture.bind(target, &self.gl);
+        if units.bound.len() <= index {
+            units.bound.resize(index + 1, None);
+        }
+        units.bound[index] = native.map(|native| (target, native));
+    }
+
+    /// Bind a texture for mutation on the reserved last unit, leaving the units used for drawing untouched.
+    pub(crate) unsafe fn bind_scratch_texture(&self, target: u32, texture: NativeTexture) {
+        self.debug_assert_current();
+        let units = self.bindings.borrow();
+        self.gl.active_texture(TEXTURE0 + units.scratch);
+        self.gl.bind_texture(target, Some(texture));
+    }
+
+    /// Undo [`ManagedContext::bind_scratch_texture`] and restore the active unit.
+    pub(crate) unsafe fn release_scratch_texture(&self, target: u32) {
+        let units = self.bindings.borrow();
+        self.gl.bind_texture(target, None);
+        self.gl.active_texture(TEXTURE0 + units.active);
+    }
+
+    /// Forget the tracked texture bindings, e.g. after external code changed them behind rapax's back.
+    ///
+    /// The texture unit reserved for mutating textures is the last one reported by `MAX_COMBINED_TEXTURE_IMAGE_UNITS`.
+    pub fn invalidate_texture_units(&mut self) {
+        let mut units = self.bindings.borrow_mut();
+        units.invalidate();
+        units.active = 0;
+        unsafe {
+            self.gl.active_texture(TEXTURE0);
+        }
+    }
+
+    /// Bind a range of a uniform buffer to a binding point, skipping the GL call if it is already bound there.
+    ///
+    /// A `size` of 0 binds the whole buffer.
+    pub(crate) unsafe fn bind_uniform_buffer(
+        &self,
+        binding: u32,
+        buffer: NativeBuffer,
+        offset: i32,
+        size: i32,
+    ) {
+        self.debug_assert_current();
+        let mut tables = self.bindings.borrow_mut();
+        let index = binding as usize;
+        if tables.uniform_buffers.get(index).copied().flatten() == Some((buffer, offset, size)) {
+            return;
+        }
+        if size == 0 {
+            self.gl
+                .bind_buffer_base(UNIFORM_BUFFER, binding, Some(buffer));
+        } else {
+            self.gl
+                .bind_buffer_range(UNIFORM_BUFFER, binding, Some(buffer), offset, size);
+        }
+        if tables.uniform_buffers.len() <= index {
+            tables.uniform_buffers.resize(index + 1, None);
+        }
+        tables.uniform_buffers[index] = Some((buffer, offset, size));
+    }
+
+    /// Compare the tracked texture unit and uniform buffer bindings with the bindings read back from GL, see
+    /// [`ManagedContext::dump_state`].
+    pub(crate) unsafe fn binding_divergences(&self, out: &mut Vec<StateDivergence>) {
+        let tables = self.bindings.borrow();
+        let active = (self.gl.get_parameter_i32(ACTIVE_TEXTURE) as u32).wrapping_sub(TEXTURE0);
+        if active != tables.active {
+            out.push(StateDivergence::new(
+                "active texture unit",
+                tables.active,
+                active,
+            ));
+        }
+
+        for (unit, binding) in tables.bound.iter().enumerate() {
+            let Some((target, texture)) = binding else {
+                continue;
+            };
+            let query = match *target {
+                TEXTURE_2D => TEXTURE_BINDING_2D,
+                TEXTURE_CUBE_MAP => TEXTURE_BINDING_CUBE_MAP,
+                TEXTURE_2D_ARRAY => TEXTURE_BINDING_2D_ARRAY,
+                TEXTURE_3D => TEXTURE_BINDING_3D,
+                _ => continue,
+            };
+            self.gl.active_texture(TEXTURE0 + unit as u32);
+            let actual = self.gl.get_parameter_i32(query) as u32;
+            if actual != texture.0.get() {
+                out.push(StateDivergence::new(
+                    format!("texture unit {}", unit),
+                    texture.0.get(),
+                    actual,
+                ));
+            }
+        }
+        self.gl.active_texture(TEXTURE0 + active);
+
+        for (index, binding) in tables.uniform_buffers.iter().enumerate() {
+            let Some((buffer, ..)) = binding else {
+                continue;
+            };
+            let actual = self
+                .gl
+                .get_parameter_indexed_i32(UNIFORM_BUFFER_BINDING, index as u32)
+                as u32;
+            if actual != buffer.0.get() {
+                out.push(StateDivergence::new(
+                    format!("uniform buffer binding {}", index),
+                    buffer.0.get(),
+                    actual,
+                ));
+            }
+        }
+    }
+
+    /// Forget the tracked uniform buffer bindings, e.g. after external code changed them behind rapax's back.
+    pub fn invalidate_buffer_bindings(&mut self) {
+        self.bindings.borrow_mut().uniform_buffers.clear();
+    }
+}
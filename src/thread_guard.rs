@@ -0,0 +1,53 @@
+use super::*;
+
+use std::thread::ThreadId;
+
+/// A user check that the GL context is current, see [`ManagedContext::set_current_context_check`].
+pub struct CurrentContextCheck(Box<dyn Fn() -> bool>);
+
+impl std::fmt::Debug for CurrentContextCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CurrentContextCheck")
+    }
+}
+
+/// Panic in debug builds if called from another thread than `owner`.
+#[track_caller]
+pub(crate) fn debug_assert_thread(owner: ThreadId) {
+    if cfg!(debug_assertions) {
+        let thread = std::thread::current();
+        assert!(
+            thread.id() == owner,
+            "GL object used on thread {:?} ({:?}), but its context was created on {:?}; GL contexts can only be used \
+             on the thread they are current on",
+            thread.name().unwrap_or("<unnamed>"),
+            thread.id(),
+            owner
+        );
+    }
+}
+
+impl ManagedContext {
+    /// Check that the GL context is current at every rapax entry point, in debug builds.
+    ///
+    /// rapax can't ask the windowing library, so e.g. pass `move || window_context.is_current()`. Without a check,
+    /// only the thread the context was created on is verified.
+    pub fn set_current_context_check(&mut self, check: impl Fn() -> bool + 'static) {
+        self.current_check = Some(CurrentContextCheck(Box::new(check)));
+    }
+
+    /// Panic in debug builds if the context is used from another thread than the one that created it, or if the
+    /// [`ManagedContext::set_current_context_check`] fails.
+    #[track_caller]
+    pub(crate) fn debug_assert_current(&self) {
+        if cfg!(debug_assertions) {
+            debug_assert_thread(self.owner_thread);
+            if let Some(check) = &self.current_check {
+                assert!(
+                    (check.0)(),
+                    "the GL context is not current; make it current before using rapax"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use super::*;
+
+use std::ffi::c_void;
+
+/// A member of a uniform block, at the offsets the driver laid it out at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniformBlockMember {
+    /// The member name, without any trailing `[0]` array suffix.
+    pub name: String,
+    /// The GLSL type of the member, e.g. `GL_FLOAT_VEC4`.
+    pub ty: u32,
+    /// The number of array elements, 1 for non-array members.
+    pub array_size: usize,
+    /// The offset of the member from the start of the block, in bytes.
+    pub offset: usize,
+    /// The distance between array elements, in bytes.
+    pub array_stride: usize,
+    /// The distance between matrix columns, or rows if `row_major`, in bytes, 0 for non-matrix members.
+    pub matrix_stride: usize,
+    /// Whether a matrix member is stored row by row instead of column by column.
+    pub row_major: bool,
+}
+
+/// Reflection information about a named uniform block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniformBlockInfo {
+    /// The uniform block index within the program.
+    pub index: u32,
+    /// The minimum buffer size required to back the block, in bytes.
+    pub size: usize,
+    /// The active members of the block.
+    pub members: Vec<UniformBlockMember>,
+}
+
+impl UniformBlockInfo {
+    /// Look up a member by name.
+    pub fn member(&self, name: &str) -> Option<&UniformBlockMember> {
+        self.members.iter().find(|member| member.name == name)
+    }
+}
+
+/// `glGetActiveUniformsiv`, which glow does not expose.
+pub(crate) type GetActiveUniformsFn = unsafe extern "system" fn(
+    program: u32,
+    count: i32,
+    indices: *const u32,
+    pname: u32,
+    params: *mut i32,
+);
+
+impl ManagedContext {
+    /// Load `glGetActiveUniformsiv` so [`ShaderProgram::uniform_block_info`] can query member offsets.
+    ///
+    /// `loader` is the same function used to create the glow context, e.g. `window.get_proc_address`.
+    ///
+    /// # Safety
+    /// `loader` must return valid function pointers for the current context.
+    pub unsafe fn load_uniform_block_reflection(
+        &mut self,
+        mut loader: impl FnMut(&str) -> *const c_void,
+    ) -> Result<(), Error> {
+        self.get_active_uniforms_fn = Some(load_fn(&mut loader, "glGetActiveUniformsiv")?);
+        Ok(())
+    }
+}
+
+impl ShaderProgram {
+    /// Query the layout of the named uniform block, with the member offsets & strides reported by the driver, so
+    /// `shared` and `packed` blocks work as well as `std140` ones.
+    ///
+    /// Returns `None` if the program has no such block, or [`ManagedContext::load_uniform_block_reflection`] wasn't
+    /// called.
+    pub fn uniform_block_info(&self, ctx: &ManagedContext, name: &str) -> Option<UniformBlockInfo> {
+        let get_active_uniforms = ctx.get_active_uniforms_fn?;
+        unsafe {
+            let index = self.gl.get_uniform_block_index(self.program, name)?;
+            let size = self.gl.get_active_uniform_block_parameter_i32(
+                self.program,
+                index,
+                UNIFORM_BLOCK_DATA_SIZE,
+            ) as usize;
+            let count = self.gl.get_active_uniform_block_parameter_i32(
+                self.program,
+                index,
+                UNIFORM_BLOCK_ACTIVE_UNIFORMS,
+            ) as usize;
+            let mut indices = vec![0; count];
+            self.gl.get_active_uniform_block_parameter_i32_slice(
+                self.program,
+                index,
+                UNIFORM_BLOCK_ACTIVE_UNIFORM_INDICES,
+                &mut indices,
+            );
+            let indices: Vec<u32> = indices.into_iter().map(|index| index as u32).collect();
+            let query = |pname: u32| {
+                let mut params = vec![0; count];
+                get_active_uniforms(
+                    self.program.0.get(),
+                    count as i32,
+                    indices.as_ptr(),
+                    pname,
+                    params.as_mut_ptr(),
+                );
+                params
+            };
+            let offsets = query(UNIFORM_OFFSET);
+            let array_strides = query(UNIFORM_ARRAY_STRIDE);
+            let matrix_strides = query(UNIFORM_MATRIX_STRIDE);
+            let row_major = query(UNIFORM_IS_ROW_MAJOR);
+
+            let mut members = Vec::with_capacity(count);
+            for (i, uniform_index) in indices.iter().enumerate() {
+                let uniform = self.gl.get_active_uniform(self.program, *uniform_index)?;
+                members.push(UniformBlockMember {
+                    name: uniform.name.trim_end_matches("[0]").to_owned(),
+                    ty: uniform.utype,
+                    array_size: uniform.size as usize,
+                    offset: offsets[i].max(0) as usize,
+                    array_stride: array_strides[i].max(0) as usize,
+                    matrix_stride: matrix_strides[i].max(0) as usize,
+                    row_major: row_major[i] != 0,
+                });
+            }
+            // the driver reports members in any order
+            members.sort_by_key(|member| member.offset);
+
+            Some(UniformBlockInfo {
+                index,
+                size,
+                members,
+            })
+        }
+    }
+
+    /// Assign the named uniform block to a uniform buffer binding point. Reassigning the same point is skipped.
+    ///
+    /// ## Panics
+    /// The program must contain an active uniform block with the given name.
+    pub fn bind_uniform_block(&self, name: &str, binding: u32) {
+        let mut bindings = self.block_bindings.lock().unwrap();
+        if bindings.get(name) == Some(&binding) {
+            return;
+        }
+        unsafe {
+            let index = self.gl.get_uniform_block_index(self.program, name);
+            assert!(index.is_some(), "No such uniform block name!");
+            self.gl
+                .uniform_block_binding(self.program, index.unwrap(), binding);
+        }
+        bindings.insert(name.to_owned(), binding);
+    }
+}
+
+/// A value that can be written into a uniform block.
+pub trait Std140Value {
+    /// Write the value at the start of `out`, which spans the rest of the block from the member's offset. Matrices
+    /// are laid out with the `member`'s matrix stride & majority.
+    fn write_std140(&self, out: &mut [u8], member: &UniformBlockMember);
+}
+
+macro_rules! impl_std140_value {
+    ($($ty:ty),*) => {
+        $(
+            impl Std140Value for $ty {
+                fn write_std140(&self, out: &mut [u8], _member: &UniformBlockMember) {
+                    let bytes = bytemuck::bytes_of(self);
+                    out[..bytes.len()].copy_from_slice(bytes);
+                }
+            }
+        )*
+    };
+}
+
+impl_std140_value!(
+    f32, i32, u32, [f32; 2], [f32; 3], [f32; 4], [i32; 2], [i32; 3], [i32; 4], [u32; 2], [u32; 3],
+    [u32; 4]
+);
+
+impl Std140Value for bool {
+    fn write_std140(&self, out: &mut [u8], member: &UniformBlockMember) {
+        (*self as u32).write_std140(out, member);
+    }
+}
+
+/// A mat2 given as columns.
+impl Std140Value for [[f32; 2]; 2] {
+    fn write_std140(&self, out: &mut [u8], member: &UniformBlockMember) {
+        write_matrix(self.as_flattened(), 2, out, member);
+    }
+}
+
+/// A column-major mat3.
+impl Std140Value for [f32; 9] {
+    fn write_std140(&self, out: &mut [u8], member: &UniformBlockMember) {
+        write_matrix(self, 3, out, member);
+    }
+}
+
+/// A column-major mat4.
+impl Std140Value for [f32; 16] {
+    fn write_std140(&self, out: &mut [u8], member: &UniformBlockMember) {
+        write_matrix(self, 4, out, member);
+    }
+}
+
+/// Write a column-major `matrix` with `rows` rows, placing each column (or row, for `row_major` members)
+/// `matrix_stride` bytes apart.
+fn write_matrix(matrix: &[f32], rows: usize, out: &mut [u8], member: &UniformBlockMember) {
+    for (column, values) in matrix.chunks(rows).enumerate() {
+        for (row, value) in values.iter().enumerate() {
+            let offset = if member.row_major {
+                row * member.matrix_stride + column * 4
+            } else {
+                column * member.matrix_stride + row * 4
+            };
+            out[offset..offset + 4].copy_from_slice(&value.to_ne_bytes());
+        }
+    }
+}
+
+/// Lays out Rust values at the offsets reported by [`ShaderProgram::uniform_block_info`],
+/// producing data ready to be uploaded into a uniform buffer.
+#[derive(Debug, Clone)]
+pub struct Std140Writer<'a> {
+    info: &'a UniformBlockInfo,
+    data: Vec<u8>,
+}
+
+impl<'a> Std140Writer<'a> {
+    /// Create a zero-filled writer for the given uniform block.
+    pub fn new(info: &'a UniformBlockInfo) -> Self {
+        Self {
+            info,
+            data: vec![0; info.size],
+        }
+    }
+
+    /// Write a value to the named member.
+    ///
+    /// ## Panics
+    /// The block must contain a member with the given name.
+    pub fn write(&mut self, name: &str, value: impl Std140Value) -> &mut Self {
+        self.write_array(name, &[value])
+    }
+
+    /// Write consecutive elements of the named array member, starting at element 0.
+    ///
+    /// ## Panics
+    /// The block must contain a member with the given name, with at least `values.len()` elements.
+    pub fn write_array(&mut self, name: &str, values: &[impl Std140Value]) -> &mut Self {
+        let member = self.info.member(name);
+        assert!(member.is_some(), "No such uniform block member!");
+        let member = member.unwrap();
+        assert!(values.len() <= member.array_size, "out of bounds write!");
+
+        for (i, value) in values.iter().enumerate() {
+            let offset = member.offset + i * member.array_stride;
+            value.write_std140(&mut self.data[offset..], member);
+        }
+
+        self
+    }
+
+    /// The laid out block data.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Consume the writer, returning the laid out block data.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(
+        name: &str,
+        ty: u32,
+        offset: usize,
+        array: (usize, usize),
+        matrix_stride: usize,
+    ) -> UniformBlockMember {
+        UniformBlockMember {
+            name: name.to_owned(),
+            ty,
+            array_size: array.0,
+            offset,
+            array_stride: array.1,
+            matrix_stride,
+            row_major: false,
+        }
+    }
+
+    fn floats(bytes: &[u8]) -> Vec<f32> {
+        bytes
+            .chunks(4)
+            .map(|chunk| f32::from_ne_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn writes_std140_members() {
+        // layout(std140) uniform Block { float a; vec3 b; mat3 c; vec2 d[2]; bool e; }
+        let info = UniformBlockInfo {
+            index: 0,
+            size: 128,
+            members: vec![
+                member("a", FLOAT, 0, (1, 0), 0),
+                member("b", FLOAT_VEC3, 16, (1, 0), 0),
+                member("c", FLOAT_MAT3, 32, (1, 0), 16),
+                member("d", FLOAT_VEC2, 80, (2, 16), 0),
+                member("e", BOOL, 112, (1, 0), 0),
+            ],
+        };
+        let mut writer = Std140Writer::new(&info);
+        writer
+            .write("a", 1.0f32)
+            .write("b", [2.0f32, 3.0, 4.0])
+            .write("c", [5.0f32, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0])
+            .write_array("d", &[[14.0f32, 15.0], [16.0, 17.0]])
+            .write("e", true);
+        let data = writer.into_bytes();
+        assert_eq!(data.len(), 128);
+
+        #[rustfmt::skip]
+        let expected = [
+            1.0, 0.0, 0.0, 0.0,
+            2.0, 3.0, 4.0, 0.0,
+            5.0, 6.0, 7.0, 0.0,
+            8.0, 9.0, 10.0, 0.0,
+            11.0, 12.0, 13.0, 0.0,
+            14.0, 15.0, 0.0, 0.0,
+            16.0, 17.0, 0.0, 0.0,
+        ];
+        assert_eq!(floats(&data[..112]), expected);
+        assert_eq!(data[112..116], 1u32.to_ne_bytes());
+        assert!(data[116..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn writes_matrices_with_the_reported_stride() {
+        // a packed mat3 with tightly packed columns, followed by a row-major mat2
+        let mut row_major = member("m", FLOAT_MAT2, 36, (1, 0), 8);
+        row_major.row_major = true;
+        let info = UniformBlockInfo {
+            index: 0,
+            size: 52,
+            members: vec![member("c", FLOAT_MAT3, 0, (1, 0), 12), row_major],
+        };
+        let mut writer = Std140Writer::new(&info);
+        writer
+            .write("c", [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0])
+            .write("m", [[10.0f32, 11.0], [12.0, 13.0]]);
+
+        #[rustfmt::skip]
+        let expected = [
+            1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0,
+            10.0, 12.0, 11.0, 13.0,
+        ];
+        assert_eq!(floats(writer.as_bytes()), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds write!")]
+    fn rejects_writes_past_the_array() {
+        let info = UniformBlockInfo {
+            index: 0,
+            size: 32,
+            members: vec![member("d", FLOAT, 0, (2, 16), 0)],
+        };
+        Std140Writer::new(&info).write_array("d", &[1.0f32, 2.0, 3.0]);
+    }
+}
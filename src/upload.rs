@@ -0,0 +1,249 @@
+use super::*;
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::task::{Context as TaskContext, Poll, Waker};
+use std::time::{Duration, Instant};
+
+/// CPU-side description of a 2D texture to be created by [`ManagedContext::pump_uploads`].
+#[derive(Debug, Clone)]
+pub struct PendingTexture {
+    pub pixels: Vec<u8>,
+    pub internal_format: InternalTextureFormat,
+    pub format: TextureFormat,
+    pub ty: DataType,
+    pub width: i32,
+    pub height: i32,
+    pub wrapping_mode_s: TextureWrap,
+    pub wrapping_mode_t: TextureWrap,
+    pub min_filter: TextureFilteringMode,
+    pub mag_filter: TextureFilteringMode,
+    pub mipmap_policy: MipmapPolicy,
+}
+
+/// CPU-side description of a buffer to be created by [`ManagedContext::pump_uploads`].
+#[derive(Debug, Clone)]
+pub struct PendingBuffer {
+    pub data: Vec<u8>,
+    pub ty: BufferType,
+    pub usage: BufferUsage,
+}
+
+#[derive(Debug)]
+enum UploadRequest {
+    Texture(PendingTexture),
+    Buffer(PendingBuffer),
+}
+
+#[derive(Debug, Default)]
+struct UploadState {
+    ready: bool,
+    waker: Option<Waker>,
+}
+
+#[derive(Debug)]
+struct QueuedUpload {
+    id: u64,
+    request: UploadRequest,
+    state: Arc<Mutex<UploadState>>,
+}
+
+/// Submits resources for creation on the GL thread. Can be cloned and sent to worker threads.
+#[derive(Debug, Clone)]
+pub struct ResourceUploader {
+    sender: Sender<QueuedUpload>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl ResourceUploader {
+    /// Queue a texture for creation.
+    pub fn submit_texture(&self, texture: PendingTexture) -> UploadHandle<Texture2D> {
+        self.submit(UploadRequest::Texture(texture))
+    }
+
+    /// Queue a buffer for creation.
+    pub fn submit_buffer(&self, buffer: PendingBuffer) -> UploadHandle<BufferHandle> {
+        self.submit(UploadRequest::Buffer(buffer))
+    }
+
+    fn submit<T>(&self, request: UploadRequest) -> UploadHandle<T> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let state = Arc::new(Mutex::new(UploadState::default()));
+        // the receiver lives as long as the context; if it is gone, the handle never becomes ready
+        let _ = self.sender.send(QueuedUpload {
+            id,
+            request,
+            state: state.clone(),
+        });
+
+        UploadHandle {
+            id,
+            state,
+            _resource: PhantomData,
+        }
+    }
+}
+
+/// A resource queued with a [`ResourceUploader`].
+///
+/// The handle can be sent between threads; the resource itself is taken on the GL thread using
+/// [`ManagedContext::take_upload`] once the handle is ready.
+#[derive(Debug)]
+pub struct UploadHandle<T> {
+    id: u64,
+    state: Arc<Mutex<UploadState>>,
+    _resource: PhantomData<fn() -> T>,
+}
+
+impl<T> UploadHandle<T> {
+    /// Returns true once the resource has been created.
+    pub fn is_ready(&self) -> bool {
+        self.state.lock().unwrap().ready
+    }
+
+    /// A future that resolves once the resource has been created.
+    pub fn ready(&self) -> UploadReady {
+        UploadReady {
+            state: self.state.clone(),
+        }
+    }
+}
+
+/// Future returned by [`UploadHandle::ready`].
+#[derive(Debug)]
+pub struct UploadReady {
+    state: Arc<Mutex<UploadState>>,
+}
+
+impl Future for UploadReady {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.ready {
+            Poll::Ready(())
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+/// The GL thread side of the upload queue, owned by the [`ManagedContext`].
+#[derive(Debug)]
+pub(crate) struct UploadQueue {
+    uploader: ResourceUploader,
+    receiver: Receiver<QueuedUpload>,
+    completed: HashMap<u64, Box<dyn Any>>,
+}
+
+impl UploadQueue {
+    pub(crate) fn new() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            uploader: ResourceUploader {
+                sender,
+                next_id: Arc::new(AtomicU64::new(0)),
+            },
+            receiver,
+            completed: HashMap::new(),
+        }
+    }
+}
+
+impl ManagedContext {
+    /// Get an uploader that worker threads can submit resources to.
+    pub fn resource_uploader(&self) -> ResourceUploader {
+        self.uploads.uploader.clone()
+    }
+
+    /// Create queued resources until `budget` is used up, returning how many were created.
+    ///
+    /// Call this once per frame. At least one queued resource is created per call, so progress is made even if a
+    /// single upload exceeds the budget.
+    pub fn pump_uploads(&mut self, budget: Duration) -> usize {
+        span!("pump_uploads", budget_us = budget.as_micros() as u64);
+        let start = Instant::now();
+        let mut count = 0;
+        while count == 0 || start.elapsed() < budget {
+            let Ok(upload) = self.uploads.receiver.try_recv() else {
+                break;
+            };
+
+            let resource: Box<dyn Any> = match upload.request {
+                UploadRequest::Texture(texture) => Box::new(self.create_pending_texture(&texture)),
+                UploadRequest::Buffer(buffer) => Box::new(self.create_pending_buffer(&buffer)),
+            };
+            self.uploads.completed.insert(upload.id, resource);
+
+            let mut state = upload.state.lock().unwrap();
+            state.ready = true;
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+            count += 1;
+        }
+
+        count
+    }
+
+    /// Take a created resource, or `None` if it isn't ready or was already taken.
+    pub fn take_upload<T: 'static>(
+        &mut self,
+        handle: &UploadHandle<T>,
+    ) -> Option<Result<T, Error>> {
+        let resource = self.uploads.completed.remove(&handle.id)?;
+        let resource = resource
+            .downcast::<Result<T, Error>>()
+            .expect("upload handle type does not match the resource");
+        Some(*resource)
+    }
+
+    pub(crate) fn create_pending_texture(
+        &mut self,
+        texture: &PendingTexture,
+    ) -> Result<Texture2D, Error> {
+        let handle = TextureHandle::new_with(
+            self,
+            &SamplerDescriptor {
+                wrapping_mode_s: texture.wrapping_mode_s,
+                wrapping_mode_t: texture.wrapping_mode_t,
+                min_filter: texture.min_filter,
+                mag_filter: texture.mag_filter,
+                mipmap_policy: texture.mipmap_policy,
+                ..*self.default_sampler()
+            },
+        )?;
+
+        Ok(handle.allocate_2d_data(
+            self,
+            Some(&texture.pixels),
+            texture.internal_format,
+            texture.format,
+            texture.width,
+            texture.height,
+            texture.ty,
+        ))
+    }
+
+    pub(crate) fn create_pending_buffer(
+        &mut self,
+        buffer: &PendingBuffer,
+    ) -> Result<BufferHandle, Error> {
+        match buffer.ty {
+            BufferType::ArrayBuffer => BufferHandle::array_buffer(self, buffer.usage, &buffer.data),
+            BufferType::ElementArrayBuffer => {
+                BufferHandle::index_buffer(self, buffer.usage, &buffer.data)
+            }
+            BufferType::UniformBuffer => {
+                BufferHandle::uniform_buffer(self, buffer.usage, &buffer.data)
+            }
+        }
+    }
+}
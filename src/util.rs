@@ -0,0 +1,70 @@
+//! Helpers that don't touch OpenGL, shared with rapax's own subsystems.
+
+use super::*;
+
+/// Packs rectangles into rows of increasing height, e.g. to manage lightmap or shadow atlases. The glyph & SDF atlases
+/// use it too.
+///
+/// Each rectangle goes onto the shortest row it fits into, or opens a new row below the last one. Rectangles can't be
+/// freed one by one; [`RectPacker::clear`] the packer and allocate everything again instead.
+#[derive(Debug, Clone)]
+pub struct RectPacker {
+    width: i32,
+    height: i32,
+    // y, height and used width of each shelf
+    shelves: Vec<(i32, i32, i32)>,
+}
+
+impl RectPacker {
+    /// Create a packer for an area of `width` x `height` pixels.
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            shelves: Vec::new(),
+        }
+    }
+
+    /// Allocate a `width` x `height` rectangle, or `None` if it doesn't fit anymore.
+    pub fn allocate(&mut self, width: i32, height: i32) -> Option<Rect> {
+        if width > self.width {
+            return None;
+        }
+        let shelf = self
+            .shelves
+            .iter_mut()
+            .filter(|(_, shelf_height, used)| height <= *shelf_height && used + width <= self.width)
+            .min_by_key(|(_, shelf_height, _)| *shelf_height);
+        if let Some((y, _, used)) = shelf {
+            let rect = Rect::new(*used, *y, width, height);
+            *used += width;
+            return Some(rect);
+        }
+
+        let y = self
+            .shelves
+            .last()
+            .map(|(y, shelf_height, _)| y + shelf_height)
+            .unwrap_or(0);
+        if y + height > self.height {
+            return None;
+        }
+        self.shelves.push((y, height, width));
+        Some(Rect::new(0, y, width, height))
+    }
+
+    /// Free every rectangle.
+    pub fn clear(&mut self) {
+        self.shelves.clear();
+    }
+
+    /// The width & height of the packed area.
+    pub fn size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+
+    /// Returns true if no rectangle is allocated.
+    pub fn is_empty(&self) -> bool {
+        self.shelves.is_empty()
+    }
+}
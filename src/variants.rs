@@ -0,0 +1,66 @@
+use super::*;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Permutations of a vertex & fragment shader pair, compiled on demand for each set of `#define`s.
+///
+/// Material systems can request e.g. `&["NORMAL_MAP", "SKINNING"]` without compiling every combination up front.
+/// Programs are cached by define set, ignoring order & duplicates, and shared with [`RenderPipeline::new_shared`].
+#[derive(Debug)]
+pub struct ShaderVariants {
+    vertex_shader_source: String,
+    fragment_shader_source: String,
+    programs: Mutex<HashMap<Vec<String>, Arc<ShaderProgram>>>,
+}
+
+impl ShaderVariants {
+    /// Create an empty cache for the given sources. The sources are preprocessed by inserting the requested defines
+    /// after their `#version` directive.
+    pub fn new(vertex_shader_source: &str, fragment_shader_source: &str) -> Self {
+        Self {
+            vertex_shader_source: vertex_shader_source.into(),
+            fragment_shader_source: fragment_shader_source.into(),
+            programs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The program compiled with `defines`, compiling it if it isn't cached yet. A define of the form `NAME=VALUE`
+    /// is defined to `VALUE`.
+    ///
+    /// Compile errors aren't cached, so a failing permutation is recompiled on the next call.
+    pub fn get(&self, ctx: &ManagedContext, defines: &[&str]) -> Result<Arc<ShaderProgram>, Error> {
+        let mut key: Vec<String> = defines.iter().map(|define| define.to_string()).collect();
+        key.sort_unstable();
+        key.dedup();
+
+        if let Some(program) = self.programs.lock().unwrap().get(&key) {
+            return Ok(program.clone());
+        }
+
+        span!("compile_shader_variant", defines = ?key);
+        let defines: Vec<&str> = key.iter().map(String::as_str).collect();
+        #[allow(clippy::arc_with_non_send_sync)]
+        let program = Arc::new(ShaderProgram::new(
+            ctx,
+            &inject_defines(&self.vertex_shader_source, &defines),
+            &inject_defines(&self.fragment_shader_source, &defines),
+        )?);
+        self.programs.lock().unwrap().insert(key, program.clone());
+        Ok(program)
+    }
+
+    /// The number of compiled permutations.
+    pub fn len(&self) -> usize {
+        self.programs.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop every cached permutation, e.g. after the sources were hot-reloaded. Pipelines keep their programs alive.
+    pub fn clear(&self) {
+        self.programs.lock().unwrap().clear();
+    }
+}
@@ -0,0 +1,231 @@
+//! Vector path rendering with [`lyon`].
+//!
+//! Paths are tessellated once into a [`Mesh`], optionally cached per path by a [`MeshCache`], and drawn with
+//! [`RenderPipeline::vector_fill`] or [`RenderPipeline::vector_stroke`].
+
+use super::*;
+
+use lyon::path::{Path, Side};
+use lyon::tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, StrokeOptions, StrokeTessellator,
+    StrokeVertex, VertexBuffers,
+};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Vertex shader for [`STROKE_FS`]. Reads a `vec2` position at location 0 and the `float` side of the stroke at
+/// location 1.
+pub const STROKE_VS: &str = r#"
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in float a_side;
+
+uniform mat4 u_transform;
+
+out float v_side;
+
+void main() {
+    v_side = a_side;
+    gl_Position = u_transform * vec4(a_position, 0.0, 1.0);
+}
+"#;
+
+/// Fill a stroke with the `vec4 u_color` uniform, antialiasing both of its sides.
+pub const STROKE_FS: &str = r#"
+uniform vec4 u_color;
+
+in float v_side;
+
+out vec4 o_color;
+
+void main() {
+    float coverage = clamp((1.0 - abs(v_side)) / max(fwidth(v_side), 1e-4), 0.0, 1.0);
+    o_color = vec4(u_color.rgb, u_color.a * coverage);
+}
+"#;
+
+/// Tessellated geometry of a path, ready to be drawn with the pipeline matching how it was tessellated.
+#[derive(Debug)]
+pub struct Mesh {
+    vertices: BufferHandle,
+    indices: BufferHandle,
+    index_count: u32,
+}
+
+impl Mesh {
+    /// Tessellate the interior of a path. Draw it with [`RenderPipeline::vector_fill`].
+    pub fn fill(
+        ctx: &mut ManagedContext,
+        path: &Path,
+        options: &FillOptions,
+    ) -> Result<Self, Error> {
+        span!("tessellate_fill");
+        let mut geometry: VertexBuffers<[f32; 2], u32> = VertexBuffers::new();
+        FillTessellator::new()
+            .tessellate_path(
+                path,
+                options,
+                &mut BuffersBuilder::new(&mut geometry, |vertex: FillVertex| {
+                    vertex.position().to_array()
+                }),
+            )
+            .map_err(|error| Error::Tessellation(error.to_string()))?;
+        Self::upload(
+            ctx,
+            bytemuck::cast_slice(&geometry.vertices),
+            &geometry.indices,
+        )
+    }
+
+    /// Tessellate the outline of a path. Draw it with [`RenderPipeline::vector_stroke`].
+    pub fn stroke(
+        ctx: &mut ManagedContext,
+        path: &Path,
+        options: &StrokeOptions,
+    ) -> Result<Self, Error> {
+        span!("tessellate_stroke");
+        let mut geometry: VertexBuffers<[f32; 3], u32> = VertexBuffers::new();
+        StrokeTessellator::new()
+            .tessellate_path(
+                path,
+                options,
+                &mut BuffersBuilder::new(&mut geometry, |vertex: StrokeVertex| {
+                    let [x, y] = vertex.position().to_array();
+                    let side = match vertex.side() {
+                        Side::Positive => 1.0,
+                        Side::Negative => -1.0,
+                    };
+                    [x, y, side]
+                }),
+            )
+            .map_err(|error| Error::Tessellation(error.to_string()))?;
+        Self::upload(
+            ctx,
+            bytemuck::cast_slice(&geometry.vertices),
+            &geometry.indices,
+        )
+    }
+
+    fn upload(ctx: &mut ManagedContext, vertices: &[u8], indices: &[u32]) -> Result<Self, Error> {
+        Ok(Self {
+            vertices: BufferHandle::array_buffer(ctx, BufferUsage::Immutable, vertices)?,
+            indices: BufferHandle::index_buffer(
+                ctx,
+                BufferUsage::Immutable,
+                bytemuck::cast_slice(indices),
+            )?
+            .with_index_type(DataType::UnsignedInt),
+            index_count: indices.len() as u32,
+        })
+    }
+
+    /// Bind the mesh buffers and draw all triangles.
+    pub fn draw(&self, dctx: &mut Drawable) {
+        dctx.apply_bindings(&[&self.vertices], Some(&self.indices));
+        dctx.draw_indexed(DrawMode::Triangles, 0..self.index_count, 0, 1);
+    }
+
+    /// The number of indices of the mesh.
+    pub fn index_count(&self) -> u32 {
+        self.index_count
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum MeshKind {
+    Fill,
+    Stroke,
+}
+
+/// Tessellated meshes keyed per path, so unchanged paths aren't tessellated again every frame.
+#[derive(Debug)]
+pub struct MeshCache<K> {
+    meshes: HashMap<(K, MeshKind), Mesh>,
+}
+
+impl<K: Hash + Eq> Default for MeshCache<K> {
+    fn default() -> Self {
+        Self {
+            meshes: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq> MeshCache<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The fill mesh of the path identified by `key`, tessellating `path` if it isn't cached.
+    pub fn fill(
+        &mut self,
+        ctx: &mut ManagedContext,
+        key: K,
+        path: &Path,
+        options: &FillOptions,
+    ) -> Result<&Mesh, Error> {
+        self.get_or_insert((key, MeshKind::Fill), || Mesh::fill(ctx, path, options))
+    }
+
+    /// The stroke mesh of the path identified by `key`, tessellating `path` if it isn't cached.
+    pub fn stroke(
+        &mut self,
+        ctx: &mut ManagedContext,
+        key: K,
+        path: &Path,
+        options: &StrokeOptions,
+    ) -> Result<&Mesh, Error> {
+        self.get_or_insert((key, MeshKind::Stroke), || Mesh::stroke(ctx, path, options))
+    }
+
+    fn get_or_insert(
+        &mut self,
+        key: (K, MeshKind),
+        tessellate: impl FnOnce() -> Result<Mesh, Error>,
+    ) -> Result<&Mesh, Error> {
+        use std::collections::hash_map::Entry;
+        match self.meshes.entry(key) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => Ok(entry.insert(tessellate()?)),
+        }
+    }
+
+    /// Drop the cached meshes of a path, e.g. after it changed.
+    pub fn invalidate(&mut self, key: &K)
+    where
+        K: Clone,
+    {
+        self.meshes.remove(&(key.clone(), MeshKind::Fill));
+        self.meshes.remove(&(key.clone(), MeshKind::Stroke));
+    }
+
+    /// Drop every cached mesh.
+    pub fn clear(&mut self) {
+        self.meshes.clear();
+    }
+}
+
+impl RenderPipeline {
+    /// An alpha blended pipeline drawing [`Mesh::fill`] meshes with a single color. See [`shaders::FLAT_COLOR_FS`].
+    pub fn vector_fill(ctx: &ManagedContext) -> Result<Self, Error> {
+        Self::built_in(
+            ctx,
+            shaders::FLAT_COLOR_VS,
+            shaders::FLAT_COLOR_FS,
+            VertexLayout::new(0).with_attribute(2, DataType::Float, false),
+        )
+        .map(Self::alpha_blended)
+    }
+
+    /// An alpha blended pipeline drawing antialiased [`Mesh::stroke`] meshes. See [`STROKE_FS`].
+    pub fn vector_stroke(ctx: &ManagedContext) -> Result<Self, Error> {
+        Self::built_in(
+            ctx,
+            STROKE_VS,
+            STROKE_FS,
+            VertexLayout::new(0)
+                .with_attribute(2, DataType::Float, false)
+                .with_attribute(1, DataType::Float, false),
+        )
+        .map(Self::alpha_blended)
+    }
+}
@@ -0,0 +1,225 @@
+use super::*;
+
+use std::sync::Arc;
+
+/// A vertex array object with the buffers of a pipeline's vertex layout already attached.
+///
+/// Recording the bindings once and using [`ManagedContext::with_pipeline_vertex_array`] skips
+/// [`Drawable::apply_bindings`] on every draw. The internal OpenGL vertex array object will be automatically freed on drop.
+#[derive(Debug)]
+pub struct VertexArrayObject {
+    pub(crate) vao: NativeVertexArray,
+    pub(crate) layout_id: VertexLayoutId,
+    pub(crate) has_index_buffer: bool,
+    pub(crate) index_type: Option<DataType>,
+    enabled_attributes: Vec<u32>,
+    gl: Arc<Context>,
+}
+
+impl VertexArrayObject {
+    /// Record the vertex & index buffer bindings for the vertex layout of `pipeline`.
+    pub fn new(
+        ctx: &mut ManagedContext,
+        pipeline: &RenderPipeline,
+        vertex_buffers: &[impl BindableBuffer],
+        index_buffer: Option<impl BindableBuffer>,
+    ) -> Result<Self, Error> {
+        unsafe {
+            let vao = ctx.gl.create_vertex_array().map_err(Error::Unsupported)?;
+            ctx.gl.bind_vertex_array(Some(vao));
+            let enabled_attributes =
+                set_vertex_attributes(ctx, &pipeline.vertex_attributes, vertex_buffers);
+            let (has_index_buffer, index_type) = match index_buffer {
+                Some(index_buffer) => {
+                    index_buffer.bind(ELEMENT_ARRAY_BUFFER, &ctx.gl);
+                    (true, index_buffer.index_type())
+                }
+                None => (false, None),
+            };
+            ctx.gl.bind_vertex_array(None);
+
+            Ok(Self {
+                vao,
+                layout_id: pipeline.vertex_layout_id(),
+                has_index_buffer,
+                index_type,
+                enabled_attributes,
+                gl: ctx.gl.clone(),
+            })
+        }
+    }
+
+    /// The id of the vertex layout the vertex array was recorded for.
+    pub fn layout_id(&self) -> VertexLayoutId {
+        self.layout_id
+    }
+
+    /// Attach an index buffer, replacing the current one.
+    ///
+    /// ## Panics
+    /// The buffer must be an element array buffer.
+    pub fn set_index_buffer(&mut self, buffer: &BufferHandle) {
+        assert_eq!(
+            buffer.ty(),
+            BufferType::ElementArrayBuffer,
+            "Attempted to bind buffer to invalid binding point"
+        );
+        self.with_bound(|gl| unsafe { gl.bind_buffer(ELEMENT_ARRAY_BUFFER, Some(buffer.buffer)) });
+        self.has_index_buffer = true;
+        self.index_type = buffer.index_type();
+    }
+
+    /// Set the instancing divisor of an attribute.
+    pub fn set_divisor(&self, index: u32, divisor: u32) {
+        self.with_bound(|gl| unsafe { gl.vertex_attrib_divisor(index, divisor) });
+    }
+
+    /// Enable an attribute array.
+    pub fn enable_attrib(&mut self, index: u32) {
+        self.with_bound(|gl| unsafe { gl.enable_vertex_attrib_array(index) });
+        if !self.enabled_attributes.contains(&index) {
+            self.enabled_attributes.push(index);
+        }
+    }
+
+    /// Disable an attribute array.
+    pub fn disable_attrib(&mut self, index: u32) {
+        self.with_bound(|gl| unsafe { gl.disable_vertex_attrib_array(index) });
+        self.enabled_attributes.retain(|&enabled| enabled != index);
+    }
+
+    /// The indices of the enabled attribute arrays.
+    pub fn enabled_attributes(&self) -> &[u32] {
+        &self.enabled_attributes
+    }
+
+    fn with_bound(&self, f: impl FnOnce(&Context)) {
+        unsafe { self.gl.bind_vertex_array(Some(self.vao)) };
+        f(&self.gl);
+        unsafe { self.gl.bind_vertex_array(None) };
+    }
+}
+
+impl Drop for VertexArrayObject {
+    fn drop(&mut self) {
+        unsafe { self.gl.delete_vertex_array(self.vao) }
+    }
+}
+
+/// Point the attributes of the bound vertex array at their vertex buffers, and enable them.
+///
+/// Divisors are only set for per-instance attributes on contexts with [`Features::INSTANCING`], where
+/// `glVertexAttribDivisor` exists.
+///
+/// Returns the enabled attribute locations.
+pub(crate) unsafe fn set_vertex_attributes(
+    ctx: &ManagedContext,
+    attributes: &[VertexAttributeDescriptor],
+    vertex_buffers: &[impl BindableBuffer],
+) -> Vec<u32> {
+    let gl = &ctx.gl;
+    let instancing = ctx.features.contains(Features::INSTANCING);
+    for (idx, attr) in attributes.iter().enumerate() {
+        let buffer = &vertex_buffers[attr.buffer_index];
+        assert!(
+            !attr.ty.is_packed() || attr.size == 4,
+            "packed vertex attributes must have 4 components"
+        );
+        buffer.bind(ARRAY_BUFFER, gl);
+
+        // also resets the attribute's buffer binding to its own location, matching `set_vertex_formats`
+        let location = attr.location_at(idx);
+        gl.vertex_attrib_pointer_f32(
+            location,
+            attr.size,
+            attr.ty as _,
+            attr.normalized,
+            attr.stride,
+            attr.offset,
+        );
+        if attr.divisor != 0 && instancing {
+            gl.vertex_attrib_divisor(location, attr.divisor);
+        }
+        gl.enable_vertex_attrib_array(location);
+    }
+
+    attribute_locations(attributes)
+}
+
+/// Record the formats of the attributes in `vao`, each reading from the buffer binding of its own location, and enable
+/// them. Buffers are then attached with [`bind_vertex_buffers`] without respecifying the formats.
+///
+/// With direct state access loaded, `vao` is edited without binding it and must come from
+/// [`DirectStateAccess::create_vertex_array`]; otherwise it must be bound.
+///
+/// Requires [`Features::VERTEX_ATTRIB_BINDING`].
+pub(crate) unsafe fn set_vertex_formats(
+    ctx: &ManagedContext,
+    vao: NativeVertexArray,
+    attributes: &[VertexAttributeDescriptor],
+) {
+    let gl = &ctx.gl;
+    for (idx, attr) in attributes.iter().enumerate() {
+        assert!(
+            !attr.ty.is_packed() || attr.size == 4,
+            "packed vertex attributes must have 4 components"
+        );
+        let location = attr.location_at(idx);
+        if let Some(dsa) = ctx.dsa() {
+            gl.vertex_array_attrib_format_f32(
+                vao,
+                location,
+                attr.size,
+                attr.ty as _,
+                attr.normalized,
+                0,
+            );
+            gl.vertex_array_attrib_binding_f32(vao, location, location);
+            dsa.vertex_array_binding_divisor(vao, location, attr.divisor);
+            gl.enable_vertex_array_attrib(vao, location);
+        } else {
+            gl.vertex_attrib_format_f32(location, attr.size, attr.ty as _, attr.normalized, 0);
+            gl.vertex_attrib_binding(location, location);
+            gl.vertex_binding_divisor(location, attr.divisor);
+            gl.enable_vertex_attrib_array(location);
+        }
+    }
+}
+
+/// Attach the vertex buffers to the buffer bindings of a vertex array recorded with [`set_vertex_formats`], and
+/// enable the attributes again: pooled vertex arrays are shared, and [`Drawable`] disables them on drop.
+///
+/// Returns false without attaching anything if a buffer has no [`BindableBuffer::native_buffer`].
+pub(crate) unsafe fn bind_vertex_buffers(
+    gl: &Context,
+    attributes: &[VertexAttributeDescriptor],
+    vertex_buffers: &[impl BindableBuffer],
+) -> bool {
+    let Some(buffers) = vertex_buffers
+        .iter()
+        .map(|buffer| buffer.native_buffer(ARRAY_BUFFER))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return false;
+    };
+
+    for (idx, attr) in attributes.iter().enumerate() {
+        gl.bind_vertex_buffer(
+            attr.location_at(idx),
+            Some(buffers[attr.buffer_index]),
+            attr.offset,
+            attr.stride,
+        );
+        gl.enable_vertex_attrib_array(attr.location_at(idx));
+    }
+    true
+}
+
+/// The locations of `attributes`, see [`VertexAttributeDescriptor::location_at`].
+pub(crate) fn attribute_locations(attributes: &[VertexAttributeDescriptor]) -> Vec<u32> {
+    attributes
+        .iter()
+        .enumerate()
+        .map(|(idx, attr)| attr.location_at(idx))
+        .collect()
+}
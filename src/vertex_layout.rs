@@ -0,0 +1,521 @@
+use super::*;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Identifies a vertex attribute layout by its hash, e.g. to check a [`VertexArrayObject`] was recorded for a pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VertexLayoutId(pub u64);
+
+impl VertexLayoutId {
+    /// Hash a list of vertex attributes.
+    pub fn from_attributes(attributes: &[VertexAttributeDescriptor]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        attributes.hash(&mut hasher);
+        Self(hasher.finish())
+    }
+}
+
+/// A packed layout of vertex attributes within a single vertex buffer.
+///
+/// Attribute offsets and the vertex stride are computed automatically, and the layout can
+/// interleave separate per-attribute streams into a buffer, converting them to the attribute data types.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VertexLayout {
+    buffer_index: usize,
+    attributes: Vec<VertexAttributeDescriptor>,
+}
+
+impl VertexLayout {
+    /// Create an empty layout for the vertex buffer at `buffer_index`.
+    pub fn new(buffer_index: usize) -> Self {
+        Self {
+            buffer_index,
+            attributes: vec![],
+        }
+    }
+
+    /// Create a layout from existing descriptors, keeping only those that read from `buffer_index`.
+    pub fn from_attributes(buffer_index: usize, attributes: &[VertexAttributeDescriptor]) -> Self {
+        Self {
+            buffer_index,
+            attributes: attributes
+                .iter()
+                .filter(|attr| attr.buffer_index == buffer_index)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Append an attribute with `size` components of type `ty`.
+    ///
+    /// Integer types with `normalized` set map the `[0, 1]` (or `[-1, 1]` for signed types) range to the full integer
+    /// range.
+    pub fn with_attribute(self, size: i32, ty: DataType, normalized: bool) -> Self {
+        let offset = align_to_4(self.packed_size());
+        let mut attributes = self.attributes;
+        attributes.push(VertexAttributeDescriptor {
+            buffer_index: self.buffer_index,
+            size,
+            ty,
+            normalized,
+            stride: 0,
+            offset,
+            divisor: 0,
+            location: None,
+        });
+
+        let stride = align_to_4(
+            attributes
+                .iter()
+                .map(|attr| attr.offset + attribute_size(attr))
+                .max()
+                .unwrap_or(0),
+        );
+        for attr in attributes.iter_mut() {
+            attr.stride = stride;
+        }
+
+        Self { attributes, ..self }
+    }
+
+    /// Set the instancing divisor of every attribute in the layout.
+    pub fn with_divisor(self, divisor: u32) -> Self {
+        let mut attributes = self.attributes;
+        for attr in attributes.iter_mut() {
+            attr.divisor = divisor;
+        }
+
+        Self { attributes, ..self }
+    }
+
+    /// The attribute descriptors, ready to be passed to [`RenderPipeline::with_vertex_attribute`].
+    pub fn attributes(&self) -> &[VertexAttributeDescriptor] {
+        &self.attributes
+    }
+
+    /// The size of a single vertex, in bytes.
+    pub fn stride(&self) -> usize {
+        self.attributes
+            .first()
+            .map(|attr| attr.stride as usize)
+            .unwrap_or(0)
+    }
+
+    fn packed_size(&self) -> i32 {
+        self.attributes
+            .iter()
+            .map(|attr| attr.offset + attribute_size(attr))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Interleave one stream per attribute into vertex buffer data.
+    ///
+    /// Each stream holds `size` components per vertex, which are converted to the attribute's data type.
+    ///
+    /// ## Panics
+    /// There must be one stream per attribute, and all streams must hold the same number of vertices.
+    pub fn interleave(&self, streams: &[&[f32]]) -> Vec<u8> {
+        assert_eq!(
+            streams.len(),
+            self.attributes.len(),
+            "stream count does not match attribute count"
+        );
+        let vertex_count = self.vertex_count(streams);
+        let stride = self.stride();
+
+        let mut data = vec![0u8; vertex_count * stride];
+        for (attr, stream) in self.attributes.iter().zip(streams) {
+            let size = attr.size as usize;
+            let component_size = attr.ty.sizeof();
+            for vertex in 0..vertex_count {
+                let base = vertex * stride + attr.offset as usize;
+                if attr.ty.is_packed() {
+                    let mut value = [0.0; 4];
+                    value.copy_from_slice(&stream[vertex * size..vertex * size + 4]);
+                    let packed = pack_2_10_10_10(value, attr.ty, attr.normalized);
+                    data[base..base + 4].copy_from_slice(&packed.to_ne_bytes());
+                    continue;
+                }
+
+                for component in 0..size {
+                    let start = base + component * component_size;
+                    encode_component(
+                        stream[vertex * size + component],
+                        attr.ty,
+                        attr.normalized,
+                        &mut data[start..start + component_size],
+                    );
+                }
+            }
+        }
+
+        data
+    }
+
+    /// Split vertex buffer data back into one stream per attribute.
+    pub fn deinterleave(&self, data: &[u8]) -> Vec<Vec<f32>> {
+        let stride = self.stride();
+        let vertex_count = data.len().checked_div(stride).unwrap_or(0);
+
+        self.attributes
+            .iter()
+            .map(|attr| {
+                let size = attr.size as usize;
+                let component_size = attr.ty.sizeof();
+                let mut stream = Vec::with_capacity(vertex_count * size);
+                for vertex in 0..vertex_count {
+                    let base = vertex * stride + attr.offset as usize;
+                    if attr.ty.is_packed() {
+                        let packed = u32::from_ne_bytes(data[base..base + 4].try_into().unwrap());
+                        stream.extend(unpack_2_10_10_10(packed, attr.ty, attr.normalized));
+                        continue;
+                    }
+
+                    for component in 0..size {
+                        let start = base + component * component_size;
+                        stream.push(decode_component(
+                            &data[start..start + component_size],
+                            attr.ty,
+                            attr.normalized,
+                        ));
+                    }
+                }
+                stream
+            })
+            .collect()
+    }
+
+    fn vertex_count(&self, streams: &[&[f32]]) -> usize {
+        let mut counts = self
+            .attributes
+            .iter()
+            .zip(streams)
+            .map(|(attr, stream)| stream.len() / attr.size as usize);
+        let count = counts.next().unwrap_or(0);
+        assert!(
+            counts.all(|c| c == count),
+            "streams hold different vertex counts"
+        );
+        count
+    }
+}
+
+impl RenderPipeline {
+    /// Add every attribute of a vertex layout to the pipeline.
+    pub fn with_vertex_layout(self, layout: &VertexLayout) -> Self {
+        layout
+            .attributes()
+            .iter()
+            .cloned()
+            .fold(self, |pipeline, attr| pipeline.with_vertex_attribute(attr))
+    }
+
+    /// The id of the pipeline's vertex attribute layout.
+    pub fn vertex_layout_id(&self) -> VertexLayoutId {
+        VertexLayoutId::from_attributes(&self.vertex_attributes)
+    }
+
+    /// The layout of the pipeline's attributes that read from the vertex buffer at `buffer_index`.
+    pub fn vertex_layout(&self, buffer_index: usize) -> VertexLayout {
+        VertexLayout::from_attributes(buffer_index, &self.vertex_attributes)
+    }
+}
+
+fn attribute_size(attr: &VertexAttributeDescriptor) -> i32 {
+    if attr.ty.is_packed() {
+        attr.ty.sizeof() as i32
+    } else {
+        attr.size * attr.ty.sizeof() as i32
+    }
+}
+
+fn align_to_4(value: i32) -> i32 {
+    (value + 3) & !3
+}
+
+/// Pack four components into a `2_10_10_10_REV` value of type `ty`.
+///
+/// Normalized values map `[-1, 1]` (signed) or `[0, 1]` (unsigned) to the full range of each component.
+///
+/// ## Panics
+/// `ty` must be a packed data type.
+pub fn pack_2_10_10_10(value: [f32; 4], ty: DataType, normalized: bool) -> u32 {
+    let signed = match ty {
+        DataType::Int2_10_10_10_Rev => true,
+        DataType::UnsignedInt2_10_10_10_Rev => false,
+        _ => panic!("not a packed data type"),
+    };
+
+    let mut packed = 0;
+    for (component, value) in value.into_iter().enumerate() {
+        let bits = if component == 3 { 2 } else { 10 };
+        let mask = (1u32 << bits) - 1;
+        let field = if signed {
+            let max = ((1 << (bits - 1)) - 1) as f32;
+            let value = if normalized {
+                (value.clamp(-1.0, 1.0) * max).round()
+            } else {
+                value.clamp(-max - 1.0, max).round()
+            };
+            value as i32 as u32 & mask
+        } else {
+            let max = mask as f32;
+            let value = if normalized {
+                (value.clamp(0.0, 1.0) * max).round()
+            } else {
+                value.clamp(0.0, max).round()
+            };
+            value as u32
+        };
+        packed |= field << (component * 10);
+    }
+
+    packed
+}
+
+/// Unpack a `2_10_10_10_REV` value of type `ty` into four components.
+///
+/// ## Panics
+/// `ty` must be a packed data type.
+pub fn unpack_2_10_10_10(packed: u32, ty: DataType, normalized: bool) -> [f32; 4] {
+    let signed = match ty {
+        DataType::Int2_10_10_10_Rev => true,
+        DataType::UnsignedInt2_10_10_10_Rev => false,
+        _ => panic!("not a packed data type"),
+    };
+
+    let mut value = [0.0; 4];
+    for (component, value) in value.iter_mut().enumerate() {
+        let bits = if component == 3 { 2 } else { 10 };
+        let field = (packed >> (component * 10)) & ((1 << bits) - 1);
+        *value = if signed {
+            // sign extend the field
+            let field = ((field << (32 - bits)) as i32) >> (32 - bits);
+            let max = ((1 << (bits - 1)) - 1) as f32;
+            if normalized {
+                (field as f32 / max).max(-1.0)
+            } else {
+                field as f32
+            }
+        } else if normalized {
+            field as f32 / ((1 << bits) - 1) as f32
+        } else {
+            field as f32
+        };
+    }
+
+    value
+}
+
+/// Encode a unit normal as a normalized [`DataType::Int2_10_10_10_Rev`] value.
+pub fn encode_normal(normal: [f32; 3]) -> u32 {
+    pack_2_10_10_10(
+        [normal[0], normal[1], normal[2], 0.0],
+        DataType::Int2_10_10_10_Rev,
+        true,
+    )
+}
+
+/// Encode a unit tangent and its bitangent sign (`1.0` or `-1.0`) as a normalized
+/// [`DataType::Int2_10_10_10_Rev`] value.
+pub fn encode_tangent(tangent: [f32; 3], handedness: f32) -> u32 {
+    pack_2_10_10_10(
+        [tangent[0], tangent[1], tangent[2], handedness.signum()],
+        DataType::Int2_10_10_10_Rev,
+        true,
+    )
+}
+
+fn encode_component(value: f32, ty: DataType, normalized: bool, out: &mut [u8]) {
+    macro_rules! int {
+        ($ty:ty, $signed:expr) => {{
+            let value = if normalized {
+                let value = if $signed {
+                    value.clamp(-1.0, 1.0)
+                } else {
+                    value.clamp(0.0, 1.0)
+                };
+                (value * <$ty>::MAX as f32).round() as $ty
+            } else {
+                value as $ty
+            };
+            out.copy_from_slice(&value.to_ne_bytes());
+        }};
+    }
+
+    match ty {
+        DataType::SignedByte => int!(i8, true),
+        DataType::UnsignedByte => int!(u8, false),
+        DataType::SignedShort => int!(i16, true),
+        DataType::UnsignedShort => int!(u16, false),
+        DataType::SignedInt => int!(i32, true),
+        DataType::UnsignedInt => int!(u32, false),
+        DataType::HalfFloat => out.copy_from_slice(&f32_to_f16(value).to_ne_bytes()),
+        DataType::Float => out.copy_from_slice(&value.to_ne_bytes()),
+        DataType::Double => out.copy_from_slice(&(value as f64).to_ne_bytes()),
+        DataType::Fixed => out.copy_from_slice(&((value * 65536.0) as i32).to_ne_bytes()),
+        DataType::Int2_10_10_10_Rev | DataType::UnsignedInt2_10_10_10_Rev => {
+            unreachable!("packed types are encoded per attribute")
+        }
+    }
+}
+
+fn decode_component(bytes: &[u8], ty: DataType, normalized: bool) -> f32 {
+    macro_rules! int {
+        ($ty:ty, $signed:expr) => {{
+            let value = <$ty>::from_ne_bytes(bytes.try_into().unwrap());
+            if normalized {
+                let value = value as f32 / <$ty>::MAX as f32;
+                if $signed {
+                    value.max(-1.0)
+                } else {
+                    value
+                }
+            } else {
+                value as f32
+            }
+        }};
+    }
+
+    match ty {
+        DataType::SignedByte => int!(i8, true),
+        DataType::UnsignedByte => int!(u8, false),
+        DataType::SignedShort => int!(i16, true),
+        DataType::UnsignedShort => int!(u16, false),
+        DataType::SignedInt => int!(i32, true),
+        DataType::UnsignedInt => int!(u32, false),
+        DataType::HalfFloat => f16_to_f32(u16::from_ne_bytes(bytes.try_into().unwrap())),
+        DataType::Float => f32::from_ne_bytes(bytes.try_into().unwrap()),
+        DataType::Double => f64::from_ne_bytes(bytes.try_into().unwrap()) as f32,
+        DataType::Fixed => i32::from_ne_bytes(bytes.try_into().unwrap()) as f32 / 65536.0,
+        DataType::Int2_10_10_10_Rev | DataType::UnsignedInt2_10_10_10_Rev => {
+            unreachable!("packed types are decoded per attribute")
+        }
+    }
+}
+
+/// Convert to an IEEE half-precision float, rounding to nearest, ties to even.
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let mantissa = bits & 0x7F_FFFF;
+
+    if exponent == 0xFF {
+        // infinity or NaN
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7C00 | nan;
+    }
+
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1F {
+        // overflow to infinity
+        sign | 0x7C00
+    } else if exponent <= 0 {
+        // subnormal or zero
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        sign | round_shift(mantissa, (14 - exponent) as u32)
+    } else {
+        // a carry out of the mantissa correctly rounds up to the next exponent, or to infinity
+        sign | round_shift((exponent as u32) << 23 | mantissa, 13)
+    }
+}
+
+/// Shift `value` right by `shift` bits, rounding to nearest, ties to even.
+fn round_shift(value: u32, shift: u32) -> u16 {
+    let truncated = value >> shift;
+    let remainder = value & ((1 << shift) - 1);
+    let half = 1 << (shift - 1);
+    let round_up = remainder > half || (remainder == half && truncated & 1 == 1);
+    (truncated + round_up as u32) as u16
+}
+
+fn f16_to_f32(value: u16) -> f32 {
+    let sign = ((value & 0x8000) as u32) << 16;
+    let exponent = ((value >> 10) & 0x1F) as u32;
+    let mantissa = (value & 0x3FF) as u32;
+
+    let bits = match (exponent, mantissa) {
+        (0, 0) => sign,
+        (0, _) => {
+            // subnormal, renormalize
+            let shift = mantissa.leading_zeros() - 21;
+            let mantissa = (mantissa << shift) & 0x3FF;
+            sign | ((127 - 15 + 1 - shift) << 23) | (mantissa << 13)
+        }
+        (0x1F, _) => sign | 0x7F80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+    };
+
+    f32::from_bits(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_to_f16() {
+        assert_eq!(f32_to_f16(0.0), 0x0000);
+        assert_eq!(f32_to_f16(-0.0), 0x8000);
+        assert_eq!(f32_to_f16(1.0), 0x3C00);
+        assert_eq!(f32_to_f16(-2.0), 0xC000);
+        assert_eq!(f32_to_f16(0.1), 0x2E66);
+        assert_eq!(f32_to_f16(65504.0), 0x7BFF);
+    }
+
+    #[test]
+    fn rounds_ties_to_even() {
+        // halfway between 0x3C00 & 0x3C01 rounds down to the even mantissa, between 0x3C01 & 0x3C02 up
+        assert_eq!(f32_to_f16(1.0 + 1.0 / 2048.0), 0x3C00);
+        assert_eq!(f32_to_f16(1.0 + 3.0 / 2048.0), 0x3C02);
+        // just above halfway rounds up
+        assert_eq!(f32_to_f16(1.0 + 1.0 / 2048.0 + 1.0 / 65536.0), 0x3C01);
+    }
+
+    #[test]
+    fn rounds_subnormals() {
+        assert_eq!(f32_to_f16(2f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_f16(5.96e-8), 0x0001);
+        assert_eq!(f32_to_f16(2f32.powi(-14) - 2f32.powi(-24)), 0x03FF);
+        // half the smallest subnormal ties to zero, anything above rounds up
+        assert_eq!(f32_to_f16(2f32.powi(-25)), 0x0000);
+        assert_eq!(f32_to_f16(2f32.powi(-25) * 1.01), 0x0001);
+        assert_eq!(f32_to_f16(-2f32.powi(-30)), 0x8000);
+        // rounding up the largest subnormal carries into the smallest normal
+        assert_eq!(f32_to_f16(2f32.powi(-14) - 2f32.powi(-26)), 0x0400);
+    }
+
+    #[test]
+    fn carries_into_the_exponent() {
+        assert_eq!(f32_to_f16(2.0 - 2f32.powi(-12)), 0x4000);
+        assert_eq!(f32_to_f16(2.0 - 2f32.powi(-11) * 1.01), 0x3FFF);
+    }
+
+    #[test]
+    fn overflows_to_infinity() {
+        assert_eq!(f32_to_f16(65520.0), 0x7C00);
+        assert_eq!(f32_to_f16(65519.0), 0x7BFF);
+        assert_eq!(f32_to_f16(1e10), 0x7C00);
+        assert_eq!(f32_to_f16(f32::INFINITY), 0x7C00);
+        assert_eq!(f32_to_f16(f32::NEG_INFINITY), 0xFC00);
+    }
+
+    #[test]
+    fn keeps_nan() {
+        assert_eq!(f32_to_f16(f32::NAN), 0x7E00);
+        assert!(f16_to_f32(f32_to_f16(-f32::NAN)).is_nan());
+    }
+
+    #[test]
+    fn round_trips_through_f32() {
+        for bits in (0..0x7C00).chain(0x8000..0xFC00) {
+            assert_eq!(f32_to_f16(f16_to_f32(bits)), bits);
+        }
+    }
+}